@@ -0,0 +1,162 @@
+//! Benchmarks for representative workloads.
+//!
+//! Run with `cargo bench`; pass a name to run only the workloads containing
+//! it, e.g. `cargo bench -- array`. Each workload reports the median time
+//! per diff, the allocations per diff and the diff's [`DiffMetrics`].
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use deep_diff::{DiffMetrics, DiffOptions, deep_diff_with_metrics};
+use serde_json::{Value, json};
+
+// Counts allocations made through the global allocator
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const RUNS: usize = 15;
+
+struct Workload {
+    name: &'static str,
+    a: Value,
+    b: Value,
+    options: DiffOptions,
+}
+
+// An object with `width` keys, every `every`th of which differs
+fn wide_object(width: usize, every: usize) -> (Value, Value) {
+    let a: Value = (0..width).map(|i| (format!("key{i}"), json!(i))).collect();
+    let b: Value = (0..width)
+        .map(|i| {
+            let value = if i % every == 0 { json!(-1) } else { json!(i) };
+            (format!("key{i}"), value)
+        })
+        .collect();
+    (a, b)
+}
+
+// Objects nested `depth` levels deep, differing at the innermost value
+fn deep_nesting(depth: usize) -> (Value, Value) {
+    let (mut a, mut b) = (json!(1), json!(2));
+    for i in 0..depth {
+        a = json!({ format!("level{i}"): a, "same": i });
+        b = json!({ format!("level{i}"): b, "same": i });
+    }
+    (a, b)
+}
+
+// An array of `len` records, every `every`th of which differs
+fn big_array(len: usize, every: usize) -> (Value, Value) {
+    let record = |i: usize, n: usize| json!({"id": i, "name": format!("item {i}"), "n": n});
+    let a = (0..len).map(|i| record(i, i)).collect();
+    let b = (0..len)
+        .map(|i| record(i, if i % every == 0 { i + 1 } else { i }))
+        .collect();
+    (a, b)
+}
+
+fn workloads() -> Vec<Workload> {
+    let mut workloads = Vec::new();
+    let mut add = |name, (a, b): (Value, Value), options| {
+        workloads.push(Workload {
+            name,
+            a,
+            b,
+            options,
+        })
+    };
+    add(
+        "wide_object/few",
+        wide_object(10_000, 1_000),
+        DiffOptions::default(),
+    );
+    add(
+        "wide_object/many",
+        wide_object(10_000, 2),
+        DiffOptions::default(),
+    );
+    add("deep_nesting", deep_nesting(1_000), DiffOptions::default());
+    add(
+        "big_array/few",
+        big_array(10_000, 1_000),
+        DiffOptions::default(),
+    );
+    add(
+        "big_array/many",
+        big_array(10_000, 2),
+        DiffOptions::default(),
+    );
+    add(
+        "big_array/keyed",
+        big_array(10_000, 1_000),
+        DiffOptions::builder().array_key("", "id").build(),
+    );
+    let (a, _) = big_array(10_000, 1);
+    add("identical", (a.clone(), a), DiffOptions::default());
+    workloads
+}
+
+fn run(workload: &Workload) -> (Duration, usize, DiffMetrics) {
+    let mut times = Vec::with_capacity(RUNS);
+    let mut allocations = 0;
+    let mut metrics = DiffMetrics::default();
+    for _ in 0..RUNS {
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        let start = Instant::now();
+        let (result, run_metrics) = black_box(deep_diff_with_metrics(
+            &workload.a,
+            &workload.b,
+            &workload.options,
+        ));
+        times.push(start.elapsed());
+        allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+        metrics = run_metrics;
+        drop(result);
+    }
+    times.sort();
+    (times[RUNS / 2], allocations, metrics)
+}
+
+fn main() {
+    let filter = std::env::args().skip(1).find(|arg| !arg.starts_with('-'));
+    for workload in workloads() {
+        if filter
+            .as_ref()
+            .is_some_and(|f| !workload.name.contains(f.as_str()))
+        {
+            continue;
+        }
+        let (time, allocations, metrics) = run(&workload);
+        println!(
+            "{:<18} {:>10.3?} {:>9} allocs {:>9} nodes {:>6} deep {:>6} diffs",
+            workload.name,
+            time,
+            allocations,
+            metrics.nodes_visited,
+            metrics.max_depth,
+            metrics.differences,
+        );
+    }
+}
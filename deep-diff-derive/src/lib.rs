@@ -0,0 +1,274 @@
+//! `#[derive(DeepDiff)]` for the `deep-diff` crate.
+//!
+//! Use it through the `derive` feature of `deep-diff` rather than directly.
+
+use proc_macro::{Delimiter, TokenStream, TokenTree};
+
+/// Implements `deep_diff::DeepDiff` for a struct or enum whose fields all
+/// implement it.
+///
+/// Named fields are reported as key segments and tuple fields as index
+/// segments. An enum variant's fields are reported under the variant's
+/// name, and a change to a different variant is reported as a single
+/// change of the whole value.
+#[proc_macro_derive(DeepDiff)]
+pub fn derive_deep_diff(input: TokenStream) -> TokenStream {
+    let code = match parse_item(input) {
+        Ok(item) => generate(&item),
+        Err(message) => format!("::core::compile_error!({message:?});"),
+    };
+    code.parse().expect("generated code is valid Rust")
+}
+
+// The fields of a struct or enum variant
+enum Fields {
+    Named(Vec<String>),
+    Unnamed(usize),
+    Unit,
+}
+
+enum Item {
+    Struct(String, Fields),
+    Enum(String, Vec<(String, Fields)>),
+}
+
+// Splits a token stream on the commas that are not nested inside `<...>`
+fn split_commas(stream: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut parts = vec![Vec::new()];
+    let mut depth = 0usize;
+    let mut after_dash = false;
+    for token in stream {
+        if let TokenTree::Punct(punct) = &token {
+            match punct.as_char() {
+                ',' if depth == 0 => {
+                    parts.push(Vec::new());
+                    after_dash = false;
+                    continue;
+                }
+                '<' => depth += 1,
+                // `->` in a function pointer type does not close a bracket
+                '>' if !after_dash => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            after_dash = punct.as_char() == '-';
+        } else {
+            after_dash = false;
+        }
+        parts.last_mut().unwrap().push(token);
+    }
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+// Skips leading attributes and a visibility modifier
+fn skip_prefix(tokens: &[TokenTree]) -> &[TokenTree] {
+    let mut rest = tokens;
+    loop {
+        match rest {
+            [TokenTree::Punct(p), TokenTree::Group(g), tail @ ..]
+                if p.as_char() == '#' && g.delimiter() == Delimiter::Bracket =>
+            {
+                rest = tail;
+            }
+            [TokenTree::Ident(i), TokenTree::Group(g), tail @ ..]
+                if i.to_string() == "pub" && g.delimiter() == Delimiter::Parenthesis =>
+            {
+                rest = tail;
+            }
+            [TokenTree::Ident(i), tail @ ..] if i.to_string() == "pub" => rest = tail,
+            _ => return rest,
+        }
+    }
+}
+
+fn parse_fields(token: Option<&TokenTree>) -> Result<Fields, String> {
+    match token {
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => {
+            let names = split_commas(group.stream())
+                .iter()
+                .map(|field| match skip_prefix(field).first() {
+                    Some(TokenTree::Ident(name)) => Ok(name.to_string()),
+                    _ => Err("expected a field name".to_string()),
+                })
+                .collect::<Result<_, _>>()?;
+            Ok(Fields::Named(names))
+        }
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis => {
+            Ok(Fields::Unnamed(split_commas(group.stream()).len()))
+        }
+        _ => Ok(Fields::Unit),
+    }
+}
+
+fn parse_item(input: TokenStream) -> Result<Item, String> {
+    let tokens: Vec<TokenTree> = input.into_iter().collect();
+    let (keyword, name, rest) = match skip_prefix(&tokens) {
+        [TokenTree::Ident(keyword), TokenTree::Ident(name), rest @ ..] => {
+            (keyword.to_string(), name.to_string(), rest)
+        }
+        _ => return Err("expected a struct or enum".to_string()),
+    };
+    if matches!(rest.first(), Some(TokenTree::Punct(p)) if p.as_char() == '<') {
+        return Err("#[derive(DeepDiff)] does not support generic types".to_string());
+    }
+    match keyword.as_str() {
+        "struct" => Ok(Item::Struct(name, parse_fields(rest.first())?)),
+        "enum" => {
+            let Some(TokenTree::Group(body)) = rest.first() else {
+                return Err("expected enum variants".to_string());
+            };
+            let variants = split_commas(body.stream())
+                .iter()
+                .map(|variant| match skip_prefix(variant) {
+                    [TokenTree::Ident(name), rest @ ..] => {
+                        Ok((name.to_string(), parse_fields(rest.first())?))
+                    }
+                    _ => Err("expected a variant name".to_string()),
+                })
+                .collect::<Result<_, String>>()?;
+            Ok(Item::Enum(name, variants))
+        }
+        _ => Err("#[derive(DeepDiff)] only supports structs and enums".to_string()),
+    }
+}
+
+const PRIVATE: &str = "::deep_diff::__private";
+
+// The JSON key of a field, without any raw identifier prefix
+fn key(field: &str) -> &str {
+    field.strip_prefix("r#").unwrap_or(field)
+}
+
+// Builds the JSON form of fields bound to `bindings`
+fn fields_to_json(fields: &Fields, bindings: &[String]) -> String {
+    let json = |binding: &String| format!("::deep_diff::DeepDiff::to_json({binding})");
+    match fields {
+        Fields::Named(names) => {
+            let inserts: String = names
+                .iter()
+                .zip(bindings)
+                .map(|(name, binding)| {
+                    format!(
+                        "map.insert({:?}.to_string(), {});",
+                        key(name),
+                        json(binding)
+                    )
+                })
+                .collect();
+            format!(
+                "{{ let mut map = {PRIVATE}::Map::new(); {inserts} {PRIVATE}::Value::Object(map) }}"
+            )
+        }
+        Fields::Unnamed(1) => json(&bindings[0]),
+        Fields::Unnamed(_) => {
+            let values: Vec<String> = bindings.iter().map(json).collect();
+            format!(
+                "{PRIVATE}::Value::Array({PRIVATE}::vec![{}])",
+                values.join(", ")
+            )
+        }
+        Fields::Unit => format!("{PRIVATE}::Value::Null"),
+    }
+}
+
+// Diffs fields bound to `a` and `b` under the path expression `path`
+fn fields_diff(fields: &Fields, a: &[String], b: &[String], path: &str) -> String {
+    let child_paths: Vec<String> = match fields {
+        Fields::Named(names) => names
+            .iter()
+            .map(|name| format!("{path}.child_key({:?})", key(name)))
+            .collect(),
+        Fields::Unnamed(1) => vec![path.to_string()],
+        Fields::Unnamed(n) => (0..*n)
+            .map(|i| format!("{path}.child_index({i})"))
+            .collect(),
+        Fields::Unit => Vec::new(),
+    };
+    child_paths
+        .iter()
+        .zip(a.iter().zip(b))
+        .map(|(child, (a, b))| {
+            format!("::deep_diff::DeepDiff::diff_at({a}, {b}, &{child}, _differences);")
+        })
+        .collect()
+}
+
+// Names the fields of `fields` in a pattern, binding them to `prefix0`, `prefix1`, ...
+fn pattern(fields: &Fields, prefix: &str) -> (String, Vec<String>) {
+    match fields {
+        Fields::Named(names) => {
+            let bindings: Vec<String> = (0..names.len()).map(|i| format!("{prefix}{i}")).collect();
+            let parts: Vec<String> = names
+                .iter()
+                .zip(&bindings)
+                .map(|(name, binding)| format!("{name}: {binding}"))
+                .collect();
+            (format!(" {{ {} }}", parts.join(", ")), bindings)
+        }
+        Fields::Unnamed(n) => {
+            let bindings: Vec<String> = (0..*n).map(|i| format!("{prefix}{i}")).collect();
+            (format!("({})", bindings.join(", ")), bindings)
+        }
+        Fields::Unit => (String::new(), Vec::new()),
+    }
+}
+
+fn generate(item: &Item) -> String {
+    let (name, to_json, diff_at) = match item {
+        Item::Struct(name, fields) => {
+            let (pattern_a, a) = pattern(fields, "__a");
+            let (pattern_b, b) = pattern(fields, "__b");
+            let to_json = format!("let Self{pattern_a} = self; {}", fields_to_json(fields, &a));
+            let diff_at = format!(
+                "let Self{pattern_a} = self; let Self{pattern_b} = _other; {}",
+                fields_diff(fields, &a, &b, "_path")
+            );
+            (name, to_json, diff_at)
+        }
+        Item::Enum(name, variants) => {
+            let mut to_json_arms = String::new();
+            let mut diff_arms = String::new();
+            for (variant, fields) in variants {
+                let (pattern_a, a) = pattern(fields, "__a");
+                let (pattern_b, b) = pattern(fields, "__b");
+                let json = match fields {
+                    Fields::Unit => format!("{PRIVATE}::Value::String({variant:?}.to_string())"),
+                    _ => format!(
+                        "{PRIVATE}::tagged({variant:?}, {})",
+                        fields_to_json(fields, &a)
+                    ),
+                };
+                to_json_arms += &format!("Self::{variant}{pattern_a} => {json},");
+                let path = format!("_path.child_key({variant:?})");
+                diff_arms += &format!(
+                    "(Self::{variant}{pattern_a}, Self::{variant}{pattern_b}) => {{ {} }}",
+                    fields_diff(fields, &a, &b, &path)
+                );
+            }
+            if variants.len() > 1 {
+                diff_arms += &format!(
+                    "_ => _differences.push({PRIVATE}::changed(_path, \
+                     ::deep_diff::DeepDiff::to_json(self), \
+                     ::deep_diff::DeepDiff::to_json(_other))),"
+                );
+            }
+            if variants.is_empty() {
+                // An empty enum has no values to diff
+                let unreachable = "match *self {}".to_string();
+                (name, unreachable.clone(), unreachable)
+            } else {
+                let to_json = format!("match self {{ {to_json_arms} }}");
+                let diff_at = format!("match (self, _other) {{ {diff_arms} }}");
+                (name, to_json, diff_at)
+            }
+        }
+    };
+    format!(
+        "#[automatically_derived] \
+         impl ::deep_diff::DeepDiff for {name} {{ \
+             fn to_json(&self) -> {PRIVATE}::Value {{ {to_json} }} \
+             fn diff_at(&self, _other: &Self, _path: &::deep_diff::Path, \
+                 _differences: &mut {PRIVATE}::Vec<::deep_diff::Difference>) {{ {diff_at} }} \
+         }}"
+    )
+}
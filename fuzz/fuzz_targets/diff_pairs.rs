@@ -0,0 +1,31 @@
+//! Checks the diff invariants on the first two JSON documents in the input,
+//! e.g. `{"a": [1, 2]} {"a": [2]}`.
+
+#![no_main]
+
+use deep_diff::{ArrayDiffStrategy, DiffOptions, apply_diff, deep_diff_with_options, invert_diff};
+use libfuzzer_sys::fuzz_target;
+use serde_json::{Deserializer, Value};
+
+fuzz_target!(|data: &[u8]| {
+    let mut documents = Deserializer::from_slice(data).into_iter::<Value>();
+    let (Some(Ok(a)), Some(Ok(b))) = (documents.next(), documents.next()) else {
+        return;
+    };
+    let options = [
+        DiffOptions::default(),
+        DiffOptions::builder()
+            .array_strategy(ArrayDiffStrategy::Lcs)
+            .detect_moves(true)
+            .detect_renames(true)
+            .build(),
+    ];
+    for options in &options {
+        assert!(deep_diff_with_options(&a, &a, options).is_empty());
+        let diffs = deep_diff_with_options(&a, &b, options);
+        assert_eq!(apply_diff(&a, &diffs).as_ref(), Ok(&b));
+        let inverted = invert_diff(&diffs);
+        assert_eq!(apply_diff(&b, &inverted).as_ref(), Ok(&a));
+        assert_eq!(invert_diff(&inverted), diffs);
+    }
+});
@@ -0,0 +1,30 @@
+/* Generated with cbindgen from src/ffi.rs. Do not edit by hand. */
+
+#ifndef DEEP_DIFF_H
+#define DEEP_DIFF_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/**
+ * Diffs two NUL-terminated JSON documents and returns the differences as a
+ * NUL-terminated JSON array, serialized as with the `serde` feature.
+ *
+ * Returns null if either argument is null, not valid UTF-8 or not valid
+ * JSON. A non-null result must be released with
+ * `deep_diff_string_free`.
+ */
+char *deep_diff_json_strings(const char *a, const char *b);
+
+/**
+ * Releases a string returned by `deep_diff_json_strings`. Does nothing
+ * if `s` is null.
+ */
+void deep_diff_string_free(char *s);
+
+#ifdef __cplusplus
+} /* extern "C" */
+#endif
+
+#endif /* DEEP_DIFF_H */
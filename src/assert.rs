@@ -0,0 +1,131 @@
+//! Assertions for tests that show a rendered diff when values differ.
+//!
+//! ```
+//! use deep_diff::{assert_json_eq, assert_json_matches};
+//! use serde_json::json;
+//!
+//! let response = json!({"id": 7, "name": "Alice", "created": "2024-01-01"});
+//! assert_json_matches!(response, json!({"name": "Alice"}));
+//! assert_json_eq!(response["id"], json!(7));
+//! ```
+
+use alloc::format;
+use alloc::string::String;
+
+use serde_json::Value;
+
+use crate::render::render_text;
+use crate::{DiffMode, DiffOptions, deep_diff, deep_diff_with_options};
+
+/// Checks that `actual` equals `expected`, returning a description of their
+/// differences if not, with `-` lines for expected values and `+` lines for
+/// actual ones.
+pub fn check_eq(actual: &Value, expected: &Value) -> Result<(), String> {
+    let differences = deep_diff(expected, actual);
+    if differences.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "JSON values are not equal (- expected, + actual):\n{}",
+        render_text(&differences)
+    ))
+}
+
+/// Checks that `actual` contains everything in `expected`, ignoring object
+/// fields that only `actual` has; arrays must still have the same length.
+pub fn check_matches(actual: &Value, expected: &Value) -> Result<(), String> {
+    let options = DiffOptions::builder().mode(DiffMode::SubsetOf).build();
+    let differences = deep_diff_with_options(expected, actual, &options);
+    if differences.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "JSON value does not match (- expected, + actual):\n{}",
+        render_text(&differences)
+    ))
+}
+
+/// Asserts that two JSON values are equal, panicking with a rendered diff
+/// if they are not.
+///
+/// Takes the actual value first, then the expected one, optionally
+/// followed by a format string and arguments describing the assertion.
+#[macro_export]
+macro_rules! assert_json_eq {
+    ($actual:expr, $expected:expr $(,)?) => {
+        if let Err(message) = $crate::assert::check_eq(&$actual, &$expected) {
+            panic!("{}", message);
+        }
+    };
+    ($actual:expr, $expected:expr, $($arg:tt)+) => {
+        if let Err(message) = $crate::assert::check_eq(&$actual, &$expected) {
+            panic!("{}: {}", format_args!($($arg)+), message);
+        }
+    };
+}
+
+/// Asserts that a JSON value contains everything in the expected one,
+/// ignoring extra object fields in the actual value; see
+/// [`check_matches`](crate::assert::check_matches).
+#[macro_export]
+macro_rules! assert_json_matches {
+    ($actual:expr, $expected:expr $(,)?) => {
+        if let Err(message) = $crate::assert::check_matches(&$actual, &$expected) {
+            panic!("{}", message);
+        }
+    };
+    ($actual:expr, $expected:expr, $($arg:tt)+) => {
+        if let Err(message) = $crate::assert::check_matches(&$actual, &$expected) {
+            panic!("{}: {}", format_args!($($arg)+), message);
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Test the message describing unequal values.
+    #[test]
+    fn test_check_eq() {
+        assert_eq!(check_eq(&json!({"a": [1]}), &json!({"a": [1]})), Ok(()));
+        assert_eq!(
+            check_eq(&json!({"a": 2, "b": true}), &json!({"a": 1})).unwrap_err(),
+            "JSON values are not equal (- expected, + actual):\n\
+             - a 1\n\
+             + a 2\n\
+             + b true\n"
+        );
+    }
+
+    /// Test that partial matching ignores extra fields but not other changes.
+    #[test]
+    fn test_check_matches() {
+        let actual = json!({"id": 1, "user": {"name": "Al", "age": 3}, "tags": ["x", "y"]});
+        assert!(check_matches(&actual, &json!({"user": {"name": "Al"}})).is_ok());
+        assert!(check_matches(&actual, &json!({"tags": ["x", "y"]})).is_ok());
+        assert!(check_matches(&actual, &json!({"tags": ["x"]})).is_err());
+        assert!(check_matches(&actual, &json!({"missing": 1})).is_err());
+        assert_eq!(
+            check_matches(&actual, &json!({"user": {"name": "Bo"}})).unwrap_err(),
+            "JSON value does not match (- expected, + actual):\n\
+             - user.name \"Bo\"\n\
+             + user.name \"Al\"\n"
+        );
+    }
+
+    /// Test that the macros pass on matching values.
+    #[test]
+    fn test_assert_macros_pass() {
+        assert_json_eq!(json!({"a": 1}), json!({"a": 1}));
+        assert_json_matches!(json!({"a": 1, "b": 2}), json!({"a": 1}), "case {}", 1);
+    }
+
+    /// Test that the macro panics with the rendered diff and message.
+    #[test]
+    #[should_panic(expected = "checking user: JSON values are not equal")]
+    fn test_assert_json_eq_panics() {
+        assert_json_eq!(json!({"a": 1}), json!({"a": 2}), "checking {}", "user");
+    }
+}
@@ -0,0 +1,241 @@
+//! Audit-log events built from a diff, one per changed field, for services
+//! that must record who changed what and when.
+//!
+//! ```
+//! use deep_diff::audit::Auditor;
+//! use deep_diff::deep_diff;
+//! use serde_json::json;
+//!
+//! let before = json!({"email": "al@example.com", "plan": "free"});
+//! let after = json!({"email": "al@example.org", "plan": "free"});
+//! let events = Auditor::new("admin@example.com", "2024-05-01T12:00:00Z")
+//!     .entity("user-42")
+//!     .events(&deep_diff(&before, &after));
+//! assert_eq!(
+//!     events[0].to_json(),
+//!     json!({
+//!         "actor": "admin@example.com",
+//!         "timestamp": "2024-05-01T12:00:00Z",
+//!         "entity_id": "user-42",
+//!         "field": "email",
+//!         "action": "changed",
+//!         "old": "al@example.com",
+//!         "new": "al@example.org",
+//!     })
+//! );
+//! ```
+
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use serde_json::{Value, json};
+
+use crate::Difference;
+use crate::report::kind_name;
+
+/// One change to one field of an entity, made by an actor at a point in
+/// time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEvent {
+    /// Who made the change, e.g. a user or service name.
+    pub actor: String,
+    /// When the change was made, as given to [`Auditor::new`], e.g. an
+    /// RFC 3339 timestamp.
+    pub timestamp: String,
+    /// The entity changed, e.g. `"user-42"`, or `None` if the auditor was
+    /// not told.
+    pub entity_id: Option<String>,
+    /// The field changed, by default the path of the difference, e.g.
+    /// `"address.city"`.
+    pub field: String,
+    /// The kind of change, named as in
+    /// [`DiffReport::to_json`](crate::DiffReport::to_json), e.g.
+    /// `"changed"` or `"added"`.
+    pub action: &'static str,
+    /// The value before the change, or `None` if there was none.
+    pub old: Option<Value>,
+    /// The value after the change, or `None` if there is none.
+    pub new: Option<Value>,
+}
+
+impl AuditEvent {
+    /// Returns the event as a JSON object with the keys `actor`,
+    /// `timestamp`, `entity_id`, `field`, `action`, `old` and `new`, missing
+    /// values written as `null`.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "actor": self.actor,
+            "timestamp": self.timestamp,
+            "entity_id": self.entity_id,
+            "field": self.field,
+            "action": self.action,
+            "old": self.old,
+            "new": self.new,
+        })
+    }
+}
+
+// Names the entity or field a difference belongs to
+type Naming<T> = Arc<dyn Fn(&Difference) -> T + Send + Sync>;
+
+/// Turns differences into [`AuditEvent`]s for one actor and timestamp,
+/// naming entities and fields with the configured rules.
+#[derive(Clone)]
+pub struct Auditor {
+    actor: String,
+    timestamp: String,
+    entity: Naming<Option<String>>,
+    field: Naming<String>,
+}
+
+impl core::fmt::Debug for Auditor {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Auditor")
+            .field("actor", &self.actor)
+            .field("timestamp", &self.timestamp)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Auditor {
+    /// Creates an auditor recording changes by `actor` at `timestamp`,
+    /// naming each field by the path of its difference, with no entity.
+    pub fn new(actor: impl Into<String>, timestamp: impl Into<String>) -> Self {
+        Auditor {
+            actor: actor.into(),
+            timestamp: timestamp.into(),
+            entity: Arc::new(|_| None),
+            field: Arc::new(|diff| diff.path.clone()),
+        }
+    }
+
+    /// Attributes every event to the entity `id`, e.g. the ID of the
+    /// document diffed.
+    pub fn entity(self, id: impl Into<String>) -> Self {
+        let id = id.into();
+        self.entity_with(move |_| Some(id.clone()))
+    }
+
+    /// Names the entity of each event with `name`, e.g. to tell records
+    /// apart when diffing a collection.
+    pub fn entity_with<F>(mut self, name: F) -> Self
+    where
+        F: Fn(&Difference) -> Option<String> + Send + Sync + 'static,
+    {
+        self.entity = Arc::new(name);
+        self
+    }
+
+    /// Names the field of each event with `name`, e.g. to drop the part of
+    /// the path that identifies the entity.
+    ///
+    /// ```
+    /// use deep_diff::audit::Auditor;
+    /// use deep_diff::{Path, PathSegment, deep_diff};
+    /// use serde_json::json;
+    ///
+    /// // Users are stored as `{"users": {<id>: {...}}}`
+    /// let a = json!({"users": {"u1": {"name": "Al"}}});
+    /// let b = json!({"users": {"u1": {"name": "Bo"}}});
+    /// let auditor = Auditor::new("admin", "2024-05-01T12:00:00Z")
+    ///     .entity_with(|diff| match diff.segments.segments().get(1) {
+    ///         Some(PathSegment::Key(id)) => Some(id.clone()),
+    ///         _ => None,
+    ///     })
+    ///     .field_with(|diff| {
+    ///         let segments = diff.segments.segments();
+    ///         Path::from(segments[segments.len().min(2)..].to_vec()).to_string()
+    ///     });
+    /// let events = auditor.events(&deep_diff(&a, &b));
+    /// assert_eq!(events[0].entity_id.as_deref(), Some("u1"));
+    /// assert_eq!(events[0].field, "name");
+    /// ```
+    pub fn field_with<F>(mut self, name: F) -> Self
+    where
+        F: Fn(&Difference) -> String + Send + Sync + 'static,
+    {
+        self.field = Arc::new(name);
+        self
+    }
+
+    /// Returns one event per difference, in the same order.
+    pub fn events(&self, differences: &[Difference]) -> Vec<AuditEvent> {
+        differences.iter().map(|diff| self.event(diff)).collect()
+    }
+
+    /// Returns the event recording one difference.
+    pub fn event(&self, diff: &Difference) -> AuditEvent {
+        AuditEvent {
+            actor: self.actor.clone(),
+            timestamp: self.timestamp.clone(),
+            entity_id: (self.entity)(diff),
+            field: (self.field)(diff),
+            action: kind_name(&diff.kind),
+            old: diff.before.clone(),
+            new: diff.after.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DiffOptions, deep_diff, deep_diff_with_options};
+
+    /// Test that each difference becomes one event with its values.
+    #[test]
+    fn test_audit_events() {
+        let a = json!({"name": "Al", "phone": "1", "tags": []});
+        let b = json!({"name": "Bo", "email": "bo@example.com", "tags": ["vip"]});
+        let events = Auditor::new("admin", "2024-05-01T12:00:00Z").events(&deep_diff(&a, &b));
+        let summary: Vec<(&str, &str, Option<&Value>, Option<&Value>)> = events
+            .iter()
+            .map(|e| (e.field.as_str(), e.action, e.old.as_ref(), e.new.as_ref()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("name", "changed", Some(&json!("Al")), Some(&json!("Bo"))),
+                ("phone", "removed", Some(&json!("1")), None),
+                ("tags[0]", "added", None, Some(&json!("vip"))),
+                ("email", "added", None, Some(&json!("bo@example.com"))),
+            ]
+        );
+        assert!(
+            events
+                .iter()
+                .all(|e| e.actor == "admin" && e.entity_id.is_none())
+        );
+        assert_eq!(events[1].to_json()["new"], Value::Null);
+        assert_eq!(events[1].to_json()["entity_id"], Value::Null);
+    }
+
+    /// Test that entities and fields are named by the configured rules.
+    #[test]
+    fn test_audit_naming() {
+        let options = DiffOptions::builder().array_key("orders", "id").build();
+        let a = json!({"orders": [{"id": "o1", "total": 10}, {"id": "o2", "total": 5}]});
+        let b = json!({"orders": [{"id": "o2", "total": 6}, {"id": "o1", "total": 10}]});
+        let diffs = deep_diff_with_options(&a, &b, &options);
+        // Records are compared where they stood in `a`, so indices map to it
+        let auditor = Auditor::new("svc", "t")
+            .entity_with(move |diff| match diff.segments.segments().get(1) {
+                Some(crate::PathSegment::Index(i)) => a["orders"][i]
+                    .get("id")
+                    .map(|id| id.as_str().unwrap_or_default().to_string()),
+                _ => None,
+            })
+            .field_with(|diff| match diff.segments.segments().last() {
+                Some(crate::PathSegment::Key(key)) => key.clone(),
+                _ => diff.path.clone(),
+            });
+        let events = auditor.events(&diffs);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].entity_id.as_deref(), Some("o2"));
+        assert_eq!(events[0].field, "total");
+        let fixed = Auditor::new("svc", "t").entity("doc-1").event(&diffs[0]);
+        assert_eq!(fixed.entity_id.as_deref(), Some("doc-1"));
+        assert_eq!(fixed.field, "orders[1].total");
+    }
+}
@@ -0,0 +1,147 @@
+//! Diffing many pairs of documents at once, e.g. the records of two
+//! exports.
+
+use alloc::vec::Vec;
+
+use serde_json::Value;
+
+use crate::Difference;
+use crate::engine::diff_on_thread;
+use crate::options::{DEFAULT_OPTIONS, DiffOptions};
+
+/// Computes the differences of each pair of JSON values, in the order the
+/// pairs are given.
+///
+/// With the `parallel` feature the pairs are spread across threads, each
+/// pair being diffed on a single thread.
+///
+/// ```
+/// use deep_diff::{deep_diff, diff_batch};
+/// use serde_json::json;
+///
+/// let pairs = [
+///     (json!({"id": 1, "name": "a"}), json!({"id": 1, "name": "b"})),
+///     (json!({"id": 2}), json!({"id": 2})),
+/// ];
+/// let results = diff_batch(&pairs);
+/// assert_eq!(results.len(), 2);
+/// assert_eq!(results[0], deep_diff(&pairs[0].0, &pairs[0].1));
+/// assert!(results[1].is_empty());
+/// ```
+pub fn diff_batch(pairs: &[(Value, Value)]) -> Vec<Vec<Difference>> {
+    diff_batch_with_options(pairs, &DEFAULT_OPTIONS)
+}
+
+/// Computes the differences of each pair of JSON values using the given
+/// options, in the order the pairs are given.
+pub fn diff_batch_with_options(
+    pairs: &[(Value, Value)],
+    options: &DiffOptions,
+) -> Vec<Vec<Difference>> {
+    diff_batch_with_callback(pairs, options, |_, _| {})
+}
+
+/// Like [`diff_batch_with_options`], calling `on_done` with the index of
+/// each pair and its differences as soon as the pair is diffed, e.g. to
+/// report progress.
+///
+/// With the `parallel` feature `on_done` is called from the threads doing
+/// the work, so pairs may complete out of order; the returned differences
+/// are always in the order of `pairs`.
+pub fn diff_batch_with_callback<F>(
+    pairs: &[(Value, Value)],
+    options: &DiffOptions,
+    on_done: F,
+) -> Vec<Vec<Difference>>
+where
+    F: Fn(usize, &[Difference]) + Sync,
+{
+    #[cfg(feature = "parallel")]
+    return crate::parallel::batch(pairs, options, &on_done);
+    #[cfg(not(feature = "parallel"))]
+    pairs
+        .iter()
+        .enumerate()
+        .map(|(index, pair)| diff_pair(index, pair, options, &on_done))
+        .collect()
+}
+
+// Diffs one pair of a batch and reports it done
+pub(crate) fn diff_pair<F>(
+    index: usize,
+    (a, b): &(Value, Value),
+    options: &DiffOptions,
+    on_done: &F,
+) -> Vec<Difference>
+where
+    F: Fn(usize, &[Difference]) + Sync,
+{
+    let differences = diff_on_thread(a, b, options);
+    on_done(index, &differences);
+    differences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deep_diff_with_options;
+    use alloc::vec;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use serde_json::json;
+
+    // Many pairs of varying size, some equal
+    fn pairs() -> Vec<(Value, Value)> {
+        (0..300)
+            .map(|i| {
+                let a = json!({"id": i, "items": (0..i % 7).collect::<Vec<_>>()});
+                let b = match i % 3 {
+                    0 => a.clone(),
+                    1 => json!({"id": i, "items": (0..i % 5).collect::<Vec<_>>()}),
+                    _ => json!({"id": i + 1, "name": "record"}),
+                };
+                (a, b)
+            })
+            .collect()
+    }
+
+    /// Test that a batch gives the differences of each pair, in order.
+    #[test]
+    fn test_diff_batch() {
+        let pairs = pairs();
+        let options = DiffOptions::builder().ignore("id").context(1).build();
+        let results = diff_batch_with_options(&pairs, &options);
+        assert_eq!(results.len(), pairs.len());
+        for ((a, b), differences) in pairs.iter().zip(&results) {
+            assert_eq!(differences, &deep_diff_with_options(a, b, &options));
+        }
+        assert!(results[0].is_empty());
+        assert_eq!(diff_batch(&[]), Vec::<Vec<Difference>>::new());
+        let limited = DiffOptions::builder().max_differences(1).build();
+        let results = diff_batch_with_options(&[(json!([1, 2]), json!([3, 4]))], &limited);
+        assert_eq!(
+            results,
+            vec![deep_diff_with_options(
+                &json!([1, 2]),
+                &json!([3, 4]),
+                &limited
+            )]
+        );
+    }
+
+    /// Test that the callback sees every pair exactly once.
+    #[test]
+    fn test_diff_batch_callback() {
+        let pairs = pairs();
+        let calls: Vec<AtomicUsize> = pairs.iter().map(|_| AtomicUsize::new(0)).collect();
+        let reported = AtomicUsize::new(0);
+        let results = diff_batch_with_callback(&pairs, &DiffOptions::default(), |index, diffs| {
+            calls[index].fetch_add(1, Ordering::Relaxed);
+            reported.fetch_add(diffs.len(), Ordering::Relaxed);
+        });
+        assert!(calls.iter().all(|count| count.load(Ordering::Relaxed) == 1));
+        assert_eq!(
+            reported.load(Ordering::Relaxed),
+            results.iter().map(Vec::len).sum::<usize>()
+        );
+    }
+}
@@ -0,0 +1,376 @@
+//! Command line tool diffing two JSON files.
+
+use std::io::Read;
+use std::process::ExitCode;
+
+use deep_diff::render::render_text;
+use deep_diff::{
+    ArrayDiffStrategy, DiffKind, DiffOptions, Difference, Path, PathSegment, Severity, Withheld,
+    apply_diff, deep_diff_with_options, merge_patch, to_json_patch,
+};
+use serde_json::Value;
+
+const USAGE: &str = "\
+Usage: deep-diff [OPTIONS] <A> <B>
+
+Compares two JSON files and prints their differences. Either file may be
+`-` to read it from standard input.
+Exits with 0 if they are equal, 1 if they differ and 2 on errors.
+
+Options:
+  -f, --format <FORMAT>    Output format: text, patch, merge-patch or json [default: text]
+  -i, --ignore <PATTERN>   Ignores paths matching PATTERN, e.g. `**.updated_at`; repeatable
+  -a, --array-strategy <STRATEGY>
+                           How arrays are compared: index, lcs or set [default: index]
+      --ndjson             Compares JSON Lines files line by line, prefixing each
+                           path with its line number; supports text and json output
+  -h, --help               Prints this help
+";
+
+// How the differences are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Patch,
+    MergePatch,
+    Json,
+}
+
+// Parsed command line arguments
+#[derive(Debug)]
+struct Args {
+    format: Format,
+    ignore: Vec<String>,
+    array_strategy: ArrayDiffStrategy,
+    ndjson: bool,
+    files: Vec<String>,
+}
+
+// Parses the arguments after the program name; `Ok(None)` asks for help
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut parsed = Args {
+        format: Format::Text,
+        ignore: Vec::new(),
+        array_strategy: ArrayDiffStrategy::Index,
+        ndjson: false,
+        files: Vec::new(),
+    };
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        // Accepts both `--flag value` and `--flag=value`
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => {
+                (flag.to_string(), Some(value.to_string()))
+            }
+            _ => (arg.clone(), None),
+        };
+        let mut value = |name: &str| {
+            inline
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("missing value for {}", name))
+        };
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-f" | "--format" => {
+                parsed.format = match value("--format")?.as_str() {
+                    "text" => Format::Text,
+                    "patch" => Format::Patch,
+                    "merge-patch" => Format::MergePatch,
+                    "json" => Format::Json,
+                    other => return Err(format!("unknown format '{}'", other)),
+                }
+            }
+            "-i" | "--ignore" => {
+                let pattern = value("--ignore")?;
+                // A pattern that does not parse would otherwise match nothing
+                DiffOptions::builder()
+                    .ignore(&pattern)
+                    .try_build()
+                    .map_err(|e| e.to_string())?;
+                parsed.ignore.push(pattern);
+            }
+            "--ndjson" => parsed.ndjson = true,
+            "-a" | "--array-strategy" => {
+                parsed.array_strategy = match value("--array-strategy")?.as_str() {
+                    "index" => ArrayDiffStrategy::Index,
+                    "lcs" => ArrayDiffStrategy::Lcs,
+                    "set" => ArrayDiffStrategy::UnorderedSet,
+                    other => return Err(format!("unknown array strategy '{}'", other)),
+                }
+            }
+            _ if flag.starts_with('-') && flag != "-" => {
+                return Err(format!("unknown option '{}'", flag));
+            }
+            _ => parsed.files.push(arg),
+        }
+    }
+    if parsed.files.len() != 2 {
+        return Err("expected exactly two files to compare".to_string());
+    }
+    if parsed.files.iter().all(|file| file == "-") {
+        return Err("only one file can be read from standard input".to_string());
+    }
+    if parsed.ndjson && matches!(parsed.format, Format::Patch | Format::MergePatch) {
+        return Err("--ndjson only supports the text and json formats".to_string());
+    }
+    Ok(Some(parsed))
+}
+
+// Reads a whole file, or standard input for `-`
+fn read_input(path: &str) -> Result<String, String> {
+    let mut text = String::new();
+    let read = if path == "-" {
+        std::io::stdin().read_to_string(&mut text).map(|_| ())
+    } else {
+        std::fs::read_to_string(path).map(|contents| text = contents)
+    };
+    read.map_err(|e| format!("{}: {}", path, e))?;
+    Ok(text)
+}
+
+fn parse_json(path: &str, text: &str) -> Result<Value, String> {
+    serde_json::from_str(text).map_err(|e| format!("{}: invalid JSON: {}", path, e))
+}
+
+// Parses a JSON Lines file; blank lines are kept as `None` so line numbers stay accurate
+fn parse_ndjson(path: &str, text: &str) -> Result<Vec<Option<Value>>, String> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| {
+            if line.trim().is_empty() {
+                return Ok(None);
+            }
+            serde_json::from_str(line)
+                .map(Some)
+                .map_err(|e| format!("{}:{}: invalid JSON: {}", path, i + 1, e))
+        })
+        .collect()
+}
+
+// Diffs corresponding lines, prefixing each path with the 1-based line number
+fn diff_lines(a: &[Option<Value>], b: &[Option<Value>], options: &DiffOptions) -> Vec<Difference> {
+    let mut differences = Vec::new();
+    for line in 0..a.len().max(b.len()) {
+        let prefix = |diff: Difference| {
+            let mut segments = vec![PathSegment::Index(line + 1)];
+            segments.extend(diff.segments.segments().iter().cloned());
+            let segments = Path::from(segments);
+            Difference {
+                path: segments.to_string(),
+                segments,
+                ..diff
+            }
+        };
+        let (a_line, b_line) = (
+            a.get(line).cloned().flatten(),
+            b.get(line).cloned().flatten(),
+        );
+        let line_diffs = match (&a_line, &b_line) {
+            (Some(a_value), Some(b_value)) => deep_diff_with_options(a_value, b_value, options),
+            (None, None) => Vec::new(),
+            (before, _) => {
+                let kind = if before.is_some() {
+                    DiffKind::Removed
+                } else {
+                    DiffKind::Added
+                };
+                vec![options.finish_difference(Difference {
+                    path: String::new(),
+                    segments: Path::root(),
+                    kind,
+                    severity: Severity::Info,
+                    before: a_line.clone(),
+                    after: b_line.clone(),
+                    context: None,
+                    withheld: Withheld::default(),
+                })]
+            }
+        };
+        differences.extend(line_diffs.into_iter().map(prefix));
+    }
+    differences
+}
+
+// Diffs the two files, returning the output and whether they differ
+fn run(args: &Args) -> Result<(String, bool), String> {
+    let (a_path, b_path) = (&args.files[0], &args.files[1]);
+    let (a_text, b_text) = (read_input(a_path)?, read_input(b_path)?);
+    let options = args
+        .ignore
+        .iter()
+        .fold(DiffOptions::builder(), |builder, pattern| {
+            builder.ignore(pattern)
+        })
+        .array_strategy(args.array_strategy)
+        .try_build()
+        .map_err(|e| e.to_string())?;
+    if args.ndjson {
+        let a = parse_ndjson(a_path, &a_text)?;
+        let b = parse_ndjson(b_path, &b_text)?;
+        let differences = diff_lines(&a, &b, &options);
+        let output = match args.format {
+            Format::Json => pretty(&serde_json::to_value(&differences).unwrap()),
+            _ => render_text(&differences),
+        };
+        return Ok((output, !differences.is_empty()));
+    }
+    let a = parse_json(a_path, &a_text)?;
+    let b = parse_json(b_path, &b_text)?;
+    let differences = deep_diff_with_options(&a, &b, &options);
+    let output = match args.format {
+        Format::Text => render_text(&differences),
+        Format::Patch => pretty(&to_json_patch(&differences)),
+        // Built from the differences found, so ignored paths are left out
+        Format::MergePatch => {
+            let patched = apply_diff(&a, &differences).map_err(|e| e.to_string())?;
+            pretty(&merge_patch(&a, &patched))
+        }
+        Format::Json => pretty(&serde_json::to_value(&differences).unwrap()),
+    };
+    Ok((output, !differences.is_empty()))
+}
+
+fn pretty(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap() + "\n"
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            print!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(message) => {
+            eprintln!("deep-diff: {}\n\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match run(&args) {
+        Ok((output, differ)) => {
+            print!("{}", output);
+            ExitCode::from(u8::from(differ))
+        }
+        Err(message) => {
+            eprintln!("deep-diff: {}", message);
+            ExitCode::from(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<Args>, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    /// Test that flags and files are parsed in any order.
+    #[test]
+    fn test_parse_args() {
+        let args = parse(&[
+            "a.json",
+            "-f",
+            "patch",
+            "--ignore=x.y",
+            "b.json",
+            "-i",
+            "**.t",
+        ])
+        .unwrap()
+        .unwrap();
+        assert_eq!(args.format, Format::Patch);
+        assert_eq!(args.ignore, ["x.y", "**.t"]);
+        assert_eq!(args.files, ["a.json", "b.json"]);
+        let args = parse(&["--array-strategy", "lcs", "a", "b"])
+            .unwrap()
+            .unwrap();
+        assert_eq!(args.array_strategy, ArrayDiffStrategy::Lcs);
+    }
+
+    /// Test that invalid arguments are rejected and help is recognised.
+    #[test]
+    fn test_parse_args_errors() {
+        assert!(parse(&["--help"]).unwrap().is_none());
+        assert!(parse(&["a"]).is_err());
+        assert!(parse(&["a", "b", "--format", "xml"]).is_err());
+        assert!(parse(&["a", "b", "--format"]).is_err());
+        assert!(parse(&["a", "b", "--verbose"]).is_err());
+        assert!(parse(&["-", "-"]).is_err());
+        assert!(parse(&["--ndjson", "-f", "patch", "a", "b"]).is_err());
+        assert_eq!(
+            parse(&["a", "b", "--ignore", "items[x"]).unwrap_err(),
+            "invalid path pattern 'items[x'"
+        );
+        let args = parse(&["-", "b", "--ndjson"]).unwrap().unwrap();
+        assert!(args.ndjson);
+        assert_eq!(args.files, ["-", "b"]);
+    }
+
+    /// Test each output format on files written to a temporary directory.
+    #[test]
+    fn test_run_formats() {
+        let dir = std::env::temp_dir().join(format!("deep-diff-cli-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (a, b) = (dir.join("a.json"), dir.join("b.json"));
+        std::fs::write(&a, r#"{"name": "Alice", "t": 1}"#).unwrap();
+        std::fs::write(&b, r#"{"name": "Bob", "t": 2}"#).unwrap();
+        let files = vec![a.display().to_string(), b.display().to_string()];
+        let args = |format| Args {
+            format,
+            ignore: vec!["t".to_string()],
+            array_strategy: ArrayDiffStrategy::Index,
+            ndjson: false,
+            files: files.clone(),
+        };
+        let (text, differ) = run(&args(Format::Text)).unwrap();
+        assert!(differ);
+        assert_eq!(text, "- name \"Alice\"\n+ name \"Bob\"\n");
+        let (patch, _) = run(&args(Format::Patch)).unwrap();
+        assert!(patch.contains("\"op\": \"replace\""));
+        let (merge, _) = run(&args(Format::MergePatch)).unwrap();
+        assert_eq!(merge, "{\n  \"name\": \"Bob\"\n}\n");
+        let (json, _) = run(&args(Format::Json)).unwrap();
+        assert!(json.contains("\"kind\": \"changed\""));
+        let missing = Args {
+            files: vec!["missing.json".to_string(), files[1].clone()],
+            ..args(Format::Text)
+        };
+        assert!(run(&missing).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Test that JSON Lines are diffed line by line.
+    #[test]
+    fn test_diff_lines() {
+        let a = parse_ndjson("a", "{\"id\": 1}\n\n{\"id\": 3}\n{\"id\": 4}\n").unwrap();
+        let b = parse_ndjson("b", "{\"id\": 1}\n{\"id\": 2}\n{\"id\": 30}\n").unwrap();
+        let differences = diff_lines(&a, &b, &DiffOptions::default());
+        assert_eq!(
+            render_text(&differences),
+            "+ [2]    {\"id\":2}\n\
+             - [3].id 3\n\
+             + [3].id 30\n\
+             - [4]    {\"id\":4}\n"
+        );
+        assert_eq!(
+            parse_ndjson("f", "{}\n{oops\n").unwrap_err(),
+            "f:2: invalid JSON: key must be a string at line 1 column 2"
+        );
+    }
+
+    /// Test that lines found on one side only are redacted like the rest.
+    #[test]
+    fn test_diff_lines_redact() {
+        let a = parse_ndjson("a", "{\"id\": 1}\n").unwrap();
+        let b = parse_ndjson("b", "{\"id\": 1}\n{\"token\": \"abcdef\"}\n").unwrap();
+        let options = DiffOptions::builder().redact(["token"]).build();
+        let differences = diff_lines(&a, &b, &options);
+        assert_eq!(
+            differences[0].after,
+            Some(serde_json::json!({"token": deep_diff::REDACTED}))
+        );
+    }
+}
@@ -0,0 +1,101 @@
+//! Comparison of base64-encoded binary fields by their decoded bytes.
+
+use alloc::vec::Vec;
+
+use crate::DiffKind;
+
+/// Compares two base64 strings by the bytes they encode.
+///
+/// Returns `None` if either string is not valid base64, `Some(None)` if
+/// both encode the same bytes, and otherwise the
+/// [`DiffKind::BinaryChanged`] describing both payloads.
+pub(crate) fn compare(a: &str, b: &str) -> Option<Option<DiffKind>> {
+    let (old, new) = (decode(a)?, decode(b)?);
+    if old == new {
+        return Some(None);
+    }
+    Some(Some(DiffKind::BinaryChanged {
+        old_len: old.len(),
+        new_len: new.len(),
+        old_hash: fnv1a(&old),
+        new_hash: fnv1a(&new),
+    }))
+}
+
+// Decodes standard or URL-safe base64, with or without padding
+pub(crate) fn decode(text: &str) -> Option<Vec<u8>> {
+    let text = text.as_bytes();
+    let unpadded = text
+        .strip_suffix(b"==")
+        .or(text.strip_suffix(b"="))
+        .unwrap_or(text);
+    if unpadded.len() % 4 == 1 || (unpadded.len() != text.len() && !text.len().is_multiple_of(4)) {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(unpadded.len() * 3 / 4);
+    for chunk in unpadded.chunks(4) {
+        let mut group = 0u32;
+        for &c in chunk {
+            group = (group << 6) | sextet(c)?;
+        }
+        group <<= 6 * (4 - chunk.len() as u32);
+        let decoded = group.to_be_bytes();
+        bytes.extend_from_slice(&decoded[1..chunk.len()]);
+    }
+    Some(bytes)
+}
+
+fn sextet(c: u8) -> Option<u32> {
+    let value = match c {
+        b'A'..=b'Z' => c - b'A',
+        b'a'..=b'z' => c - b'a' + 26,
+        b'0'..=b'9' => c - b'0' + 52,
+        b'+' | b'-' => 62,
+        b'/' | b'_' => 63,
+        _ => return None,
+    };
+    Some(u32::from(value))
+}
+
+// The 64-bit FNV-1a hash, which is stable across platforms and releases
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test decoding with and without padding, in both alphabets.
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode("aGVsbG8="), Some(b"hello".to_vec()));
+        assert_eq!(decode("aGVsbG8"), Some(b"hello".to_vec()));
+        assert_eq!(decode("aGk="), Some(b"hi".to_vec()));
+        assert_eq!(decode(""), Some(Vec::new()));
+        assert_eq!(decode("-_-_"), decode("+/+/"));
+        assert_eq!(decode("aGVsbG8=="), None);
+        assert_eq!(decode("a"), None);
+        assert_eq!(decode("not base64!"), None);
+    }
+
+    /// Test that payloads are compared by their decoded bytes.
+    #[test]
+    fn test_compare() {
+        assert_eq!(compare("aGk=", "aGk"), Some(None));
+        assert_eq!(compare("aGk=", "no way!"), None);
+        assert_eq!(
+            compare("aGk=", "aGVsbG8="),
+            Some(Some(DiffKind::BinaryChanged {
+                old_len: 2,
+                new_len: 5,
+                old_hash: fnv1a(b"hi"),
+                new_hash: fnv1a(b"hello"),
+            }))
+        );
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
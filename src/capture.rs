@@ -0,0 +1,248 @@
+//! Summaries standing in for values too large to keep in a difference.
+
+use alloc::borrow::Cow;
+use alloc::string::{String, ToString};
+
+use serde_json::{Value, json};
+
+use crate::render::truncate;
+use crate::structural_hash;
+
+// The key of the object a summary is stored as
+const MARKER: &str = "$truncated";
+
+// The most characters of a value kept in its preview
+const PREVIEW_LEN: usize = 64;
+
+/// A summary of a value longer than
+/// [`max_captured_value_bytes`](crate::DiffOptionsBuilder::max_captured_value_bytes),
+/// kept in a difference's `before` or `after` in place of the value.
+///
+/// It is stored as an object with a single `$truncated` key, so it fits
+/// where a value goes; [`Truncated::from_value`] reads it back. Whether a
+/// value was cut is recorded in [`Difference::withheld`](crate::Difference::withheld),
+/// since real data may have the same shape.
+///
+/// ```
+/// use deep_diff::{DiffOptions, Truncated, deep_diff_with_options};
+/// use serde_json::json;
+///
+/// let options = DiffOptions::builder().max_captured_value_bytes(16).build();
+/// let a = json!({"blob": "x".repeat(1000)});
+/// let b = json!({"blob": "y".repeat(1000)});
+/// let diffs = deep_diff_with_options(&a, &b, &options);
+/// let before = Truncated::from_value(diffs[0].before.as_ref().unwrap()).unwrap();
+/// assert_eq!(before.bytes, 1002);
+/// assert_eq!(before.preview, "\"xxxxxxxxxxxxxx…");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Truncated {
+    /// The length of the value serialized as compact JSON.
+    pub bytes: usize,
+    /// The [`structural_hash`] of the value, to tell whether two cut values
+    /// are the same.
+    pub hash: u64,
+    /// The start of the value as compact JSON, ending with `…`.
+    pub preview: String,
+}
+
+impl Truncated {
+    /// Summarizes `value`, keeping at most `preview_len` characters of it
+    /// as a preview.
+    pub fn new(value: &Value, preview_len: usize) -> Truncated {
+        let text = value.to_string();
+        Truncated {
+            bytes: text.len(),
+            hash: structural_hash(value),
+            preview: truncate(text, preview_len),
+        }
+    }
+
+    /// Reads a summary back from the value stored in its place, or returns
+    /// `None` if `value` is not one.
+    pub fn from_value(value: &Value) -> Option<Truncated> {
+        let summary = value
+            .as_object()
+            .filter(|map| map.len() == 1)?
+            .get(MARKER)?;
+        Some(Truncated {
+            bytes: usize::try_from(summary.get("bytes")?.as_u64()?).ok()?,
+            hash: summary.get("hash")?.as_u64()?,
+            preview: summary.get("preview")?.as_str()?.to_string(),
+        })
+    }
+
+    /// The value the summary is stored as, e.g.
+    /// `{"$truncated": {"bytes": 1002, "hash": 1234, "preview": "\"xx…"}}`.
+    pub fn to_value(&self) -> Value {
+        json!({
+            MARKER: {
+                "bytes": self.bytes,
+                "hash": self.hash,
+                "preview": self.preview,
+            }
+        })
+    }
+}
+
+/// Replaces `value` with its summary if it is longer than `limit` bytes as
+/// compact JSON. Returns whether it was replaced.
+pub(crate) fn capture(value: &mut Cow<'_, Value>, limit: usize) -> bool {
+    if serialized_len(value, limit).is_some() {
+        return false;
+    }
+    *value = Cow::Owned(Truncated::new(value, PREVIEW_LEN.min(limit)).to_value());
+    true
+}
+
+// The length of `value` serialized as compact JSON, or `None` as soon as it
+// is known to be longer than `limit`
+fn serialized_len(value: &Value, limit: usize) -> Option<usize> {
+    let len = match value {
+        Value::Null | Value::Bool(true) => 4,
+        Value::Bool(false) => 5,
+        Value::Number(number) => number.to_string().len(),
+        Value::String(text) => string_len(text, limit)?,
+        Value::Array(values) => {
+            let mut len = 2 + values.len().saturating_sub(1);
+            for value in values {
+                len += serialized_len(value, limit.checked_sub(len)?)?;
+            }
+            len
+        }
+        Value::Object(map) => {
+            let mut len = 2 + (2 * map.len()).saturating_sub(1);
+            for (key, value) in map {
+                len += string_len(key, limit.checked_sub(len)?)?;
+                len += serialized_len(value, limit.checked_sub(len)?)?;
+            }
+            len
+        }
+    };
+    (len <= limit).then_some(len)
+}
+
+// The length of a string serialized with its quotes and escapes, or `None`
+// if it is longer than `limit`
+fn string_len(text: &str, limit: usize) -> Option<usize> {
+    // Escapes only lengthen the string
+    if text.len() + 2 > limit {
+        return None;
+    }
+    let len = 2 + text
+        .chars()
+        .map(|c| match c {
+            '"' | '\\' | '\n' | '\r' | '\t' | '\u{8}' | '\u{c}' => 2,
+            c if u32::from(c) < 0x20 => 6,
+            c => c.len_utf8(),
+        })
+        .sum::<usize>();
+    (len <= limit).then_some(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ApplyError, DiffOptions, apply_diff, deep_diff_ref_with_options, deep_diff_with_options,
+    };
+
+    /// Test that lengths are measured exactly as serialized.
+    #[test]
+    fn test_serialized_len() {
+        let values = [
+            json!(null),
+            json!(false),
+            json!(-12.5),
+            json!("quote \" slash \\ tab \t bell \u{7} é"),
+            json!([]),
+            json!({}),
+            json!([1, "two", {"three": [null, true]}]),
+            json!({"a\nb": {"c": []}, "d": 1}),
+        ];
+        for value in &values {
+            let len = value.to_string().len();
+            assert_eq!(serialized_len(value, len), Some(len), "{}", value);
+            assert_eq!(serialized_len(value, len - 1), None, "{}", value);
+        }
+    }
+
+    /// Test that summaries round trip through the value they are stored as.
+    #[test]
+    fn test_truncated_value() {
+        let value = json!({"data": "x".repeat(100)});
+        let summary = Truncated::new(&value, 10);
+        assert_eq!(summary.bytes, 111);
+        assert_eq!(summary.hash, structural_hash(&value));
+        assert_eq!(summary.preview, "{\"data\":\"…");
+        assert_eq!(Truncated::from_value(&summary.to_value()), Some(summary));
+        assert_eq!(Truncated::from_value(&value), None);
+        assert_eq!(Truncated::from_value(&json!({"$truncated": 1})), None);
+    }
+
+    /// Test that only values over the limit are replaced.
+    #[test]
+    fn test_max_captured_value_bytes() {
+        let options = DiffOptions::builder().max_captured_value_bytes(10).build();
+        let a = json!({"small": 1, "big": [1, 2, 3, 4, 5, 6], "gone": "a long string"});
+        let b = json!({"small": 2, "big": "short"});
+        let diffs = deep_diff_with_options(&a, &b, &options);
+        assert_eq!(diffs.len(), 3);
+        let big = diffs.iter().find(|diff| diff.path == "big").unwrap();
+        let before = Truncated::from_value(big.before.as_ref().unwrap()).unwrap();
+        assert_eq!((before.bytes, before.preview.as_str()), (13, "[1,2,3,4,…"));
+        assert_eq!(big.after, Some(json!("short")));
+        assert_eq!(
+            big.withheld,
+            crate::Withheld {
+                before: true,
+                after: false
+            }
+        );
+        let gone = diffs.iter().find(|diff| diff.path == "gone").unwrap();
+        assert!(Truncated::from_value(gone.before.as_ref().unwrap()).is_some());
+        let small = diffs.iter().find(|diff| diff.path == "small").unwrap();
+        assert_eq!(
+            (&small.before, &small.after),
+            (&Some(json!(1)), &Some(json!(2)))
+        );
+        assert!(small.withheld.is_none());
+        let borrowed = deep_diff_ref_with_options(&a, &b, &options);
+        assert!(
+            borrowed
+                .iter()
+                .all(|diff| diff.before.as_deref() != Some(&a["big"]))
+        );
+    }
+
+    /// Test that cut values are still compared in full and cannot be
+    /// applied.
+    #[test]
+    fn test_max_captured_value_bytes_apply() {
+        let options = DiffOptions::builder().max_captured_value_bytes(8).build();
+        let a = json!({"blob": "x".repeat(50)});
+        let b = json!({"blob": format!("{}y", "x".repeat(49))});
+        let diffs = deep_diff_with_options(&a, &b, &options);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(
+            apply_diff(&a, &diffs),
+            Err(ApplyError::ValueNotCaptured("blob".to_string()))
+        );
+        let removed = deep_diff_with_options(&a, &json!({}), &options);
+        assert_eq!(apply_diff(&a, &removed), Ok(json!({})));
+    }
+
+    /// Test that real data shaped like a summary is applied like any other.
+    #[test]
+    fn test_real_truncated_shape() {
+        let a = json!({"blob": 1});
+        let b = json!({"blob": Truncated::new(&json!("x".repeat(50)), 8).to_value()});
+        let diffs = crate::deep_diff(&a, &b);
+        assert!(diffs[0].withheld.is_none());
+        assert_eq!(apply_diff(&a, &diffs), Ok(b.clone()));
+        assert_eq!(
+            crate::to_json_patch(&diffs),
+            json!([{"op": "replace", "path": "/blob", "value": b["blob"]}])
+        );
+    }
+}
@@ -0,0 +1,211 @@
+//! Plain-English sentences describing a diff, for release notes and
+//! notifications.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use serde_json::Value;
+
+use crate::minimize::path_format;
+use crate::render::truncate;
+use crate::{DiffKind, Difference, Path, PathSegment};
+
+// The longest value shown in a sentence before it is cut short
+const VALUE_MAX_LEN: usize = 40;
+
+/// Describes the differences in sentences, one per change, e.g.
+/// ``"`price` changed from 10 to 12"``.
+///
+/// Changes to the elements of an array are counted in a single sentence per
+/// array and kind of change, e.g. ``"3 items added to `tags`"``, placed
+/// where the first of them was found. Values longer than 40 characters are
+/// cut short with `…`.
+///
+/// ```
+/// use deep_diff::{deep_diff, summarize};
+/// use serde_json::json;
+///
+/// let a = json!({"price": 10, "tags": ["new"], "sku": "A1"});
+/// let b = json!({"price": 12, "tags": ["new", "sale", "eco", "vip"], "stock": 3});
+/// assert_eq!(
+///     summarize(&deep_diff(&a, &b)),
+///     [
+///         "`price` changed from 10 to 12",
+///         "`sku` removed, was \"A1\"",
+///         "3 items added to `tags`",
+///         "`stock` added as 3",
+///     ]
+/// );
+/// ```
+pub fn summarize(differences: &[Difference]) -> Vec<String> {
+    let mut sentences: Vec<Sentence> = Vec::new();
+    // The sentence counting each array's elements, by array and change
+    let mut groups: BTreeMap<(Path, &'static str), usize> = BTreeMap::new();
+    for diff in differences {
+        let segments = diff.segments.segments();
+        let change = match diff.kind {
+            DiffKind::Added => "added to",
+            DiffKind::Removed => "removed from",
+            DiffKind::Changed | DiffKind::TypeChanged { .. } => "changed in",
+            DiffKind::Moved { .. } => "moved in",
+            _ => "",
+        };
+        match segments.split_last() {
+            Some((PathSegment::Index(_), parent)) if !change.is_empty() => {
+                let parent = Path::from(parent.to_vec());
+                match groups.get(&(parent.clone(), change)) {
+                    Some(&at) => {
+                        if let Sentence::Items { count, .. } = &mut sentences[at] {
+                            *count += 1;
+                        }
+                    }
+                    None => {
+                        let array = name(&parent.render(path_format(diff)));
+                        groups.insert((parent, change), sentences.len());
+                        sentences.push(Sentence::Items {
+                            count: 1,
+                            change,
+                            array,
+                        });
+                    }
+                }
+            }
+            _ => sentences.push(Sentence::One(describe(diff))),
+        }
+    }
+    sentences
+        .into_iter()
+        .map(|sentence| match sentence {
+            Sentence::One(text) => text,
+            Sentence::Items {
+                count,
+                change,
+                array,
+            } => format!("{} {} {}", plural(count, "item"), change, array),
+        })
+        .collect()
+}
+
+// A sentence about one difference, or counting the elements of an array
+enum Sentence {
+    One(String),
+    Items {
+        count: usize,
+        change: &'static str,
+        array: String,
+    },
+}
+
+// The sentence describing a single difference
+fn describe(diff: &Difference) -> String {
+    let path = name(&diff.path);
+    match &diff.kind {
+        DiffKind::Added => format!("{} added as {}", path, value(&diff.after)),
+        DiffKind::Removed => format!("{} removed, was {}", path, value(&diff.before)),
+        DiffKind::Changed | DiffKind::TypeChanged { .. } => format!(
+            "{} changed from {} to {}",
+            path,
+            value(&diff.before),
+            value(&diff.after)
+        ),
+        DiffKind::Moved { from, to } => format!("{} moved from {} to {}", path, from, to),
+        DiffKind::BinaryChanged {
+            old_len, new_len, ..
+        } => format!(
+            "{} changed from {} to {}",
+            path,
+            plural(*old_len, "byte"),
+            plural(*new_len, "byte")
+        ),
+        DiffKind::KeyOrderChanged => format!("keys of {} reordered", path),
+        DiffKind::Renamed { from_key, to_key } => {
+            let renamed = format!("`{}` renamed to `{}`", from_key, to_key);
+            match diff.segments.parent().filter(|parent| !parent.is_empty()) {
+                Some(parent) => {
+                    let parent = name(&parent.render(path_format(diff)));
+                    format!("{} in {}", renamed, parent)
+                }
+                None => renamed,
+            }
+        }
+    }
+}
+
+// A path as it appears in a sentence
+fn name(path: &str) -> String {
+    if path.is_empty() {
+        "the document".to_string()
+    } else {
+        format!("`{}`", path)
+    }
+}
+
+// A value as it appears in a sentence
+fn value(value: &Option<Value>) -> String {
+    match value {
+        Some(value) => truncate(value.to_string(), VALUE_MAX_LEN),
+        None => "nothing".to_string(),
+    }
+}
+
+// A count of things, e.g. `1 item` or `3 items`
+fn plural(count: usize, noun: &str) -> String {
+    match count {
+        1 => format!("1 {}", noun),
+        _ => format!("{} {}s", count, noun),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ArrayDiffStrategy, DiffOptions, PathFormat, deep_diff, deep_diff_with_options};
+    use serde_json::json;
+
+    /// Test the sentence for each kind of change to a field.
+    #[test]
+    fn test_summarize_fields() {
+        let a = json!({"name": "Al", "age": 30, "bio": "x".repeat(50), "user": {"nick": "al"}});
+        let b = json!({"name": "Bo", "age": "30", "user": {"handle": "al"}, "bio": "y"});
+        let options = DiffOptions::builder().detect_renames(true).build();
+        assert_eq!(
+            summarize(&deep_diff_with_options(&a, &b, &options)),
+            [
+                "`age` changed from 30 to \"30\"",
+                format!("`bio` changed from \"{}… to \"y\"", "x".repeat(38)).as_str(),
+                "`name` changed from \"Al\" to \"Bo\"",
+                "`nick` renamed to `handle` in `user`",
+            ]
+        );
+        assert_eq!(
+            summarize(&deep_diff(&json!(1), &json!(2))),
+            ["the document changed from 1 to 2"]
+        );
+        assert!(summarize(&[]).is_empty());
+    }
+
+    /// Test that changes to array elements are counted per array.
+    #[test]
+    fn test_summarize_arrays() {
+        let a = json!({"tags": ["a", "b", "c"], "items": [{"n": 1}], "ids": [1, 2]});
+        let b = json!({"tags": ["a"], "items": [{"n": 2}, {"n": 3}], "ids": [3, 2]});
+        assert_eq!(
+            summarize(&deep_diff(&a, &b)),
+            [
+                "1 item changed in `ids`",
+                "`items[0].n` changed from 1 to 2",
+                "1 item added to `items`",
+                "2 items removed from `tags`",
+            ]
+        );
+        let options = DiffOptions::builder()
+            .array_strategy(ArrayDiffStrategy::Lcs)
+            .detect_moves(true)
+            .path_format(PathFormat::JsonPointer)
+            .build();
+        let diffs = deep_diff_with_options(&json!([1, 2, 3]), &json!([3, 1, 2]), &options);
+        assert_eq!(summarize(&diffs), ["1 item moved in the document"]);
+    }
+}
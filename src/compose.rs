@@ -0,0 +1,284 @@
+//! Composing sequential diffs into one.
+
+use alloc::vec::Vec;
+
+use crate::engine::change_kind;
+use crate::patch::apply_one;
+use crate::{DiffKind, Difference, Path, PathSegment, Withheld};
+
+/// Combines two diffs applied one after the other into a single diff with
+/// the same effect, so a chain of incremental changes can be stored compactly.
+///
+/// Each difference of `second` is merged into an earlier one at the same
+/// path, or into an earlier addition or replacement of one of its parents,
+/// whenever no difference in between could affect its location. Changes
+/// that revert each other cancel out. The rest are kept in order.
+pub fn compose(first: &[Difference], second: &[Difference]) -> Vec<Difference> {
+    let mut composed = first.to_vec();
+    for diff in second {
+        if !absorb(&mut composed, diff) {
+            composed.push(diff.clone());
+        }
+    }
+    composed
+}
+
+// Merges `diff` into an earlier difference if possible, returning whether it did
+fn absorb(composed: &mut Vec<Difference>, diff: &Difference) -> bool {
+    let target = diff.segments.segments();
+    for k in (0..composed.len()).rev() {
+        let earlier = composed[k].segments.segments();
+        if earlier == target {
+            let Some(combined) = combine(&composed[k], diff) else {
+                return false;
+            };
+            if changes_shifting(&composed[k], combined.as_ref())
+                && composed[k + 1..]
+                    .iter()
+                    .any(|between| shares_array(between.segments.segments(), target))
+            {
+                return false;
+            }
+            match combined {
+                Some(combined) => composed[k] = combined,
+                None => {
+                    composed.remove(k);
+                }
+            }
+            return true;
+        }
+        if target.starts_with(earlier) {
+            return fold(&mut composed[k], diff);
+        }
+        if interferes(&composed[k], target) {
+            return false;
+        }
+    }
+    false
+}
+
+// Combines two differences at the same path. Returns `None` if they cannot
+// be combined, and `Some(None)` if they cancel out.
+fn combine(earlier: &Difference, later: &Difference) -> Option<Option<Difference>> {
+    use DiffKind::*;
+    let replaced = |kind| Difference {
+        kind,
+        after: later.after.clone(),
+        withheld: Withheld {
+            after: later.withheld.after,
+            ..earlier.withheld
+        },
+        ..earlier.clone()
+    };
+    let combined = match (&earlier.kind, &later.kind) {
+        (Added, Changed | TypeChanged { .. }) => replaced(Added),
+        (Added, Removed) => return Some(None),
+        (Changed | TypeChanged { .. }, Removed) => Difference {
+            kind: Removed,
+            after: None,
+            withheld: Withheld {
+                after: false,
+                ..earlier.withheld
+            },
+            ..earlier.clone()
+        },
+        (Changed | TypeChanged { .. }, Changed | TypeChanged { .. }) | (Removed, Added) => {
+            match (&earlier.before, &later.after) {
+                (Some(before), Some(after)) if before == after => return Some(None),
+                (Some(before), Some(after)) => replaced(change_kind(before, after)),
+                // Without both values the types are unknown
+                _ => replaced(Changed),
+            }
+        }
+        _ => return None,
+    };
+    Some(Some(combined))
+}
+
+// Applies `later`, which lies below `earlier`, to the value `earlier` introduced
+fn fold(earlier: &mut Difference, later: &Difference) -> bool {
+    // A withheld value is not the one `later` changes
+    if matches!(earlier.kind, DiffKind::Moved { .. }) || earlier.withheld.after {
+        return false;
+    }
+    let Some(value) = earlier.after.as_mut() else {
+        return false;
+    };
+    let relative = Path::from(later.segments.segments()[earlier.segments.len()..].to_vec());
+    let rebased = Difference {
+        segments: relative,
+        ..later.clone()
+    };
+    let mut folded = value.clone();
+    if apply_one(&mut folded, &rebased).is_err() {
+        return false;
+    }
+    *value = folded;
+    if matches!(
+        earlier.kind,
+        DiffKind::Changed | DiffKind::TypeChanged { .. }
+    ) && let (Some(before), Some(after)) = (&earlier.before, &earlier.after)
+    {
+        earlier.kind = change_kind(before, after);
+    }
+    true
+}
+
+// Whether an index shift by `kind` at some position moves other elements
+fn shifts(kind: &DiffKind) -> bool {
+    matches!(
+        kind,
+        DiffKind::Added | DiffKind::Removed | DiffKind::Moved { .. }
+    )
+}
+
+// Whether replacing `earlier` with `combined` changes how later indices shift
+fn changes_shifting(earlier: &Difference, combined: Option<&Difference>) -> bool {
+    shifts(&earlier.kind) != combined.is_some_and(|combined| shifts(&combined.kind))
+}
+
+// Whether `path` lies in the same array as `target`, if `target` is an element
+fn shares_array(path: &[PathSegment], target: &[PathSegment]) -> bool {
+    let Some((PathSegment::Index(_), parent)) = target.split_last() else {
+        return false;
+    };
+    path.len() > parent.len()
+        && path.starts_with(parent)
+        && matches!(path[parent.len()], PathSegment::Index(_))
+}
+
+// Whether `between` could change what `target` refers to: it lies below
+// it, renames a key along the way, or shifts the elements of an array
+// along the way
+fn interferes(between: &Difference, target: &[PathSegment]) -> bool {
+    let path = between.segments.segments();
+    if path.starts_with(target) {
+        return true;
+    }
+    if let DiffKind::Renamed { from_key, .. } = &between.kind {
+        let source = between.segments.parent().unwrap_or_default();
+        return target.starts_with(source.child_key(from_key.as_str()).segments());
+    }
+    let Some((PathSegment::Index(_), parent)) = path.split_last() else {
+        return false;
+    };
+    shifts(&between.kind)
+        && target.len() > parent.len()
+        && target.starts_with(parent)
+        && matches!(target[parent.len()], PathSegment::Index(_))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ArrayDiffStrategy, DiffOptions, JsonType, Severity, apply_diff, deep_diff,
+        deep_diff_with_options,
+    };
+    use serde_json::{Value, json};
+
+    // Checks that composing the diffs a -> b and b -> c turns a into c
+    fn compose_chain(a: &Value, b: &Value, c: &Value, options: &DiffOptions) -> Vec<Difference> {
+        let first = deep_diff_with_options(a, b, options);
+        let second = deep_diff_with_options(b, c, options);
+        let composed = compose(&first, &second);
+        assert_eq!(apply_diff(a, &composed).as_ref(), Ok(c));
+        composed
+    }
+
+    /// Test that changes at the same path are merged.
+    #[test]
+    fn test_compose_same_path() {
+        let a = json!({"v": 1, "s": "x"});
+        let b = json!({"v": 2, "s": "y", "n": 1});
+        let c = json!({"v": "3", "s": "y", "n": 2});
+        let composed = compose_chain(&a, &b, &c, &DiffOptions::default());
+        assert_eq!(
+            composed,
+            vec![
+                Difference {
+                    path: "s".to_string(),
+                    segments: "s".parse().unwrap(),
+                    kind: DiffKind::Changed,
+                    severity: Severity::Info,
+                    before: Some(json!("x")),
+                    after: Some(json!("y")),
+                    context: None,
+                    withheld: Withheld::default(),
+                },
+                Difference {
+                    path: "v".to_string(),
+                    segments: "v".parse().unwrap(),
+                    kind: DiffKind::TypeChanged {
+                        from: JsonType::Number,
+                        to: JsonType::String,
+                    },
+                    severity: Severity::Info,
+                    before: Some(json!(1)),
+                    after: Some(json!("3")),
+                    context: None,
+                    withheld: Withheld::default(),
+                },
+                Difference {
+                    path: "n".to_string(),
+                    segments: "n".parse().unwrap(),
+                    kind: DiffKind::Added,
+                    severity: Severity::Info,
+                    before: None,
+                    after: Some(json!(2)),
+                    context: None,
+                    withheld: Withheld::default(),
+                },
+            ]
+        );
+    }
+
+    /// Test that changes which revert each other cancel out.
+    #[test]
+    fn test_compose_cancels() {
+        let a = json!({"v": 1, "gone": true});
+        let b = json!({"v": 2, "new": 1});
+        assert!(compose_chain(&a, &b, &a, &DiffOptions::default()).is_empty());
+    }
+
+    /// Test that changes below an added value are folded into it.
+    #[test]
+    fn test_compose_folds_into_parent() {
+        let a = json!({});
+        let b = json!({"cfg": {"x": 1, "y": [1]}});
+        let c = json!({"cfg": {"x": 2, "y": [1, 2]}});
+        let composed = compose_chain(&a, &b, &c, &DiffOptions::default());
+        assert_eq!(composed.len(), 1);
+        assert_eq!(composed[0].after, Some(c["cfg"].clone()));
+    }
+
+    /// Test that array changes separated by index shifts stay correct.
+    #[test]
+    fn test_compose_arrays() {
+        let lcs = DiffOptions::builder()
+            .array_strategy(ArrayDiffStrategy::Lcs)
+            .build();
+        let cases = [
+            (json!([1, 2, 3]), json!([0, 1, 2, 3]), json!([0, 1, 3])),
+            (json!([1, 2, 3]), json!([1, 3]), json!([1, 4, 3])),
+            (json!([1, 2, 3]), json!([1, 5, 3]), json!([5, 3])),
+            (
+                json!([[1], [2]]),
+                json!([[0], [1], [2]]),
+                json!([[0], [1, 1], [2]]),
+            ),
+        ];
+        for (a, b, c) in &cases {
+            compose_chain(a, b, c, &lcs);
+            compose_chain(a, b, c, &DiffOptions::default());
+        }
+    }
+
+    /// Test that composing with an empty diff changes nothing.
+    #[test]
+    fn test_compose_empty() {
+        let diffs = deep_diff(&json!({"a": 1}), &json!({"a": 2}));
+        assert_eq!(compose(&diffs, &[]), diffs);
+        assert_eq!(compose(&[], &diffs), diffs);
+    }
+}
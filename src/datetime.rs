@@ -0,0 +1,146 @@
+//! Parsing of RFC 3339 (ISO 8601) timestamps into instants, so they can be
+//! compared regardless of their time zone offset.
+
+// Nanoseconds in a second
+const NANOS: i128 = 1_000_000_000;
+
+/// Parses a timestamp such as `2024-01-01T01:00:00.5+01:00` into
+/// nanoseconds since the Unix epoch.
+///
+/// Both `T` and a space separate the date from the time, and the offset is
+/// either `Z` or `±HH:MM`. Timestamps without an offset are rejected, since
+/// the instant they refer to is unknown.
+pub(crate) fn parse_instant(text: &str) -> Option<i128> {
+    let bytes = text.as_bytes();
+    if bytes.len() < 20 || !matches!(bytes[10], b'T' | b't' | b' ') {
+        return None;
+    }
+    let year = digits(&bytes[0..4])?;
+    let month = digits(&bytes[5..7])?;
+    let day = digits(&bytes[8..10])?;
+    let hour = digits(&bytes[11..13])?;
+    let minute = digits(&bytes[14..16])?;
+    let second = digits(&bytes[17..19])?;
+    let separators = [(4, b'-'), (7, b'-'), (13, b':'), (16, b':')];
+    if separators.iter().any(|&(at, separator)| bytes[at] != separator)
+        || !(1..=12).contains(&month)
+        || day == 0
+        || day > days_in_month(year, month)
+        || hour > 23
+        || minute > 59
+        // Leap seconds are accepted as the last second of the minute
+        || second > 60
+    {
+        return None;
+    }
+
+    let mut rest = &bytes[19..];
+    let mut nanos = 0;
+    if let Some(fraction) = rest.strip_prefix(b".") {
+        let len = fraction.iter().take_while(|b| b.is_ascii_digit()).count();
+        if len == 0 {
+            return None;
+        }
+        // Digits beyond nanosecond precision are dropped
+        for (i, digit) in fraction[..len].iter().take(9).enumerate() {
+            nanos += i128::from(digit - b'0') * 10i128.pow(8 - i as u32);
+        }
+        rest = &fraction[len..];
+    }
+    let offset_minutes = match rest {
+        [b'Z' | b'z'] => 0,
+        [sign @ (b'+' | b'-'), h1, h2, b':', m1, m2] => {
+            let (hours, minutes) = (digits(&[*h1, *h2])?, digits(&[*m1, *m2])?);
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            let offset = i128::from(hours * 60 + minutes);
+            if *sign == b'-' { -offset } else { offset }
+        }
+        _ => return None,
+    };
+
+    let days = days_from_civil(year, month, day);
+    let seconds = days * 86_400 + i128::from(hour * 3600 + minute * 60 + second.min(59))
+        - offset_minutes * 60;
+    Some(seconds * NANOS + nanos)
+}
+
+// Parses a run of ASCII digits
+fn digits(bytes: &[u8]) -> Option<u32> {
+    bytes.iter().try_fold(0u32, |value, byte| {
+        byte.is_ascii_digit()
+            .then(|| value * 10 + u32::from(byte - b'0'))
+    })
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Counts the days from 1970-01-01 to the given date in the proleptic
+// Gregorian calendar, using Howard Hinnant's `days_from_civil`
+fn days_from_civil(year: u32, month: u32, day: u32) -> i128 {
+    let year = i128::from(year) - i128::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = i128::from(month);
+    let day_of_year =
+        (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i128::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that timestamps are parsed into instants since the epoch.
+    #[test]
+    fn test_parse_instant() {
+        assert_eq!(parse_instant("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_instant("1970-01-02T00:00:01Z"), Some(86_401 * NANOS));
+        assert_eq!(
+            parse_instant("2000-03-01T00:00:00Z"),
+            Some(951_868_800 * NANOS)
+        );
+        assert_eq!(
+            parse_instant("1969-12-31T23:59:59.25Z"),
+            Some(-NANOS + NANOS / 4)
+        );
+        assert_eq!(
+            parse_instant("2024-01-01T00:00:00Z"),
+            parse_instant("2024-01-01 01:00:00+01:00")
+        );
+        assert_eq!(
+            parse_instant("2023-12-31T19:30:00.000-04:30"),
+            parse_instant("2024-01-01T00:00:00Z")
+        );
+    }
+
+    /// Test that strings which are not complete timestamps are rejected.
+    #[test]
+    fn test_parse_instant_invalid() {
+        for text in [
+            "2024-01-01",
+            "2024-01-01T00:00:00",
+            "2024-02-30T00:00:00Z",
+            "2023-02-29T00:00:00Z",
+            "2024-01-01T24:00:00Z",
+            "2024-01-01T00:00:00.Z",
+            "2024-01-01T00:00:00+0100",
+            "2024/01/01T00:00:00Z",
+            "not a timestamp at all",
+        ] {
+            assert_eq!(parse_instant(text), None, "{}", text);
+        }
+        assert!(parse_instant("2024-02-29T00:00:00Z").is_some());
+    }
+}
@@ -0,0 +1,301 @@
+//! Exact comparison of numbers by their decimal digits, for values beyond
+//! the precision of `f64` such as 128-bit IDs and monetary amounts.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+use serde_json::Number;
+
+// Differences spanning more digits than this are approximated with `f64`
+// rather than computed digit by digit
+const MAX_SPAN: i64 = 1 << 16;
+
+/// A number as `digits × 10^exponent`, normalized so equal values have
+/// equal representations.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct Decimal {
+    negative: bool,
+    // Most significant first, with no leading or trailing zeros; empty for
+    // zero
+    digits: Vec<u8>,
+    exponent: i64,
+    // Whether the number was written without a fraction or exponent
+    integer: bool,
+}
+
+impl Decimal {
+    /// Parses the JSON text of a number, e.g. `-12.50e3`.
+    pub(crate) fn parse(text: &str) -> Option<Decimal> {
+        let (negative, text) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        let (mantissa, exponent) = match text.find(['e', 'E']) {
+            Some(at) => (&text[..at], text[at + 1..].parse::<i64>().ok()?),
+            None => (text, 0),
+        };
+        let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let all = || whole.bytes().chain(fraction.bytes());
+        if whole.is_empty() || !all().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let fraction_len = i64::try_from(fraction.len()).ok()?;
+        let mut decimal = Decimal {
+            negative,
+            digits: all().map(|b| b - b'0').collect(),
+            exponent: exponent.checked_sub(fraction_len)?,
+            integer: mantissa.len() == text.len() && fraction.is_empty(),
+        };
+        decimal.normalize();
+        Some(decimal)
+    }
+
+    /// The decimal value of `number`, exactly as written when `serde_json`
+    /// keeps arbitrary precision.
+    pub(crate) fn of(number: &Number) -> Option<Decimal> {
+        Decimal::parse(&number.to_string())
+    }
+
+    fn normalize(&mut self) {
+        let leading = self.digits.iter().take_while(|&&d| d == 0).count();
+        self.digits.drain(..leading);
+        while self.digits.last() == Some(&0) {
+            self.digits.pop();
+            self.exponent += 1;
+        }
+        if self.digits.is_empty() {
+            self.negative = false;
+            self.exponent = 0;
+        }
+    }
+
+    // The power of ten just above the leading digit
+    fn magnitude(&self) -> i64 {
+        self.exponent + self.digits.len() as i64
+    }
+
+    /// The same number, forgetting whether it was written as an integer,
+    /// so it compares and hashes by value alone.
+    pub(crate) fn by_value(mut self) -> Decimal {
+        self.integer = false;
+        self
+    }
+
+    /// Compares two numbers by value, ignoring how they were written.
+    pub(crate) fn cmp_value(&self, other: &Decimal) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, false) => self.cmp_abs(other),
+            (true, true) => other.cmp_abs(self),
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+        }
+    }
+
+    /// Renders the number in one canonical form: plain digits for integers
+    /// and short fractions, otherwise scientific notation.
+    pub(crate) fn canonical(&self) -> String {
+        if self.digits.is_empty() {
+            return "0".to_string();
+        }
+        let sign = if self.negative { "-" } else { "" };
+        let digits: String = self.digits.iter().map(|d| char::from(b'0' + d)).collect();
+        let magnitude = self.magnitude();
+        match (self.exponent, magnitude) {
+            (0.., ..=40) => format!("{}{}{}", sign, digits, "0".repeat(self.exponent as usize)),
+            (..0, 1..=40) => {
+                let (whole, fraction) = digits.split_at(magnitude as usize);
+                format!("{}{}.{}", sign, whole, fraction)
+            }
+            (..0, -5..=0) => format!("{}0.{}{}", sign, "0".repeat(-magnitude as usize), digits),
+            _ => {
+                let (first, rest) = digits.split_at(1);
+                let point = if rest.is_empty() { "" } else { "." };
+                format!("{}{}{}{}e{}", sign, first, point, rest, magnitude - 1)
+            }
+        }
+    }
+
+    // Whether the two numbers differ by no more than `tolerance`
+    fn within(&self, other: &Decimal, tolerance: &Decimal) -> bool {
+        match self.abs_diff(other) {
+            Some(diff) => diff.cmp_abs(tolerance) != Ordering::Greater,
+            None => (self.to_f64() - other.to_f64()).abs() <= tolerance.to_f64(),
+        }
+    }
+
+    fn to_f64(&self) -> f64 {
+        let mut text: String = self.digits.iter().map(|d| char::from(b'0' + d)).collect();
+        if text.is_empty() {
+            return 0.0;
+        }
+        if self.negative {
+            text.insert(0, '-');
+        }
+        text.push('e');
+        text.push_str(&self.exponent.to_string());
+        text.parse().unwrap_or(f64::NAN)
+    }
+
+    // Compares absolute values
+    fn cmp_abs(&self, other: &Decimal) -> Ordering {
+        match (self.digits.is_empty(), other.digits.is_empty()) {
+            (true, true) => return Ordering::Equal,
+            (true, false) => return Ordering::Less,
+            (false, true) => return Ordering::Greater,
+            (false, false) => {}
+        }
+        // Normalized digits compare lexicographically once the magnitudes
+        // agree, a missing digit counting as zero
+        self.magnitude()
+            .cmp(&other.magnitude())
+            .then_with(|| self.digits.cmp(&other.digits))
+    }
+
+    // The digits of the absolute value, least significant first, scaled to
+    // `10^exponent`
+    fn aligned(&self, exponent: i64, len: usize) -> Vec<u8> {
+        let mut aligned = Vec::with_capacity(len);
+        aligned.resize((self.exponent - exponent) as usize, 0);
+        aligned.extend(self.digits.iter().rev());
+        aligned.resize(len, 0);
+        aligned
+    }
+
+    // The absolute difference between two numbers, or `None` if it would
+    // take too many digits to compute
+    fn abs_diff(&self, other: &Decimal) -> Option<Decimal> {
+        let exponent = self.exponent.min(other.exponent);
+        let span = self.magnitude().max(other.magnitude()) - exponent + 1;
+        if span > MAX_SPAN {
+            return None;
+        }
+        let (big, small) = match self.cmp_abs(other) {
+            Ordering::Less => (other, self),
+            _ => (self, other),
+        };
+        let (big, small) = (
+            big.aligned(exponent, span as usize),
+            small.aligned(exponent, span as usize),
+        );
+        let subtract = self.negative == other.negative;
+        let mut carry = 0i8;
+        let mut digits: Vec<u8> = big
+            .iter()
+            .zip(&small)
+            .map(|(&x, &y)| {
+                let mut digit = if subtract {
+                    x as i8 - y as i8 - carry
+                } else {
+                    x as i8 + y as i8 + carry
+                };
+                carry = 0;
+                if digit < 0 {
+                    digit += 10;
+                    carry = 1;
+                } else if digit > 9 {
+                    digit -= 10;
+                    carry = 1;
+                }
+                digit as u8
+            })
+            .collect();
+        digits.reverse();
+        let mut diff = Decimal {
+            negative: false,
+            digits,
+            exponent,
+            integer: false,
+        };
+        diff.normalize();
+        Some(diff)
+    }
+}
+
+/// Returns whether two numbers have exactly the same value. Integers and
+/// numbers written with a fraction or exponent only match if `coerce` is
+/// set, as when they are parsed as `i64` and `f64`.
+pub(crate) fn equal(a: &Number, b: &Number, coerce: bool) -> bool {
+    match (Decimal::of(a), Decimal::of(b)) {
+        (Some(x), Some(y)) => {
+            x.negative == y.negative
+                && x.digits == y.digits
+                && x.exponent == y.exponent
+                && (coerce || x.integer == y.integer)
+        }
+        _ => a == b,
+    }
+}
+
+/// Returns whether two numbers differ by no more than `tolerance`.
+pub(crate) fn within(a: &Number, b: &Number, tolerance: &Decimal) -> bool {
+    match (Decimal::of(a), Decimal::of(b)) {
+        (Some(x), Some(y)) => x.within(&y, tolerance),
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(text: &str) -> Number {
+        text.parse().unwrap()
+    }
+
+    fn decimal(text: &str) -> Decimal {
+        Decimal::parse(text).unwrap()
+    }
+
+    /// Test that equal values parse to the same representation.
+    #[test]
+    fn test_parse_normalizes() {
+        assert_eq!(decimal("1.50"), decimal("15e-1"));
+        assert_eq!(decimal("-0.0"), decimal("0e5"));
+        assert!(equal(&number("1200"), &number("12e2"), true));
+        assert!(decimal("12").integer);
+        assert!(!decimal("12.0").integer);
+        assert_eq!(Decimal::parse("1.2.3"), None);
+        assert_eq!(Decimal::parse("-"), None);
+        assert_eq!(Decimal::parse(".5"), None);
+    }
+
+    /// Test that numbers render in one canonical form.
+    #[test]
+    fn test_canonical() {
+        let canonical = |text: &str| decimal(text).canonical();
+        assert_eq!(canonical("1.0"), "1");
+        assert_eq!(canonical("-12e2"), "-1200");
+        assert_eq!(canonical("12.50"), "12.5");
+        assert_eq!(canonical("0.00120"), "0.0012");
+        assert_eq!(canonical("1.5e-9"), "1.5e-9");
+        assert_eq!(canonical("1e41"), "1e41");
+        assert_eq!(canonical("-0"), "0");
+        assert_eq!(decimal("-2").cmp_value(&decimal("-10")), Ordering::Greater);
+        assert_eq!(decimal("0.5").cmp_value(&decimal("0")), Ordering::Greater);
+    }
+
+    /// Test that numbers compare by exact value.
+    #[test]
+    fn test_equal() {
+        assert!(equal(&number("2.50"), &number("2.5"), false));
+        assert!(!equal(&number("2"), &number("2.0"), false));
+        assert!(equal(&number("2"), &number("2.0"), true));
+        assert!(!equal(&number("0.1"), &number("0.2"), false));
+    }
+
+    /// Test that tolerances are applied to the exact difference.
+    #[test]
+    fn test_within() {
+        let cent = decimal("0.01");
+        assert!(within(&number("10.00"), &number("10.01"), &cent));
+        assert!(!within(&number("10.00"), &number("10.011"), &cent));
+        assert!(within(&number("-0.005"), &number("0.005"), &cent));
+        assert!(!within(&number("-0.006"), &number("0.005"), &cent));
+        assert!(within(&number("99.995"), &number("100.004"), &cent));
+        assert!(decimal("1e-100000").within(&decimal("0"), &cent));
+        assert!(!decimal("1e100000").within(&decimal("1"), &cent));
+        assert!(!decimal("1e300").within(&decimal("1e-300"), &cent));
+    }
+}
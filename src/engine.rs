@@ -0,0 +1,1433 @@
+//! The traversal that compares two values, and the entry points that run it.
+
+use alloc::borrow::Cow;
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as HashMap;
+use alloc::collections::BTreeSet;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+use serde_json::{Map, Value};
+
+use crate::hash::SubtreeHashes;
+use crate::options::{ArrayDiffStrategy, Comparison, DEFAULT_OPTIONS, DiffMode, DiffOptions};
+#[cfg(feature = "parallel")]
+use crate::parallel;
+use crate::path::PathTrail;
+#[cfg(feature = "serde")]
+use crate::raw;
+use crate::{
+    DiffKind, Difference, DifferenceRef, JsonType, ParsePathError, Path, PathSegment, Withheld,
+    binary, lcs, patch, similarity,
+};
+
+// The object an array stands for when compared with an object: its pairs if
+// every element is a `[key, value]` pair, otherwise its elements by index
+fn array_as_object(values: &[Value]) -> Value {
+    match array_pairs(values) {
+        Some(pairs) => pairs
+            .into_iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect(),
+        None => values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| (index.to_string(), value.clone()))
+            .collect(),
+    }
+}
+
+// The elements of an array as keys and values, if every one is a
+// `[key, value]` pair
+pub(crate) fn array_pairs(values: &[Value]) -> Option<Vec<(&String, &Value)>> {
+    values
+        .iter()
+        .map(|value| match value.as_array().map(Vec::as_slice) {
+            Some([Value::String(key), value]) => Some((key, value)),
+            _ => None,
+        })
+        .collect()
+}
+
+// Determines if two json types are equivalent
+fn same_json_type(a: &Value, b: &Value) -> bool {
+    core::mem::discriminant(a) == core::mem::discriminant(b)
+}
+
+// The kind of a replacement of `a` by `b`
+pub(crate) fn change_kind(a: &Value, b: &Value) -> DiffKind {
+    if same_json_type(a, b) {
+        DiffKind::Changed
+    } else {
+        DiffKind::TypeChanged {
+            from: JsonType::of(a),
+            to: JsonType::of(b),
+        }
+    }
+}
+
+// A unit of pending work for the traversal
+pub(crate) enum Task<'a> {
+    // Compares two values found at the same path
+    Compare {
+        a: &'a Value,
+        b: &'a Value,
+        path: PathTrail,
+        depth: usize,
+    },
+    // Reports a difference that is already known, such as an added element
+    Report {
+        path: PathTrail,
+        kind: DiffKind,
+        before: Option<&'a Value>,
+        after: Option<&'a Value>,
+    },
+    // Reports a difference found between values that are not in either
+    // document, such as an array read as an object
+    Found(DifferenceRef<'a>),
+}
+
+// Queues a comparison of two values at the same path
+fn push_compare<'a>(
+    tasks: &mut Vec<Task<'a>>,
+    a: &'a Value,
+    b: &'a Value,
+    path: PathTrail,
+    depth: usize,
+) {
+    tasks.push(Task::Compare { a, b, path, depth });
+}
+
+// Queues a known difference to be reported in order
+fn push_report<'a>(
+    tasks: &mut Vec<Task<'a>>,
+    path: PathTrail,
+    kind: DiffKind,
+    before: Option<&'a Value>,
+    after: Option<&'a Value>,
+) {
+    tasks.push(Task::Report {
+        path,
+        kind,
+        before,
+        after,
+    });
+}
+
+// Walks two documents depth first using an explicit stack rather than
+// recursion, so deeply nested input cannot overflow the call stack.
+// Differences are produced one at a time, in traversal order.
+pub(crate) struct Walker<'a, 'o> {
+    pub(crate) options: &'o DiffOptions,
+    pub(crate) stack: Vec<Task<'a>>,
+    // Subtree hashes of both documents, when identical subtrees are pruned
+    pub(crate) hashes: Option<(Arc<SubtreeHashes>, Arc<SubtreeHashes>)>,
+    // Whether to record each descent into a pair of containers, for a visitor
+    pub(crate) record_descents: bool,
+    // The containers last descended into, when recording them
+    pub(crate) descent: Option<Descent<'a>>,
+    // The number of comparisons run and the deepest level reached, for metrics
+    pub(crate) visited: usize,
+    pub(crate) deepest: usize,
+}
+
+// Two containers at the same path whose children are about to be compared
+pub(crate) type Descent<'a> = (Path, &'a Value, &'a Value);
+
+impl<'a, 'o> Walker<'a, 'o> {
+    pub(crate) fn new(a: &'a Value, b: &'a Value, options: &'o DiffOptions) -> Self {
+        let hashes = options.hash_pruning.then(|| {
+            (
+                Arc::new(SubtreeHashes::new(a)),
+                Arc::new(SubtreeHashes::new(b)),
+            )
+        });
+        Self::with_hashes(a, b, options, hashes)
+    }
+
+    fn with_hashes(
+        a: &'a Value,
+        b: &'a Value,
+        options: &'o DiffOptions,
+        hashes: Option<(Arc<SubtreeHashes>, Arc<SubtreeHashes>)>,
+    ) -> Self {
+        let mut stack = Vec::new();
+        push_compare(&mut stack, a, b, PathTrail::root(), 0);
+        Walker {
+            options,
+            stack,
+            hashes,
+            record_descents: false,
+            descent: None,
+            visited: 0,
+            deepest: 0,
+        }
+    }
+
+    // Whether two values are identical, checked in full only when their
+    // hashes match, so that a collision cannot hide a difference
+    fn same_hash(&self, a: &Value, b: &Value) -> bool {
+        self.hashes.as_ref().is_some_and(|(a_hashes, b_hashes)| {
+            a_hashes
+                .get(a)
+                .is_some_and(|hash| b_hashes.get(b) == Some(hash))
+                && a == b
+        })
+    }
+
+    // Schedules `tasks` so that they run in the order given
+    fn schedule(&mut self, tasks: Vec<Task<'a>>) {
+        self.stack.extend(tasks.into_iter().rev());
+    }
+
+    // Builds a difference, rendering its path in the configured format
+    fn difference(
+        &self,
+        segments: Path,
+        kind: DiffKind,
+        before: Option<Cow<'a, Value>>,
+        after: Option<Cow<'a, Value>>,
+    ) -> DifferenceRef<'a> {
+        DifferenceRef {
+            path: segments.render(self.options.path_format),
+            severity: self.options.severity_for(&segments),
+            segments,
+            kind,
+            before,
+            after,
+            withheld: Withheld::default(),
+        }
+    }
+
+    fn change(&self, path: Path, a: &'a Value, b: &'a Value) -> DifferenceRef<'a> {
+        let kind = change_kind(a, b);
+        self.difference(path, kind, Some(Cow::Borrowed(a)), Some(Cow::Borrowed(b)))
+    }
+
+    // Compares two values, returning their difference if they are not
+    // containers to descend into, and scheduling their children otherwise
+    fn compare(
+        &mut self,
+        a: &'a Value,
+        b: &'a Value,
+        trail: PathTrail,
+        depth: usize,
+    ) -> Option<DifferenceRef<'a>> {
+        let options = self.options;
+        if options.skips(&trail) || self.same_hash(a, b) {
+            return None;
+        }
+        // The full path is only assembled for rules to match against when
+        // there are any, and for the differences found
+        let path = if options.matches_paths() {
+            trail.to_path()
+        } else {
+            Path::root()
+        };
+        match options.custom_comparison(&path, a, b) {
+            Comparison::Equal => return None,
+            Comparison::Different => return Some(self.change(trail.to_path(), a, b)),
+            Comparison::Custom {
+                kind,
+                before,
+                after,
+            } => {
+                let (before, after) = (before.map(Cow::Owned), after.map(Cow::Owned));
+                return Some(self.difference(trail.to_path(), kind, before, after));
+            }
+            Comparison::Default => {}
+        }
+        if options.empty_matches_null(a, b) {
+            return None;
+        }
+        if options.coerce_containers && self.compare_coerced(a, b, &trail, depth) {
+            return None;
+        }
+        if !same_json_type(a, b) {
+            return Some(self.change(trail.to_path(), a, b));
+        }
+        match options.semantic_equal(&path, a, b) {
+            Some(true) => return None,
+            Some(false) => return Some(self.change(trail.to_path(), a, b)),
+            None => {}
+        }
+        let mut tasks = Vec::new();
+        match (a, b) {
+            // Deals with numbers, which may be compared with a tolerance
+            (Value::Number(_), _) => {
+                if !options.numbers_equal(a, b) {
+                    return Some(self.change(trail.to_path(), a, b));
+                }
+            }
+            // Deals with strings, which may be compared loosely
+            (Value::String(a_str), Value::String(b_str)) => {
+                if options
+                    .binary_paths
+                    .iter()
+                    .any(|pattern| pattern.matches(&path))
+                    && let Some(change) = binary::compare(a_str, b_str)
+                {
+                    return change.map(|kind| self.difference(trail.to_path(), kind, None, None));
+                }
+                if !options.strings_equal(&path, a_str, b_str) {
+                    return Some(self.change(trail.to_path(), a, b));
+                }
+            }
+            // Containers beyond the depth limit are compared as a whole
+            (Value::Array(_) | Value::Object(_), _)
+                if options.max_depth.is_some_and(|max| depth >= max) =>
+            {
+                if a != b {
+                    return Some(self.change(trail.to_path(), a, b));
+                }
+            }
+            // Objects with too little in common are reported as replaced
+            (Value::Object(_), _)
+                if options
+                    .replaced_below
+                    .is_some_and(|threshold| similarity(a, b) < threshold) =>
+            {
+                return Some(self.change(trail.to_path(), a, b));
+            }
+            // Deals with arrays
+            (Value::Array(a_values), Value::Array(b_values)) => {
+                let keyed = options.array_key_for(&path).is_some_and(|key| {
+                    diff_keyed(
+                        a_values,
+                        b_values,
+                        key,
+                        &mut tasks,
+                        &trail,
+                        options,
+                        depth + 1,
+                    )
+                });
+                if !keyed {
+                    let strategy = options.array_strategy_for(&path);
+                    diff_array(
+                        a_values,
+                        b_values,
+                        &mut tasks,
+                        &trail,
+                        strategy,
+                        options,
+                        depth + 1,
+                    );
+                }
+            }
+            // Deals with objects
+            (Value::Object(map), Value::Object(b_map)) => {
+                let renames = match options.renamed_above {
+                    Some(threshold) if options.mode == DiffMode::Full => {
+                        pair_renames(map, b_map, &trail, threshold, options)
+                    }
+                    _ => HashMap::new(),
+                };
+                for (ak, av) in map {
+                    let child = trail.child_key(ak);
+                    match b_map.get(ak) {
+                        Some(bv) => push_compare(&mut tasks, av, bv, child, depth + 1),
+                        None => {
+                            if let Some(&(bk, bv)) = renames.get(ak.as_str()) {
+                                let kind = DiffKind::Renamed {
+                                    from_key: ak.clone(),
+                                    to_key: bk.clone(),
+                                };
+                                let child = trail.child_key(bk);
+                                push_report(&mut tasks, child, kind, Some(av), Some(bv));
+                            } else if options.mode != DiffMode::SupersetOf
+                                && !options.skips(&child)
+                                && !options.absent_matches(av)
+                            {
+                                push_report(&mut tasks, child, DiffKind::Removed, Some(av), None);
+                            }
+                        }
+                    }
+                }
+                let renamed: BTreeSet<&str> = renames.values().map(|(bk, _)| bk.as_str()).collect();
+                for (bk, bv) in b_map {
+                    if options.mode != DiffMode::SubsetOf
+                        && !map.contains_key(bk)
+                        && !renamed.contains(bk.as_str())
+                    {
+                        let child = trail.child_key(bk);
+                        if !options.skips(&child) && !options.absent_matches(bv) {
+                            push_report(&mut tasks, child, DiffKind::Added, None, Some(bv));
+                        }
+                    }
+                }
+            }
+            // Deals with the remaining primitive types
+            _ => {
+                if a != b {
+                    return Some(self.change(trail.to_path(), a, b));
+                }
+            }
+        }
+        if self.record_descents {
+            self.descent = Some((trail.to_path(), a, b));
+        }
+        self.schedule(tasks);
+        None
+    }
+}
+
+impl<'a> Walker<'a, '_> {
+    // Compares an array with an object by reading the array as an object,
+    // scheduling the differences found. Returns false, scheduling nothing,
+    // unless the values are an array and an object.
+    fn compare_coerced(
+        &mut self,
+        a: &'a Value,
+        b: &'a Value,
+        trail: &PathTrail,
+        depth: usize,
+    ) -> bool {
+        let coerced;
+        let (a, b) = match (a, b) {
+            (Value::Array(values), Value::Object(_)) => {
+                coerced = array_as_object(values);
+                (&coerced, b)
+            }
+            (Value::Object(_), Value::Array(values)) => {
+                coerced = array_as_object(values);
+                (a, &coerced)
+            }
+            _ => return false,
+        };
+        let mut inner = Walker {
+            options: self.options,
+            stack: Vec::new(),
+            hashes: None,
+            record_descents: false,
+            descent: None,
+            visited: 0,
+            deepest: 0,
+        };
+        push_compare(&mut inner.stack, a, b, trail.clone(), depth);
+        // The coerced array only lives here, so the values are cloned
+        let owned = |value: Option<Cow<'_, Value>>| value.map(|v| Cow::Owned(v.into_owned()));
+        let tasks = inner
+            .by_ref()
+            .map(|diff| {
+                Task::Found(DifferenceRef {
+                    path: diff.path,
+                    segments: diff.segments,
+                    kind: diff.kind,
+                    severity: diff.severity,
+                    before: owned(diff.before),
+                    after: owned(diff.after),
+                    withheld: diff.withheld,
+                })
+            })
+            .collect();
+        self.visited += inner.visited;
+        self.deepest = self.deepest.max(inner.deepest);
+        self.schedule(tasks);
+        true
+    }
+
+    // Carries out one task, returning the difference it found, if any
+    pub(crate) fn run(&mut self, task: Task<'a>) -> Option<DifferenceRef<'a>> {
+        let found = match task {
+            Task::Compare { a, b, path, depth } => {
+                self.visited += 1;
+                self.deepest = self.deepest.max(depth);
+                self.compare(a, b, path, depth)
+            }
+            Task::Report {
+                path,
+                kind,
+                before,
+                after,
+            } => {
+                let (before, after) = (before.map(Cow::Borrowed), after.map(Cow::Borrowed));
+                Some(self.difference(path.to_path(), kind, before, after))
+            }
+            // Found by a walker with the same options, so already filtered,
+            // redacted and captured
+            Task::Found(difference) => return Some(difference),
+        };
+        let mut found = found.filter(|diff| !self.options.is_suppressed(diff))?;
+        let sides = [
+            (&mut found.before, &mut found.withheld.before),
+            (&mut found.after, &mut found.withheld.after),
+        ];
+        for (value, withheld) in sides {
+            if let Some(value) = value {
+                *withheld |= self
+                    .options
+                    .finish_value(value, &found.segments, &found.kind);
+            }
+        }
+        Some(found)
+    }
+}
+
+impl<'a> Iterator for Walker<'a, '_> {
+    type Item = DifferenceRef<'a>;
+
+    fn next(&mut self) -> Option<DifferenceRef<'a>> {
+        while let Some(task) = self.stack.pop() {
+            let found = self.run(task);
+            if found.is_some() {
+                return found;
+            }
+        }
+        None
+    }
+}
+
+// Diffs two arrays using the configured strategy
+fn diff_array<'a>(
+    a_values: &'a [Value],
+    b_values: &'a [Value],
+    tasks: &mut Vec<Task<'a>>,
+    path: &PathTrail,
+    strategy: ArrayDiffStrategy,
+    options: &DiffOptions,
+    depth: usize,
+) {
+    match strategy {
+        ArrayDiffStrategy::Index => {
+            for (i, (va, vb)) in a_values.iter().zip(b_values).enumerate() {
+                push_compare(tasks, va, vb, path.child_index(i), depth);
+            }
+            // Extra elements are removed from the back, so the
+            // remaining indices stay valid while applying the diff
+            for (i, va) in a_values.iter().enumerate().skip(b_values.len()).rev() {
+                let child = path.child_index(i);
+                if !options.skips(&child) {
+                    push_report(tasks, child, DiffKind::Removed, Some(va), None);
+                }
+            }
+            for (i, vb) in b_values.iter().enumerate().skip(a_values.len()) {
+                let child = path.child_index(i);
+                if !options.skips(&child) {
+                    push_report(tasks, child, DiffKind::Added, None, Some(vb));
+                }
+            }
+        }
+        ArrayDiffStrategy::Lcs => {
+            diff_lcs(a_values, b_values, tasks, path, strategy, options, depth)
+        }
+        ArrayDiffStrategy::UnorderedSet => {
+            diff_unordered(a_values, b_values, tasks, path, options);
+        }
+    }
+}
+
+// Diffs two arrays by walking the gaps between their common subsequence
+fn diff_lcs<'a>(
+    a_values: &'a [Value],
+    b_values: &'a [Value],
+    tasks: &mut Vec<Task<'a>>,
+    path: &PathTrail,
+    strategy: ArrayDiffStrategy,
+    options: &DiffOptions,
+    depth: usize,
+) {
+    let mut matches = lcs::lcs(a_values, b_values, |x, y| x == y);
+    // Order of the elements of `a_values` once any moves have been applied
+    let mut order: Vec<usize> = (0..a_values.len()).collect();
+    if options.detect_moves && strategy.supports_moves() {
+        matches = push_moves(
+            a_values, b_values, &matches, &mut order, tasks, path, options,
+        );
+    }
+    let mut position = vec![0; order.len()];
+    for (pos, &i) in order.iter().enumerate() {
+        position[i] = pos;
+    }
+    let working: Vec<&Value> = order.iter().map(|&i| &a_values[i]).collect();
+    let matches = matches.into_iter().map(|(i, j)| (position[i], j));
+    let end = (working.len(), b_values.len());
+
+    // Position in the array as it is transformed from `a_values` into `b_values`
+    let mut pos = 0;
+    let (mut i, mut j) = (0, 0);
+    for (mi, mj) in matches.chain(core::iter::once(end)) {
+        let removed = &working[i..mi];
+        let added = &b_values[j..mj];
+        let paired = removed.len().min(added.len());
+        for (va, vb) in removed.iter().zip(added) {
+            push_compare(tasks, va, vb, path.child_index(pos), depth);
+            pos += 1;
+        }
+        // Removed from the back, so earlier indices in the gap stay valid
+        for (k, va) in removed.iter().enumerate().skip(paired).rev() {
+            let child = path.child_index(pos + k - paired);
+            if !options.skips(&child) {
+                push_report(tasks, child, DiffKind::Removed, Some(va), None);
+            }
+        }
+        for vb in &added[paired..] {
+            let child = path.child_index(pos);
+            if !options.skips(&child) {
+                push_report(tasks, child, DiffKind::Added, None, Some(vb));
+            }
+            pos += 1;
+        }
+        // Step over the matched element itself
+        pos += 1;
+        (i, j) = (mi + 1, mj + 1);
+    }
+}
+
+// Pairs each key only in `a` with the most similar key only in `b`, if
+// their values are similar enough for a rename
+fn pair_renames<'a>(
+    a: &'a Map<String, Value>,
+    b: &'a Map<String, Value>,
+    path: &PathTrail,
+    threshold: f64,
+    options: &DiffOptions,
+) -> HashMap<&'a str, (&'a String, &'a Value)> {
+    // Keys that would otherwise be reported as removed or added
+    let only =
+        |x: &'a Map<String, Value>, y: &Map<String, Value>| -> Vec<(&'a String, &'a Value)> {
+            x.iter()
+                .filter(|&(key, value)| {
+                    !y.contains_key(key)
+                        && !options.skips(&path.child_key(key))
+                        && !options.absent_matches(value)
+                })
+                .collect()
+        };
+    let mut added = only(b, a);
+    let mut renames = HashMap::new();
+    for (ak, av) in only(a, b) {
+        let best = added
+            .iter()
+            .enumerate()
+            .filter_map(|(j, &(_, bv))| {
+                let score = match av == bv {
+                    true => 1.0,
+                    false if threshold < 1.0 => similarity(av, bv),
+                    false => return None,
+                };
+                (score >= threshold).then_some((j, score))
+            })
+            .fold(None, |best: Option<(usize, f64)>, (j, score)| match best {
+                Some((_, top)) if top >= score => best,
+                _ => Some((j, score)),
+            });
+        if let Some((j, _)) = best {
+            renames.insert(ak.as_str(), added.remove(j));
+        }
+    }
+    renames
+}
+
+// Pairs up elements outside the common subsequence that are equal on both
+// sides and moves them into place in `order`, one at a time. Returns the
+// common subsequence extended with the moved pairs, ordered by `b` index.
+fn push_moves<'a>(
+    a_values: &'a [Value],
+    b_values: &'a [Value],
+    matches: &[(usize, usize)],
+    order: &mut Vec<usize>,
+    tasks: &mut Vec<Task<'a>>,
+    path: &PathTrail,
+    options: &DiffOptions,
+) -> Vec<(usize, usize)> {
+    let mut anchor_of_b = vec![None; b_values.len()];
+    let mut matched_a = vec![false; a_values.len()];
+    for &(i, j) in matches {
+        anchor_of_b[j] = Some(i);
+        matched_a[i] = true;
+    }
+    let mut unmatched_a: HashMap<String, Vec<usize>> = HashMap::new();
+    for (i, va) in a_values.iter().enumerate().rev() {
+        if !matched_a[i] {
+            unmatched_a.entry(va.to_string()).or_default().push(i);
+        }
+    }
+    for j in 0..b_values.len() {
+        if anchor_of_b[j].is_some() {
+            continue;
+        }
+        let Some(i) = unmatched_a
+            .get_mut(&b_values[j].to_string())
+            .and_then(Vec::pop)
+        else {
+            continue;
+        };
+        // Place the element right after the one preceding it in `b`
+        let Some(from) = order.iter().position(|&x| x == i) else {
+            continue;
+        };
+        order.remove(from);
+        let to = (0..j)
+            .rev()
+            .find_map(|k| anchor_of_b[k])
+            .and_then(|previous| order.iter().position(|&x| x == previous))
+            .map_or(0, |at| at + 1);
+        order.insert(to, i);
+        anchor_of_b[j] = Some(i);
+        let child = path.child_index(to);
+        if from != to && !options.skips(&child) {
+            let kind = DiffKind::Moved { from, to };
+            push_report(tasks, child, kind, Some(&a_values[i]), Some(&b_values[j]));
+        }
+    }
+    anchor_of_b
+        .into_iter()
+        .enumerate()
+        .filter_map(|(j, i)| Some((i?, j)))
+        .collect()
+}
+
+// Reports the elements left over after matching two arrays out of order:
+// removals from the back of `a_values` first, then additions
+fn push_unmatched<'a>(
+    a_values: &'a [Value],
+    b_values: &'a [Value],
+    removed: &[usize],
+    matched_b: &[bool],
+    tasks: &mut Vec<Task<'a>>,
+    path: &PathTrail,
+    options: &DiffOptions,
+) {
+    for &i in removed.iter().rev() {
+        let child = path.child_index(i);
+        if !options.skips(&child) {
+            push_report(tasks, child, DiffKind::Removed, Some(&a_values[i]), None);
+        }
+    }
+    for (j, vb) in b_values.iter().enumerate() {
+        let child = path.child_index(j);
+        if !matched_b[j] && !options.skips(&child) {
+            push_report(tasks, child, DiffKind::Added, None, Some(vb));
+        }
+    }
+}
+
+// Diffs two arrays of records by matching them on their `key` field.
+// Returns false without scheduling anything if some element has no such key.
+fn diff_keyed<'a>(
+    a_values: &'a [Value],
+    b_values: &'a [Value],
+    key: &str,
+    tasks: &mut Vec<Task<'a>>,
+    path: &PathTrail,
+    options: &DiffOptions,
+    depth: usize,
+) -> bool {
+    let ids = |values: &[Value]| -> Option<Vec<String>> {
+        values
+            .iter()
+            .map(|v| Some(v.get(key)?.to_string()))
+            .collect()
+    };
+    let (Some(a_ids), Some(b_ids)) = (ids(a_values), ids(b_values)) else {
+        return false;
+    };
+    let mut unmatched_b: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (j, id) in b_ids.iter().enumerate().rev() {
+        unmatched_b.entry(id).or_default().push(j);
+    }
+    // Records present on both sides are compared where they stood in `a`
+    let mut matched_b = vec![false; b_values.len()];
+    let mut removed = Vec::new();
+    for (i, id) in a_ids.iter().enumerate() {
+        match unmatched_b.get_mut(id.as_str()).and_then(Vec::pop) {
+            Some(j) => {
+                matched_b[j] = true;
+                push_compare(
+                    tasks,
+                    &a_values[i],
+                    &b_values[j],
+                    path.child_index(i),
+                    depth,
+                );
+            }
+            None => removed.push(i),
+        }
+    }
+    push_unmatched(
+        a_values, b_values, &removed, &matched_b, tasks, path, options,
+    );
+    true
+}
+
+// Diffs two arrays as multisets, reporting only unmatched elements
+fn diff_unordered<'a>(
+    a_values: &'a [Value],
+    b_values: &'a [Value],
+    tasks: &mut Vec<Task<'a>>,
+    path: &PathTrail,
+    options: &DiffOptions,
+) {
+    let mut unmatched_b: HashMap<String, Vec<usize>> = HashMap::new();
+    for (j, vb) in b_values.iter().enumerate().rev() {
+        unmatched_b.entry(vb.to_string()).or_default().push(j);
+    }
+    let mut matched_b = vec![false; b_values.len()];
+    let mut removed = Vec::new();
+    for (i, va) in a_values.iter().enumerate() {
+        match unmatched_b.get_mut(&va.to_string()).and_then(Vec::pop) {
+            Some(j) => matched_b[j] = true,
+            None => removed.push(i),
+        }
+    }
+    push_unmatched(
+        a_values, b_values, &removed, &matched_b, tasks, path, options,
+    );
+}
+
+/// Computes the differences between two JSON values.
+pub fn deep_diff(a: &Value, b: &Value) -> Vec<Difference> {
+    deep_diff_with_options(a, b, &DiffOptions::default())
+}
+
+/// Computes the differences between two JSON values using the given options.
+pub fn deep_diff_with_options(a: &Value, b: &Value, options: &DiffOptions) -> Vec<Difference> {
+    deep_diff_result(a, b, options).differences
+}
+
+/// The differences found by [`deep_diff_result`], along with whether the
+/// diff stopped early.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DiffResult {
+    /// The differences found, in traversal order.
+    pub differences: Vec<Difference>,
+    /// Whether the diff stopped after
+    /// [`max_differences`](crate::DiffOptionsBuilder::max_differences) were found,
+    /// leaving further differences unreported.
+    pub truncated: bool,
+}
+
+impl DiffResult {
+    /// Returns the path of each difference, in order.
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.differences.iter().map(|diff| &diff.segments)
+    }
+
+    /// Returns whether the value at `path`, e.g. `"a.b"`, differs: there is a
+    /// difference at it, inside it, or at a container replacing it whole.
+    ///
+    /// Paths that fail to parse are never contained.
+    pub fn contains_path(&self, path: &str) -> bool {
+        let Ok(path) = path.parse::<Path>() else {
+            return false;
+        };
+        self.paths()
+            .any(|diff| diff.starts_with(&path) || path.starts_with(diff))
+    }
+
+    /// Returns the differences at `prefix`, e.g. `"items"`, or inside it.
+    ///
+    /// Segments are compared whole, so `"items"` does not match `items2`.
+    /// Prefixes that fail to parse match nothing.
+    pub fn at_prefix(&self, prefix: &str) -> impl Iterator<Item = &Difference> {
+        let prefix = prefix.parse::<Path>().ok();
+        self.differences.iter().filter(move |diff| {
+            prefix
+                .as_ref()
+                .is_some_and(|prefix| diff.segments.starts_with(prefix))
+        })
+    }
+
+    /// Returns the number of segments in the longest path with a
+    /// difference, or 0 if there are none.
+    pub fn max_depth(&self) -> usize {
+        self.paths().map(Path::len).max().unwrap_or(0)
+    }
+}
+
+/// Computes the differences between two JSON values using the given options,
+/// reporting whether a limit cut the diff short.
+pub fn deep_diff_result(a: &Value, b: &Value, options: &DiffOptions) -> DiffResult {
+    let (differences, truncated) = collect_limited(Walker::new(a, b, options), options);
+    let mut differences: Vec<Difference> = differences.into_iter().map(Difference::from).collect();
+    attach_context(&mut differences, a, b, options);
+    DiffResult {
+        differences,
+        truncated,
+    }
+}
+
+// Computes the differences like `deep_diff_with_options`, always walking on
+// the calling thread, e.g. when the caller is already one of many threads
+pub(crate) fn diff_on_thread(a: &Value, b: &Value, options: &DiffOptions) -> Vec<Difference> {
+    let mut differences: Vec<Difference> = Walker::new(a, b, options)
+        .take(options.max_differences.unwrap_or(usize::MAX))
+        .map(Difference::from)
+        .collect();
+    attach_context(&mut differences, a, b, options);
+    differences
+}
+
+/// Error returned when two values cannot be diffed as the options ask.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffError {
+    /// A path pattern given to the options does not parse, e.g. `"items[x"`.
+    InvalidPattern(String),
+}
+
+impl core::fmt::Display for DiffError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DiffError::InvalidPattern(pattern) => write!(f, "invalid path pattern '{}'", pattern),
+        }
+    }
+}
+
+impl core::error::Error for DiffError {}
+
+/// Computes the differences between two JSON values like
+/// [`deep_diff_result`], failing instead of ignoring options that cannot be
+/// applied.
+///
+/// ```
+/// use deep_diff::{DiffError, DiffOptions, try_deep_diff};
+/// use serde_json::json;
+///
+/// let (a, b) = (json!({"id": 1}), json!({"id": 2}));
+/// let options = DiffOptions::builder().ignore("id").build();
+/// assert!(try_deep_diff(&a, &b, &options).unwrap().differences.is_empty());
+/// let options = DiffOptions::builder().ignore("[id").build();
+/// assert_eq!(
+///     try_deep_diff(&a, &b, &options),
+///     Err(DiffError::InvalidPattern("[id".to_string()))
+/// );
+/// ```
+pub fn try_deep_diff(a: &Value, b: &Value, options: &DiffOptions) -> Result<DiffResult, DiffError> {
+    options.check()?;
+    Ok(deep_diff_result(a, b, options))
+}
+
+/// Measurements of a single diff, as returned by [`deep_diff_with_metrics`].
+///
+/// Allocations are not counted, since that takes a global allocator; the
+/// benchmarks in `benches/` install one.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DiffMetrics {
+    /// The number of pairs of values compared.
+    pub nodes_visited: usize,
+    /// The deepest level of nesting compared, where the roots are at 0.
+    pub max_depth: usize,
+    /// The number of differences reported.
+    pub differences: usize,
+    /// How long the diff took.
+    pub elapsed: Duration,
+}
+
+/// Computes the differences between two JSON values like
+/// [`deep_diff_result`], measuring the work it took.
+///
+/// The diff always runs on the calling thread, even with the `parallel`
+/// feature, so the measurements are exact and repeatable.
+#[cfg(feature = "std")]
+pub fn deep_diff_with_metrics(
+    a: &Value,
+    b: &Value,
+    options: &DiffOptions,
+) -> (DiffResult, DiffMetrics) {
+    let start = Instant::now();
+    let mut walker = Walker::new(a, b, options);
+    let limit = options.max_differences.unwrap_or(usize::MAX);
+    let mut differences: Vec<Difference> =
+        walker.by_ref().take(limit).map(Difference::from).collect();
+    attach_context(&mut differences, a, b, options);
+    let metrics = DiffMetrics {
+        nodes_visited: walker.visited,
+        max_depth: walker.deepest,
+        differences: differences.len(),
+        elapsed: start.elapsed(),
+    };
+    let truncated = walker.next().is_some();
+    (
+        DiffResult {
+            differences,
+            truncated,
+        },
+        metrics,
+    )
+}
+
+/// Computes the differences between two JSON values without cloning the
+/// values involved.
+pub fn deep_diff_ref<'a>(a: &'a Value, b: &'a Value) -> Vec<DifferenceRef<'a>> {
+    deep_diff_ref_with_options(a, b, &DiffOptions::default())
+}
+
+/// Computes the differences between two JSON values using the given options,
+/// without cloning the values involved.
+pub fn deep_diff_ref_with_options<'a>(
+    a: &'a Value,
+    b: &'a Value,
+    options: &DiffOptions,
+) -> Vec<DifferenceRef<'a>> {
+    collect_limited(Walker::new(a, b, options), options).0
+}
+
+/// Lazily yields the differences between two JSON values as they are found.
+///
+/// Nothing is compared until the iterator is advanced, so stopping early
+/// (e.g. after the first difference) skips the rest of the traversal.
+pub fn diff_iter<'a>(a: &'a Value, b: &'a Value) -> impl Iterator<Item = Difference> + 'a {
+    diff_iter_with_options(a, b, &DEFAULT_OPTIONS)
+}
+
+/// Lazily yields the differences between two JSON values using the given
+/// options, stopping after [`max_differences`](crate::DiffOptionsBuilder::max_differences).
+pub fn diff_iter_with_options<'a>(
+    a: &'a Value,
+    b: &'a Value,
+    options: &'a DiffOptions,
+) -> impl Iterator<Item = Difference> + 'a {
+    Walker::new(a, b, options)
+        .take(options.max_differences.unwrap_or(usize::MAX))
+        .map(Difference::from)
+}
+
+/// Returns whether two JSON values differ, stopping at the first mismatch.
+pub fn are_different(a: &Value, b: &Value) -> bool {
+    are_different_with_options(a, b, &DiffOptions::default())
+}
+
+/// Returns whether two JSON values differ under the given options, stopping
+/// at the first mismatch.
+pub fn are_different_with_options(a: &Value, b: &Value, options: &DiffOptions) -> bool {
+    Walker::new(a, b, options).next().is_some()
+}
+
+/// Returns the first difference between two JSON values, if any, without
+/// looking for the others.
+pub fn first_difference(a: &Value, b: &Value) -> Option<Difference> {
+    first_difference_with_options(a, b, &DiffOptions::default())
+}
+
+/// Returns the first difference between two JSON values under the given
+/// options, if any, without looking for the others.
+pub fn first_difference_with_options(
+    a: &Value,
+    b: &Value,
+    options: &DiffOptions,
+) -> Option<Difference> {
+    Walker::new(a, b, options).next().map(Difference::from)
+}
+
+/// Computes the differences between the values at `path` in two documents,
+/// such as `"spec.template"` or `"/spec/template"`, ignoring everything
+/// else.
+///
+/// The path is parsed as a [`Path`]; pointer tokens made of digits select
+/// array elements. If only one document has a value there, it is reported
+/// as added or removed as a whole. Paths are reported from the root of the
+/// documents, or from `path` itself if `relative_paths` is set. Fails if
+/// `path` cannot be parsed.
+///
+/// ```
+/// use deep_diff::deep_diff_at;
+/// use serde_json::json;
+///
+/// let a = json!({"spec": {"template": {"image": "app:1"}, "replicas": 1}});
+/// let b = json!({"spec": {"template": {"image": "app:2"}, "replicas": 3}});
+/// let diffs = deep_diff_at(&a, &b, "spec.template", false).unwrap();
+/// assert_eq!(diffs.len(), 1);
+/// assert_eq!(diffs[0].path, "spec.template.image");
+/// let diffs = deep_diff_at(&a, &b, "/spec/template", true).unwrap();
+/// assert_eq!(diffs[0].path, "image");
+/// ```
+pub fn deep_diff_at(
+    a: &Value,
+    b: &Value,
+    path: &str,
+    relative_paths: bool,
+) -> Result<Vec<Difference>, ParsePathError> {
+    deep_diff_at_with_options(a, b, path, relative_paths, &DiffOptions::default())
+}
+
+/// Like [`deep_diff_at`], comparing the values at `path` with the given
+/// options.
+///
+/// The options see the two values as whole documents: their patterns are
+/// matched against paths relative to `path`, whichever way the differences
+/// are reported.
+pub fn deep_diff_at_with_options(
+    a: &Value,
+    b: &Value,
+    path: &str,
+    relative_paths: bool,
+    options: &DiffOptions,
+) -> Result<Vec<Difference>, ParsePathError> {
+    let path: Path = path.parse()?;
+    let (a, b) = (descend(a, &path), descend(b, &path));
+    let differences = match (&a, &b) {
+        (Some((a, _)), Some((b, _))) => deep_diff_with_options(a, b, options),
+        (None, None) => Vec::new(),
+        (Some((value, _)), None) | (None, Some((value, _))) => {
+            let added = b.is_some();
+            let excluded = match options.mode {
+                DiffMode::Full => false,
+                DiffMode::SubsetOf => added,
+                DiffMode::SupersetOf => !added,
+            };
+            if excluded || options.absent_matches(value) {
+                Vec::new()
+            } else {
+                let root = Path::root();
+                vec![options.finish_difference(Difference {
+                    path: root.render(options.path_format),
+                    severity: options.severity_for(&root),
+                    segments: root,
+                    kind: if added {
+                        DiffKind::Added
+                    } else {
+                        DiffKind::Removed
+                    },
+                    before: (!added).then(|| (*value).clone()),
+                    after: added.then(|| (*value).clone()),
+                    context: None,
+                    withheld: Withheld::default(),
+                })]
+            }
+        }
+    };
+    if relative_paths {
+        return Ok(differences);
+    }
+    // Segments are typed after the containers actually found at `path`
+    let prefix = a.or(b).map_or(path, |(_, prefix)| prefix);
+    Ok(differences
+        .into_iter()
+        .map(|mut diff| {
+            let segments = prefix.segments().iter().chain(diff.segments.segments());
+            diff.segments = Path::from(segments.cloned().collect::<Vec<_>>());
+            diff.path = diff.segments.render(options.path_format);
+            diff
+        })
+        .collect())
+}
+
+// Walks down `path`, reading digit keys as indices into arrays and indices
+// as keys into objects. Returns the value found and its path, typed after
+// the containers on the way.
+fn descend<'a>(mut value: &'a Value, path: &Path) -> Option<(&'a Value, Path)> {
+    let mut typed = Vec::with_capacity(path.len());
+    for segment in path.segments() {
+        let (next, segment) = match (value, segment) {
+            (Value::Object(map), PathSegment::Key(key)) => (map.get(key)?, segment.clone()),
+            (Value::Object(map), PathSegment::Index(index)) => {
+                let key = index.to_string();
+                (map.get(&key)?, PathSegment::Key(key))
+            }
+            (Value::Array(values), PathSegment::Index(index)) => {
+                (values.get(*index)?, segment.clone())
+            }
+            (Value::Array(values), PathSegment::Key(key)) => {
+                let index: usize = key.parse().ok()?;
+                (values.get(index)?, PathSegment::Index(index))
+            }
+            _ => return None,
+        };
+        value = next;
+        typed.push(segment);
+    }
+    Some((value, Path::from(typed)))
+}
+
+/// Computes the differences between two Rust values of the same type by
+/// serializing both to JSON.
+///
+/// Fails if either value cannot be represented as JSON.
+#[cfg(feature = "serde")]
+pub fn diff_values<T>(a: &T, b: &T) -> Result<Vec<Difference>, serde_json::Error>
+where
+    T: serde::Serialize + ?Sized,
+{
+    diff_serialized(a, b)
+}
+
+// Builds both values into JSON trees and diffs them with the default options
+#[cfg(feature = "serde")]
+fn diff_serialized<A, B>(a: &A, b: &B) -> Result<Vec<Difference>, serde_json::Error>
+where
+    A: serde::Serialize + ?Sized,
+    B: serde::Serialize + ?Sized,
+{
+    Ok(deep_diff(
+        &serde_json::to_value(a)?,
+        &serde_json::to_value(b)?,
+    ))
+}
+
+/// Parses two JSON documents and computes the differences between them
+/// using the given options.
+///
+/// Unlike parsing into `serde_json::Value` first, objects that repeat a key
+/// are handled as set by
+/// [`duplicate_keys`](crate::DiffOptionsBuilder::duplicate_keys), so they can be
+/// rejected or reported. Fails if either document is not valid JSON.
+///
+/// With [`compare_key_order`](crate::DiffOptionsBuilder::compare_key_order), objects
+/// at the same path whose shared keys are written in a different order are
+/// also reported, after the other differences and in path order.
+///
+/// ```
+/// use deep_diff::{DiffOptions, DuplicateKeys, deep_diff_str};
+///
+/// let options = DiffOptions::builder()
+///     .duplicate_keys(DuplicateKeys::Preserve)
+///     .build();
+/// let diffs = deep_diff_str(r#"{"a": 1}"#, r#"{"a": 1, "a": 2}"#, &options).unwrap();
+/// assert_eq!(diffs[0].path, "a#2");
+/// ```
+#[cfg(feature = "serde")]
+pub fn deep_diff_str(
+    a: &str,
+    b: &str,
+    options: &DiffOptions,
+) -> Result<Vec<Difference>, serde_json::Error> {
+    if !options.key_order {
+        let a = raw::parse(a, options.duplicate_keys)?;
+        let b = raw::parse(b, options.duplicate_keys)?;
+        return Ok(deep_diff_with_options(&a, &b, options));
+    }
+    let (a, orders_a) = raw::parse_ordered(a, options.duplicate_keys)?;
+    let (b, orders_b) = raw::parse_ordered(b, options.duplicate_keys)?;
+    let mut diffs = deep_diff_with_options(&a, &b, options);
+    diffs.extend(key_order_changes(orders_a, orders_b, options));
+    Ok(diffs)
+}
+
+// Compares the order of the keys each pair of objects at the same path have
+// in common
+#[cfg(feature = "serde")]
+fn key_order_changes(
+    a: raw::KeyOrders,
+    b: raw::KeyOrders,
+    options: &DiffOptions,
+) -> Vec<Difference> {
+    let b: alloc::collections::BTreeMap<Path, Vec<String>> = b.into_iter().collect();
+    let mut a = a;
+    a.sort_by(|(x, _), (y, _)| x.cmp(y));
+    let mut changes = Vec::new();
+    for (path, keys_a) in a {
+        let Some(keys_b) = b.get(&path) else {
+            continue;
+        };
+        let shared_a = keys_a.iter().filter(|key| keys_b.contains(key));
+        let shared_b = keys_b.iter().filter(|key| keys_a.contains(key));
+        if shared_a.eq(shared_b) {
+            continue;
+        }
+        // The object, or one it is inside, is excluded from the diff
+        if core::iter::successors(Some(path.clone()), Path::parent)
+            .any(|ancestor| options.is_skipped(&ancestor))
+        {
+            continue;
+        }
+        changes.push(Difference {
+            path: path.render(options.path_format),
+            severity: options.severity_for(&path),
+            segments: path,
+            kind: DiffKind::KeyOrderChanged,
+            before: Some(Value::from(keys_a)),
+            after: Some(Value::from(keys_b.clone())),
+            context: None,
+            withheld: Withheld::default(),
+        });
+    }
+    changes
+}
+
+/// Computes the differences between any two serializable values, such as
+/// documents decoded from CBOR or MessagePack.
+///
+/// Both values are first built into JSON trees by their `Serialize`
+/// implementations; integer and boolean map keys become string keys. Fails
+/// if either value cannot be represented as JSON, e.g. because a map has
+/// non-scalar keys.
+#[cfg(feature = "binary-formats")]
+pub fn deep_diff_serialize<A, B>(a: &A, b: &B) -> Result<Vec<Difference>, serde_json::Error>
+where
+    A: serde::Serialize + ?Sized,
+    B: serde::Serialize + ?Sized,
+{
+    diff_serialized(a, b)
+}
+
+/// Diffs many documents against the same base document, hashing the base
+/// only once so identical subtrees are skipped in constant time.
+///
+/// Each diff hashes the other document; see
+/// [`hash_pruning`](crate::DiffOptionsBuilder::hash_pruning).
+#[derive(Debug, Clone)]
+pub struct DiffSession<'a> {
+    base: &'a Value,
+    base_hashes: Arc<SubtreeHashes>,
+    options: DiffOptions,
+}
+
+impl<'a> DiffSession<'a> {
+    /// Starts a session diffing against `base` with the given options.
+    pub fn new(base: &'a Value, options: DiffOptions) -> Self {
+        DiffSession {
+            base,
+            base_hashes: Arc::new(SubtreeHashes::new(base)),
+            options,
+        }
+    }
+
+    /// Computes the differences from the base document to `other`.
+    pub fn diff(&self, other: &Value) -> Vec<Difference> {
+        let hashes = (
+            Arc::clone(&self.base_hashes),
+            Arc::new(SubtreeHashes::new(other)),
+        );
+        let walker = Walker::with_hashes(self.base, other, &self.options, Some(hashes));
+        let (differences, _) = collect_limited(walker, &self.options);
+        let mut differences: Vec<Difference> =
+            differences.into_iter().map(Difference::from).collect();
+        attach_context(&mut differences, self.base, other, &self.options);
+        differences
+    }
+}
+
+/// Diffs many pairs of documents with one set of options, such as the
+/// records of a batch job.
+///
+/// The options are checked once, when the engine is created, and each
+/// method then diffs like the free function it names; nothing else is
+/// precomputed. To reuse work on one side of many diffs, see
+/// [`DiffSession`].
+///
+/// ```
+/// use deep_diff::{DiffEngine, DiffOptions};
+/// use serde_json::json;
+///
+/// let engine = DiffEngine::new(DiffOptions::builder().ignore("**.updated_at").build()).unwrap();
+/// let records = [
+///     (json!({"id": 1, "updated_at": 1}), json!({"id": 1, "updated_at": 2})),
+///     (json!({"id": 2, "name": "a"}), json!({"id": 2, "name": "b"})),
+/// ];
+/// let changed: Vec<bool> = records.iter().map(|(a, b)| engine.are_different(a, b)).collect();
+/// assert_eq!(changed, [false, true]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DiffEngine {
+    options: DiffOptions,
+}
+
+impl DiffEngine {
+    /// Creates an engine diffing with `options`, failing if any of them
+    /// cannot be applied, like [`try_deep_diff`].
+    pub fn new(options: DiffOptions) -> Result<DiffEngine, DiffError> {
+        options.check()?;
+        Ok(DiffEngine { options })
+    }
+
+    /// The options the engine diffs with.
+    pub fn options(&self) -> &DiffOptions {
+        &self.options
+    }
+
+    /// Computes the differences between two JSON values, like
+    /// [`deep_diff_with_options`].
+    pub fn diff(&self, a: &Value, b: &Value) -> Vec<Difference> {
+        self.diff_result(a, b).differences
+    }
+
+    /// Computes the differences between two JSON values, like
+    /// [`deep_diff_result`].
+    pub fn diff_result(&self, a: &Value, b: &Value) -> DiffResult {
+        deep_diff_result(a, b, &self.options)
+    }
+
+    /// Iterates over the differences between two JSON values, like
+    /// [`diff_iter_with_options`].
+    pub fn diff_iter<'a>(
+        &'a self,
+        a: &'a Value,
+        b: &'a Value,
+    ) -> impl Iterator<Item = Difference> + 'a {
+        diff_iter_with_options(a, b, &self.options)
+    }
+
+    /// Returns whether two JSON values differ, like
+    /// [`are_different_with_options`].
+    pub fn are_different(&self, a: &Value, b: &Value) -> bool {
+        are_different_with_options(a, b, &self.options)
+    }
+
+    /// Computes the differences of each pair of JSON values, like
+    /// [`diff_batch_with_options`](crate::diff_batch_with_options).
+    pub fn diff_batch(&self, pairs: &[(Value, Value)]) -> Vec<Vec<Difference>> {
+        crate::diff_batch_with_options(pairs, &self.options)
+    }
+}
+
+// Attaches the configured context to each difference: the unchanged keys of
+// the object holding it, or the array elements around it
+fn attach_context(differences: &mut [Difference], a: &Value, b: &Value, options: &DiffOptions) {
+    let Some(elements) = options.context else {
+        return;
+    };
+    // Every path with a difference at or below it
+    let changed: BTreeSet<Path> = differences
+        .iter()
+        .flat_map(|diff| core::iter::successors(Some(diff.segments.clone()), Path::parent))
+        .collect();
+    for diff in differences.iter_mut() {
+        let Some(parent_path) = diff.segments.parent() else {
+            continue;
+        };
+        let side = if diff.kind == DiffKind::Removed { a } else { b };
+        let Some(parent) = patch::get(side, parent_path.segments()) else {
+            continue;
+        };
+        let context: Map<String, Value> = match (parent, diff.segments.segments().last()) {
+            (Value::Object(map), _) => map
+                .iter()
+                .filter_map(|(key, value)| {
+                    let path = parent_path.child_key(key.as_str());
+                    let shown = !changed.contains(&path) && !options.is_skipped(&path);
+                    shown.then(|| (key.clone(), options.redacted(value, &path)))
+                })
+                .collect(),
+            (Value::Array(values), Some(&PathSegment::Index(index))) => {
+                let end = values
+                    .len()
+                    .min(index.saturating_add(elements).saturating_add(1));
+                (index.saturating_sub(elements)..end)
+                    .filter(|&i| i != index)
+                    .map(|i| {
+                        let path = parent_path.child_index(i);
+                        (i.to_string(), options.redacted(&values[i], &path))
+                    })
+                    .collect()
+            }
+            _ => continue,
+        };
+        diff.context = Some(Value::Object(context));
+    }
+}
+
+// Collects differences up to the configured limit, and whether any were left over
+fn collect_limited<'a>(
+    mut walker: Walker<'a, '_>,
+    options: &DiffOptions,
+) -> (Vec<DifferenceRef<'a>>, bool) {
+    #[cfg(feature = "parallel")]
+    if options.max_differences.is_none() {
+        return (parallel::collect(walker), false);
+    }
+    let differences = match options.max_differences {
+        Some(max) => walker.by_ref().take(max).collect(),
+        None => walker.by_ref().collect(),
+    };
+    (differences, walker.next().is_some())
+}
@@ -0,0 +1,233 @@
+//! Export of differences to CSV and JSON Lines, for loading large diffs
+//! into spreadsheets and data tools.
+
+use std::io::{self, Write};
+
+use serde_json::Value;
+
+use crate::Difference;
+use crate::render::{csv_field, truncate};
+use crate::report::difference_json;
+
+/// Writes the differences to `writer` as CSV, one row per difference after
+/// a header row:
+///
+/// ```text
+/// path,kind,severity,before,after
+/// name,changed,info,"""Alice""","""Bob"""
+/// email,added,info,,"""bob@example.com"""
+/// ```
+///
+/// Values are written as compact JSON and left empty where missing, so
+/// `null` stays distinct from no value. Fields are quoted as RFC 4180 asks,
+/// and lines end with CRLF. The kinds and severities are named as in
+/// [`DiffReport::to_json`](crate::DiffReport::to_json).
+///
+/// ```
+/// use deep_diff::{deep_diff, export_csv};
+/// use serde_json::json;
+///
+/// let diffs = deep_diff(&json!({"n": 1}), &json!({"n": 2}));
+/// let mut out = Vec::new();
+/// export_csv(&diffs, &mut out).unwrap();
+/// assert_eq!(out, b"path,kind,severity,before,after\r\nn,changed,info,1,2\r\n");
+/// ```
+pub fn export_csv<W: Write>(differences: &[Difference], writer: W) -> io::Result<()> {
+    write_csv(differences, writer, None)
+}
+
+/// Like [`export_csv`], cutting values longer than `max_value_len`
+/// characters short with `…`, so huge values do not swamp the file.
+pub fn export_csv_truncated<W: Write>(
+    differences: &[Difference],
+    writer: W,
+    max_value_len: usize,
+) -> io::Result<()> {
+    write_csv(differences, writer, Some(max_value_len))
+}
+
+/// Writes the differences to `writer` as JSON Lines: one JSON object per
+/// line, in the format of each difference in
+/// [`DiffReport::to_json`](crate::DiffReport::to_json).
+///
+/// ```
+/// use deep_diff::{deep_diff, export_jsonl};
+/// use serde_json::json;
+///
+/// let diffs = deep_diff(&json!({"n": 1}), &json!({"n": 2}));
+/// let mut out = Vec::new();
+/// export_jsonl(&diffs, &mut out).unwrap();
+/// let line: serde_json::Value = serde_json::from_slice(&out).unwrap();
+/// assert_eq!(line["kind"], "changed");
+/// assert_eq!(line["after"], 2);
+/// ```
+pub fn export_jsonl<W: Write>(differences: &[Difference], writer: W) -> io::Result<()> {
+    write_jsonl(differences, writer, None)
+}
+
+/// Like [`export_jsonl`], replacing values whose JSON is longer than
+/// `max_value_len` characters with that JSON as a string, cut short with
+/// `…`, and marking the line with `"truncated": true`.
+pub fn export_jsonl_truncated<W: Write>(
+    differences: &[Difference],
+    writer: W,
+    max_value_len: usize,
+) -> io::Result<()> {
+    write_jsonl(differences, writer, Some(max_value_len))
+}
+
+fn write_csv<W: Write>(
+    differences: &[Difference],
+    mut writer: W,
+    max_value_len: Option<usize>,
+) -> io::Result<()> {
+    writer.write_all(b"path,kind,severity,before,after\r\n")?;
+    for diff in differences {
+        let json = difference_json(diff);
+        let field = |name: &str| json[name].as_str().unwrap_or_default().to_string();
+        let value = |value: &Option<Value>| {
+            let text = value.as_ref().map_or_else(String::new, Value::to_string);
+            match max_value_len {
+                Some(max_len) => truncate(text, max_len),
+                None => text,
+            }
+        };
+        write_row(
+            &mut writer,
+            &[
+                &diff.path,
+                &field("kind"),
+                &field("severity"),
+                &value(&diff.before),
+                &value(&diff.after),
+            ],
+        )?;
+    }
+    writer.flush()
+}
+
+// Writes one CSV row of quoted fields
+fn write_row<W: Write>(writer: &mut W, fields: &[&str]) -> io::Result<()> {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            writer.write_all(b",")?;
+        }
+        writer.write_all(csv_field(field).as_bytes())?;
+    }
+    writer.write_all(b"\r\n")
+}
+
+fn write_jsonl<W: Write>(
+    differences: &[Difference],
+    mut writer: W,
+    max_value_len: Option<usize>,
+) -> io::Result<()> {
+    for diff in differences {
+        let mut json = difference_json(diff);
+        if let (Some(max_len), Value::Object(map)) = (max_value_len, &mut json) {
+            let mut truncated = false;
+            for name in ["before", "after"] {
+                let Some(value) = map.get_mut(name) else {
+                    continue;
+                };
+                let text = value.to_string();
+                if text.chars().count() > max_len {
+                    *value = Value::String(truncate(text, max_len));
+                    truncated = true;
+                }
+            }
+            if truncated {
+                map.insert("truncated".into(), Value::Bool(true));
+            }
+        }
+        serde_json::to_writer(&mut writer, &json)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deep_diff;
+    use serde_json::json;
+
+    fn csv(differences: &[Difference], max_value_len: Option<usize>) -> String {
+        let mut out = Vec::new();
+        write_csv(differences, &mut out, max_value_len).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn jsonl(differences: &[Difference], max_value_len: Option<usize>) -> Vec<Value> {
+        let mut out = Vec::new();
+        write_jsonl(differences, &mut out, max_value_len).unwrap();
+        String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    /// Test that CSV rows quote values and leave missing ones empty.
+    #[test]
+    fn test_export_csv() {
+        let a = json!({"name": "Alice", "tags": ["a,b"], "n": null});
+        let b = json!({"name": "Bob", "tags": ["a,b", "line\nbreak"], "n": 1});
+        assert_eq!(
+            csv(&deep_diff(&a, &b), None),
+            "path,kind,severity,before,after\r\n\
+             n,type_changed,info,null,1\r\n\
+             name,changed,info,\"\"\"Alice\"\"\",\"\"\"Bob\"\"\"\r\n\
+             tags[1],added,info,,\"\"\"line\\nbreak\"\"\"\r\n"
+        );
+        assert_eq!(csv(&[], None), "path,kind,severity,before,after\r\n");
+        let mut out = Vec::new();
+        export_csv(&deep_diff(&a, &b), &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            csv(&deep_diff(&a, &b), None)
+        );
+    }
+
+    /// Test that long CSV values are cut short.
+    #[test]
+    fn test_export_csv_truncated() {
+        let diffs = deep_diff(&json!({"blob": "x".repeat(100)}), &json!({"blob": 1}));
+        let out = csv(&diffs, Some(6));
+        assert_eq!(
+            out.lines().nth(1),
+            Some("blob,type_changed,info,\"\"\"xxxx…\",1")
+        );
+    }
+
+    /// Test that JSON Lines hold one difference per line.
+    #[test]
+    fn test_export_jsonl() {
+        let a = json!({"items": [1, 2], "name": "Al"});
+        let b = json!({"items": [1], "name": "Bo"});
+        let diffs = deep_diff(&a, &b);
+        let lines = jsonl(&diffs, None);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["path"], "items[1]");
+        assert_eq!(lines[0]["kind"], "removed");
+        assert_eq!(lines[1]["before"], "Al");
+        assert!(lines[1].get("truncated").is_none());
+        let mut out = Vec::new();
+        export_jsonl(&diffs, &mut out).unwrap();
+        assert_eq!(out.iter().filter(|&&b| b == b'\n').count(), 2);
+    }
+
+    /// Test that long JSON Lines values are replaced by a cut string.
+    #[test]
+    fn test_export_jsonl_truncated() {
+        let a = json!({"blob": {"data": "x".repeat(100)}, "n": 1});
+        let b = json!({"blob": null, "n": 2});
+        let lines = jsonl(&deep_diff(&a, &b), Some(10));
+        let blob = lines.iter().find(|line| line["path"] == "blob").unwrap();
+        assert_eq!(blob["before"], "{\"data\":\"…");
+        assert_eq!(blob["after"], Value::Null);
+        assert_eq!(blob["truncated"], true);
+        let n = lines.iter().find(|line| line["path"] == "n").unwrap();
+        assert!(n.get("truncated").is_none());
+    }
+}
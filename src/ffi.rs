@@ -0,0 +1,105 @@
+//! A C interface for services written in other languages.
+//!
+//! Build a shared or static library with
+//! `cargo rustc --release --features ffi --crate-type cdylib` (or
+//! `staticlib`) and include `include/deep_diff.h`, which is generated from
+//! this module by `cbindgen`.
+
+use alloc::ffi::CString;
+use core::ffi::{CStr, c_char};
+use core::ptr;
+
+use serde_json::Value;
+
+use crate::deep_diff;
+
+/// Diffs two NUL-terminated JSON documents and returns the differences as a
+/// NUL-terminated JSON array, serialized as with the `serde` feature.
+///
+/// Returns null if either argument is null, not valid UTF-8 or not valid
+/// JSON. A non-null result must be released with
+/// [`deep_diff_string_free`].
+///
+/// # Safety
+///
+/// `a` and `b` must each be null or point to a NUL-terminated string that
+/// stays valid for the duration of the call.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn deep_diff_json_strings(a: *const c_char, b: *const c_char) -> *mut c_char {
+    // SAFETY: the caller guarantees both pointers are null or valid
+    let (Some(a), Some(b)) = (unsafe { parse(a) }, unsafe { parse(b) }) else {
+        return ptr::null_mut();
+    };
+    let json =
+        serde_json::to_string(&deep_diff(&a, &b)).expect("differences always serialize to JSON");
+    // Serialized JSON escapes every control character, NUL included
+    CString::new(json).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// Releases a string returned by [`deep_diff_json_strings`]. Does nothing
+/// if `s` is null.
+///
+/// # Safety
+///
+/// `s` must be null or a string returned by [`deep_diff_json_strings`] that
+/// has not been released yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn deep_diff_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        // SAFETY: the string was created by `CString::into_raw`
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+// Parses a C string as JSON
+unsafe fn parse(text: *const c_char) -> Option<Value> {
+    if text.is_null() {
+        return None;
+    }
+    // SAFETY: the caller guarantees the pointer is a valid C string
+    let text = unsafe { CStr::from_ptr(text) }.to_str().ok()?;
+    serde_json::from_str(text).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(a: &str, b: &str) -> Option<String> {
+        let (a, b) = (CString::new(a).unwrap(), CString::new(b).unwrap());
+        unsafe {
+            let result = deep_diff_json_strings(a.as_ptr(), b.as_ptr());
+            if result.is_null() {
+                return None;
+            }
+            let text = CStr::from_ptr(result).to_str().unwrap().to_string();
+            deep_diff_string_free(result);
+            Some(text)
+        }
+    }
+
+    /// Test that the differences are returned as a JSON array.
+    #[test]
+    fn test_deep_diff_json_strings() {
+        let result = diff(r#"{"a": 1, "b": "x"}"#, r#"{"a": 2, "b": "x"}"#).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&result).unwrap(),
+            serde_json::json!([
+                {"path": "a", "segments": ["a"], "kind": "changed", "before": 1, "after": 2}
+            ])
+        );
+        assert_eq!(diff("[1]", "[1]").as_deref(), Some("[]"));
+    }
+
+    /// Test that invalid input returns null.
+    #[test]
+    fn test_deep_diff_json_strings_invalid() {
+        assert_eq!(diff("{", "{}"), None);
+        assert_eq!(diff("1", "nope"), None);
+        let b = CString::new("1").unwrap();
+        unsafe {
+            assert!(deep_diff_json_strings(ptr::null(), b.as_ptr()).is_null());
+            deep_diff_string_free(ptr::null_mut());
+        }
+    }
+}
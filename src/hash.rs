@@ -0,0 +1,488 @@
+//! Structural hashes of JSON subtrees, used to skip identical subtrees and
+//! to detect changes without a full diff.
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as HashMap;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::hash::{Hash, Hasher};
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+use serde_json::{Map, Number, Value};
+
+use crate::decimal::Decimal;
+use crate::engine::array_pairs;
+use crate::options::{DEFAULT_OPTIONS, is_empty_container};
+use crate::{ArrayDiffStrategy, DiffOptions, Path, binary, datetime};
+
+/// Hashes `value` so that values [`deep_diff`](crate::deep_diff) finds no
+/// differences between hash the same, e.g. to find changed documents or
+/// duplicates before running a full diff.
+///
+/// The hash ignores the order of object keys and is the same across runs
+/// and platforms for a given version of the crate. It is not
+/// cryptographic: different values may collide, so a matching hash only
+/// means a diff is very likely empty.
+///
+/// ```
+/// use deep_diff::structural_hash;
+/// use serde_json::json;
+///
+/// let a = json!({"name": "Alice", "tags": ["a", "b"]});
+/// let b = json!({"tags": ["a", "b"], "name": "Alice"});
+/// assert_eq!(structural_hash(&a), structural_hash(&b));
+/// assert_ne!(structural_hash(&a), structural_hash(&json!({"name": "Bob"})));
+/// ```
+pub fn structural_hash(value: &Value) -> u64 {
+    hash_tree(value, &DEFAULT_OPTIONS, 0)
+}
+
+/// Like [`structural_hash`], hashing values the same whenever
+/// [`deep_diff_with_options`](crate::deep_diff_with_options) finds no
+/// differences between them with `options`.
+///
+/// Ignored and excluded paths are left out, `null` and empty containers
+/// are hashed as missing when the options equate them, string modes are
+/// applied, unordered and keyed arrays ignore element order, and numbers
+/// are hashed by value when coerced. Comparisons that are not exact, such
+/// as numeric tolerances, datetime tolerances and custom comparators, can
+/// only be reflected by hashing the values involved more coarsely, which
+/// makes collisions likelier. [`DiffMode`](crate::DiffMode) and
+/// [`ignore_if`](crate::DiffOptionsBuilder::ignore_if) rules are not
+/// reflected.
+///
+/// ```
+/// use deep_diff::{DiffOptions, StringCompare, structural_hash_with_options};
+/// use serde_json::json;
+///
+/// let options = DiffOptions::builder()
+///     .ignore("**.updated_at")
+///     .string_compare(StringCompare::CaseInsensitive)
+///     .build();
+/// let a = json!({"name": "Alice", "updated_at": 1});
+/// let b = json!({"name": "ALICE", "updated_at": 2});
+/// assert_eq!(
+///     structural_hash_with_options(&a, &options),
+///     structural_hash_with_options(&b, &options)
+/// );
+/// ```
+pub fn structural_hash_with_options(value: &Value, options: &DiffOptions) -> u64 {
+    hash_tree(value, options, 0)
+}
+
+/// A 256-bit [`structural_hash`], for deduplicating large collections
+/// where 64 bits would risk collisions.
+///
+/// It is built from four independently seeded 64-bit hashes, so it is no
+/// more collision resistant than them against deliberate attacks.
+pub fn structural_hash_256(value: &Value) -> [u8; 32] {
+    structural_hash_256_with_options(value, &DEFAULT_OPTIONS)
+}
+
+/// A 256-bit [`structural_hash_with_options`].
+pub fn structural_hash_256_with_options(value: &Value, options: &DiffOptions) -> [u8; 32] {
+    let mut hash = [0; 32];
+    for (seed, chunk) in hash.chunks_exact_mut(8).enumerate() {
+        chunk.copy_from_slice(&hash_tree(value, options, seed as u64).to_be_bytes());
+    }
+    hash
+}
+
+// Hashes a whole document as the options compare it, children before their
+// parents
+fn hash_tree(root: &Value, options: &DiffOptions, seed: u64) -> u64 {
+    // Paths are only assembled when some rule depends on them
+    let uses_paths = options.matches_paths()
+        || !options.ignored_paths.is_empty()
+        || !options.ignored_keys.is_empty()
+        || !options.included_paths.is_empty();
+    let child_path = |path: &Path, child: &dyn Fn(&Path) -> Path| {
+        if uses_paths {
+            child(path)
+        } else {
+            Path::root()
+        }
+    };
+    let skipped = |path: &Path| uses_paths && options.is_skipped(path);
+    // A child is left out if it is skipped or stands for a missing key
+    let kept = |path: &Path, value: &Value| !(options.absent_matches(value) || skipped(path));
+    let mut hashes: HashMap<usize, u64> = HashMap::new();
+    let mut stack = vec![(root, Path::root(), false)];
+    while let Some((value, path, children_done)) = stack.pop() {
+        // Values a custom comparator may equate cannot be told apart
+        let opaque = uses_paths
+            && options
+                .comparators
+                .iter()
+                .any(|(pattern, _)| pattern.matches(&path));
+        // Arrays read as objects are compared under the paths of the object,
+        // which their children were not hashed under
+        let coerced = options.coerce_containers
+            && uses_paths
+            && matches!(value, Value::Array(_) | Value::Object(_));
+        let shallow = opaque || coerced || options.value_comparator.is_some();
+        if !children_done && !shallow {
+            let children: Vec<(&Value, Path)> = match value {
+                Value::Array(values) => values
+                    .iter()
+                    .enumerate()
+                    .map(|(index, v)| (v, child_path(&path, &|p| p.child_index(index))))
+                    .filter(|(_, path)| !skipped(path))
+                    .collect(),
+                Value::Object(map) => map
+                    .iter()
+                    .map(|(key, v)| (v, child_path(&path, &|p| p.child_key(key.as_str()))))
+                    .filter(|(v, path)| kept(path, v))
+                    .collect(),
+                _ => Vec::new(),
+            };
+            stack.push((value, path, true));
+            stack.extend(children.into_iter().map(|(v, path)| (v, path, false)));
+            continue;
+        }
+        let mut hasher = new_hasher();
+        seed.hash(&mut hasher);
+        let value_or_null = if options.empty_equals_missing && is_empty_container(value) {
+            &Value::Null
+        } else {
+            value
+        };
+        if opaque {
+            hashes.insert(address(value), hasher.finish());
+            continue;
+        }
+        match value_or_null {
+            // Arrays may equal objects once read as them
+            Value::Array(_) if options.coerce_containers => {
+                core::mem::discriminant(&Value::Object(Map::new())).hash(&mut hasher)
+            }
+            other => core::mem::discriminant(other).hash(&mut hasher),
+        }
+        match value_or_null {
+            _ if shallow => {}
+            Value::Array(values) if options.coerce_containers => {
+                let entries = match array_pairs(values) {
+                    Some(pairs) => entries_hash(
+                        pairs
+                            .into_iter()
+                            .filter(|(_, v)| kept(&path, v))
+                            .map(|(key, v)| (key.as_str(), hashes[&address(v)])),
+                    ),
+                    None => entries_hash(
+                        values
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, v)| kept(&path, v))
+                            .map(|(index, v)| (index.to_string(), hashes[&address(v)])),
+                    ),
+                };
+                entries.hash(&mut hasher);
+            }
+            Value::Null => {}
+            Value::Bool(b) => b.hash(&mut hasher),
+            Value::Number(n) => hash_number(n, options, &mut hasher),
+            Value::String(s) => hash_string(s, &path, options, &mut hasher),
+            Value::Array(values) => {
+                let unordered = options.array_strategy_for(&path)
+                    == ArrayDiffStrategy::UnorderedSet
+                    || options.array_key_for(&path).is_some();
+                let children = values
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| !skipped(&child_path(&path, &|p| p.child_index(*index))))
+                    .map(|(_, v)| hashes[&address(v)]);
+                if unordered {
+                    children.fold(0u64, u64::wrapping_add).hash(&mut hasher);
+                } else {
+                    children.for_each(|child| child.hash(&mut hasher));
+                }
+            }
+            Value::Object(map) => {
+                let entries = entries_hash(
+                    map.iter()
+                        .filter(|(key, v)| {
+                            kept(&child_path(&path, &|p| p.child_key(key.as_str())), v)
+                        })
+                        .map(|(key, v)| (key.as_str(), hashes[&address(v)])),
+                );
+                entries.hash(&mut hasher);
+            }
+        }
+        hashes.insert(address(value), hasher.finish());
+    }
+    hashes[&address(root)]
+}
+
+// Combines the hashes of an object's entries whatever their order
+fn entries_hash<K: AsRef<str>>(entries: impl Iterator<Item = (K, u64)>) -> u64 {
+    entries.fold(0u64, |sum, (key, hash)| {
+        let mut entry = new_hasher();
+        key.as_ref().hash(&mut entry);
+        hash.hash(&mut entry);
+        sum.wrapping_add(entry.finish())
+    })
+}
+
+fn hash_number(number: &Number, options: &DiffOptions, hasher: &mut impl Hasher) {
+    // Numbers within a tolerance of each other may hash differently whatever
+    // is hashed, so none of the value is
+    if options.numeric_epsilon.is_some() || options.decimal_tolerance.is_some() {
+        return;
+    }
+    match Decimal::of(number) {
+        Some(decimal) if options.coerce_numbers => decimal.by_value().hash(hasher),
+        Some(decimal) if cfg!(feature = "arbitrary-precision") => decimal.hash(hasher),
+        _ => number.to_string().hash(hasher),
+    }
+}
+
+fn hash_string(text: &str, path: &Path, options: &DiffOptions, hasher: &mut impl Hasher) {
+    if options
+        .binary_paths
+        .iter()
+        .any(|pattern| pattern.matches(path))
+        && let Some(bytes) = binary::decode(text)
+    {
+        bytes.hash(hasher);
+        return;
+    }
+    // Instants within the tolerance may hash differently, like numbers
+    if options.datetime_tolerance.is_some() && datetime::parse_instant(text).is_some() {
+        return;
+    }
+    options.normalize_string(path, text).hash(hasher);
+}
+
+/// The structural hash of every subtree of a document, keyed by the
+/// address of the value at its root.
+///
+/// Equal values always hash the same, regardless of the order of their
+/// object keys.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SubtreeHashes(HashMap<usize, u64>);
+
+impl SubtreeHashes {
+    /// Hashes every subtree of `value`, children before their parents.
+    pub(crate) fn new(value: &Value) -> Self {
+        let mut hashes: HashMap<usize, u64> = HashMap::new();
+        // Each value is visited twice: once to queue its children, and once
+        // they have all been hashed
+        let mut stack = vec![(value, false)];
+        while let Some((value, children_done)) = stack.pop() {
+            if !children_done {
+                stack.push((value, true));
+                match value {
+                    Value::Array(values) => stack.extend(values.iter().map(|v| (v, false))),
+                    Value::Object(map) => stack.extend(map.values().map(|v| (v, false))),
+                    _ => {}
+                }
+                continue;
+            }
+            let child = |v: &Value| hashes[&address(v)];
+            let mut hasher = new_hasher();
+            core::mem::discriminant(value).hash(&mut hasher);
+            match value {
+                Value::Null => {}
+                Value::Bool(b) => b.hash(&mut hasher),
+                // Equal numbers may be written differently when their text
+                // is kept
+                Value::Number(n) => match Decimal::of(n) {
+                    Some(decimal) if cfg!(feature = "arbitrary-precision") => {
+                        decimal.hash(&mut hasher)
+                    }
+                    _ => n.to_string().hash(&mut hasher),
+                },
+                Value::String(s) => s.hash(&mut hasher),
+                Value::Array(values) => {
+                    for v in values {
+                        child(v).hash(&mut hasher);
+                    }
+                }
+                // Entries are combined commutatively, so key order does not matter
+                Value::Object(map) => {
+                    let entries = map.iter().fold(0u64, |sum, (key, v)| {
+                        let mut entry = new_hasher();
+                        key.hash(&mut entry);
+                        child(v).hash(&mut entry);
+                        sum.wrapping_add(entry.finish())
+                    });
+                    entries.hash(&mut hasher);
+                }
+            }
+            hashes.insert(address(value), hasher.finish());
+        }
+        SubtreeHashes(hashes)
+    }
+
+    /// The hash of `value`, which must belong to the hashed document.
+    pub(crate) fn get(&self, value: &Value) -> Option<u64> {
+        self.0.get(&address(value)).copied()
+    }
+}
+
+fn address(value: &Value) -> usize {
+    value as *const Value as usize
+}
+
+// A deterministic hasher; `core` only has SipHash-2-4, which `std`'s
+// default hasher improves on
+#[cfg(feature = "std")]
+fn new_hasher() -> std::hash::DefaultHasher {
+    std::hash::DefaultHasher::new()
+}
+
+#[cfg(not(feature = "std"))]
+#[allow(deprecated)]
+fn new_hasher() -> core::hash::SipHasher {
+    core::hash::SipHasher::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Test that equal subtrees hash the same and different ones do not.
+    #[test]
+    fn test_subtree_hashes() {
+        let a = json!({"x": {"k": [1, 2]}, "y": {"k": [1, 2]}, "z": {"k": [2, 1]}});
+        let hashes = SubtreeHashes::new(&a);
+        let hash = |v: &Value| hashes.get(v).unwrap();
+        assert_eq!(hash(&a["x"]), hash(&a["y"]));
+        assert_ne!(hash(&a["x"]), hash(&a["z"]));
+        assert_ne!(hash(&a["x"]["k"][0]), hash(&a["x"]["k"][1]));
+        assert_eq!(hashes.get(&json!(1)), None);
+    }
+
+    /// Test that distinct scalars of similar shape hash differently.
+    #[test]
+    fn test_subtree_hashes_scalars() {
+        let a = json!([null, false, 0, "0", "", [], {}, 1.0, 1]);
+        let hashes = SubtreeHashes::new(&a);
+        let all: Vec<u64> = a
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| hashes.get(v).unwrap())
+            .collect();
+        for (i, x) in all.iter().enumerate() {
+            for y in &all[i + 1..] {
+                assert_ne!(x, y);
+            }
+        }
+    }
+
+    /// Test that the structural hash follows `deep_diff` equality.
+    #[test]
+    fn test_structural_hash() {
+        let a = json!({"a": [1, {"b": null}], "c": "x"});
+        let b = json!({"c": "x", "a": [1, {"b": null}]});
+        assert_eq!(structural_hash(&a), structural_hash(&b));
+        for other in [
+            json!({"a": [{"b": null}, 1], "c": "x"}),
+            json!({"a": [1, {}], "c": "x"}),
+            json!({"a": [1, {"b": null}], "c": "y"}),
+            json!({"a": [1.0, {"b": null}], "c": "x"}),
+        ] {
+            assert_ne!(structural_hash(&a), structural_hash(&other));
+        }
+        let wide = structural_hash_256(&a);
+        assert_eq!(wide, structural_hash_256(&b));
+        assert_eq!(wide[..8], structural_hash(&a).to_be_bytes());
+        assert_ne!(wide[..8], wide[8..16]);
+    }
+
+    /// Test that documents equal under the options hash the same.
+    #[test]
+    fn test_structural_hash_with_options() {
+        let options = DiffOptions::builder()
+            .ignore("meta")
+            .string_compare(crate::StringCompare::TrimWhitespace)
+            .null_semantics(crate::NullSemantics::AbsentEqualsNull)
+            .array_strategy_at("tags", ArrayDiffStrategy::UnorderedSet)
+            .coerce_numbers(true)
+            .build();
+        let pairs = [
+            (json!({"a": 1, "meta": 1}), json!({"a": 1.0, "meta": 2})),
+            (json!({"s": " x ", "n": null}), json!({"s": "x"})),
+            (json!({"tags": [1, 2, 2]}), json!({"tags": [2, 1, 2]})),
+        ];
+        for (a, b) in &pairs {
+            assert!(crate::deep_diff_with_options(a, b, &options).is_empty());
+            assert_eq!(
+                structural_hash_with_options(a, &options),
+                structural_hash_with_options(b, &options)
+            );
+            assert_ne!(structural_hash(a), structural_hash(b));
+        }
+        let (a, b) = (json!({"tags": [1, 2]}), json!({"tags": [1, 3]}));
+        assert_ne!(
+            structural_hash_with_options(&a, &options),
+            structural_hash_with_options(&b, &options)
+        );
+    }
+
+    /// Test that ignored array elements are left out of the hash.
+    #[test]
+    fn test_structural_hash_ignored_index() {
+        let options = DiffOptions::builder().ignore("items[0]").build();
+        let a = json!({"items": [1, 2]});
+        let b = json!({"items": [9, 2]});
+        assert!(crate::deep_diff_with_options(&a, &b, &options).is_empty());
+        assert_eq!(
+            structural_hash_with_options(&a, &options),
+            structural_hash_with_options(&b, &options)
+        );
+        let c = json!({"items": [1, 3]});
+        assert_ne!(
+            structural_hash_with_options(&a, &options),
+            structural_hash_with_options(&c, &options)
+        );
+    }
+
+    /// Test that arrays hash like the objects they are compared with.
+    #[test]
+    fn test_structural_hash_coerce_containers() {
+        let options = DiffOptions::builder().coerce_containers(true).build();
+        let pairs = [
+            (json!([["a", 1], ["b", [2]]]), json!({"b": [2], "a": 1})),
+            (
+                json!({"x": [true, null]}),
+                json!({"x": {"1": null, "0": true}}),
+            ),
+        ];
+        for (a, b) in &pairs {
+            assert!(crate::deep_diff_with_options(a, b, &options).is_empty());
+            assert_eq!(
+                structural_hash_with_options(a, &options),
+                structural_hash_with_options(b, &options)
+            );
+        }
+        assert_ne!(
+            structural_hash_with_options(&json!([1]), &options),
+            structural_hash_with_options(&json!({"0": 2}), &options)
+        );
+    }
+
+    /// Test that inexact comparisons hash the values involved coarsely.
+    #[test]
+    fn test_structural_hash_tolerances() {
+        let options = DiffOptions::builder()
+            .numeric_epsilon(0.5)
+            .compare_at("id", |_, _| crate::Comparison::Equal)
+            .build();
+        let a = json!({"x": 1.0, "id": "a"});
+        let b = json!({"x": 1.4, "id": [1]});
+        assert!(crate::deep_diff_with_options(&a, &b, &options).is_empty());
+        assert_eq!(
+            structural_hash_with_options(&a, &options),
+            structural_hash_with_options(&b, &options)
+        );
+        assert_ne!(
+            structural_hash_with_options(&a, &options),
+            structural_hash_with_options(&json!({"x": "1", "id": "a"}), &options)
+        );
+    }
+}
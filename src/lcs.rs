@@ -0,0 +1,135 @@
+//! Longest common subsequence of two sequences using Myers' O(ND) algorithm.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Returns the index pairs `(i, j)` of a longest common subsequence of `a`
+/// and `b`, in increasing order, where `eq(&a[i], &b[j])` holds.
+pub(crate) fn lcs<T>(a: &[T], b: &[T], eq: impl Fn(&T, &T) -> bool) -> Vec<(usize, usize)> {
+    // Common prefixes and suffixes are matched directly, which keeps the
+    // search below small for the usual case of a few local edits
+    let prefix = a.iter().zip(b).take_while(|(x, y)| eq(x, y)).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| eq(x, y))
+        .count();
+    let mid_a = &a[prefix..a.len() - suffix];
+    let mid_b = &b[prefix..b.len() - suffix];
+
+    let mut matches: Vec<(usize, usize)> = (0..prefix).map(|i| (i, i)).collect();
+    matches.extend(
+        myers(mid_a, mid_b, &eq)
+            .into_iter()
+            .map(|(i, j)| (i + prefix, j + prefix)),
+    );
+    let (a_tail, b_tail) = (a.len() - suffix, b.len() - suffix);
+    matches.extend((0..suffix).map(|k| (a_tail + k, b_tail + k)));
+    matches
+}
+
+// Finds the shortest edit script between `a` and `b` and returns its matches
+fn myers<T>(a: &[T], b: &[T], eq: &impl Fn(&T, &T) -> bool) -> Vec<(usize, usize)> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = n + m;
+    if max == 0 {
+        return Vec::new();
+    }
+    // `v[at(k)]` holds the furthest x reached on diagonal k = x - y
+    let at = |k: isize| (k + max) as usize;
+    let mut v = vec![0isize; 2 * max as usize + 2];
+    let mut trace = Vec::new();
+
+    // Forward pass: extend the furthest reaching path on each diagonal
+    'search: for d in 0..=max {
+        trace.push(v.clone());
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[at(k - 1)] < v[at(k + 1)]) {
+                v[at(k + 1)]
+            } else {
+                v[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && eq(&a[x as usize], &b[y as usize]) {
+                x += 1;
+                y += 1;
+            }
+            v[at(k)] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    // Backward pass: walk the trace to recover the diagonal (matching) moves
+    let mut matches = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && v[at(k - 1)] < v[at(k + 1)]) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = v[at(prev_k)];
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            matches.push((x as usize - 1, y as usize - 1));
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            x = prev_x;
+            y = prev_y;
+        }
+    }
+    matches.reverse();
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    fn common(a: &str, b: &str) -> String {
+        let (a, b) = (chars(a), chars(b));
+        lcs(&a, &b, |x, y| x == y)
+            .into_iter()
+            .map(|(i, j)| {
+                assert_eq!(a[i], b[j]);
+                a[i]
+            })
+            .collect()
+    }
+
+    /// Test the classic example from Myers' paper.
+    #[test]
+    fn test_lcs_myers_example() {
+        assert_eq!(common("ABCABBA", "CBABAC").len(), 4);
+    }
+
+    /// Test empty and identical inputs.
+    #[test]
+    fn test_lcs_trivial() {
+        assert_eq!(common("", ""), "");
+        assert_eq!(common("abc", ""), "");
+        assert_eq!(common("", "abc"), "");
+        assert_eq!(common("abc", "abc"), "abc");
+        assert_eq!(common("abc", "xyz"), "");
+    }
+
+    /// Test insertions and deletions around shared elements.
+    #[test]
+    fn test_lcs_edits() {
+        assert_eq!(common("bcd", "abcd"), "bcd");
+        assert_eq!(common("abcd", "acd"), "acd");
+        assert_eq!(common("axbycz", "abc"), "abc");
+        assert_eq!(common("kitten", "sitting"), "ittn");
+    }
+}
@@ -10,102 +10,424 @@
 //! let b = json!({"name": "Bob"});
 //! let diffs = deep_diff(&a, &b);
 //! assert_eq!(diffs[0].path, "name");
+//! ```
 //!
+//! # `no_std`
+//!
+//! Without the default `std` feature the crate only needs `alloc`, so the
+//! diff engine runs on embedded and `wasm32-unknown-unknown` targets.
+//! Streaming, metrics, colored output, exports and the `parallel` feature
+//! need `std`.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
 
 use serde_json::Value;
 
+pub mod assert;
+pub mod audit;
+mod batch;
+mod binary;
+mod capture;
+mod changelog;
+mod compose;
+mod datetime;
+mod decimal;
+mod engine;
+#[cfg(feature = "std")]
+mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod hash;
+mod lcs;
+mod merge;
+mod minimize;
+mod multi;
+pub mod normalize;
+mod options;
+#[cfg(feature = "parallel")]
+mod parallel;
+mod patch;
+mod path;
+mod policy;
+#[cfg(test)]
+mod properties;
+mod quantity;
+#[cfg(feature = "serde")]
+mod raw;
+mod records;
+mod redact;
+pub mod render;
+mod report;
+mod schema;
+#[cfg(feature = "serde")]
+mod serialize;
+mod set;
+pub mod specdiff;
+#[cfg(feature = "std")]
+mod stream;
+mod summary;
+mod text;
+mod typed;
+mod visit;
+
+// Lets the code generated by `#[derive(DeepDiff)]` be tested in this crate
+#[cfg(all(test, feature = "derive"))]
+extern crate self as deep_diff;
+
+pub use batch::{diff_batch, diff_batch_with_callback, diff_batch_with_options};
+pub use capture::Truncated;
+pub use changelog::summarize;
+pub use compose::compose;
+#[cfg(feature = "derive")]
+pub use deep_diff_derive::DeepDiff;
+#[cfg(feature = "binary-formats")]
+pub use engine::deep_diff_serialize;
+pub use engine::{
+    DiffEngine, DiffError, DiffResult, DiffSession, are_different, are_different_with_options,
+    deep_diff, deep_diff_at, deep_diff_at_with_options, deep_diff_ref, deep_diff_ref_with_options,
+    deep_diff_result, deep_diff_with_options, diff_iter, diff_iter_with_options, first_difference,
+    first_difference_with_options, try_deep_diff,
+};
+#[cfg(feature = "std")]
+pub use engine::{DiffMetrics, deep_diff_with_metrics};
+#[cfg(feature = "serde")]
+pub use engine::{deep_diff_str, diff_values};
+#[cfg(feature = "std")]
+pub use export::{export_csv, export_csv_truncated, export_jsonl, export_jsonl_truncated};
+pub use hash::{
+    structural_hash, structural_hash_256, structural_hash_256_with_options,
+    structural_hash_with_options,
+};
+pub use merge::{Conflict, MergeResult, three_way_merge};
+pub use minimize::{minimize, minimize_with_threshold};
+pub use multi::{MultiDifference, deep_diff_many};
+pub use options::{
+    ArrayDiffStrategy, Comparison, DiffMode, DiffOptions, DiffOptionsBuilder, DuplicateKeys,
+    NullSemantics, StringCompare, ValueComparator,
+};
+pub use patch::{
+    ApplyError, ApplyFailure, ConflictReport, ParsePatchError, apply_diff, apply_diff_checked,
+    check_applicability, from_json_patch, from_merge_patch, merge_patch, to_json_patch,
+};
+pub use path::{ParsePathError, Path, PathFormat, PathPattern, PathSegment};
+pub use policy::DiffPolicy;
+pub use records::{
+    ColumnDiff, ColumnExample, RecordDiff, RecordSetDiff, TableDiff, diff_record_sets,
+    diff_record_sets_with_options,
+};
+pub use redact::REDACTED;
+pub use report::{DiffReport, KindGroups, ReportOrder};
+pub use schema::{SchemaDiff, SchemaViolation, deep_diff_with_schema, validate_schema};
+pub use set::DiffSet;
+#[cfg(feature = "std")]
+pub use stream::{StreamDiff, diff_streams};
+pub use summary::{DiffSummary, similarity, similarity_with_options};
+pub use text::{TextDelta, TextGranularity, TextOp};
+pub use typed::DeepDiff;
+#[doc(hidden)]
+pub use typed::private as __private;
+// The renderers were first published as `deep_diff::format`
+#[doc(hidden)]
+pub use render as format;
+pub use visit::{DiffVisitor, walk_diff, walk_diff_with_options};
+
+/// The kind of change a [`Difference`] describes.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DiffKind {
+    /// The value only exists in the second input.
+    Added,
+    /// The value only exists in the first input.
+    Removed,
+    /// The value exists in both inputs with the same type but differs.
+    Changed,
+    /// The value exists in both inputs but its JSON type differs.
+    TypeChanged {
+        /// The type before the change.
+        from: JsonType,
+        /// The type after the change.
+        to: JsonType,
+    },
+    /// An array element was moved from index `from` to index `to`, where
+    /// `to` is also the last segment of the difference's path.
+    Moved {
+        /// The element's index before the move.
+        from: usize,
+        /// The element's index after the move.
+        to: usize,
+    },
+    /// A base64-encoded field registered with
+    /// [`DiffOptionsBuilder::binary_at`] decodes to different bytes. The
+    /// difference carries no `before` or `after`, only the size and FNV-1a
+    /// hash of both payloads.
+    BinaryChanged {
+        /// The number of bytes before the change.
+        old_len: usize,
+        /// The number of bytes after the change.
+        new_len: usize,
+        /// The hash of the bytes before the change.
+        old_hash: u64,
+        /// The hash of the bytes after the change.
+        new_hash: u64,
+    },
+    /// An object lists the keys it shares with the other input in a
+    /// different order, as found by [`deep_diff_str`] with
+    /// [`DiffOptionsBuilder::compare_key_order`]. `before` and `after` hold
+    /// the object's keys in their order on each side.
+    KeyOrderChanged,
+    /// An object key was renamed from `from_key` to `to_key`, which is also
+    /// the last segment of the difference's path, as found with
+    /// [`DiffOptionsBuilder::detect_renames`]. `before` and `after` hold
+    /// the value under each key, which may differ if renames of similar
+    /// values are detected.
+    Renamed {
+        /// The key before the rename.
+        from_key: String,
+        /// The key after the rename.
+        to_key: String,
+    },
+}
+
+impl DiffKind {
+    /// Returns the kind of change that undoes this one.
+    pub fn invert(self) -> DiffKind {
+        match self {
+            DiffKind::Added => DiffKind::Removed,
+            DiffKind::Removed => DiffKind::Added,
+            DiffKind::TypeChanged { from, to } => DiffKind::TypeChanged { from: to, to: from },
+            DiffKind::Moved { from, to } => DiffKind::Moved { from: to, to: from },
+            DiffKind::Renamed { from_key, to_key } => DiffKind::Renamed {
+                from_key: to_key,
+                to_key: from_key,
+            },
+            DiffKind::BinaryChanged {
+                old_len,
+                new_len,
+                old_hash,
+                new_hash,
+            } => DiffKind::BinaryChanged {
+                old_len: new_len,
+                new_len: old_len,
+                old_hash: new_hash,
+                new_hash: old_hash,
+            },
+            kind => kind,
+        }
+    }
+}
+
+/// How much a difference matters, as configured with
+/// [`DiffOptionsBuilder::severity`].
+///
+/// Severities are ordered, so `diff.severity >= Severity::Warning` selects
+/// warnings and critical differences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Severity {
+    /// A difference no rule singled out.
+    #[default]
+    Info,
+    /// A difference worth a closer look.
+    Warning,
+    /// A difference that should, e.g., fail a build.
+    Critical,
+}
+
+/// The type of a JSON value, as reported by [`DiffKind::TypeChanged`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum JsonType {
+    /// `null`.
+    Null,
+    /// `true` or `false`.
+    Bool,
+    /// An integer or floating point number.
+    Number,
+    /// A string.
+    String,
+    /// An array.
+    Array,
+    /// An object.
+    Object,
+}
+
+impl JsonType {
+    /// Returns the type of `value`.
+    pub fn of(value: &Value) -> JsonType {
+        match value {
+            Value::Null => JsonType::Null,
+            Value::Bool(_) => JsonType::Bool,
+            Value::Number(_) => JsonType::Number,
+            Value::String(_) => JsonType::String,
+            Value::Array(_) => JsonType::Array,
+            Value::Object(_) => JsonType::Object,
+        }
+    }
+
+    /// Returns the type's name as used by JSON Schema, e.g. `"boolean"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            JsonType::Null => "null",
+            JsonType::Bool => "boolean",
+            JsonType::Number => "number",
+            JsonType::String => "string",
+            JsonType::Array => "array",
+            JsonType::Object => "object",
+        }
+    }
+}
+
+impl core::fmt::Display for JsonType {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Difference {
     /// The path to the value that changed (e.g., `"name"` or `"items[0]"`).
     pub path: String,
+    /// The same location as `path`, split into typed segments.
+    pub segments: Path,
+    /// What kind of change happened at `path`.
+    pub kind: DiffKind,
+    /// How much the change matters, from the highest matching
+    /// [`severity`](DiffOptionsBuilder::severity) rule.
+    pub severity: Severity,
     /// The value before the change (in the first input).
     pub before: Option<Value>,
     /// The value after the change (in the second input).
     pub after: Option<Value>,
+    /// The unchanged keys of the object holding the change, or the array
+    /// elements around it, when [`context`](DiffOptionsBuilder::context) is
+    /// set.
+    pub context: Option<Value>,
+    /// Which of `before` and `after` were replaced by a
+    /// [`redact`](DiffOptionsBuilder::redact) placeholder or a
+    /// [`Truncated`] summary.
+    pub withheld: Withheld,
 }
 
-// Determines if two json types are equivalent
-fn same_json_type(a: &Value, b: &Value) -> bool {
-    std::mem::discriminant(a) == std::mem::discriminant(b)
+/// Records which values of a [`Difference`] were withheld from the report.
+///
+/// A withheld value no longer holds the real data, so a difference whose
+/// `after` is withheld cannot be applied. The record is kept apart from the
+/// values, so real data that happens to look like a placeholder is never
+/// mistaken for one.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Withheld {
+    /// Whether `before` was redacted or truncated.
+    pub before: bool,
+    /// Whether `after` was redacted or truncated.
+    pub after: bool,
 }
 
-fn recurse(a: &Value, b: &Value, differences: &mut Vec<Difference>, path: String) {
-    if !same_json_type(a, b) {
-        differences.push(Difference {
-            path: path.clone(),
-            before: Some(a.clone()),
-            after: Some(b.clone()),
-        });
-        return;
-    }
-    match a {
-        // Deals with primitive types
-        Value::String(_) | Value::Number(_) | Value::Bool(_) | Value::Null => {
-            if a != b {
-                differences.push(Difference {
-                    path: path.clone(),
-                    before: Some(a.clone()),
-                    after: Some(b.clone()),
-                })
-            }
-        }
-        // Deals with arrays
-        Value::Array(a_values) => {
-            let b_values = b.as_array().unwrap();
-            for i in 0..a_values.len().max(b_values.len()) {
-                let va = a_values.get(i).unwrap_or(&Value::Null);
-                let vb = b_values.get(i).unwrap_or(&Value::Null);
-                recurse(va, vb, differences, format!("{}[{}]", path, i));
-            }
+impl Withheld {
+    /// Returns whether neither value was withheld.
+    pub fn is_none(&self) -> bool {
+        !self.before && !self.after
+    }
+}
+
+impl Difference {
+    /// Returns the path rendered as an RFC 6901 JSON Pointer
+    /// (e.g. `"/person/name/first"` or `"/items/0"`).
+    pub fn pointer(&self) -> String {
+        self.segments.to_pointer()
+    }
+
+    /// Returns the edits inside a changed string, so changes to long text
+    /// are pinpointed rather than shown as two whole strings.
+    ///
+    /// Returns `None` unless both `before` and `after` are strings. The
+    /// delta is computed on each call.
+    pub fn text_delta(&self, granularity: TextGranularity) -> Option<TextDelta> {
+        let before = self.before.as_ref()?.as_str()?;
+        let after = self.after.as_ref()?.as_str()?;
+        Some(TextDelta::new(before, after, granularity))
+    }
+
+    /// Returns the difference that undoes this one, with `before` and
+    /// `after` swapped.
+    pub fn invert(&self) -> Difference {
+        Difference {
+            path: self.path.clone(),
+            segments: self.segments.clone(),
+            kind: self.kind.clone().invert(),
+            severity: self.severity,
+            before: self.after.clone(),
+            after: self.before.clone(),
+            context: self.context.clone(),
+            withheld: Withheld {
+                before: self.withheld.after,
+                after: self.withheld.before,
+            },
         }
-        // Deals with objects
-        Value::Object(map) => {
-            for (ak, av) in map {
-                match b.get(ak) {
-                    Some(bv) => {
-                        let full_path = if path.is_empty() {
-                            ak.to_string()
-                        } else {
-                            format!("{}.{}", path, ak)
-                        };
-                        recurse(av, bv, differences, full_path);
-                    }
-                    None => differences.push(Difference {
-                        path: format!("{}", ak),
-                        before: Some(av.clone()),
-                        after: None,
-                    }),
-                }
-            }
-            for (bk, bv) in b.as_object().unwrap() {
-                if !map.contains_key(bk) {
-                    let full_path = if path.is_empty() {
-                        bk.to_string()
-                    } else {
-                        format!("{}.{}", path, bk)
-                    };
-                    differences.push(Difference {
-                        path: full_path,
-                        before: None,
-                        after: Some(bv.clone()),
-                    });
-                }
-            }
+    }
+}
+
+/// A [`Difference`] that borrows its values from the documents being
+/// compared instead of cloning them.
+///
+/// Returned by [`deep_diff_ref`]; values are only owned when a custom
+/// comparator reported ones that do not appear in either document.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DifferenceRef<'a> {
+    /// The path to the value that changed, as in [`Difference::path`].
+    pub path: String,
+    /// The same location as `path`, split into typed segments.
+    pub segments: Path,
+    /// What kind of change happened at `path`.
+    pub kind: DiffKind,
+    /// How much the change matters, as in [`Difference::severity`].
+    pub severity: Severity,
+    /// The value before the change (in the first input).
+    pub before: Option<Cow<'a, Value>>,
+    /// The value after the change (in the second input).
+    pub after: Option<Cow<'a, Value>>,
+    /// Which values were withheld, as in [`Difference::withheld`].
+    pub withheld: Withheld,
+}
+
+impl DifferenceRef<'_> {
+    /// Clones the borrowed values into an owned [`Difference`].
+    pub fn to_owned(&self) -> Difference {
+        self.clone().into()
+    }
+}
+
+impl From<DifferenceRef<'_>> for Difference {
+    fn from(difference: DifferenceRef<'_>) -> Self {
+        Difference {
+            path: difference.path,
+            segments: difference.segments,
+            kind: difference.kind,
+            severity: difference.severity,
+            before: difference.before.map(Cow::into_owned),
+            after: difference.after.map(Cow::into_owned),
+            context: None,
+            withheld: difference.withheld,
         }
     }
 }
 
-/// Computes the differences between two JSON values.
-pub fn deep_diff(a: &Value, b: &Value) -> Vec<Difference> {
-    let mut differences = Vec::new();
-    recurse(a, b, &mut differences, "".to_string());
-    differences
+/// Inverts a whole diff so it rolls a document back to its previous state.
+///
+/// The differences are returned in reverse order, so that applying the
+/// result undoes the original changes one by one.
+pub fn invert_diff(differences: &[Difference]) -> Vec<Difference> {
+    differences.iter().rev().map(Difference::invert).collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use serde_json::{Value, json};
+    use alloc::sync::Arc;
+    use core::time::Duration;
+    use serde_json::{Number, Value, json};
 
     /// Test that no differences are found when comparing identical primitive JSON values.
     #[test]
@@ -125,8 +447,13 @@ mod tests {
             result,
             vec![Difference {
                 path: "".to_string(),
+                segments: "".parse().unwrap(),
+                kind: DiffKind::Changed,
+                severity: Severity::Info,
                 before: Some(json!("Alice")),
                 after: Some(json!("Bob")),
+                context: None,
+                withheld: Withheld::default(),
             }]
         );
     }
@@ -145,8 +472,13 @@ mod tests {
             result,
             vec![Difference {
                 path: "[1]".to_string(),
+                segments: "[1]".parse().unwrap(),
+                kind: DiffKind::Changed,
+                severity: Severity::Info,
                 before: Some(json!(2)),
                 after: Some(json!(3)),
+                context: None,
+                withheld: Withheld::default(),
             }]
         );
     }
@@ -161,8 +493,13 @@ mod tests {
             result,
             vec![Difference {
                 path: "[1]".to_string(),
+                segments: "[1]".parse().unwrap(),
+                kind: DiffKind::Changed,
+                severity: Severity::Info,
                 before: Some(json!("Bob")),
                 after: Some(json!("Hob")),
+                context: None,
+                withheld: Withheld::default(),
             }]
         );
     }
@@ -177,12 +514,85 @@ mod tests {
             result,
             vec![Difference {
                 path: "[1]".to_string(),
+                segments: "[1]".parse().unwrap(),
+                kind: DiffKind::Removed,
+                severity: Severity::Info,
                 before: Some(json!(2)),
-                after: Some(Value::Null),
+                after: None,
+                context: None,
+                withheld: Withheld::default(),
             }]
         );
     }
 
+    /// Test that several extra elements are removed from the back.
+    #[test]
+    fn test_array_shrunk() {
+        let a = json!([1, 2, null]);
+        let b = json!([1]);
+        let result = deep_diff(&a, &b);
+        assert_eq!(
+            result,
+            vec![
+                Difference {
+                    path: "[2]".to_string(),
+                    segments: "[2]".parse().unwrap(),
+                    kind: DiffKind::Removed,
+                    severity: Severity::Info,
+                    before: Some(Value::Null),
+                    after: None,
+                    context: None,
+                    withheld: Withheld::default(),
+                },
+                Difference {
+                    path: "[1]".to_string(),
+                    segments: "[1]".parse().unwrap(),
+                    kind: DiffKind::Removed,
+                    severity: Severity::Info,
+                    before: Some(json!(2)),
+                    after: None,
+                    context: None,
+                    withheld: Withheld::default(),
+                },
+            ]
+        );
+        assert_eq!(apply_diff(&a, &result), Ok(b));
+    }
+
+    /// Test that extra elements in the second array are reported as added.
+    #[test]
+    fn test_array_grown() {
+        let a = json!([1]);
+        let b = json!([1, null, 3]);
+        let result = deep_diff(&a, &b);
+        assert_eq!(
+            result,
+            vec![
+                Difference {
+                    path: "[1]".to_string(),
+                    segments: "[1]".parse().unwrap(),
+                    kind: DiffKind::Added,
+                    severity: Severity::Info,
+                    before: None,
+                    after: Some(Value::Null),
+                    context: None,
+                    withheld: Withheld::default(),
+                },
+                Difference {
+                    path: "[2]".to_string(),
+                    segments: "[2]".parse().unwrap(),
+                    kind: DiffKind::Added,
+                    severity: Severity::Info,
+                    before: None,
+                    after: Some(json!(3)),
+                    context: None,
+                    withheld: Withheld::default(),
+                },
+            ]
+        );
+        assert_eq!(apply_diff(&a, &result), Ok(b));
+    }
+
     // ======================
     // Object Comparison Tests
     // ======================
@@ -205,8 +615,13 @@ mod tests {
             result,
             vec![Difference {
                 path: "age".to_string(),
+                segments: "age".parse().unwrap(),
+                kind: DiffKind::Changed,
+                severity: Severity::Info,
                 before: Some(json!(25)),
                 after: Some(json!(26)),
+                context: None,
+                withheld: Withheld::default(),
             }]
         );
     }
@@ -225,8 +640,13 @@ mod tests {
             result,
             vec![Difference {
                 path: "person.name.first".to_string(),
+                segments: "person.name.first".parse().unwrap(),
+                kind: DiffKind::Changed,
+                severity: Severity::Info,
                 before: Some(json!("Alice")),
                 after: Some(json!("Bob")),
+                context: None,
+                withheld: Withheld::default(),
             }]
         );
     }
@@ -241,9 +661,1890 @@ mod tests {
             result,
             vec![Difference {
                 path: "person.name.first[2]".to_string(),
+                segments: "person.name.first[2]".parse().unwrap(),
+                kind: DiffKind::Changed,
+                severity: Severity::Info,
                 before: Some(json!(3)),
                 after: Some(json!(4)),
+                context: None,
+                withheld: Withheld::default(),
+            }]
+        );
+    }
+
+    // ======================
+    // Diff Options Tests
+    // ======================
+
+    /// Test that default options behave exactly like `deep_diff`.
+    #[test]
+    fn test_default_options_match_deep_diff() {
+        let a = json!({"name": "Bob", "tags": ["a", "b"]});
+        let b = json!({"name": "Alice", "tags": ["a"]});
+        let options = DiffOptions::default();
+        assert_eq!(deep_diff_with_options(&a, &b, &options), deep_diff(&a, &b));
+    }
+
+    /// Test that ignored paths are not reported.
+    #[test]
+    fn test_options_ignore_path() {
+        let a = json!({"name": "Bob", "meta": {"timestamp": 1}});
+        let b = json!({"name": "Bob", "meta": {"timestamp": 2}});
+        let options = DiffOptions::builder().ignore("meta.timestamp").build();
+        assert!(deep_diff_with_options(&a, &b, &options).is_empty());
+    }
+
+    /// Test that numbers within the configured epsilon are considered equal.
+    #[test]
+    fn test_options_numeric_epsilon() {
+        let a = json!({"price": 10.0, "qty": 1});
+        let b = json!({"price": 10.0001, "qty": 2});
+        let options = DiffOptions::builder().numeric_epsilon(0.001).build();
+        let result = deep_diff_with_options(&a, &b, &options);
+        assert_eq!(
+            result,
+            vec![Difference {
+                path: "qty".to_string(),
+                segments: "qty".parse().unwrap(),
+                kind: DiffKind::Changed,
+                severity: Severity::Info,
+                before: Some(json!(1)),
+                after: Some(json!(2)),
+                context: None,
+                withheld: Withheld::default(),
+            }]
+        );
+    }
+
+    /// Test that a decimal tolerance is applied exactly.
+    #[test]
+    fn test_options_decimal_tolerance() {
+        let a = json!({"price": 0.1, "total": 0.3, "qty": 1});
+        let b = json!({"price": 0.11, "total": 0.31000000000000005, "qty": 2});
+        let options = DiffOptions::builder()
+            .decimal_tolerance("0.01".parse().unwrap())
+            .numeric_epsilon(1.0)
+            .build();
+        let paths: Vec<_> = deep_diff_with_options(&a, &b, &options)
+            .into_iter()
+            .map(|diff| diff.path)
+            .collect();
+        assert_eq!(paths, ["qty", "total"]);
+    }
+
+    /// Test that dissimilar objects are reported as replaced whole.
+    #[test]
+    fn test_options_collapse_replaced_subtrees() {
+        let a = json!({"user": {"id": 1, "name": "a", "role": "x"}, "config": {"a": 1, "b": 2, "c": 3, "d": 4, "e": 5, "f": 6}});
+        let b = json!({"user": {"id": 2, "name": "b", "role": "y"}, "config": {"a": 1, "b": 2, "c": 4, "d": 4, "e": 5, "f": 6}});
+        let options = DiffOptions::builder()
+            .collapse_replaced_subtrees(true)
+            .build();
+        let diffs = deep_diff_with_options(&a, &b, &options);
+        let paths: Vec<_> = diffs.iter().map(|diff| diff.path.as_str()).collect();
+        assert_eq!(paths, ["config.c", "user"]);
+        assert_eq!(diffs[1].before, Some(a["user"].clone()));
+        assert_eq!(diffs[1].kind, DiffKind::Changed);
+
+        let options = DiffOptions::builder()
+            .replaced_subtree_threshold(0.9)
+            .build();
+        assert_eq!(deep_diff_with_options(&a, &b, &options).len(), 1);
+        let options = DiffOptions::builder()
+            .replaced_subtree_threshold(0.9)
+            .collapse_replaced_subtrees(false)
+            .build();
+        assert_eq!(deep_diff_with_options(&a, &b, &options).len(), 4);
+    }
+
+    /// Test that differences carry their unchanged siblings as context.
+    #[test]
+    fn test_options_context() {
+        let a = json!({"items": [
+            {"id": 1, "price": 5},
+            {"id": 2, "price": 7, "stock": 1, "secret": "x"},
+            {"id": 3, "price": 9},
+            "gone"
+        ]});
+        let mut b = a.clone();
+        b["items"][1]["price"] = json!(8);
+        b["items"][1]["stock"] = json!(0);
+        b["items"][3] = json!("back");
+        let options = DiffOptions::builder()
+            .context(1)
+            .ignore("**.secret")
+            .build();
+        let diffs = deep_diff_with_options(&a, &b, &options);
+        assert_eq!(diffs.len(), 3);
+        assert_eq!(diffs[0].context, Some(json!({"id": 2})));
+        assert_eq!(diffs[1].context, Some(json!({"id": 2})));
+        assert_eq!(diffs[2].context, Some(json!({"2": {"id": 3, "price": 9}})));
+
+        let options = DiffOptions::builder().context(1).build();
+        let root = deep_diff_with_options(&json!(1), &json!(2), &options);
+        assert_eq!(root[0].context, None);
+        assert_eq!(deep_diff(&a, &b)[0].context, None);
+    }
+
+    /// Test that numbers beyond `f64` precision are compared exactly.
+    #[cfg(feature = "arbitrary-precision")]
+    #[test]
+    fn test_arbitrary_precision() {
+        let parse = |text: &str| serde_json::from_str::<Value>(text).unwrap();
+        let a = parse(r#"{"id": 340282366920938463463374607431768211455, "x": 1.5, "y": 1}"#);
+        let b = parse(r#"{"id": 340282366920938463463374607431768211454, "x": 1.50, "y": 1.0}"#);
+        let paths: Vec<_> = deep_diff(&a, &b).into_iter().map(|d| d.path).collect();
+        assert_eq!(paths, ["id", "y"]);
+        let options = DiffOptions::builder().coerce_numbers(true).build();
+        assert_eq!(deep_diff_with_options(&a, &b, &options).len(), 1);
+    }
+
+    /// Test that numbers beyond `f64` precision are kept when parsing text.
+    #[cfg(all(feature = "arbitrary-precision", feature = "serde"))]
+    #[test]
+    fn test_arbitrary_precision_str() {
+        let diffs = deep_diff_str(
+            r#"[100000000000000000000000000001]"#,
+            r#"[100000000000000000000000000000]"#,
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(diffs.len(), 1);
+    }
+
+    /// Test that containers below the maximum depth are reported as a single change.
+    #[test]
+    fn test_options_max_depth() {
+        let a = json!({ "person": { "name": { "first": "Alice" } } });
+        let b = json!({ "person": { "name": { "first": "Bob" } } });
+        let options = DiffOptions::builder().max_depth(1).build();
+        let result = deep_diff_with_options(&a, &b, &options);
+        assert_eq!(
+            result,
+            vec![Difference {
+                path: "person".to_string(),
+                segments: "person".parse().unwrap(),
+                kind: DiffKind::Changed,
+                severity: Severity::Info,
+                before: Some(json!({ "name": { "first": "Alice" } })),
+                after: Some(json!({ "name": { "first": "Bob" } })),
+                context: None,
+                withheld: Withheld::default(),
+            }]
+        );
+    }
+
+    // ======================
+    // Inversion Tests
+    // ======================
+
+    /// Test that inverting a difference swaps before and after, and added with removed.
+    #[test]
+    fn test_invert_difference() {
+        let diff = Difference {
+            path: "email".to_string(),
+            segments: "email".parse().unwrap(),
+            kind: DiffKind::Added,
+            severity: Severity::Info,
+            before: None,
+            after: Some(json!("bob@example.com")),
+            context: None,
+            withheld: Withheld::default(),
+        };
+        assert_eq!(
+            diff.invert(),
+            Difference {
+                path: "email".to_string(),
+                segments: "email".parse().unwrap(),
+                kind: DiffKind::Removed,
+                severity: Severity::Info,
+                before: Some(json!("bob@example.com")),
+                after: None,
+                context: None,
+                withheld: Withheld::default(),
+            }
+        );
+        assert_eq!(diff.invert().invert(), diff);
+    }
+
+    /// Test that an inverted diff rolls the target back to the original.
+    #[test]
+    fn test_invert_diff_round_trip() {
+        let a = json!({"name": "Alice", "age": 30, "tags": ["a", "b"]});
+        let b = json!({"name": "Bob", "tags": ["a", "c"], "email": "x"});
+        let diffs = deep_diff(&a, &b);
+        assert_eq!(apply_diff(&b, &invert_diff(&diffs)), Ok(a));
+    }
+
+    // ======================
+    // Diff Kind Tests
+    // ======================
+
+    /// Test that each kind of change is classified explicitly.
+    #[test]
+    fn test_diff_kinds() {
+        let a = json!({"name": "Bob", "age": 25, "nick": null});
+        let b = json!({"name": "Alice", "age": "25", "email": null});
+        let kinds: Vec<(String, DiffKind)> = deep_diff(&a, &b)
+            .into_iter()
+            .map(|d| (d.path, d.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (
+                    "age".to_string(),
+                    DiffKind::TypeChanged {
+                        from: JsonType::Number,
+                        to: JsonType::String,
+                    },
+                ),
+                ("name".to_string(), DiffKind::Changed),
+                ("nick".to_string(), DiffKind::Removed),
+                ("email".to_string(), DiffKind::Added),
+            ]
+        );
+    }
+
+    /// Test that type changes name the types on each side.
+    #[test]
+    fn test_type_changed_detail() {
+        let a = json!({"a": "1", "b": [1], "c": true});
+        let b = json!({"a": 1, "b": {"0": 1}, "c": null});
+        let kinds: Vec<DiffKind> = deep_diff(&a, &b).into_iter().map(|d| d.kind).collect();
+        let type_changed = |from, to| DiffKind::TypeChanged { from, to };
+        assert_eq!(
+            kinds,
+            vec![
+                type_changed(JsonType::String, JsonType::Number),
+                type_changed(JsonType::Array, JsonType::Object),
+                type_changed(JsonType::Bool, JsonType::Null),
+            ]
+        );
+        assert_eq!(
+            kinds[0].clone().invert(),
+            type_changed(JsonType::Number, JsonType::String)
+        );
+        assert_eq!(JsonType::of(&json!(false)).to_string(), "boolean");
+    }
+
+    // ======================
+    // Null Semantics Tests
+    // ======================
+
+    /// Test that by default a `null` value and a missing key are distinct.
+    #[test]
+    fn test_null_distinct_from_missing() {
+        let a = json!({"name": "Bob", "email": "bob@example.com"});
+        let b = json!({"email": null, "nick": null});
+        let result = deep_diff(&a, &b);
+        assert_eq!(
+            result,
+            vec![
+                Difference {
+                    path: "email".to_string(),
+                    segments: "email".parse().unwrap(),
+                    kind: DiffKind::TypeChanged {
+                        from: JsonType::String,
+                        to: JsonType::Null,
+                    },
+                    severity: Severity::Info,
+                    before: Some(json!("bob@example.com")),
+                    after: Some(Value::Null),
+                    context: None,
+                    withheld: Withheld::default(),
+                },
+                Difference {
+                    path: "name".to_string(),
+                    segments: "name".parse().unwrap(),
+                    kind: DiffKind::Removed,
+                    severity: Severity::Info,
+                    before: Some(json!("Bob")),
+                    after: None,
+                    context: None,
+                    withheld: Withheld::default(),
+                },
+                Difference {
+                    path: "nick".to_string(),
+                    segments: "nick".parse().unwrap(),
+                    kind: DiffKind::Added,
+                    severity: Severity::Info,
+                    before: None,
+                    after: Some(Value::Null),
+                    context: None,
+                    withheld: Withheld::default(),
+                },
+            ]
+        );
+    }
+
+    /// Test that `null` and missing keys can be treated as equal.
+    #[test]
+    fn test_null_equals_missing() {
+        let a = json!({"name": "Bob", "email": null});
+        let b = json!({"name": "Bob", "nick": null});
+        let options = DiffOptions::builder()
+            .null_semantics(NullSemantics::AbsentEqualsNull)
+            .build();
+        assert!(deep_diff_with_options(&a, &b, &options).is_empty());
+    }
+
+    /// Test that empty containers can be treated as equal to missing keys and `null`.
+    #[test]
+    fn test_empty_equals_missing() {
+        let a = json!({"tags": [], "meta": {}, "list": [null, []], "x": 1});
+        let b = json!({"extra": {}, "list": [{}, null], "x": 1, "y": []});
+        let options = DiffOptions::builder().empty_equals_missing(true).build();
+        assert!(deep_diff_with_options(&a, &b, &options).is_empty());
+        let c = json!({"tags": [1], "x": 1});
+        let paths: Vec<String> = deep_diff_with_options(&a, &c, &options)
+            .into_iter()
+            .map(|d| d.path)
+            .collect();
+        assert_eq!(paths, vec!["list", "tags[0]"]);
+        assert_eq!(deep_diff(&a, &b).len(), 6);
+    }
+
+    /// Test that arrays can be compared with objects as pairs or by index.
+    #[test]
+    fn test_options_coerce_containers() {
+        let options = DiffOptions::builder().coerce_containers(true).build();
+        let a = json!({"env": [["HOST", "a"], ["PORT", 80]], "ids": [10, 20]});
+        let b = json!({"env": {"HOST": "b", "PORT": 80}, "ids": {"0": 10, "1": 21}});
+        let diffs = deep_diff_with_options(&a, &b, &options);
+        let paths: Vec<&str> = diffs.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(paths, vec!["env.HOST", "ids.1"]);
+        assert_eq!(diffs[0].after, Some(json!("b")));
+        assert_eq!(deep_diff_with_options(&b, &a, &options).len(), 2);
+        let kinds: Vec<DiffKind> = deep_diff(&a, &b).into_iter().map(|d| d.kind).collect();
+        assert!(
+            kinds
+                .iter()
+                .all(|kind| matches!(kind, DiffKind::TypeChanged { .. }))
+        );
+        assert_eq!(
+            deep_diff_with_options(&json!([1]), &json!("1"), &options)[0].kind,
+            DiffKind::TypeChanged {
+                from: JsonType::Array,
+                to: JsonType::String
+            }
+        );
+    }
+
+    // ======================
+    // Path Format Tests
+    // ======================
+
+    /// Test that paths can be rendered as escaped JSON Pointers.
+    #[test]
+    fn test_path_format_json_pointer() {
+        let a = json!({"person": {"a/b": [1, 2], "c~d": 1}});
+        let b = json!({"person": {"a/b": [1, 3], "c~d": 2}});
+        let options = DiffOptions::builder()
+            .path_format(PathFormat::JsonPointer)
+            .build();
+        let paths: Vec<String> = deep_diff_with_options(&a, &b, &options)
+            .into_iter()
+            .map(|d| d.path)
+            .collect();
+        assert_eq!(paths, vec!["/person/a~1b/1", "/person/c~0d"]);
+    }
+
+    /// Test that keys containing separators are quoted in the escaped format.
+    #[test]
+    fn test_path_format_escaped() {
+        let a = json!({"config": {"a.b": {"items[0]": 1}}});
+        let b = json!({"config": {"a.b": {"items[0]": 2}}});
+        let options = DiffOptions::builder()
+            .path_format(PathFormat::Escaped)
+            .build();
+        let result = deep_diff_with_options(&a, &b, &options);
+        assert_eq!(result[0].path, r#"config["a.b"]["items[0]"]"#);
+        assert_eq!(result[0].path.parse(), Ok(result[0].segments.clone()));
+    }
+
+    /// Test that dotted paths convert to JSON Pointers.
+    #[test]
+    fn test_difference_pointer() {
+        let a = json!({ "person": { "name": { "first": [1, 2, 3] } } });
+        let b = json!({ "person": { "name": { "first": [1, 2, 4] } } });
+        let result = deep_diff(&a, &b);
+        assert_eq!(result[0].pointer(), "/person/name/first/2");
+    }
+
+    /// Test that typed segments keep keys containing separators intact.
+    #[test]
+    fn test_typed_segments() {
+        let a = json!({"a.b": {"items[0]": [1, 2]}});
+        let b = json!({"a.b": {"items[0]": [1, 3]}});
+        let result = deep_diff(&a, &b);
+        assert_eq!(
+            result[0].segments,
+            Path(vec![
+                PathSegment::Key("a.b".to_string()),
+                PathSegment::Key("items[0]".to_string()),
+                PathSegment::Index(1),
+            ])
+        );
+        assert_eq!(result[0].pointer(), "/a.b/items[0]/1");
+    }
+
+    // ======================
+    // Path Correctness Tests
+    // ======================
+
+    fn paths_and_kinds(a: &Value, b: &Value) -> Vec<(String, DiffKind)> {
+        deep_diff(a, b)
+            .into_iter()
+            .map(|d| {
+                assert_eq!(d.path, d.segments.to_string());
+                (d.path, d.kind)
+            })
+            .collect()
+    }
+
+    /// Test added, removed and changed keys at the top level.
+    #[test]
+    fn test_paths_top_level() {
+        let a = json!({"changed": 1, "removed": 2});
+        let b = json!({"changed": 3, "added": 4});
+        assert_eq!(
+            paths_and_kinds(&a, &b),
+            vec![
+                ("changed".to_string(), DiffKind::Changed),
+                ("removed".to_string(), DiffKind::Removed),
+                ("added".to_string(), DiffKind::Added),
+            ]
+        );
+    }
+
+    /// Test added, removed and changed keys one level down.
+    #[test]
+    fn test_paths_nested_once() {
+        let a = json!({"outer": {"changed": 1, "removed": 2}});
+        let b = json!({"outer": {"changed": 3, "added": 4}});
+        assert_eq!(
+            paths_and_kinds(&a, &b),
+            vec![
+                ("outer.changed".to_string(), DiffKind::Changed),
+                ("outer.removed".to_string(), DiffKind::Removed),
+                ("outer.added".to_string(), DiffKind::Added),
+            ]
+        );
+    }
+
+    /// Test added, removed and changed keys several levels down.
+    #[test]
+    fn test_paths_deeply_nested() {
+        let a = json!({"a": {"b": {"c": {"changed": 1, "removed": 2}}}});
+        let b = json!({"a": {"b": {"c": {"changed": 3, "added": 4}}}});
+        assert_eq!(
+            paths_and_kinds(&a, &b),
+            vec![
+                ("a.b.c.changed".to_string(), DiffKind::Changed),
+                ("a.b.c.removed".to_string(), DiffKind::Removed),
+                ("a.b.c.added".to_string(), DiffKind::Added),
+            ]
+        );
+    }
+
+    /// Test added, removed and changed keys of objects inside arrays.
+    #[test]
+    fn test_paths_objects_in_arrays() {
+        let a = json!({"items": [{"id": 1}, {"id": 2, "removed": true}]});
+        let b = json!({"items": [{"id": 1}, {"id": 3, "added": true}]});
+        assert_eq!(
+            paths_and_kinds(&a, &b),
+            vec![
+                ("items[1].id".to_string(), DiffKind::Changed),
+                ("items[1].removed".to_string(), DiffKind::Removed),
+                ("items[1].added".to_string(), DiffKind::Added),
+            ]
+        );
+    }
+
+    /// Test changes inside arrays nested directly in arrays.
+    #[test]
+    fn test_paths_nested_arrays() {
+        let a = json!([[1, [2, {"k": 3}]]]);
+        let b = json!([[1, [2, {"k": 4, "new": 5}]]]);
+        assert_eq!(
+            paths_and_kinds(&a, &b),
+            vec![
+                ("[0][1][1].k".to_string(), DiffKind::Changed),
+                ("[0][1][1].new".to_string(), DiffKind::Added),
+            ]
+        );
+    }
+
+    /// Test that nested removals keep their parent path in JSON Pointer form.
+    #[test]
+    fn test_paths_nested_removal_pointer() {
+        let a = json!({"a": {"b": [{"c": 1}]}});
+        let b = json!({"a": {"b": [{}]}});
+        let options = DiffOptions::builder()
+            .path_format(PathFormat::JsonPointer)
+            .build();
+        let result = deep_diff_with_options(&a, &b, &options);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].path, "/a/b/0/c");
+        assert_eq!(result[0].kind, DiffKind::Removed);
+    }
+
+    /// Test that a nested removal can be ignored by its full path.
+    #[test]
+    fn test_paths_ignore_nested_removal() {
+        let a = json!({"meta": {"etag": "x", "v": 1}});
+        let b = json!({"meta": {"v": 1}});
+        let options = DiffOptions::builder().ignore("meta.etag").build();
+        assert!(deep_diff_with_options(&a, &b, &options).is_empty());
+    }
+
+    // ======================
+    // LCS Array Strategy Tests
+    // ======================
+
+    fn lcs_options() -> DiffOptions {
+        DiffOptions::builder()
+            .array_strategy(ArrayDiffStrategy::Lcs)
+            .build()
+    }
+
+    /// Test that an insertion at the front is reported as a single addition.
+    #[test]
+    fn test_lcs_insert_front() {
+        let a = json!([1, 2, 3]);
+        let b = json!([0, 1, 2, 3]);
+        let result = deep_diff_with_options(&a, &b, &lcs_options());
+        assert_eq!(
+            result,
+            vec![Difference {
+                path: "[0]".to_string(),
+                segments: "[0]".parse().unwrap(),
+                kind: DiffKind::Added,
+                severity: Severity::Info,
+                before: None,
+                after: Some(json!(0)),
+                context: None,
+                withheld: Withheld::default(),
             }]
         );
     }
+
+    /// Test that deletions are reported as removals.
+    #[test]
+    fn test_lcs_deletions() {
+        let a = json!(["a", "b", "c", "d", "e"]);
+        let b = json!(["a", "c", "d"]);
+        let paths: Vec<(String, DiffKind)> = deep_diff_with_options(&a, &b, &lcs_options())
+            .into_iter()
+            .map(|d| (d.path, d.kind))
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("[1]".to_string(), DiffKind::Removed),
+                ("[3]".to_string(), DiffKind::Removed),
+            ]
+        );
+    }
+
+    /// Test that elements replaced in place are still diffed field by field.
+    #[test]
+    fn test_lcs_modified_element() {
+        let a = json!([{"id": 1}, {"id": 2, "v": "x"}, {"id": 3}]);
+        let b = json!([{"id": 0}, {"id": 1}, {"id": 2, "v": "y"}, {"id": 3}]);
+        let paths: Vec<(String, DiffKind)> = deep_diff_with_options(&a, &b, &lcs_options())
+            .into_iter()
+            .map(|d| (d.path, d.kind))
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("[0]".to_string(), DiffKind::Added),
+                ("[2].v".to_string(), DiffKind::Changed),
+            ]
+        );
+    }
+
+    /// Test that LCS diffs apply and invert cleanly.
+    #[test]
+    fn test_lcs_round_trip() {
+        let a = json!({"list": [1, 2, 3, 4, 5, 6, 7], "other": [["x"], 9]});
+        let b = json!({"list": [0, 2, 3, 8, 9, 5, 7, 10, 11], "other": [9, ["y"]]});
+        let diffs = deep_diff_with_options(&a, &b, &lcs_options());
+        assert_eq!(apply_diff(&a, &diffs), Ok(b.clone()));
+        assert_eq!(apply_diff(&b, &invert_diff(&diffs)), Ok(a));
+    }
+
+    // ======================
+    // Keyed Array Tests
+    // ======================
+
+    /// Test that reordered records are matched by key and diffed field by field.
+    #[test]
+    fn test_array_key_reordered() {
+        let a = json!({"items": [{"id": 1, "price": 10}, {"id": 2, "price": 20}]});
+        let b = json!({"items": [{"id": 2, "price": 25}, {"id": 1, "price": 10}]});
+        let options = DiffOptions::builder().array_key("items", "id").build();
+        let result = deep_diff_with_options(&a, &b, &options);
+        assert_eq!(
+            result,
+            vec![Difference {
+                path: "items[1].price".to_string(),
+                segments: "items[1].price".parse().unwrap(),
+                kind: DiffKind::Changed,
+                severity: Severity::Info,
+                before: Some(json!(20)),
+                after: Some(json!(25)),
+                context: None,
+                withheld: Withheld::default(),
+            }]
+        );
+    }
+
+    /// Test that unmatched records are reported as added or removed.
+    #[test]
+    fn test_array_key_added_removed() {
+        let a = json!([{"id": "a"}, {"id": "b"}, {"id": "c"}]);
+        let b = json!([{"id": "c"}, {"id": "d"}, {"id": "a"}]);
+        let options = DiffOptions::builder().default_array_key("id").build();
+        let diffs = deep_diff_with_options(&a, &b, &options);
+        let paths: Vec<(String, DiffKind)> = diffs
+            .iter()
+            .map(|d| (d.path.clone(), d.kind.clone()))
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("[1]".to_string(), DiffKind::Removed),
+                ("[1]".to_string(), DiffKind::Added),
+            ]
+        );
+        assert_eq!(diffs[1].after, Some(json!({"id": "d"})));
+    }
+
+    /// Test that arrays with elements lacking the key fall back to the strategy.
+    #[test]
+    fn test_array_key_fallback() {
+        let a = json!({"items": [1, 2]});
+        let b = json!({"items": [2, 1]});
+        let options = DiffOptions::builder().array_key("items", "id").build();
+        assert_eq!(deep_diff_with_options(&a, &b, &options), deep_diff(&a, &b));
+    }
+
+    // ======================
+    // Unordered Set Tests
+    // ======================
+
+    fn set_options() -> DiffOptions {
+        DiffOptions::builder()
+            .array_strategy(ArrayDiffStrategy::UnorderedSet)
+            .build()
+    }
+
+    /// Test that per-path strategies override the default one.
+    #[test]
+    fn test_array_strategy_at() {
+        let a = json!({"tags": ["a", "b"], "steps": ["a", "b"]});
+        let b = json!({"tags": ["b", "a"], "steps": ["b", "a"]});
+        let options = DiffOptions::builder()
+            .array_strategy_at("tags", ArrayDiffStrategy::UnorderedSet)
+            .build();
+        let paths: Vec<_> = deep_diff_with_options(&a, &b, &options)
+            .into_iter()
+            .map(|diff| diff.path)
+            .collect();
+        assert_eq!(paths, ["steps[0]", "steps[1]"]);
+    }
+
+    /// Test that reordering alone produces no differences.
+    #[test]
+    fn test_unordered_set_reordered() {
+        let a = json!({"tags": ["a", "b", {"k": 1}]});
+        let b = json!({"tags": [{"k": 1}, "a", "b"]});
+        assert!(deep_diff_with_options(&a, &b, &set_options()).is_empty());
+    }
+
+    /// Test that only elements without a counterpart are reported.
+    #[test]
+    fn test_unordered_set_added_removed() {
+        let a = json!(["read", "write", "read"]);
+        let b = json!(["admin", "read"]);
+        let result = deep_diff_with_options(&a, &b, &set_options());
+        assert_eq!(
+            result,
+            vec![
+                Difference {
+                    path: "[2]".to_string(),
+                    segments: "[2]".parse().unwrap(),
+                    kind: DiffKind::Removed,
+                    severity: Severity::Info,
+                    before: Some(json!("read")),
+                    after: None,
+                    context: None,
+                    withheld: Withheld::default(),
+                },
+                Difference {
+                    path: "[1]".to_string(),
+                    segments: "[1]".parse().unwrap(),
+                    kind: DiffKind::Removed,
+                    severity: Severity::Info,
+                    before: Some(json!("write")),
+                    after: None,
+                    context: None,
+                    withheld: Withheld::default(),
+                },
+                Difference {
+                    path: "[0]".to_string(),
+                    segments: "[0]".parse().unwrap(),
+                    kind: DiffKind::Added,
+                    severity: Severity::Info,
+                    before: None,
+                    after: Some(json!("admin")),
+                    context: None,
+                    withheld: Withheld::default(),
+                },
+            ]
+        );
+    }
+
+    // ======================
+    // Move Detection Tests
+    // ======================
+
+    fn move_options() -> DiffOptions {
+        DiffOptions::builder()
+            .array_strategy(ArrayDiffStrategy::Lcs)
+            .detect_moves(true)
+            .build()
+    }
+
+    /// Test that an element moved to the front is reported as a move.
+    #[test]
+    fn test_move_to_front() {
+        let a = json!(["a", "b", "c", "d"]);
+        let b = json!(["d", "a", "b", "c"]);
+        let result = deep_diff_with_options(&a, &b, &move_options());
+        assert_eq!(
+            result,
+            vec![Difference {
+                path: "[0]".to_string(),
+                segments: "[0]".parse().unwrap(),
+                kind: DiffKind::Moved { from: 3, to: 0 },
+                severity: Severity::Info,
+                before: Some(json!("d")),
+                after: Some(json!("d")),
+                context: None,
+                withheld: Withheld::default(),
+            }]
+        );
+    }
+
+    /// Test that moves combine with other edits and still apply cleanly.
+    #[test]
+    fn test_move_round_trip() {
+        let a = json!({"list": [{"id": 1}, {"id": 2}, 3, 4, 5, "x"]});
+        let b = json!({"list": [5, {"id": 1}, 4, "y", {"id": 2}, 3]});
+        let diffs = deep_diff_with_options(&a, &b, &move_options());
+        assert!(
+            diffs
+                .iter()
+                .any(|d| matches!(d.kind, DiffKind::Moved { .. }))
+        );
+        assert_eq!(apply_diff(&a, &diffs), Ok(b.clone()));
+        assert_eq!(apply_diff(&b, &invert_diff(&diffs)), Ok(a));
+    }
+
+    /// Test that moves are not reported unless requested.
+    #[test]
+    fn test_moves_disabled_by_default() {
+        let a = json!(["a", "b", "c", "d"]);
+        let b = json!(["d", "a", "b", "c"]);
+        let options = lcs_options();
+        let kinds: Vec<DiffKind> = deep_diff_with_options(&a, &b, &options)
+            .into_iter()
+            .map(|d| d.kind)
+            .collect();
+        assert_eq!(kinds, vec![DiffKind::Added, DiffKind::Removed]);
+    }
+
+    // ======================
+    // Rename Detection Tests
+    // ======================
+
+    /// Test that a key replaced by another with the same value is a rename.
+    #[test]
+    fn test_rename_detected() {
+        let a = json!({"config": {"timeout": 30, "retries": 3, "host": "a"}});
+        let b = json!({"config": {"time_out": 30, "retries": 3, "hostname": "b"}});
+        let options = DiffOptions::builder().detect_renames(true).build();
+        let result = deep_diff_with_options(&a, &b, &options);
+        assert_eq!(
+            result,
+            vec![
+                Difference {
+                    path: "config.host".to_string(),
+                    segments: "config.host".parse().unwrap(),
+                    kind: DiffKind::Removed,
+                    severity: Severity::Info,
+                    before: Some(json!("a")),
+                    after: None,
+                    context: None,
+                    withheld: Withheld::default(),
+                },
+                Difference {
+                    path: "config.time_out".to_string(),
+                    segments: "config.time_out".parse().unwrap(),
+                    kind: DiffKind::Renamed {
+                        from_key: "timeout".to_string(),
+                        to_key: "time_out".to_string(),
+                    },
+                    severity: Severity::Info,
+                    before: Some(json!(30)),
+                    after: Some(json!(30)),
+                    context: None,
+                    withheld: Withheld::default(),
+                },
+                Difference {
+                    path: "config.hostname".to_string(),
+                    segments: "config.hostname".parse().unwrap(),
+                    kind: DiffKind::Added,
+                    severity: Severity::Info,
+                    before: None,
+                    after: Some(json!("b")),
+                    context: None,
+                    withheld: Withheld::default(),
+                },
+            ]
+        );
+        assert_eq!(apply_diff(&a, &result).unwrap(), b);
+        assert_eq!(apply_diff(&b, &invert_diff(&result)).unwrap(), a);
+        assert_eq!(deep_diff(&a, &b).len(), 4);
+    }
+
+    /// Test that similar values are renamed above the threshold.
+    #[test]
+    fn test_rename_threshold() {
+        let a = json!({"owner": {"name": "Ann", "email": "ann@example.com", "age": 30}});
+        let b = json!({"author": {"name": "Ann", "email": "ann@example.com", "age": 31}});
+        let options = DiffOptions::builder().detect_renames(true).build();
+        assert_eq!(deep_diff_with_options(&a, &b, &options).len(), 2);
+        let options = DiffOptions::builder().rename_threshold(0.6).build();
+        let result = deep_diff_with_options(&a, &b, &options);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].path, "author");
+        assert_eq!(result[0].after, Some(b["author"].clone()));
+        assert_eq!(apply_diff(&a, &result).unwrap(), b);
+        let options = DiffOptions::builder()
+            .rename_threshold(0.6)
+            .mode(DiffMode::SupersetOf)
+            .build();
+        assert_eq!(
+            deep_diff_with_options(&a, &b, &options)[0].kind,
+            DiffKind::Added
+        );
+    }
+
+    // ======================
+    // Numeric Comparison Tests
+    // ======================
+
+    /// Test that integer and float representations differ by default.
+    #[test]
+    fn test_numbers_differ_by_representation() {
+        let a: Value = serde_json::from_str("[1, 2]").unwrap();
+        let b: Value = serde_json::from_str("[1.0, 2e0]").unwrap();
+        assert_eq!(deep_diff(&a, &b).len(), 2);
+    }
+
+    /// Test that numbers can be compared by value.
+    #[test]
+    fn test_coerce_numbers() {
+        let a: Value = serde_json::from_str("[1, 2, -3, 18446744073709551615]").unwrap();
+        let b: Value = serde_json::from_str("[1.0, 2e0, -3.0, 18446744073709551615]").unwrap();
+        let options = DiffOptions::builder().coerce_numbers(true).build();
+        assert!(deep_diff_with_options(&a, &b, &options).is_empty());
+    }
+
+    /// Test that coercion still reports numbers with different values.
+    #[test]
+    fn test_coerce_numbers_different_values() {
+        let a: Value = serde_json::from_str("[1, 9007199254740993]").unwrap();
+        let b: Value = serde_json::from_str("[1.5, 9007199254740992.0]").unwrap();
+        let options = DiffOptions::builder().coerce_numbers(true).build();
+        let paths: Vec<String> = deep_diff_with_options(&a, &b, &options)
+            .into_iter()
+            .map(|d| d.path)
+            .collect();
+        assert_eq!(paths, vec!["[0]", "[1]"]);
+    }
+
+    // ======================
+    // String Comparison Tests
+    // ======================
+
+    /// Test that strings can be compared ignoring case and surrounding whitespace.
+    #[test]
+    fn test_string_compare_global() {
+        let a = json!({"name": "Alice ", "city": "OSLO", "zip": "0150"});
+        let b = json!({"name": " alice", "city": "oslo", "zip": "0151"});
+        let options = DiffOptions::builder()
+            .string_compare(StringCompare::CaseInsensitive)
+            .string_compare(StringCompare::TrimWhitespace)
+            .build();
+        let paths: Vec<String> = deep_diff_with_options(&a, &b, &options)
+            .into_iter()
+            .map(|d| d.path)
+            .collect();
+        assert_eq!(paths, vec!["zip"]);
+    }
+
+    /// Test that per-path modes replace the global ones.
+    #[test]
+    fn test_string_compare_per_path() {
+        let a = json!({"email": "Bob@Example.com", "password": "Secret"});
+        let b = json!({"email": "bob@example.com", "password": "secret"});
+        let options = DiffOptions::builder()
+            .string_compare_at("email", StringCompare::CaseInsensitive)
+            .build();
+        let paths: Vec<String> = deep_diff_with_options(&a, &b, &options)
+            .into_iter()
+            .map(|d| d.path)
+            .collect();
+        assert_eq!(paths, vec!["password"]);
+    }
+
+    /// Test that timestamps are compared as instants, within a tolerance.
+    #[test]
+    fn test_compare_datetimes() {
+        let a = json!({"at": "2024-01-01T00:00:00Z", "near": "2024-01-01T00:00:00.4Z", "s": "a"});
+        let b =
+            json!({"at": "2024-01-01T01:00:00+01:00", "near": "2024-01-01T00:00:01Z", "s": "A"});
+        let exact = DiffOptions::builder()
+            .compare_datetimes(Duration::ZERO)
+            .build();
+        let paths: Vec<String> = deep_diff_with_options(&a, &b, &exact)
+            .into_iter()
+            .map(|d| d.path)
+            .collect();
+        assert_eq!(paths, vec!["near", "s"]);
+        let loose = DiffOptions::builder()
+            .compare_datetimes(Duration::from_secs(1))
+            .build();
+        assert_eq!(deep_diff_with_options(&a, &b, &loose).len(), 1);
+        assert_eq!(deep_diff(&a, &b).len(), 3);
+    }
+
+    /// Test that changed strings expose the edits between them.
+    #[test]
+    fn test_difference_text_delta() {
+        let a = json!({"text": "one two three", "n": 1});
+        let b = json!({"text": "one 2 three", "n": 2});
+        let result = deep_diff(&a, &b);
+        assert_eq!(result[0].text_delta(TextGranularity::Word), None);
+        let delta = result[1].text_delta(TextGranularity::Word).unwrap();
+        assert_eq!(
+            delta.ops,
+            vec![
+                TextOp::Equal("one ".to_string()),
+                TextOp::Delete("two".to_string()),
+                TextOp::Insert("2".to_string()),
+                TextOp::Equal(" three".to_string()),
+            ]
+        );
+    }
+
+    /// Test that base64 fields are compared by their decoded bytes.
+    #[test]
+    fn test_binary_at() {
+        let a = json!({"files": [{"data": "aGk="}, {"data": "aGk="}], "note": "aGk="});
+        let b = json!({"files": [{"data": "aGk"}, {"data": "aGVsbG8="}], "note": "aGk"});
+        let options = DiffOptions::builder().binary_at("files[*].data").build();
+        let result = deep_diff_with_options(&a, &b, &options);
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].path, "files[1].data");
+        assert!(matches!(
+            result[0].kind,
+            DiffKind::BinaryChanged {
+                old_len: 2,
+                new_len: 5,
+                ..
+            }
+        ));
+        assert_eq!((&result[0].before, &result[0].after), (&None, &None));
+        assert_eq!(result[1].path, "note");
+        assert_eq!(result[1].kind, DiffKind::Changed);
+    }
+
+    /// Test that the Kubernetes preset ignores server fields and compares quantities.
+    #[test]
+    fn test_k8s_preset() {
+        let applied = json!({
+            "kind": "Deployment",
+            "metadata": {"name": "web", "labels": null},
+            "spec": {"template": {"spec": {"containers": [
+                {"name": "app", "resources": {"limits": {"cpu": "1", "memory": "1Gi"}}},
+                {"name": "sidecar", "image": "proxy:1"}
+            ]}}}
+        });
+        let live = json!({
+            "kind": "Deployment",
+            "metadata": {
+                "name": "web",
+                "uid": "1234",
+                "resourceVersion": "99",
+                "managedFields": [{"manager": "kubectl"}],
+                "annotations": {"kubectl.kubernetes.io/last-applied-configuration": "{}"}
+            },
+            "spec": {"template": {"spec": {"containers": [
+                {"name": "sidecar", "image": "proxy:2"},
+                {"name": "app", "resources": {"limits": {"cpu": "1000m", "memory": "1024Mi"}}}
+            ]}}},
+            "status": {"replicas": 1}
+        });
+        let options = DiffOptions::k8s().build();
+        let result = deep_diff_with_options(&applied, &live, &options);
+        let paths: Vec<_> = result.iter().map(|diff| diff.path.as_str()).collect();
+        assert_eq!(paths, ["spec.template.spec.containers[1].image"]);
+        let list = json!({"kind": "List", "items": [live]});
+        let stale = json!({"kind": "List", "items": [{"status": {}}]});
+        let result = deep_diff_with_options(&list, &stale, &options);
+        assert!(result.iter().all(|diff| !diff.path.contains("status")));
+    }
+
+    // ======================
+    // Ignore Pattern Tests
+    // ======================
+
+    /// Test that volatile fields can be ignored with wildcard patterns.
+    #[test]
+    fn test_ignore_patterns() {
+        let a = json!({
+            "updated_at": 1,
+            "user": {"updated_at": 1, "name": "Bob"},
+            "items": [{"etag": "a", "qty": 1}, {"etag": "b", "qty": 2}],
+        });
+        let b = json!({
+            "updated_at": 2,
+            "user": {"updated_at": 2, "name": "Bob"},
+            "items": [{"etag": "c", "qty": 1}, {"etag": "d", "qty": 3}],
+        });
+        let options = DiffOptions::builder()
+            .ignore("**.updated_at")
+            .ignore("items[*].etag")
+            .build();
+        let paths: Vec<String> = deep_diff_with_options(&a, &b, &options)
+            .into_iter()
+            .map(|d| d.path)
+            .collect();
+        assert_eq!(paths, vec!["items[1].qty"]);
+    }
+
+    /// Test that per-path options accept patterns too.
+    #[test]
+    fn test_patterns_for_per_path_options() {
+        let a = json!({"orders": [{"lines": [{"sku": "x", "n": 1}, {"sku": "y", "n": 1}]}]});
+        let b = json!({"orders": [{"lines": [{"sku": "y", "n": 1}, {"sku": "x", "n": 2}]}]});
+        let options = DiffOptions::builder()
+            .array_key("orders[*].lines", "sku")
+            .build();
+        let paths: Vec<String> = deep_diff_with_options(&a, &b, &options)
+            .into_iter()
+            .map(|d| d.path)
+            .collect();
+        assert_eq!(paths, vec!["orders[0].lines[0].n"]);
+    }
+
+    /// Test that keys satisfying a predicate are skipped at every level.
+    #[test]
+    fn test_ignore_keys_matching() {
+        let a = json!({"_rev": 1, "name": "a", "items": [{"created_at": 1, "v": 1}]});
+        let b = json!({"_rev": 2, "name": "b", "items": [{"created_at": 2, "v": 1, "_id": 3}]});
+        let options = DiffOptions::builder()
+            .ignore_keys_matching(|key| key.starts_with('_'))
+            .ignore_keys_matching(|key| key.ends_with("_at"))
+            .build();
+        let result = deep_diff_with_options(&a, &b, &options);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].path, "name");
+    }
+
+    /// Test that differences can be suppressed based on their values.
+    #[test]
+    fn test_ignore_if() {
+        let a = json!({"a": [], "b": "TBD", "c": 1, "d": {"e": "x"}});
+        let b = json!({"a": {}, "b": "N/A", "c": 2, "d": {"e": "TBD"}});
+        let placeholder =
+            |value: Option<&Value>| matches!(value.and_then(Value::as_str), Some("TBD" | "N/A"));
+        let empty = |value: Option<&Value>| match value {
+            Some(Value::Array(values)) => values.is_empty(),
+            Some(Value::Object(map)) => map.is_empty(),
+            _ => false,
+        };
+        let options = DiffOptions::builder()
+            .ignore_if(move |_, before, after| placeholder(before) && placeholder(after))
+            .ignore_if(move |_, before, after| empty(before) && empty(after))
+            .build();
+        let paths: Vec<String> = deep_diff_with_options(&a, &b, &options)
+            .into_iter()
+            .map(|d| d.path)
+            .collect();
+        assert_eq!(paths, vec!["c", "d.e"]);
+    }
+
+    // ======================
+    // Include Filter Tests
+    // ======================
+
+    /// Test that only included subtrees are reported.
+    #[test]
+    fn test_only_included_paths() {
+        let a = json!({
+            "spec": {"image": "v1", "ports": [80]},
+            "status": {"replicas": 1, "ready": 1},
+            "metadata": {"uid": "a"},
+        });
+        let b = json!({
+            "spec": {"image": "v2", "ports": [80, 443]},
+            "status": {"replicas": 2, "ready": 2},
+            "metadata": {"uid": "b"},
+        });
+        let options = DiffOptions::builder()
+            .only(["spec.**", "status.replicas"])
+            .build();
+        let paths: Vec<String> = deep_diff_with_options(&a, &b, &options)
+            .into_iter()
+            .map(|d| d.path)
+            .collect();
+        assert_eq!(
+            paths,
+            vec!["spec.image", "spec.ports[1]", "status.replicas"]
+        );
+    }
+
+    /// Test that a parent replaced on the way to an included path is reported.
+    #[test]
+    fn test_only_reports_replaced_parent() {
+        let a = json!({"status": null, "other": 1});
+        let b = json!({"status": {"replicas": 2}, "other": 2});
+        let options = DiffOptions::builder().only(["status.replicas"]).build();
+        let result = deep_diff_with_options(&a, &b, &options);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].path, "status");
+        assert_eq!(
+            result[0].kind,
+            DiffKind::TypeChanged {
+                from: JsonType::Null,
+                to: JsonType::Object,
+            }
+        );
+    }
+
+    // ======================
+    // Custom Comparator Tests
+    // ======================
+
+    /// Test that a comparator can declare differing values equal.
+    #[test]
+    fn test_compare_at_equal() {
+        let a = json!({"items": [{"price": "10.00", "name": "a"}]});
+        let b = json!({"items": [{"price": 10, "name": "b"}]});
+        let options = DiffOptions::builder()
+            .compare_at("items[*].price", |a, b| {
+                let as_f64 = |v: &Value| match v {
+                    Value::String(s) => s.parse().ok(),
+                    _ => v.as_f64(),
+                };
+                if as_f64(a) == as_f64(b) {
+                    Comparison::Equal
+                } else {
+                    Comparison::Different
+                }
+            })
+            .build();
+        let result = deep_diff_with_options(&a, &b, &options);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].path, "items[0].name");
+    }
+
+    /// Test that a comparator can fall back to the default comparison.
+    #[test]
+    fn test_compare_at_default() {
+        let a = json!({"t": {"x": 1}});
+        let b = json!({"t": {"x": 2}});
+        let options = DiffOptions::builder()
+            .compare_at("t", |_, _| Comparison::Default)
+            .build();
+        let result = deep_diff_with_options(&a, &b, &options);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].path, "t.x");
+    }
+
+    /// Test that a comparator can report a custom difference.
+    #[test]
+    fn test_compare_at_custom() {
+        let a = json!({"t": {"x": 1}});
+        let b = json!({"t": {"x": 2, "y": 3}});
+        let options = DiffOptions::builder()
+            .compare_at("t", |_, b| Comparison::Custom {
+                kind: DiffKind::Changed,
+                before: None,
+                after: Some(b.clone()),
+            })
+            .build();
+        let result = deep_diff_with_options(&a, &b, &options);
+        assert_eq!(
+            result,
+            vec![Difference {
+                path: "t".to_string(),
+                segments: "t".parse().unwrap(),
+                kind: DiffKind::Changed,
+                severity: Severity::Info,
+                before: None,
+                after: Some(json!({"x": 2, "y": 3})),
+                context: None,
+                withheld: Withheld::default(),
+            }]
+        );
+    }
+
+    // ======================
+    // Value Comparator Tests
+    // ======================
+
+    // Compares URLs without their trailing slash and numbers by value
+    struct Lenient;
+
+    impl ValueComparator for Lenient {
+        fn compare_strings(&self, _path: &Path, a: &str, b: &str) -> Option<bool> {
+            if a.starts_with("http") {
+                Some(a.trim_end_matches('/') == b.trim_end_matches('/'))
+            } else {
+                None
+            }
+        }
+
+        fn compare_numbers(&self, _path: &Path, a: &Number, b: &Number) -> Option<bool> {
+            Some(a.as_f64() == b.as_f64())
+        }
+    }
+
+    /// Test that the value comparator decides equality across the document.
+    #[test]
+    fn test_value_comparator() {
+        let a = json!({"home": "https://a.io/", "links": ["http://b.io"], "n": 1, "s": "x"});
+        let b = json!({"home": "https://a.io", "links": ["http://b.io/"], "n": 1.0, "s": "y"});
+        let options = DiffOptions::builder().value_comparator(Lenient).build();
+        let result = deep_diff_with_options(&a, &b, &options);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].path, "s");
+    }
+
+    /// Test that per-path comparators take precedence over the value comparator.
+    #[test]
+    fn test_value_comparator_precedence() {
+        let a = json!({"n": 1});
+        let b = json!({"n": 1.0});
+        let options = DiffOptions::builder()
+            .value_comparator(Lenient)
+            .compare_at("n", |_, _| Comparison::Different)
+            .build();
+        assert_eq!(deep_diff_with_options(&a, &b, &options).len(), 1);
+    }
+
+    /// Test that a container declared unequal is reported as a single change.
+    #[test]
+    fn test_value_comparator_containers() {
+        struct Opaque;
+        impl ValueComparator for Opaque {
+            fn compare_arrays(&self, _path: &Path, a: &[Value], b: &[Value]) -> Option<bool> {
+                Some(a.len() == b.len())
+            }
+        }
+        let options = DiffOptions::builder().value_comparator(Opaque).build();
+        let result = deep_diff_with_options(&json!([1, 2]), &json!([3]), &options);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].path, "");
+        assert!(deep_diff_with_options(&json!([1]), &json!([2]), &options).is_empty());
+    }
+
+    // ======================
+    // Limit Tests
+    // ======================
+
+    /// Test that the diff stops after the maximum number of differences.
+    #[test]
+    fn test_max_differences_truncates() {
+        let a: Value = (0..100).map(|i| (format!("k{i}"), json!(i))).collect();
+        let b: Value = (0..100).map(|i| (format!("k{i}"), json!(-i - 1))).collect();
+        let options = DiffOptions::builder().max_differences(3).build();
+        let result = deep_diff_result(&a, &b, &options);
+        assert!(result.truncated);
+        assert_eq!(result.differences.len(), 3);
+        assert_eq!(result.differences, deep_diff(&a, &b)[..3].to_vec());
+    }
+
+    /// Test that reaching the limit exactly is not reported as truncation.
+    #[test]
+    fn test_max_differences_exact() {
+        let a = json!({"x": [1, 2], "y": 1});
+        let b = json!({"x": [1, 3, 4], "y": 2});
+        let options = DiffOptions::builder().max_differences(3).build();
+        let result = deep_diff_result(&a, &b, &options);
+        assert!(!result.truncated);
+        assert_eq!(result.differences, deep_diff(&a, &b));
+        let unlimited = deep_diff_result(&a, &b, &DiffOptions::default());
+        assert!(!unlimited.truncated);
+    }
+
+    /// Test that invalid options are reported instead of ignored.
+    #[test]
+    fn test_try_deep_diff() {
+        let a = json!({"id": 1, "tags": ["a"]});
+        let b = json!({"id": 2, "tags": ["b"]});
+        let options = DiffOptions::builder().ignore("id").build();
+        let result = try_deep_diff(&a, &b, &options).unwrap();
+        assert_eq!(result, deep_diff_result(&a, &b, &options));
+        let invalid = DiffOptions::builder()
+            .ignore("id")
+            .ignore("tags[")
+            .array_key("items[?", "id");
+        let error = DiffError::InvalidPattern("tags[".to_string());
+        assert_eq!(invalid.clone().try_build().unwrap_err(), error);
+        assert_eq!(try_deep_diff(&a, &b, &invalid.build()), Err(error.clone()));
+        assert_eq!(error.to_string(), "invalid path pattern 'tags['");
+    }
+
+    /// Test that an engine diffs like the functions taking its options.
+    #[test]
+    fn test_diff_engine() {
+        let options = DiffOptions::builder()
+            .ignore("**.etag")
+            .array_key("items", "id")
+            .build();
+        let engine = DiffEngine::new(options.clone()).unwrap();
+        let pairs = [
+            (
+                json!({"items": [{"id": 1, "n": 1}], "etag": 1}),
+                json!({"items": [{"id": 1, "n": 2}], "etag": 2}),
+            ),
+            (
+                json!({"items": [], "etag": 1}),
+                json!({"items": [], "etag": 2}),
+            ),
+            (json!([1, 2]), json!({"a": 1})),
+        ];
+        for (a, b) in &pairs {
+            assert_eq!(engine.diff(a, b), deep_diff_with_options(a, b, &options));
+            assert_eq!(engine.diff_result(a, b), deep_diff_result(a, b, &options));
+            assert_eq!(
+                engine.diff_iter(a, b).collect::<Vec<_>>(),
+                engine.diff(a, b)
+            );
+            assert_eq!(engine.are_different(a, b), !engine.diff(a, b).is_empty());
+        }
+        let batch: Vec<_> = pairs.iter().map(|(a, b)| engine.diff(a, b)).collect();
+        assert_eq!(engine.diff_batch(&pairs), batch);
+        assert!(DiffEngine::default().diff(&json!(1), &json!(1)).is_empty());
+        let invalid = DiffOptions::builder().ignore("items[").build();
+        assert_eq!(
+            DiffEngine::new(invalid).unwrap_err(),
+            DiffError::InvalidPattern("items[".to_string())
+        );
+    }
+
+    /// Test the path helpers on a diff result.
+    #[test]
+    fn test_diff_result_paths() {
+        let a = json!({"items": [{"a": 1}, {"a": 2}], "items2": 1, "gone": {"x": 1}});
+        let b = json!({"items": [{"a": 1}, {"a": 3, "b": 4}], "items2": 2});
+        let result = deep_diff_result(&a, &b, &DiffOptions::default());
+        let paths: Vec<_> = result.paths().map(Path::to_string).collect();
+        assert_eq!(paths, ["gone", "items[1].a", "items[1].b", "items2"]);
+        assert!(result.contains_path("items[1]"));
+        assert!(result.contains_path("gone.x"));
+        assert!(!result.contains_path("items[0]"));
+        assert!(!result.contains_path("items[x"));
+        let in_items: Vec<_> = result.at_prefix("items").map(|d| d.path.as_str()).collect();
+        assert_eq!(in_items, ["items[1].a", "items[1].b"]);
+        assert_eq!(result.at_prefix("[").count(), 0);
+        assert_eq!(result.max_depth(), 3);
+        assert_eq!(DiffResult::default().max_depth(), 0);
+    }
+
+    /// Test that the metrics count the comparisons made.
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_deep_diff_with_metrics() {
+        let a = json!({"x": [1, 2], "y": {"z": 1}});
+        let b = json!({"x": [1, 3], "y": {"z": 1}});
+        let (result, metrics) = deep_diff_with_metrics(&a, &b, &DiffOptions::default());
+        assert_eq!(result.differences, deep_diff(&a, &b));
+        assert_eq!(metrics.differences, 1);
+        assert_eq!(metrics.nodes_visited, 6);
+        assert_eq!(metrics.max_depth, 2);
+
+        let options = DiffOptions::builder().max_differences(1).build();
+        let b = json!({"x": [0, 3], "y": {"z": 2}});
+        let (result, metrics) = deep_diff_with_metrics(&a, &b, &options);
+        assert!(result.truncated);
+        assert_eq!(metrics.differences, 1);
+    }
+
+    /// Test that depth and difference limits combine on deep inputs.
+    #[test]
+    fn test_limits_combined() {
+        let mut a = json!(1);
+        let mut b = json!(2);
+        for _ in 0..50 {
+            a = json!([a, 0]);
+            b = json!([b, 1]);
+        }
+        let options = DiffOptions::builder()
+            .max_depth(10)
+            .max_differences(1)
+            .build();
+        let result = deep_diff_result(&a, &b, &options);
+        assert!(result.truncated);
+        assert_eq!(result.differences.len(), 1);
+        assert_eq!(result.differences[0].segments.len(), 10);
+    }
+
+    // ======================
+    // Deep Nesting Tests
+    // ======================
+
+    // Builds `depth` levels of alternating arrays and objects around `leaf`
+    fn nested(depth: usize, leaf: Value) -> Value {
+        (0..depth).fold(leaf, |value, level| {
+            if level % 2 == 0 {
+                Value::Array(vec![value])
+            } else {
+                Value::Object([("k".to_string(), value)].into_iter().collect())
+            }
+        })
+    }
+
+    // Takes a deeply nested value apart level by level, since dropping it
+    // in one go recurses as deep as the value is nested
+    fn dismantle(mut value: Value) {
+        loop {
+            value = match value {
+                Value::Array(mut values) => match values.pop() {
+                    Some(inner) => inner,
+                    None => break,
+                },
+                Value::Object(mut map) => match map.remove("k") {
+                    Some(inner) => inner,
+                    None => break,
+                },
+                _ => break,
+            };
+        }
+    }
+
+    /// Test that deeply nested documents are diffed without overflowing the stack.
+    #[test]
+    fn test_deeply_nested_documents() {
+        let depth = 5_000;
+        let a = nested(depth, json!(1));
+        let b = nested(depth, json!(2));
+        let mut result = deep_diff(&a, &b);
+        assert_eq!(result.len(), 1);
+        let difference = result.pop().unwrap();
+        assert_eq!(difference.segments.len(), depth);
+        assert_eq!(difference.after, Some(json!(2)));
+        assert!(difference.path.starts_with("k[0].k[0]"));
+        dismantle(a);
+        dismantle(b);
+    }
+
+    // ======================
+    // Borrowed Difference Tests
+    // ======================
+
+    /// Test that borrowed differences point into the compared documents.
+    #[test]
+    fn test_deep_diff_ref_borrows() {
+        let a = json!({"big": {"list": [1, 2, 3]}, "n": 1});
+        let b = json!({"n": 2});
+        let result = deep_diff_ref(&a, &b);
+        assert_eq!(result.len(), 2);
+        let Some(Cow::Borrowed(before)) = &result[0].before else {
+            panic!("expected a borrowed value");
+        };
+        assert!(std::ptr::eq(*before, &a["big"]));
+        assert_eq!(result[1].path, "n");
+    }
+
+    /// Test that borrowed differences convert to the owned ones.
+    #[test]
+    fn test_deep_diff_ref_to_owned() {
+        let a = json!({"a": [1, {"b": 2}], "c": "x"});
+        let b = json!({"a": [1, {"b": 3}, 4], "d": null});
+        let options = DiffOptions::builder()
+            .array_strategy(ArrayDiffStrategy::Lcs)
+            .build();
+        let owned: Vec<Difference> = deep_diff_ref_with_options(&a, &b, &options)
+            .iter()
+            .map(DifferenceRef::to_owned)
+            .collect();
+        assert_eq!(owned, deep_diff_with_options(&a, &b, &options));
+    }
+
+    // ======================
+    // Iterator Tests
+    // ======================
+
+    /// Test that the iterator yields the same differences as the Vec API.
+    #[test]
+    fn test_diff_iter_matches_deep_diff() {
+        let a = json!({"a": [1, 2, 3], "b": {"c": 1}, "d": true});
+        let b = json!({"a": [1, 5], "b": {"c": 2, "e": 3}});
+        let collected: Vec<Difference> = diff_iter(&a, &b).collect();
+        assert_eq!(collected, deep_diff(&a, &b));
+        let options = DiffOptions::builder().max_differences(2).build();
+        assert_eq!(diff_iter_with_options(&a, &b, &options).count(), 2);
+    }
+
+    /// Test that the iterator only walks as far as it is advanced.
+    #[test]
+    fn test_diff_iter_is_lazy() {
+        let a = json!({"first": 1, "rest": [1, 2, 3]});
+        let b = json!({"first": 2, "rest": [3, 2, 1]});
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let options = DiffOptions::builder()
+            .compare_at("rest[*]", move |_, _| {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                Comparison::Default
+            })
+            .build();
+        let first = diff_iter_with_options(&a, &b, &options).next().unwrap();
+        assert_eq!(first.path, "first");
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
+
+    // ======================
+    // Short-circuit Tests
+    // ======================
+
+    /// Test the short-circuiting equality check.
+    #[test]
+    fn test_are_different() {
+        let a = json!({"a": [1, 2, {"b": null}], "c": "x"});
+        assert!(!are_different(&a, &a.clone()));
+        assert!(are_different(&a, &json!({"a": [1, 2, {"b": 0}], "c": "x"})));
+        let options = DiffOptions::builder().ignore("c").build();
+        assert!(!are_different_with_options(
+            &a,
+            &json!({"a": [1, 2, {"b": null}], "c": "y"}),
+            &options
+        ));
+    }
+
+    /// Test that the first difference is the first one a full diff reports.
+    #[test]
+    fn test_first_difference() {
+        let a = json!({"a": 1, "b": [1, 2], "c": 3});
+        let b = json!({"a": 1, "b": [1, 3], "c": 4});
+        assert_eq!(
+            first_difference(&a, &b),
+            deep_diff(&a, &b).into_iter().next()
+        );
+        assert_eq!(first_difference(&a, &b).unwrap().path, "b[1]");
+        assert_eq!(first_difference(&a, &a), None);
+    }
+
+    /// Test that only the subtree at the path is diffed, with paths from
+    /// either root.
+    #[test]
+    fn test_deep_diff_at() {
+        let a = json!({"items": [{"spec": {"a": 1, "b": 2}}], "other": 1});
+        let b = json!({"items": [{"spec": {"a": 1, "b": 3}}], "other": 2});
+        for path in ["items[0].spec", "/items/0/spec"] {
+            let diffs = deep_diff_at(&a, &b, path, false).unwrap();
+            assert_eq!(diffs.len(), 1);
+            assert_eq!(diffs[0].path, "items[0].spec.b");
+            assert_eq!(diffs[0].segments, "items[0].spec.b".parse().unwrap());
+            let diffs = deep_diff_at(&a, &b, path, true).unwrap();
+            assert_eq!(diffs[0].path, "b");
+        }
+        assert!(deep_diff_at(&a, &b, "items[1]", false).unwrap().is_empty());
+        assert!(deep_diff_at(&a, &b, "items[x]", false).is_err());
+    }
+
+    /// Test that a subtree present on one side only is reported whole.
+    #[test]
+    fn test_deep_diff_at_missing() {
+        let a = json!({"spec": {}});
+        let b = json!({"spec": {"template": {"x": 1}}});
+        let diffs = deep_diff_at(&a, &b, "spec.template", false).unwrap();
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(
+            (diffs[0].path.as_str(), diffs[0].kind.clone()),
+            ("spec.template", DiffKind::Added)
+        );
+        assert_eq!(diffs[0].after, Some(json!({"x": 1})));
+        let diffs = deep_diff_at(&b, &a, "spec.template", true).unwrap();
+        assert_eq!(
+            (diffs[0].path.as_str(), diffs[0].kind.clone()),
+            ("", DiffKind::Removed)
+        );
+        let options = DiffOptions::builder().mode(DiffMode::SubsetOf).build();
+        assert!(
+            deep_diff_at_with_options(&a, &b, "spec.template", false, &options)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    /// Test that a subtree present on one side only is redacted like any
+    /// other difference.
+    #[test]
+    fn test_deep_diff_at_redact() {
+        let options = DiffOptions::builder().redact(["password"]).build();
+        let b = json!({"user": {"password": "hunter2"}});
+        for relative in [true, false] {
+            let diffs = deep_diff_at_with_options(&json!({}), &b, "user", relative, &options);
+            assert_eq!(diffs.unwrap()[0].after, Some(json!({"password": REDACTED})));
+        }
+        let diffs = deep_diff_at_with_options(&b, &json!({}), "user", true, &options);
+        assert_eq!(
+            diffs.unwrap()[0].before,
+            Some(json!({"password": REDACTED}))
+        );
+    }
+
+    // ======================
+    // Hash Pruning Tests
+    // ======================
+
+    /// Test that hash pruning reports the same differences.
+    #[test]
+    fn test_hash_pruning_same_result() {
+        let a = json!({"big": [{"x": 1}, {"x": 2}], "list": [1, 2, 3], "n": 1});
+        let b = json!({"big": [{"x": 1}, {"x": 3}], "list": [1, 3], "n": 1.0});
+        let options = DiffOptions::builder()
+            .hash_pruning(true)
+            .array_strategy(ArrayDiffStrategy::Lcs)
+            .build();
+        let plain = DiffOptions::builder()
+            .array_strategy(ArrayDiffStrategy::Lcs)
+            .build();
+        assert_eq!(
+            deep_diff_with_options(&a, &b, &options),
+            deep_diff_with_options(&a, &b, &plain)
+        );
+    }
+
+    /// Test that identical subtrees are not walked.
+    #[test]
+    fn test_hash_pruning_skips_identical() {
+        let a = json!({"same": {"v": 1}, "other": {"v": 1}});
+        let b = json!({"same": {"v": 1}, "other": {"v": 2}});
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let options = DiffOptions::builder()
+            .hash_pruning(true)
+            .compare_at("*.v", move |_, _| {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                Comparison::Default
+            })
+            .build();
+        assert_eq!(deep_diff_with_options(&a, &b, &options).len(), 1);
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    /// Test that a session reuses the base document across diffs.
+    #[test]
+    fn test_diff_session() {
+        let base = json!({"a": {"b": [1, 2]}, "c": "x"});
+        let session = DiffSession::new(&base, DiffOptions::default());
+        for other in [
+            json!({"a": {"b": [1, 2]}, "c": "y"}),
+            json!({"a": {"b": [1]}}),
+            base.clone(),
+        ] {
+            assert_eq!(session.diff(&other), deep_diff(&base, &other));
+        }
+    }
+
+    // ======================
+    // Diff Mode Tests
+    // ======================
+
+    /// Test that subset mode ignores keys only present in the second document.
+    #[test]
+    fn test_mode_subset_of() {
+        let expected = json!({"user": {"name": "Al"}, "gone": 1, "list": [{"a": 1}]});
+        let actual = json!({"user": {"name": "Bo", "age": 3}, "id": 7, "list": [{"a": 1, "b": 2}]});
+        let options = DiffOptions::builder().mode(DiffMode::SubsetOf).build();
+        let paths: Vec<String> = deep_diff_with_options(&expected, &actual, &options)
+            .into_iter()
+            .map(|d| d.path)
+            .collect();
+        assert_eq!(paths, vec!["gone", "user.name"]);
+    }
+
+    /// Test that superset mode ignores keys only present in the first document.
+    #[test]
+    fn test_mode_superset_of() {
+        let a = json!({"a": 1, "b": 2});
+        let b = json!({"a": 2, "c": 3});
+        let options = DiffOptions::builder().mode(DiffMode::SupersetOf).build();
+        let result = deep_diff_with_options(&a, &b, &options);
+        assert_eq!(result.len(), 2);
+        assert_eq!(
+            (result[0].path.as_str(), result[0].kind.clone()),
+            ("a", DiffKind::Changed)
+        );
+        assert_eq!(
+            (result[1].path.as_str(), result[1].kind.clone()),
+            ("c", DiffKind::Added)
+        );
+    }
+
+    // ======================
+    // Serializable Value Tests
+    // ======================
+
+    /// Test that raw documents are parsed with the duplicate key policy.
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deep_diff_str() {
+        let (a, b) = (r#"{"a": 1, "a": 2}"#, r#"{"a": 2}"#);
+        assert!(
+            deep_diff_str(a, b, &DiffOptions::default())
+                .unwrap()
+                .is_empty()
+        );
+        let options = DiffOptions::builder()
+            .duplicate_keys(DuplicateKeys::Error)
+            .build();
+        assert!(deep_diff_str(a, b, &options).is_err());
+        assert!(deep_diff_str(b, "{", &DiffOptions::default()).is_err());
+        let options = DiffOptions::builder()
+            .duplicate_keys(DuplicateKeys::Preserve)
+            .build();
+        let diffs = deep_diff_str(a, b, &options).unwrap();
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(
+            (diffs[0].path.as_str(), diffs[0].kind.clone()),
+            ("a", DiffKind::Changed)
+        );
+        assert_eq!(
+            (diffs[1].path.as_str(), diffs[1].kind.clone()),
+            ("a#2", DiffKind::Removed)
+        );
+    }
+
+    /// Test that reordered keys are only reported when requested.
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deep_diff_str_key_order() {
+        let a = r#"{"b": 1, "a": {"x": 1, "y": 2, "z": 3}, "c": {"p": 1, "q": 2}}"#;
+        let b = r#"{"a": {"y": 2, "w": 0, "x": 1}, "b": 2, "c": {"q": 2, "p": 1}}"#;
+        let options = DiffOptions::builder().compare_key_order(true).build();
+        let diffs = deep_diff_str(a, b, &options).unwrap();
+        let summary: Vec<_> = diffs
+            .iter()
+            .map(|diff| (diff.path.as_str(), diff.kind.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("a.z", DiffKind::Removed),
+                ("a.w", DiffKind::Added),
+                ("b", DiffKind::Changed),
+                ("", DiffKind::KeyOrderChanged),
+                ("a", DiffKind::KeyOrderChanged),
+                ("c", DiffKind::KeyOrderChanged),
+            ]
+        );
+        assert_eq!(diffs[4].before, Some(json!(["x", "y", "z"])));
+        assert_eq!(diffs[4].after, Some(json!(["y", "w", "x"])));
+
+        let options = DiffOptions::builder()
+            .compare_key_order(true)
+            .ignore("c")
+            .build();
+        let diffs = deep_diff_str(a, b, &options).unwrap();
+        assert!(diffs.iter().all(|diff| !diff.path.starts_with('c')));
+        assert_eq!(
+            deep_diff_str(a, b, &DiffOptions::default()).unwrap().len(),
+            3
+        );
+    }
+
+    /// Test that plain Rust values are diffed through their JSON trees.
+    #[cfg(all(feature = "serde", feature = "std"))]
+    #[test]
+    fn test_diff_values() {
+        use std::collections::HashMap;
+        let a = HashMap::from([("name", "Alice"), ("city", "Oslo")]);
+        let b = HashMap::from([("name", "Bob"), ("city", "Oslo")]);
+        let result = diff_values(&a, &b).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].path, "name");
+        assert_eq!(result[0].after, Some(json!("Bob")));
+        assert!(diff_values(&[1, 2], &[1, 2]).unwrap().is_empty());
+    }
+
+    /// Test that serializable values are diffed through their JSON trees.
+    #[cfg(feature = "binary-formats")]
+    #[test]
+    fn test_deep_diff_serialize() {
+        use std::collections::BTreeMap;
+        let a: BTreeMap<u32, Vec<(bool, f64)>> = BTreeMap::from([(1, vec![(true, 1.5)])]);
+        let b: BTreeMap<u32, Vec<(bool, f64)>> =
+            BTreeMap::from([(1, vec![(false, 1.5)]), (2, vec![])]);
+        let result = deep_diff_serialize(&a, &b).unwrap();
+        let paths: Vec<&str> = result.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(paths, vec!["1[0][0]", "2"]);
+    }
+
+    /// Test that values without a JSON representation are rejected.
+    #[cfg(feature = "binary-formats")]
+    #[test]
+    fn test_deep_diff_serialize_error() {
+        use std::collections::BTreeMap;
+        let a = BTreeMap::from([(vec![1], 1)]);
+        assert!(deep_diff_serialize(&a, &a).is_err());
+    }
+
+    // ======================
+    // Severity Tests
+    // ======================
+
+    /// Test that differences carry the highest matching severity.
+    #[test]
+    fn test_severity_rules() {
+        let a = json!({"security": {"tls": true, "ciphers": ["a"]}, "name": "x", "replicas": 1});
+        let b = json!({"security": {"tls": false, "ciphers": []}, "name": "y"});
+        let options = DiffOptions::builder()
+            .severity("security", Severity::Warning)
+            .severity("security.tls", Severity::Critical)
+            .severity("replicas", Severity::Warning)
+            .build();
+        let severities: Vec<(String, Severity)> = deep_diff_with_options(&a, &b, &options)
+            .into_iter()
+            .map(|d| (d.path, d.severity))
+            .collect();
+        assert_eq!(
+            severities,
+            vec![
+                ("name".to_string(), Severity::Info),
+                ("replicas".to_string(), Severity::Warning),
+                ("security.ciphers[0]".to_string(), Severity::Warning),
+                ("security.tls".to_string(), Severity::Critical),
+            ]
+        );
+        assert!(
+            deep_diff(&a, &b)
+                .iter()
+                .all(|d| d.severity == Severity::Info)
+        );
+    }
 }
@@ -0,0 +1,198 @@
+//! Three-way merging of JSON documents.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use serde_json::{Map, Value};
+
+use crate::Path;
+
+/// A location that both sides changed in incompatible ways.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// The path to the conflicting value, e.g. `"items[0].name"`.
+    pub path: String,
+    /// The same location as `path`, split into typed segments.
+    pub segments: Path,
+    /// The value in the common ancestor, if it existed there.
+    pub base: Option<Value>,
+    /// Our value, if we kept it.
+    pub ours: Option<Value>,
+    /// Their value, if they kept it.
+    pub theirs: Option<Value>,
+}
+
+/// The outcome of [`three_way_merge`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeResult {
+    /// The merged document. Where the sides conflict it holds our value.
+    pub merged: Value,
+    /// Every location where both sides made different changes.
+    pub conflicts: Vec<Conflict>,
+}
+
+impl MergeResult {
+    /// Returns whether the merge completed without conflicts.
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// Merges the changes made from `base` to `ours` with those made from
+/// `base` to `theirs`.
+///
+/// Changes made by only one side, or identically by both, are applied.
+/// Objects are merged key by key; arrays are merged element by element if
+/// all three have the same length, and otherwise conflict as a whole when
+/// both sides changed them.
+pub fn three_way_merge(base: &Value, ours: &Value, theirs: &Value) -> MergeResult {
+    let mut conflicts = Vec::new();
+    let merged = merge(
+        Some(base),
+        Some(ours),
+        Some(theirs),
+        &Path::root(),
+        &mut conflicts,
+    );
+    MergeResult {
+        merged: merged.unwrap_or(Value::Null),
+        conflicts,
+    }
+}
+
+// Merges one location, where `None` means the value does not exist
+fn merge(
+    base: Option<&Value>,
+    ours: Option<&Value>,
+    theirs: Option<&Value>,
+    path: &Path,
+    conflicts: &mut Vec<Conflict>,
+) -> Option<Value> {
+    if ours == theirs || theirs == base {
+        return ours.cloned();
+    }
+    if ours == base {
+        return theirs.cloned();
+    }
+    match (base, ours, theirs) {
+        // Deals with objects changed on both sides
+        (Some(Value::Object(b)), Some(Value::Object(o)), Some(Value::Object(t))) => {
+            let mut merged = Map::new();
+            let keys = o
+                .keys()
+                .chain(t.keys().filter(|k| !o.contains_key(*k)))
+                .chain(
+                    b.keys()
+                        .filter(|k| !o.contains_key(*k) && !t.contains_key(*k)),
+                );
+            for key in keys {
+                let child = path.child_key(key);
+                if let Some(value) = merge(b.get(key), o.get(key), t.get(key), &child, conflicts) {
+                    merged.insert(key.clone(), value);
+                }
+            }
+            Some(Value::Object(merged))
+        }
+        // Deals with arrays changed on both sides without changing length
+        (Some(Value::Array(b)), Some(Value::Array(o)), Some(Value::Array(t)))
+            if b.len() == o.len() && b.len() == t.len() =>
+        {
+            let merged = (0..b.len())
+                .map(|i| {
+                    let child = path.child_index(i);
+                    merge(Some(&b[i]), Some(&o[i]), Some(&t[i]), &child, conflicts)
+                        .unwrap_or(Value::Null)
+                })
+                .collect();
+            Some(Value::Array(merged))
+        }
+        _ => {
+            conflicts.push(Conflict {
+                path: path.to_string(),
+                segments: path.clone(),
+                base: base.cloned(),
+                ours: ours.cloned(),
+                theirs: theirs.cloned(),
+            });
+            ours.cloned()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Test that independent changes from both sides are combined.
+    #[test]
+    fn test_three_way_merge_clean() {
+        let base = json!({"name": "app", "replicas": 1, "env": {"A": "1"}, "old": true});
+        let ours = json!({"name": "app", "replicas": 3, "env": {"A": "1"}, "old": true});
+        let theirs = json!({"name": "app", "replicas": 1, "env": {"A": "1", "B": "2"}});
+        let result = three_way_merge(&base, &ours, &theirs);
+        assert!(result.is_clean());
+        assert_eq!(
+            result.merged,
+            json!({"name": "app", "replicas": 3, "env": {"A": "1", "B": "2"}})
+        );
+    }
+
+    /// Test that identical changes on both sides do not conflict.
+    #[test]
+    fn test_three_way_merge_same_change() {
+        let base = json!({"v": 1, "list": [1, 2]});
+        let ours = json!({"v": 2, "list": [1, 2, 3]});
+        let result = three_way_merge(&base, &ours, &ours.clone());
+        assert!(result.is_clean());
+        assert_eq!(result.merged, ours);
+    }
+
+    /// Test that incompatible changes are reported and ours are kept.
+    #[test]
+    fn test_three_way_merge_conflicts() {
+        let base = json!({"v": 1, "gone": 1, "list": [1, 2], "pair": [1, 2]});
+        let ours = json!({"v": 2, "list": [1, 2, 3], "pair": [5, 2]});
+        let theirs = json!({"v": 3, "gone": 2, "list": [0], "pair": [1, 6]});
+        let result = three_way_merge(&base, &ours, &theirs);
+        assert_eq!(
+            result.merged,
+            json!({"v": 2, "list": [1, 2, 3], "pair": [5, 6]})
+        );
+        assert_eq!(
+            result.conflicts,
+            vec![
+                Conflict {
+                    path: "list".to_string(),
+                    segments: "list".parse().unwrap(),
+                    base: Some(json!([1, 2])),
+                    ours: Some(json!([1, 2, 3])),
+                    theirs: Some(json!([0])),
+                },
+                Conflict {
+                    path: "v".to_string(),
+                    segments: "v".parse().unwrap(),
+                    base: Some(json!(1)),
+                    ours: Some(json!(2)),
+                    theirs: Some(json!(3)),
+                },
+                Conflict {
+                    path: "gone".to_string(),
+                    segments: "gone".parse().unwrap(),
+                    base: Some(json!(1)),
+                    ours: None,
+                    theirs: Some(json!(2)),
+                },
+            ]
+        );
+    }
+
+    /// Test that a replaced root conflicts as a whole.
+    #[test]
+    fn test_three_way_merge_root() {
+        let result = three_way_merge(&json!(1), &json!("a"), &json!([1]));
+        assert_eq!(result.merged, json!("a"));
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].path, "");
+    }
+}
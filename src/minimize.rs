@@ -0,0 +1,262 @@
+//! Coalescing of many small changes into fewer, larger ones, for compact
+//! patches.
+
+use alloc::collections::BTreeMap;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use serde_json::Value;
+
+use crate::engine::change_kind;
+use crate::patch::{apply_one, get};
+use crate::{DiffKind, Difference, Path, PathFormat, Withheld};
+
+/// Rewrites `differences`, computed against `base`, so that the changes
+/// under a path are replaced by a single replacement of that path whenever
+/// the replacement is no larger, e.g. when a whole subtree was swapped.
+///
+/// Equivalent to [`minimize_with_threshold`] with a threshold of `1.0`.
+///
+/// ```
+/// use deep_diff::{apply_diff, deep_diff, minimize};
+/// use serde_json::json;
+///
+/// let a = json!({"settings": {"profile": {"first": 1, "second": 2, "third": 3}}});
+/// let b = json!({"settings": {"profile": {"one": 1, "two": 2, "three": 3}}});
+/// let diffs = deep_diff(&a, &b);
+/// assert_eq!(diffs.len(), 6);
+/// let minimized = minimize(&a, &diffs);
+/// assert_eq!(minimized.len(), 1);
+/// assert_eq!(minimized[0].path, "settings.profile");
+/// assert_eq!(apply_diff(&a, &minimized).unwrap(), b);
+/// ```
+pub fn minimize(base: &Value, differences: &[Difference]) -> Vec<Difference> {
+    minimize_with_threshold(base, differences, 1.0)
+}
+
+/// Like [`minimize`], replacing the changes under a path whenever the
+/// replacement is at most `threshold` times their combined size, measured
+/// as serialized JSON.
+///
+/// A threshold above `1.0` favors fewer differences over fewer bytes. Only
+/// changes that are consecutive in `differences` are coalesced, so the
+/// result applies to `base` exactly like the original. Binary and key order
+/// changes are never coalesced, and if `differences` do not apply to
+/// `base` they are returned unchanged.
+pub fn minimize_with_threshold(
+    base: &Value,
+    differences: &[Difference],
+    threshold: f64,
+) -> Vec<Difference> {
+    // The run of consecutive differences under each ancestor of a
+    // difference, for the ancestors whose differences are all consecutive
+    let mut runs: BTreeMap<Path, (usize, usize)> = BTreeMap::new();
+    let mut broken: Vec<Path> = Vec::new();
+    for (index, diff) in differences.iter().enumerate() {
+        let segments = diff.segments.segments();
+        for depth in 0..segments.len() {
+            let ancestor = Path::from(segments[..depth].to_vec());
+            match runs.get_mut(&ancestor) {
+                Some((_, end)) if *end + 1 == index => *end = index,
+                Some(_) => broken.push(ancestor),
+                None => {
+                    runs.insert(ancestor, (index, index));
+                }
+            }
+        }
+    }
+    for path in broken {
+        runs.remove(&path);
+    }
+    runs.retain(|_, (start, end)| {
+        differences[*start..=*end].iter().all(|diff| {
+            !matches!(
+                diff.kind,
+                DiffKind::BinaryChanged { .. } | DiffKind::KeyOrderChanged
+            )
+        })
+    });
+
+    let Some(snapshots) = snapshot(base, differences, &runs) else {
+        return differences.to_vec();
+    };
+
+    // The differences kept so far by the index of the first one they stand
+    // for, with the index of the last one and their size
+    let mut kept: BTreeMap<usize, (usize, Option<Difference>, usize)> = differences
+        .iter()
+        .enumerate()
+        .map(|(index, diff)| (index, (index, Some(diff.clone()), size(diff))))
+        .collect();
+    // Deeper paths first, so their replacements are weighed by their parents
+    let mut candidates: Vec<(&Path, &(usize, usize))> = runs.iter().collect();
+    candidates.sort_by_key(|(path, _)| core::cmp::Reverse(path.len()));
+    for (path, &(start, end)) in candidates {
+        let (before, after) = &snapshots[path];
+        let inner: usize = kept.range(start..=end).map(|(_, (_, _, size))| size).sum();
+        let replacement = replace(path, before, after, &differences[start..=end]);
+        let replacement_size = replacement.as_ref().map_or(0, size);
+        if replacement_size as f64 > threshold * inner as f64 {
+            continue;
+        }
+        let covered: Vec<usize> = kept.range(start..=end).map(|(&index, _)| index).collect();
+        for index in covered {
+            kept.remove(&index);
+        }
+        kept.insert(start, (end, replacement, replacement_size));
+    }
+    kept.into_values().filter_map(|(_, diff, _)| diff).collect()
+}
+
+// The value at a path before and after a run of differences
+type Snapshot = (Option<Value>, Option<Value>);
+
+// Applies the differences in order, recording the value at each run's path
+// just before its first difference and just after its last one
+fn snapshot(
+    base: &Value,
+    differences: &[Difference],
+    runs: &BTreeMap<Path, (usize, usize)>,
+) -> Option<BTreeMap<Path, Snapshot>> {
+    let mut starting: BTreeMap<usize, Vec<&Path>> = BTreeMap::new();
+    let mut ending: BTreeMap<usize, Vec<&Path>> = BTreeMap::new();
+    for (path, &(start, end)) in runs {
+        starting.entry(start).or_default().push(path);
+        ending.entry(end).or_default().push(path);
+    }
+    let mut snapshots: BTreeMap<Path, Snapshot> = BTreeMap::new();
+    let mut doc = base.clone();
+    for (index, diff) in differences.iter().enumerate() {
+        for &path in starting.get(&index).into_iter().flatten() {
+            let before = get(&doc, path.segments()).cloned();
+            snapshots.insert(path.clone(), (before, None));
+        }
+        match diff.kind {
+            // Nothing in a run changes with them, so they can be left out
+            DiffKind::BinaryChanged { .. } | DiffKind::KeyOrderChanged => {}
+            _ => apply_one(&mut doc, diff).ok()?,
+        }
+        for &path in ending.get(&index).into_iter().flatten() {
+            snapshots.get_mut(path)?.1 = get(&doc, path.segments()).cloned();
+        }
+    }
+    Some(snapshots)
+}
+
+// The single difference replacing the value at `path`, or `None` if the
+// differences cancel out
+fn replace(
+    path: &Path,
+    before: &Option<Value>,
+    after: &Option<Value>,
+    replaced: &[Difference],
+) -> Option<Difference> {
+    let kind = match (before, after) {
+        (None, None) => return None,
+        (Some(x), Some(y)) if x == y => return None,
+        (None, Some(_)) => DiffKind::Added,
+        (Some(_), None) => DiffKind::Removed,
+        (Some(x), Some(y)) => change_kind(x, y),
+    };
+    Some(Difference {
+        path: path.render(path_format(&replaced[0])),
+        segments: path.clone(),
+        kind,
+        severity: replaced
+            .iter()
+            .map(|diff| diff.severity)
+            .max()
+            .unwrap_or_default(),
+        before: before.clone(),
+        after: after.clone(),
+        context: None,
+        withheld: Withheld::default(),
+    })
+}
+
+// The format a difference's path was rendered in
+pub(crate) fn path_format(diff: &Difference) -> PathFormat {
+    [
+        PathFormat::Dotted,
+        PathFormat::Escaped,
+        PathFormat::JsonPointer,
+    ]
+    .into_iter()
+    .find(|&format| diff.segments.render(format) == diff.path)
+    .unwrap_or_default()
+}
+
+// The serialized size of a difference
+fn size(diff: &Difference) -> usize {
+    let value_len = |value: &Option<Value>| value.as_ref().map_or(0, |v| v.to_string().len());
+    diff.path.len() + value_len(&diff.before) + value_len(&diff.after)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ArrayDiffStrategy, DiffOptions, apply_diff, deep_diff, deep_diff_with_options};
+    use serde_json::json;
+
+    fn paths(diffs: &[Difference]) -> Vec<&str> {
+        diffs.iter().map(|diff| diff.path.as_str()).collect()
+    }
+
+    /// Test that swapped subtrees are replaced whole and small changes kept.
+    #[test]
+    fn test_minimize() {
+        let a = json!({
+            "big": {"text": "a long value that stays the same", "x": 1, "y": 2},
+            "swapped": {"a": 1, "b": 2, "c": 3},
+            "list": [1, 2, 3, 4]
+        });
+        let b = json!({
+            "big": {"text": "a long value that stays the same", "x": 3, "y": 4},
+            "swapped": {"d": 4, "e": 5, "f": 6},
+            "list": [5, 6, 7, 8]
+        });
+        let diffs = deep_diff(&a, &b);
+        let minimized = minimize(&a, &diffs);
+        assert_eq!(paths(&minimized), ["big.x", "big.y", "list", "swapped"]);
+        assert_eq!(minimized[3].kind, DiffKind::Changed);
+        assert_eq!(apply_diff(&a, &minimized).unwrap(), b);
+        assert_eq!(minimize(&a, &[]), []);
+    }
+
+    /// Test that the threshold trades size for fewer differences.
+    #[test]
+    fn test_minimize_threshold() {
+        let a = json!({"big": {"text": "a long value that stays the same", "x": 1}});
+        let b = json!({"big": {"text": "a long value that stays the same", "x": 2}});
+        let diffs = deep_diff(&a, &b);
+        assert_eq!(paths(&minimize_with_threshold(&a, &diffs, 1.0)), ["big.x"]);
+        let minimized = minimize_with_threshold(&a, &diffs, 100.0);
+        assert_eq!(paths(&minimized), [""]);
+        assert_eq!(apply_diff(&a, &minimized).unwrap(), b);
+    }
+
+    /// Test that sequential array changes are coalesced correctly.
+    #[test]
+    fn test_minimize_sequential_arrays() {
+        let options = DiffOptions::builder()
+            .array_strategy(ArrayDiffStrategy::Lcs)
+            .path_format(PathFormat::JsonPointer)
+            .build();
+        let a = json!({"keep": "a long value that stays the same", "items": [1, 2, 3, 4, 5]});
+        let b = json!({"keep": "a long value that stays the same", "items": [0, 2, 9, 4]});
+        let diffs = deep_diff_with_options(&a, &b, &options);
+        let minimized = minimize(&a, &diffs);
+        assert_eq!(paths(&minimized), ["/items"]);
+        assert_eq!(apply_diff(&a, &minimized).unwrap(), b);
+    }
+
+    /// Test that differences which do not apply are returned unchanged.
+    #[test]
+    fn test_minimize_inapplicable() {
+        let diffs = deep_diff(
+            &json!({"a": {"b": 1, "c": 2}}),
+            &json!({"a": {"b": 2, "c": 3}}),
+        );
+        assert_eq!(minimize(&json!({}), &diffs), diffs);
+    }
+}
@@ -0,0 +1,176 @@
+//! Comparison of any number of documents at once.
+
+use alloc::collections::BTreeSet;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use serde_json::{Map, Value};
+
+use crate::Path;
+
+/// A location whose value is not the same in every document passed to
+/// [`deep_diff_many`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiDifference {
+    /// The path to the value, e.g. `"items[0].name"`.
+    pub path: String,
+    /// The same location as `path`, split into typed segments.
+    pub segments: Path,
+    /// The value in each document, in the order the documents were given,
+    /// or `None` where the document has no value there.
+    pub values: Vec<Option<Value>>,
+}
+
+impl MultiDifference {
+    /// Returns whether some document has no value at this location.
+    pub fn is_missing_somewhere(&self) -> bool {
+        self.values.iter().any(Option::is_none)
+    }
+}
+
+/// Compares several documents, such as the same config in each of dev,
+/// staging and prod, in one pass.
+///
+/// Reports every location whose value is not identical across all the
+/// documents, with the value from each. Objects are compared key by key and
+/// arrays index by index as long as every document has one there;
+/// otherwise the location is reported as a whole. Differences are reported
+/// in the same depth-first order as [`deep_diff`](crate::deep_diff).
+///
+/// ```
+/// use deep_diff::deep_diff_many;
+/// use serde_json::json;
+///
+/// let dev = json!({"replicas": 1, "image": "app:2"});
+/// let staging = json!({"replicas": 2, "image": "app:2"});
+/// let prod = json!({"replicas": 5, "image": "app:2"});
+/// let diffs = deep_diff_many(&[&dev, &staging, &prod]);
+/// assert_eq!(diffs.len(), 1);
+/// assert_eq!(diffs[0].path, "replicas");
+/// assert_eq!(diffs[0].values, [Some(json!(1)), Some(json!(2)), Some(json!(5))]);
+/// ```
+pub fn deep_diff_many(documents: &[&Value]) -> Vec<MultiDifference> {
+    let mut differences = Vec::new();
+    // Each location with the value from every document
+    let roots = documents.iter().map(|&value| Some(value)).collect();
+    let mut stack: Vec<(Path, Vec<Option<&Value>>)> = vec![(Path::root(), roots)];
+    while let Some((path, values)) = stack.pop() {
+        let Some(first) = values.first() else {
+            continue;
+        };
+        if values.iter().all(|value| value == first) {
+            continue;
+        }
+        let present: Option<Vec<&Value>> = values.iter().copied().collect();
+        match present.as_deref() {
+            // Children are pushed in reverse so they are popped in order
+            Some(all) if all.iter().all(|value| value.is_object()) => {
+                let keys: BTreeSet<&String> = all
+                    .iter()
+                    .filter_map(|value| value.as_object())
+                    .flat_map(Map::keys)
+                    .collect();
+                for key in keys.into_iter().rev() {
+                    let children = all.iter().map(|value| value.get(key)).collect();
+                    stack.push((path.child_key(key.as_str()), children));
+                }
+            }
+            Some(all) if all.iter().all(|value| value.is_array()) => {
+                let len = all
+                    .iter()
+                    .filter_map(|value| value.as_array())
+                    .map(Vec::len)
+                    .max();
+                for index in (0..len.unwrap_or(0)).rev() {
+                    let children = all.iter().map(|value| value.get(index)).collect();
+                    stack.push((path.child_index(index), children));
+                }
+            }
+            _ => differences.push(MultiDifference {
+                path: path.to_string(),
+                segments: path,
+                values: values.into_iter().map(|value| value.cloned()).collect(),
+            }),
+        }
+    }
+    differences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn summary(diffs: &[MultiDifference]) -> Vec<(&str, Vec<Option<Value>>)> {
+        diffs
+            .iter()
+            .map(|diff| (diff.path.as_str(), diff.values.clone()))
+            .collect()
+    }
+
+    /// Test that nested differences are reported with every document's value.
+    #[test]
+    fn test_deep_diff_many() {
+        let dev = json!({"db": {"host": "localhost", "pool": 5}, "debug": true, "tags": ["a"]});
+        let staging = json!({"db": {"host": "staging", "pool": 5}, "tags": ["a", "b"]});
+        let prod = json!({"db": {"host": "prod", "pool": 5}, "debug": false, "tags": ["a"]});
+        let diffs = deep_diff_many(&[&dev, &staging, &prod]);
+        assert_eq!(
+            summary(&diffs),
+            [
+                (
+                    "db.host",
+                    vec![
+                        Some(json!("localhost")),
+                        Some(json!("staging")),
+                        Some(json!("prod"))
+                    ]
+                ),
+                ("debug", vec![Some(json!(true)), None, Some(json!(false))]),
+                ("tags[1]", vec![None, Some(json!("b")), None]),
+            ]
+        );
+        assert!(diffs[1].is_missing_somewhere());
+        assert!(!diffs[0].is_missing_somewhere());
+        assert_eq!(diffs[2].segments, "tags[1]".parse().unwrap());
+    }
+
+    /// Test that containers of different types are reported whole.
+    #[test]
+    fn test_deep_diff_many_mixed_types() {
+        let a = json!({"x": {"y": 1}});
+        let b = json!({"x": [1]});
+        let c = json!({"x": {"y": 1}});
+        assert_eq!(
+            summary(&deep_diff_many(&[&a, &b, &c])),
+            [(
+                "x",
+                vec![
+                    Some(json!({"y": 1})),
+                    Some(json!([1])),
+                    Some(json!({"y": 1}))
+                ]
+            )]
+        );
+        assert_eq!(summary(&deep_diff_many(&[&a, &c])), []);
+        assert_eq!(summary(&deep_diff_many(&[&a])), []);
+        assert_eq!(summary(&deep_diff_many(&[])), []);
+    }
+
+    /// Test that two documents differ where `deep_diff` reports a change.
+    #[test]
+    fn test_deep_diff_many_matches_deep_diff() {
+        let a = json!({"a": [1, {"b": 2}], "c": null});
+        let b = json!({"a": [1, {"b": 3}, 4], "d": 1});
+        let paths: Vec<_> = crate::deep_diff(&a, &b)
+            .into_iter()
+            .map(|d| d.path)
+            .collect();
+        let many: Vec<_> = deep_diff_many(&[&a, &b])
+            .into_iter()
+            .map(|d| d.path)
+            .collect();
+        assert_eq!(many, paths);
+    }
+}
@@ -0,0 +1,216 @@
+//! Canonicalization of documents before diffing or hashing, so differences
+//! in key case, whitespace or number formatting do not show up as changes.
+//!
+//! ```
+//! use deep_diff::deep_diff;
+//! use deep_diff::normalize::{NormalizeOptions, normalize};
+//! use serde_json::json;
+//!
+//! let options = NormalizeOptions {
+//!     sort_arrays: true,
+//!     trim_strings: true,
+//!     lowercase_keys: true,
+//!     ..NormalizeOptions::default()
+//! };
+//! let a = normalize(&json!({"Tags": ["b", "a"], "name": " x"}), &options);
+//! let b = normalize(&json!({"tags": ["a", "b"], "Name": "x "}), &options);
+//! assert!(deep_diff(&a, &b).is_empty());
+//! ```
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+use serde_json::{Map, Number, Value};
+
+use crate::decimal::Decimal;
+
+/// Which canonicalizations [`normalize`] applies.
+///
+/// The default sorts object keys and collapses number representations,
+/// neither of which changes what a document means.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NormalizeOptions {
+    /// Rebuilds objects with their keys in sorted order, which only matters
+    /// when serde_json preserves insertion order.
+    pub sort_keys: bool,
+    /// Sorts the elements of every array, for arrays whose order carries no
+    /// meaning.
+    pub sort_arrays: bool,
+    /// Removes leading and trailing whitespace from strings. Object keys are
+    /// left as they are.
+    pub trim_strings: bool,
+    /// Lowercases object keys. If several keys of an object become the
+    /// same, the value of the last of them is kept.
+    pub lowercase_keys: bool,
+    /// Writes each number in one canonical form, so `1.0`, `1e0` and `1`
+    /// all become `1`, and `1.50` becomes `1.5`.
+    pub collapse_numbers: bool,
+}
+
+impl Default for NormalizeOptions {
+    /// Sorts object keys and collapses numbers.
+    fn default() -> Self {
+        NormalizeOptions {
+            sort_keys: true,
+            sort_arrays: false,
+            trim_strings: false,
+            lowercase_keys: false,
+            collapse_numbers: true,
+        }
+    }
+}
+
+/// Returns a canonical copy of `value`, applying `options` at every level.
+///
+/// Arrays are sorted after their elements have been normalized, by type
+/// (null, booleans, numbers, strings, arrays, then objects) and then by
+/// value.
+pub fn normalize(value: &Value, options: &NormalizeOptions) -> Value {
+    match value {
+        Value::Null | Value::Bool(_) => value.clone(),
+        Value::Number(number) if options.collapse_numbers => {
+            Value::Number(collapse(number).unwrap_or_else(|| number.clone()))
+        }
+        Value::Number(_) => value.clone(),
+        Value::String(s) if options.trim_strings => Value::from(s.trim()),
+        Value::String(_) => value.clone(),
+        Value::Array(values) => {
+            let mut values: Vec<Value> = values.iter().map(|v| normalize(v, options)).collect();
+            if options.sort_arrays {
+                values.sort_by(compare);
+            }
+            Value::Array(values)
+        }
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map
+                .iter()
+                .map(|(key, v)| {
+                    let key = match options.lowercase_keys {
+                        true => key.to_lowercase(),
+                        false => key.clone(),
+                    };
+                    (key, normalize(v, options))
+                })
+                .collect();
+            if options.sort_keys {
+                // Stable, so colliding keys keep their original order
+                entries.sort_by(|(x, _), (y, _)| x.cmp(y));
+            }
+            Value::Object(entries.into_iter().collect::<Map<String, Value>>())
+        }
+    }
+}
+
+// Rewrites a number in its canonical form: an integer when it has no
+// fractional part, otherwise the shortest decimal
+fn collapse(number: &Number) -> Option<Number> {
+    let decimal = Decimal::of(number)?;
+    decimal.canonical().parse().ok()
+}
+
+// A total order over values, by type and then by value
+fn compare(a: &Value, b: &Value) -> Ordering {
+    let rank = |value: &Value| match value {
+        Value::Null => 0,
+        Value::Bool(_) => 1,
+        Value::Number(_) => 2,
+        Value::String(_) => 3,
+        Value::Array(_) => 4,
+        Value::Object(_) => 5,
+    };
+    match (a, b) {
+        (Value::Bool(x), Value::Bool(y)) => x.cmp(y),
+        (Value::Number(x), Value::Number(y)) => match (Decimal::of(x), Decimal::of(y)) {
+            (Some(x), Some(y)) => x.cmp_value(&y),
+            _ => x.to_string().cmp(&y.to_string()),
+        },
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        (Value::Array(x), Value::Array(y)) => x
+            .iter()
+            .zip(y)
+            .map(|(x, y)| compare(x, y))
+            .find(|order| order.is_ne())
+            .unwrap_or_else(|| x.len().cmp(&y.len())),
+        (Value::Object(x), Value::Object(y)) => {
+            let mut x: Vec<_> = x.iter().collect();
+            let mut y: Vec<_> = y.iter().collect();
+            x.sort_by_key(|(key, _)| *key);
+            y.sort_by_key(|(key, _)| *key);
+            x.iter()
+                .zip(&y)
+                .map(|((k, v), (l, w))| k.cmp(l).then_with(|| compare(v, w)))
+                .find(|order| order.is_ne())
+                .unwrap_or_else(|| x.len().cmp(&y.len()))
+        }
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn all() -> NormalizeOptions {
+        NormalizeOptions {
+            sort_keys: true,
+            sort_arrays: true,
+            trim_strings: true,
+            lowercase_keys: true,
+            collapse_numbers: true,
+        }
+    }
+
+    /// Test that each option canonicalizes nested values.
+    #[test]
+    fn test_normalize() {
+        let value = json!({"B": [3, " z ", null, 1.0, [2], {"k": 1}, true], "a": {"C": 2.50}});
+        assert_eq!(
+            normalize(&value, &all()),
+            json!({"a": {"c": 2.5}, "b": [null, true, 1, 3, "z", [2], {"k": 1}]})
+        );
+        assert_eq!(
+            normalize(&value, &NormalizeOptions::default()),
+            json!({"B": [3, " z ", null, 1, [2], {"k": 1}, true], "a": {"C": 2.5}})
+        );
+    }
+
+    /// Test that numbers collapse to one form per value.
+    #[test]
+    fn test_collapse_numbers() {
+        let collapse = |text: &str| collapse(&text.parse().unwrap()).unwrap().to_string();
+        assert_eq!(collapse("1.0"), "1");
+        assert_eq!(collapse("-2e2"), "-200");
+        assert_eq!(collapse("0.5"), "0.5");
+        assert_eq!(collapse("-0.0"), "0");
+        assert_eq!(collapse("1e-7"), collapse("0.0000001"));
+    }
+
+    /// Test that arrays sort by type, then value, then length.
+    #[test]
+    fn test_sort_arrays() {
+        let options = NormalizeOptions {
+            sort_arrays: true,
+            ..NormalizeOptions::default()
+        };
+        let value = json!([[1, 2], [1], {"a": 2}, {"a": 1}, "b", "a", false, 10, 9.5]);
+        assert_eq!(
+            normalize(&value, &options),
+            json!([false, 9.5, 10, "a", "b", [1], [1, 2], {"a": 1}, {"a": 2}])
+        );
+    }
+
+    /// Test that keys lowercased into the same key keep the last value.
+    #[test]
+    fn test_lowercase_key_collision() {
+        let options = NormalizeOptions {
+            lowercase_keys: true,
+            ..NormalizeOptions::default()
+        };
+        assert_eq!(
+            normalize(&json!({"ID": 1, "Id": 2, "id": 3}), &options),
+            json!({"id": 3})
+        );
+    }
+}
@@ -0,0 +1,1119 @@
+//! The options that control how values are compared, and their builder.
+
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use serde_json::{Map, Number, Value};
+
+use crate::capture::capture;
+use crate::decimal::{self, Decimal};
+use crate::engine::DiffError;
+use crate::path::PathTrail;
+use crate::redact::redact;
+use crate::{
+    DiffKind, Difference, DifferenceRef, Path, PathFormat, PathPattern, PathSegment, Severity,
+    datetime, quantity, schema,
+};
+
+// Fields of a Kubernetes object that the server populates
+const K8S_SERVER_FIELDS: [&str; 7] = [
+    "status",
+    "metadata.resourceVersion",
+    "metadata.managedFields",
+    "metadata.uid",
+    "metadata.generation",
+    "metadata.creationTimestamp",
+    "metadata.selfLink",
+];
+
+// The similarity below which objects count as replaced, unless configured
+const REPLACED_SIMILARITY: f64 = 0.5;
+
+// The annotation `kubectl apply` records the applied manifest in
+const K8S_LAST_APPLIED: &str = "kubectl.kubernetes.io/last-applied-configuration";
+
+/// Strategy used to pair up array elements for comparison.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArrayDiffStrategy {
+    /// Compare elements that share the same index.
+    #[default]
+    Index,
+    /// Match elements along a longest common subsequence, so insertions and
+    /// deletions are reported as such instead of shifting every later
+    /// element.
+    ///
+    /// Unmatched elements between two matches are compared pairwise first;
+    /// the rest are reported as added or removed. Their indices refer to the
+    /// array as it is while the diff is applied in order, as in JSON Patch.
+    Lcs,
+    /// Treat arrays as multisets: only elements without an equal
+    /// counterpart on the other side are reported, as removed (at their
+    /// index in the first array) or added (at their index in the second).
+    /// Changes in order are ignored.
+    UnorderedSet,
+}
+
+impl ArrayDiffStrategy {
+    // Whether equal elements found at different positions can be reported as moves
+    pub(crate) fn supports_moves(self) -> bool {
+        self == ArrayDiffStrategy::Lcs
+    }
+}
+
+/// A way of relaxing string comparison so cosmetic differences are ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StringCompare {
+    /// Ignore differences in letter case.
+    CaseInsensitive,
+    /// Ignore leading and trailing whitespace.
+    TrimWhitespace,
+}
+
+impl StringCompare {
+    pub(crate) fn normalize(self, value: &str) -> String {
+        match self {
+            StringCompare::CaseInsensitive => value.to_lowercase(),
+            StringCompare::TrimWhitespace => value.trim().to_string(),
+        }
+    }
+}
+
+/// The verdict of a custom comparator registered with
+/// [`DiffOptionsBuilder::compare_at`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Comparison {
+    /// The values are equal; nothing is reported.
+    Equal,
+    /// The values differ and are reported as a change.
+    Different,
+    /// Compare the values as if no comparator were registered.
+    Default,
+    /// The values differ and are reported as this difference; its path is
+    /// filled in by the engine.
+    Custom {
+        /// The kind of difference to report.
+        kind: DiffKind,
+        /// The value reported as the old one.
+        before: Option<Value>,
+        /// The value reported as the new one.
+        after: Option<Value>,
+    },
+}
+
+type CompareFn = dyn Fn(&Value, &Value) -> Comparison + Send + Sync;
+
+// A user supplied comparison for values at matching paths
+#[derive(Clone)]
+pub(crate) struct Comparator(Arc<CompareFn>);
+
+impl core::fmt::Debug for Comparator {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("Comparator")
+    }
+}
+
+/// Semantic equality rules for whole JSON types, consulted across the entire
+/// document before the built-in comparison.
+///
+/// Each method returns `Some(equal)` to decide the comparison or `None` to
+/// fall back to the built-in rules; by default every method returns `None`.
+/// Containers that a comparator declares unequal are reported as a single
+/// change rather than descended into.
+pub trait ValueComparator: Send + Sync {
+    /// Compares two strings, e.g. URLs after normalization.
+    fn compare_strings(&self, _path: &Path, _a: &str, _b: &str) -> Option<bool> {
+        None
+    }
+
+    /// Compares two numbers.
+    fn compare_numbers(&self, _path: &Path, _a: &Number, _b: &Number) -> Option<bool> {
+        None
+    }
+
+    /// Compares two booleans.
+    fn compare_bools(&self, _path: &Path, _a: bool, _b: bool) -> Option<bool> {
+        None
+    }
+
+    /// Compares two arrays before their elements are diffed.
+    fn compare_arrays(&self, _path: &Path, _a: &[Value], _b: &[Value]) -> Option<bool> {
+        None
+    }
+
+    /// Compares two objects before their fields are diffed.
+    fn compare_objects(
+        &self,
+        _path: &Path,
+        _a: &Map<String, Value>,
+        _b: &Map<String, Value>,
+    ) -> Option<bool> {
+        None
+    }
+}
+
+type KeyFn = dyn Fn(&str) -> bool + Send + Sync;
+
+// A user supplied test for object keys to skip at any depth
+#[derive(Clone)]
+pub(crate) struct KeyFilter(Arc<KeyFn>);
+
+impl core::fmt::Debug for KeyFilter {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("KeyFilter")
+    }
+}
+
+type IgnoreFn = dyn Fn(&Path, Option<&Value>, Option<&Value>) -> bool + Send + Sync;
+
+// A user supplied test for differences to leave out of the results
+#[derive(Clone)]
+pub(crate) struct IgnoreFilter(Arc<IgnoreFn>);
+
+impl core::fmt::Debug for IgnoreFilter {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("IgnoreFilter")
+    }
+}
+
+// A user supplied set of equality rules for the whole document
+#[derive(Clone)]
+pub(crate) struct TypeComparator(Arc<dyn ValueComparator>);
+
+impl core::fmt::Debug for TypeComparator {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("TypeComparator")
+    }
+}
+
+/// Which side's extra object keys are reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiffMode {
+    /// Keys missing from either side are reported.
+    #[default]
+    Full,
+    /// The first document only needs to be contained in the second: keys
+    /// present only in the second are not reported. Useful when the first
+    /// document lists just the fields under test.
+    SubsetOf,
+    /// The second document only needs to be contained in the first: keys
+    /// present only in the first are not reported.
+    SupersetOf,
+}
+
+/// How a `null` value relates to a key that is missing altogether.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NullSemantics {
+    /// A key set to `null` differs from a missing key.
+    #[default]
+    Distinct,
+    /// A key set to `null` is equivalent to a missing key.
+    AbsentEqualsNull,
+}
+
+/// What [`deep_diff_str`](crate::deep_diff_str) does when an object repeats a key, which
+/// `serde_json::Value` cannot represent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateKeys {
+    /// The last value for the key is kept, as `serde_json` does.
+    #[default]
+    LastWins,
+    /// Parsing fails with an error naming the key.
+    Error,
+    /// Every value is kept, the nth occurrence of `key` under the key
+    /// `key#n`, so duplicates show up in the diff.
+    Preserve,
+}
+
+/// Options controlling how [`deep_diff_with_options`](crate::deep_diff_with_options) compares two values.
+///
+/// Build one with [`DiffOptions::builder`]; `DiffOptions::default()` behaves
+/// exactly like [`deep_diff`](crate::deep_diff).
+#[derive(Debug, Clone, Default)]
+pub struct DiffOptions {
+    pub(crate) ignored_paths: Vec<PathPattern>,
+    pub(crate) ignored_keys: Vec<KeyFilter>,
+    pub(crate) ignore_filters: Vec<IgnoreFilter>,
+    pub(crate) included_paths: Vec<PathPattern>,
+    pub(crate) numeric_epsilon: Option<f64>,
+    pub(crate) decimal_tolerance: Option<Decimal>,
+    pub(crate) array_strategy: ArrayDiffStrategy,
+    pub(crate) array_strategies: Vec<(PathPattern, ArrayDiffStrategy)>,
+    pub(crate) max_depth: Option<usize>,
+    pub(crate) max_differences: Option<usize>,
+    pub(crate) max_captured_bytes: Option<usize>,
+    pub(crate) redactions: Vec<PathPattern>,
+    pub(crate) hash_pruning: bool,
+    pub(crate) replaced_below: Option<f64>,
+    pub(crate) renamed_above: Option<f64>,
+    pub(crate) context: Option<usize>,
+    pub(crate) null_semantics: NullSemantics,
+    pub(crate) empty_equals_missing: bool,
+    pub(crate) coerce_containers: bool,
+    pub(crate) mode: DiffMode,
+    pub(crate) path_format: PathFormat,
+    pub(crate) array_keys: Vec<(PathPattern, String)>,
+    pub(crate) default_array_key: Option<String>,
+    pub(crate) detect_moves: bool,
+    pub(crate) coerce_numbers: bool,
+    pub(crate) string_modes: Vec<StringCompare>,
+    pub(crate) binary_paths: Vec<PathPattern>,
+    pub(crate) datetime_tolerance: Option<Duration>,
+    pub(crate) string_modes_at: Vec<(PathPattern, Vec<StringCompare>)>,
+    pub(crate) comparators: Vec<(PathPattern, Comparator)>,
+    pub(crate) severities: Vec<(PathPattern, Severity)>,
+    pub(crate) value_comparator: Option<TypeComparator>,
+    pub(crate) duplicate_keys: DuplicateKeys,
+    pub(crate) key_order: bool,
+    pub(crate) invalid_patterns: Vec<String>,
+}
+
+impl DiffOptions {
+    /// Starts building a set of options from the defaults.
+    pub fn builder() -> DiffOptionsBuilder {
+        DiffOptionsBuilder::default()
+    }
+
+    // Fails on the first option that cannot be applied
+    pub(crate) fn check(&self) -> Result<(), DiffError> {
+        match self.invalid_patterns.first() {
+            Some(pattern) => Err(DiffError::InvalidPattern(pattern.clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// Starts building options that compare Kubernetes manifests the way
+    /// `kubectl diff` does, for a single object or a `List` of them.
+    ///
+    /// Fields the server fills in (`status`, `metadata.resourceVersion`,
+    /// `metadata.managedFields`, `metadata.uid`, ...) are ignored, a `null`
+    /// field matches a missing one, list items such as containers are
+    /// matched by their `name`, and resource quantities are compared by
+    /// value, so `"1000m"` equals `"1"` and `"1Gi"` equals `"1024Mi"`.
+    pub fn k8s() -> DiffOptionsBuilder {
+        let mut builder = DiffOptions::builder()
+            .null_semantics(NullSemantics::AbsentEqualsNull)
+            .default_array_key("name");
+        for prefix in ["", "items[*]."] {
+            for field in K8S_SERVER_FIELDS {
+                builder = builder.ignore(format!("{}{}", prefix, field));
+            }
+            builder = builder.ignore(format!(
+                "{}metadata.annotations[\"{}\"]",
+                prefix, K8S_LAST_APPLIED
+            ));
+        }
+        for pattern in ["**.resources.limits.*", "**.resources.requests.*"] {
+            builder = builder.compare_at(pattern, |a, b| {
+                match (quantity::parse_quantity(a), quantity::parse_quantity(b)) {
+                    (Some(x), Some(y)) if x == y => Comparison::Equal,
+                    (Some(_), Some(_)) => Comparison::Different,
+                    _ => Comparison::Default,
+                }
+            });
+        }
+        // Annotations that only hold the applied manifest are added by the server
+        builder.ignore_if(|path, before, after| {
+            let annotations = matches!(
+                path.segments(),
+                [.., PathSegment::Key(metadata), PathSegment::Key(annotations)]
+                    if metadata == "metadata" && annotations == "annotations"
+            );
+            annotations
+                && [before, after].into_iter().flatten().all(|value| {
+                    value
+                        .as_object()
+                        .is_some_and(|map| map.keys().all(|key| key == K8S_LAST_APPLIED))
+                })
+        })
+    }
+
+    // Whether the value at `path` is excluded from the diff
+    pub(crate) fn is_skipped(&self, path: &Path) -> bool {
+        let included = self.included_paths.is_empty()
+            || self
+                .included_paths
+                .iter()
+                .any(|pattern| pattern.covers(path) || pattern.leads_through(path));
+        !included
+            || self
+                .ignored_paths
+                .iter()
+                .any(|pattern| pattern.matches(path))
+            || matches!(path.segments().last(), Some(PathSegment::Key(key))
+                if self.ignored_keys.iter().any(|filter| (filter.0)(key)))
+    }
+
+    // Whether a difference found by the traversal is left out of the results
+    pub(crate) fn is_suppressed(&self, diff: &DifferenceRef) -> bool {
+        let (before, after) = (diff.before.as_deref(), diff.after.as_deref());
+        self.ignore_filters
+            .iter()
+            .any(|filter| (filter.0)(&diff.segments, before, after))
+    }
+
+    // Whether the value at `trail` is excluded, assembling its path only
+    // when there are rules to match
+    pub(crate) fn skips(&self, trail: &PathTrail) -> bool {
+        let has_rules = !self.ignored_paths.is_empty()
+            || !self.ignored_keys.is_empty()
+            || !self.included_paths.is_empty();
+        has_rules && self.is_skipped(&trail.to_path())
+    }
+
+    // Whether any rule besides skipping depends on the path of the values compared
+    pub(crate) fn matches_paths(&self) -> bool {
+        !self.string_modes_at.is_empty()
+            || !self.comparators.is_empty()
+            || !self.binary_paths.is_empty()
+            || !self.array_keys.is_empty()
+            || !self.array_strategies.is_empty()
+            || self.value_comparator.is_some()
+    }
+
+    // Compares two strings using the modes configured for `path`
+    pub(crate) fn strings_equal(&self, path: &Path, a: &str, b: &str) -> bool {
+        if a == b {
+            return true;
+        }
+        if let Some(tolerance) = self.datetime_tolerance
+            && let (Some(x), Some(y)) = (datetime::parse_instant(a), datetime::parse_instant(b))
+        {
+            return x.abs_diff(y) <= tolerance.as_nanos();
+        }
+        if self.string_modes_for(path).is_empty() {
+            return false;
+        }
+        self.normalize_string(path, a) == self.normalize_string(path, b)
+    }
+
+    // The string modes configured for `path`
+    pub(crate) fn string_modes_for(&self, path: &Path) -> &[StringCompare] {
+        self.string_modes_at
+            .iter()
+            .find(|(pattern, _)| pattern.matches(path))
+            .map_or(&self.string_modes, |(_, modes)| modes)
+    }
+
+    // Applies the string modes configured for `path` to `value`
+    pub(crate) fn normalize_string(&self, path: &Path, value: &str) -> String {
+        self.string_modes_for(path)
+            .iter()
+            .fold(value.to_string(), |value, mode| mode.normalize(&value))
+    }
+
+    // Runs the first comparator registered for `path`, if any
+    pub(crate) fn custom_comparison(&self, path: &Path, a: &Value, b: &Value) -> Comparison {
+        self.comparators
+            .iter()
+            .find(|(pattern, _)| pattern.matches(path))
+            .map_or(Comparison::Default, |(_, comparator)| (comparator.0)(a, b))
+    }
+
+    // Asks the value comparator, if any, whether two values of the same type are equal
+    pub(crate) fn semantic_equal(&self, path: &Path, a: &Value, b: &Value) -> Option<bool> {
+        let comparator = &self.value_comparator.as_ref()?.0;
+        match (a, b) {
+            (Value::String(x), Value::String(y)) => comparator.compare_strings(path, x, y),
+            (Value::Number(x), Value::Number(y)) => comparator.compare_numbers(path, x, y),
+            (Value::Bool(x), Value::Bool(y)) => comparator.compare_bools(path, *x, *y),
+            (Value::Array(x), Value::Array(y)) => comparator.compare_arrays(path, x, y),
+            (Value::Object(x), Value::Object(y)) => comparator.compare_objects(path, x, y),
+            _ => None,
+        }
+    }
+
+    /// Redacts, then cuts down to
+    /// [`max_captured_value_bytes`](DiffOptionsBuilder::max_captured_value_bytes),
+    /// the values of a difference built by hand, as the diff functions do
+    /// for the differences they find, so values hidden by
+    /// [`redact`](DiffOptionsBuilder::redact) stay hidden.
+    ///
+    /// ```
+    /// use deep_diff::{DiffKind, DiffOptions, Difference, Path, REDACTED, Severity, Withheld};
+    /// use serde_json::json;
+    ///
+    /// let options = DiffOptions::builder().redact(["password"]).build();
+    /// let segments: Path = "password".parse().unwrap();
+    /// let diff = options.finish_difference(Difference {
+    ///     path: segments.to_string(),
+    ///     segments,
+    ///     kind: DiffKind::Added,
+    ///     severity: Severity::Info,
+    ///     before: None,
+    ///     after: Some(json!("hunter2")),
+    ///     context: None,
+    ///     withheld: Withheld::default(),
+    /// });
+    /// assert_eq!(diff.after, Some(json!(REDACTED)));
+    /// assert!(diff.withheld.after);
+    /// ```
+    pub fn finish_difference(&self, mut difference: Difference) -> Difference {
+        let path = difference.segments.clone();
+        self.finish_at(&mut difference, &path);
+        difference
+    }
+
+    // Finishes the values of `difference` as if it were reported at `path`,
+    // recording which of them were withheld
+    pub(crate) fn finish_at(&self, difference: &mut Difference, path: &Path) {
+        let sides = [
+            (&mut difference.before, &mut difference.withheld.before),
+            (&mut difference.after, &mut difference.withheld.after),
+        ];
+        for (slot, withheld) in sides {
+            if let Some(value) = slot.take() {
+                let mut value = Cow::Owned(value);
+                *withheld |= self.finish_value(&mut value, path, &difference.kind);
+                *slot = Some(value.into_owned());
+            }
+        }
+    }
+
+    // Redacts `value`, reported in a difference of `kind` at `path`, and
+    // then cuts it down to the captured size. Returns whether it was
+    // withheld by either.
+    pub(crate) fn finish_value(
+        &self,
+        value: &mut Cow<'_, Value>,
+        path: &Path,
+        kind: &DiffKind,
+    ) -> bool {
+        let mut withheld = false;
+        if !self.redactions.is_empty() {
+            withheld |= redact(value, path, &self.redactions);
+            // A renamed value also stood at its old key
+            if let (DiffKind::Renamed { from_key, .. }, Some(parent)) = (kind, path.parent()) {
+                withheld |= redact(
+                    value,
+                    &parent.child_key(from_key.as_str()),
+                    &self.redactions,
+                );
+            }
+        }
+        if let Some(limit) = self.max_captured_bytes {
+            withheld |= capture(value, limit);
+        }
+        withheld
+    }
+
+    // `value`, found at `path`, with the configured redactions applied
+    pub(crate) fn redacted(&self, value: &Value, path: &Path) -> Value {
+        let mut value = Cow::Borrowed(value);
+        redact(&mut value, path, &self.redactions);
+        value.into_owned()
+    }
+
+    // The highest severity of the rules matching `path` or one of its ancestors
+    pub(crate) fn severity_for(&self, path: &Path) -> Severity {
+        self.severities
+            .iter()
+            .filter(|(pattern, _)| pattern.covers(path))
+            .map(|(_, severity)| *severity)
+            .max()
+            .unwrap_or_default()
+    }
+
+    // The strategy for the array at `path`: the first matching rule, or the default
+    pub(crate) fn array_strategy_for(&self, path: &Path) -> ArrayDiffStrategy {
+        self.array_strategies
+            .iter()
+            .find(|(pattern, _)| pattern.matches(path))
+            .map_or(self.array_strategy, |(_, strategy)| *strategy)
+    }
+
+    // The key field used to match elements of the array at `path`, if any
+    pub(crate) fn array_key_for(&self, path: &Path) -> Option<&str> {
+        self.array_keys
+            .iter()
+            .find(|(pattern, _)| pattern.matches(path))
+            .map(|(_, key)| key.as_str())
+            .or(self.default_array_key.as_deref())
+    }
+
+    // Whether a key holding `value` on one side matches its absence on the other
+    pub(crate) fn absent_matches(&self, value: &Value) -> bool {
+        (self.null_semantics == NullSemantics::AbsentEqualsNull && value.is_null())
+            || (self.empty_equals_missing && is_empty_container(value))
+    }
+
+    // Whether an empty container on one side matches `null` on the other
+    pub(crate) fn empty_matches_null(&self, a: &Value, b: &Value) -> bool {
+        self.empty_equals_missing
+            && ((a.is_null() && is_empty_container(b)) || (b.is_null() && is_empty_container(a)))
+    }
+
+    pub(crate) fn numbers_equal(&self, a: &Value, b: &Value) -> bool {
+        if let (Some(tolerance), Value::Number(x), Value::Number(y)) =
+            (&self.decimal_tolerance, a, b)
+        {
+            return decimal::within(x, y, tolerance);
+        }
+        match (self.numeric_epsilon, a, b) {
+            (Some(epsilon), _, _) => match (a.as_f64(), b.as_f64()) {
+                (Some(x), Some(y)) => (x - y).abs() <= epsilon,
+                _ => a == b,
+            },
+            // Arbitrary precision numbers keep their text, which may differ
+            // for equal values, e.g. `1.5` and `1.50`
+            (None, Value::Number(x), Value::Number(y)) if cfg!(feature = "arbitrary-precision") => {
+                decimal::equal(x, y, self.coerce_numbers)
+            }
+            (None, Value::Number(x), Value::Number(y)) if self.coerce_numbers => same_number(x, y),
+            _ => a == b,
+        }
+    }
+}
+
+/// Builder for [`DiffOptions`].
+#[derive(Debug, Clone, Default)]
+pub struct DiffOptionsBuilder {
+    options: DiffOptions,
+}
+
+impl DiffOptionsBuilder {
+    // Parses a path pattern, remembering it if it is invalid
+    fn pattern(&mut self, pattern: &str) -> Option<PathPattern> {
+        let parsed = pattern.parse().ok();
+        if parsed.is_none() {
+            self.options.invalid_patterns.push(pattern.to_string());
+        }
+        parsed
+    }
+
+    /// Skips every value whose path matches `pattern` entirely, e.g.
+    /// `"metadata.timestamp"`, `"**.updated_at"` or `"items[*].etag"`.
+    ///
+    /// See [`PathPattern`] for the syntax; patterns that fail to parse
+    /// match nothing, or are reported by [`try_build`](Self::try_build).
+    pub fn ignore(mut self, pattern: impl AsRef<str>) -> Self {
+        if let Some(pattern) = self.pattern(pattern.as_ref()) {
+            self.options.ignored_paths.push(pattern);
+        }
+        self
+    }
+
+    /// Skips every object entry whose key satisfies `predicate`, at any depth,
+    /// e.g. `ignore_keys_matching(|key| key.starts_with('_'))`.
+    ///
+    /// A compiled regex can be used as
+    /// `ignore_keys_matching(move |key| regex.is_match(key))`; simple
+    /// patterns are written directly:
+    ///
+    /// ```
+    /// use deep_diff::{DiffOptions, deep_diff_with_options};
+    /// use serde_json::json;
+    ///
+    /// // Keys matching `^(created|updated)_at$` or `^x-`
+    /// let options = DiffOptions::builder()
+    ///     .ignore_keys_matching(|key| {
+    ///         matches!(key.strip_suffix("_at"), Some("created" | "updated"))
+    ///             || key.starts_with("x-")
+    ///     })
+    ///     .build();
+    /// let a = json!({"id": 1, "meta": {"updated_at": 1, "x-trace": "a"}});
+    /// let b = json!({"id": 1, "meta": {"updated_at": 2, "x-trace": "b"}});
+    /// assert!(deep_diff_with_options(&a, &b, &options).is_empty());
+    /// let c = json!({"id": 1, "meta": {"updated_by": 2, "x-trace": "b"}});
+    /// assert_eq!(deep_diff_with_options(&a, &c, &options).len(), 1);
+    /// ```
+    pub fn ignore_keys_matching<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        self.options
+            .ignored_keys
+            .push(KeyFilter(Arc::new(predicate)));
+        self
+    }
+
+    /// Leaves out every difference for which `predicate` returns `true`,
+    /// given its path and the values before and after the change (`None`
+    /// where a value is missing).
+    ///
+    /// This suppresses noise that cannot be described by paths, such as a
+    /// change between two placeholder strings. The predicate only sees
+    /// differences that would otherwise be reported; subtrees are still
+    /// descended into.
+    pub fn ignore_if<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&Path, Option<&Value>, Option<&Value>) -> bool + Send + Sync + 'static,
+    {
+        self.options
+            .ignore_filters
+            .push(IgnoreFilter(Arc::new(predicate)));
+        self
+    }
+
+    /// Restricts the diff to values whose paths match one of `patterns`,
+    /// e.g. `only(["spec.**", "status.replicas"])`; may be called several
+    /// times to include more.
+    ///
+    /// Subtrees that cannot contain an included path are not traversed at
+    /// all. Values on the way to an included path are still compared, so a
+    /// parent that was added, removed or replaced wholesale is reported.
+    pub fn only<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for pattern in patterns {
+            if let Some(pattern) = self.pattern(pattern.as_ref()) {
+                self.options.included_paths.push(pattern);
+            }
+        }
+        self
+    }
+
+    /// Treats numbers as equal when they differ by no more than `epsilon`.
+    pub fn numeric_epsilon(mut self, epsilon: f64) -> Self {
+        self.options.numeric_epsilon = Some(epsilon);
+        self
+    }
+
+    /// Treats numbers as equal when they differ by no more than
+    /// `tolerance`, computed exactly on their decimal digits rather than as
+    /// `f64`. Takes precedence over
+    /// [`numeric_epsilon`](DiffOptionsBuilder::numeric_epsilon).
+    ///
+    /// With the `arbitrary-precision` feature, this is exact for numbers of
+    /// any size, such as monetary amounts that must agree to the cent.
+    ///
+    /// ```
+    /// use deep_diff::{DiffOptions, deep_diff_with_options};
+    /// use serde_json::{Number, json};
+    ///
+    /// let cent: Number = "0.01".parse().unwrap();
+    /// let options = DiffOptions::builder().decimal_tolerance(cent).build();
+    /// let diffs = deep_diff_with_options(&json!([10.0, 5.0]), &json!([10.01, 5.02]), &options);
+    /// assert_eq!(diffs.len(), 1);
+    /// assert_eq!(diffs[0].path, "[1]");
+    /// ```
+    pub fn decimal_tolerance(mut self, tolerance: Number) -> Self {
+        self.options.decimal_tolerance = Decimal::of(&tolerance);
+        self
+    }
+
+    /// Compares numbers by value, so `1`, `1.0` and `1e0` are equal even
+    /// though serde_json stores integers and floats differently.
+    pub fn coerce_numbers(mut self, coerce: bool) -> Self {
+        self.options.coerce_numbers = coerce;
+        self
+    }
+
+    /// Relaxes how all strings are compared; may be called several times to
+    /// combine modes.
+    pub fn string_compare(mut self, mode: StringCompare) -> Self {
+        self.options.string_modes.push(mode);
+        self
+    }
+
+    /// Relaxes how strings at paths matching `pattern` are compared,
+    /// replacing the global modes there; may be called several times to
+    /// combine modes.
+    pub fn string_compare_at(mut self, pattern: impl AsRef<str>, mode: StringCompare) -> Self {
+        let Some(pattern) = self.pattern(pattern.as_ref()) else {
+            return self;
+        };
+        let modes_at = &mut self.options.string_modes_at;
+        match modes_at.iter_mut().find(|(p, _)| *p == pattern) {
+            Some((_, modes)) => modes.push(mode),
+            None => modes_at.push((pattern, vec![mode])),
+        }
+        self
+    }
+
+    /// Marks differences at paths matching `pattern`, or inside them, with
+    /// `severity`, e.g. `severity("security.**", Severity::Critical)`.
+    ///
+    /// When several rules match, the highest severity wins; differences no
+    /// rule matches are [`Severity::Info`].
+    pub fn severity(mut self, pattern: impl AsRef<str>, severity: Severity) -> Self {
+        if let Some(pattern) = self.pattern(pattern.as_ref()) {
+            self.options.severities.push((pattern, severity));
+        }
+        self
+    }
+
+    /// Decides equality of the values at paths matching `pattern` with
+    /// `compare`, e.g. `compare_at("items[*].price", |a, b| ...)`, instead
+    /// of the built-in rules.
+    ///
+    /// The comparator runs before any other comparison, including the type
+    /// check, and may return [`Comparison::Default`] to fall back to it. The
+    /// first matching comparator wins. It is consulted for values present
+    /// on both sides, not while pairing up array elements.
+    pub fn compare_at<F>(mut self, pattern: impl AsRef<str>, compare: F) -> Self
+    where
+        F: Fn(&Value, &Value) -> Comparison + Send + Sync + 'static,
+    {
+        if let Some(pattern) = self.pattern(pattern.as_ref()) {
+            let comparator = Comparator(Arc::new(compare));
+            self.options.comparators.push((pattern, comparator));
+        }
+        self
+    }
+
+    /// Consults `comparator` for every pair of same-typed values before the
+    /// built-in comparison; per-path comparators from
+    /// [`compare_at`](Self::compare_at) take precedence over it.
+    pub fn value_comparator(mut self, comparator: impl ValueComparator + 'static) -> Self {
+        self.options.value_comparator = Some(TypeComparator(Arc::new(comparator)));
+        self
+    }
+
+    /// Sets how array elements are paired up for comparison.
+    pub fn array_strategy(mut self, strategy: ArrayDiffStrategy) -> Self {
+        self.options.array_strategy = strategy;
+        self
+    }
+
+    /// Uses `strategy` for the arrays at paths matching `pattern`, e.g.
+    /// `array_strategy_at("tags", ArrayDiffStrategy::UnorderedSet)`. The
+    /// first matching rule wins over [`array_strategy`](Self::array_strategy).
+    pub fn array_strategy_at(
+        mut self,
+        pattern: impl AsRef<str>,
+        strategy: ArrayDiffStrategy,
+    ) -> Self {
+        if let Some(pattern) = self.pattern(pattern.as_ref()) {
+            self.options.array_strategies.push((pattern, strategy));
+        }
+        self
+    }
+
+    /// Lets a JSON Schema drive the comparison: object keys the schema does
+    /// not declare under `properties` (and which are thus only allowed by
+    /// `additionalProperties`) are ignored, and arrays with `uniqueItems`
+    /// are compared as sets.
+    ///
+    /// Keys of objects that also have `patternProperties` are always
+    /// compared, as are keys where `additionalProperties` is `false`. Use
+    /// [`deep_diff_with_schema`](crate::deep_diff_with_schema) to also validate the documents.
+    pub fn schema(mut self, schema: &Value) -> Self {
+        for pattern in schema::unique_item_arrays(schema) {
+            self.options
+                .array_strategies
+                .push((pattern, ArrayDiffStrategy::UnorderedSet));
+        }
+        let schema = Arc::new(schema.clone());
+        self.ignore_if(move |path, _, _| schema::is_additional(&schema, path))
+    }
+
+    /// Reports array elements that reappear at another position as
+    /// [`DiffKind::Moved`] instead of a removal plus an addition.
+    ///
+    /// Only takes effect with [`ArrayDiffStrategy::Lcs`].
+    pub fn detect_moves(mut self, detect: bool) -> Self {
+        self.options.detect_moves = detect;
+        self
+    }
+
+    /// Reports a key that disappears from an object while another appears
+    /// with the same value as [`DiffKind::Renamed`], instead of a removal
+    /// plus an addition.
+    ///
+    /// Only values that compare equal count as renamed unless a
+    /// [`rename_threshold`](DiffOptionsBuilder::rename_threshold) is set.
+    /// Renames are only detected in [`DiffMode::Full`].
+    pub fn detect_renames(mut self, detect: bool) -> Self {
+        self.options.renamed_above = match detect {
+            true => Some(self.options.renamed_above.unwrap_or(1.0)),
+            false => None,
+        };
+        self
+    }
+
+    /// Sets the [`similarity`](crate::similarity), from 0.0 to 1.0, at or above which a removed
+    /// and an added key count as renamed, and turns on
+    /// [`detect_renames`](DiffOptionsBuilder::detect_renames). Each removed
+    /// key is paired with the most similar added key.
+    pub fn rename_threshold(mut self, threshold: f64) -> Self {
+        self.options.renamed_above = Some(threshold);
+        self
+    }
+
+    /// Matches elements of the arrays at paths matching `pattern` by their
+    /// `key` field (e.g. `array_key("items", "id")`) instead of by position.
+    ///
+    /// Records are compared field by field wherever they moved to; changes
+    /// in their order alone are not reported. Arrays whose elements are not
+    /// all objects carrying `key` fall back to the array strategy.
+    pub fn array_key(mut self, pattern: impl AsRef<str>, key: impl Into<String>) -> Self {
+        if let Some(pattern) = self.pattern(pattern.as_ref()) {
+            self.options.array_keys.push((pattern, key.into()));
+        }
+        self
+    }
+
+    /// Matches elements of every array by their `key` field, unless a more
+    /// specific [`array_key`](Self::array_key) applies.
+    pub fn default_array_key(mut self, key: impl Into<String>) -> Self {
+        self.options.default_array_key = Some(key.into());
+        self
+    }
+
+    /// Stops descending below `depth` levels of nesting; deeper containers
+    /// that differ are reported as a single change.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.options.max_depth = Some(depth);
+        self
+    }
+
+    /// Stops the diff once more than `max` differences have been found,
+    /// keeping the first `max`; [`DiffResult::truncated`](crate::DiffResult::truncated) tells whether
+    /// this happened.
+    pub fn max_differences(mut self, max: usize) -> Self {
+        self.options.max_differences = Some(max);
+        self
+    }
+
+    /// Replaces `before` and `after` values longer than `bytes` as compact
+    /// JSON with a [`Truncated`](crate::Truncated) summary of their size, hash and start,
+    /// keeping diffs small when documents embed large blobs.
+    ///
+    /// Values are still compared in full. Differences holding a summary
+    /// cannot be applied where the value would be written.
+    pub fn max_captured_value_bytes(mut self, bytes: usize) -> Self {
+        self.options.max_captured_bytes = Some(bytes);
+        self
+    }
+
+    /// Replaces the values at paths matching one of `patterns`, e.g.
+    /// `redact(["**.password", "**.token"])`, with [`REDACTED`](crate::REDACTED) in
+    /// the differences reported, so diffs of payloads holding secrets can
+    /// be logged safely; may be called several times to redact more.
+    ///
+    /// Values are still compared in full, so a changed secret is reported,
+    /// without showing either value. Values inside a reported value, and in
+    /// [`context`](DiffOptionsBuilder::context), are redacted too.
+    /// Differences writing a redacted value cannot be applied and are left
+    /// out of JSON Patches.
+    pub fn redact<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for pattern in patterns {
+            if let Some(pattern) = self.pattern(pattern.as_ref()) {
+                self.options.redactions.push(pattern);
+            }
+        }
+        self
+    }
+
+    /// Hashes both documents up front so that identical subtrees are skipped
+    /// without being walked element by element.
+    ///
+    /// Pays off for large documents that mostly agree; see [`DiffSession`](crate::DiffSession)
+    /// to hash a base document once for many diffs. Subtrees whose hashes
+    /// match are still compared for equality before being skipped, so a
+    /// hash collision never hides a difference, and comparators never see
+    /// equal values.
+    pub fn hash_pruning(mut self, prune: bool) -> Self {
+        self.options.hash_pruning = prune;
+        self
+    }
+
+    /// Reports two objects with little in common as a single change of the
+    /// whole object, instead of a difference for every leaf inside it,
+    /// which reads better in reports meant for people.
+    ///
+    /// Objects count as replaced when their [`similarity`](crate::similarity) is below the
+    /// [`replaced_subtree_threshold`](DiffOptionsBuilder::replaced_subtree_threshold),
+    /// `0.5` unless set. Measuring it costs time proportional to the size
+    /// of the objects, at every level they are compared.
+    pub fn collapse_replaced_subtrees(mut self, collapse: bool) -> Self {
+        self.options.replaced_below = match collapse {
+            true => Some(self.options.replaced_below.unwrap_or(REPLACED_SIMILARITY)),
+            false => None,
+        };
+        self
+    }
+
+    /// Sets the similarity, from 0.0 to 1.0, below which two objects are
+    /// reported as replaced whole, and turns on
+    /// [`collapse_replaced_subtrees`](DiffOptionsBuilder::collapse_replaced_subtrees).
+    pub fn replaced_subtree_threshold(mut self, threshold: f64) -> Self {
+        self.options.replaced_below = Some(threshold);
+        self
+    }
+
+    /// Attaches [`Difference::context`](crate::Difference::context) to each difference, so a change
+    /// such as `items[4127].price` shows which record it belongs to.
+    ///
+    /// Changes inside an object get the object's unchanged keys, and changes
+    /// to array elements get up to `elements` elements on either side, both
+    /// as an object keyed by key or index. Context comes from the second
+    /// document, or the first for removals. Only functions returning a whole
+    /// diff attach it, not lazy iterators or [`deep_diff_ref`](crate::deep_diff_ref).
+    pub fn context(mut self, elements: usize) -> Self {
+        self.options.context = Some(elements);
+        self
+    }
+
+    /// Sets which side's extra object keys are reported.
+    pub fn mode(mut self, mode: DiffMode) -> Self {
+        self.options.mode = mode;
+        self
+    }
+
+    /// Sets whether `null` values are treated as equal to missing keys.
+    pub fn null_semantics(mut self, semantics: NullSemantics) -> Self {
+        self.options.null_semantics = semantics;
+        self
+    }
+
+    /// Sets how [`deep_diff_str`](crate::deep_diff_str) handles objects that repeat a key.
+    pub fn duplicate_keys(mut self, duplicate_keys: DuplicateKeys) -> Self {
+        self.options.duplicate_keys = duplicate_keys;
+        self
+    }
+
+    /// Sets whether [`deep_diff_str`](crate::deep_diff_str) reports objects whose shared keys
+    /// appear in a different order, as [`DiffKind::KeyOrderChanged`].
+    ///
+    /// Parsed `Value`s do not keep key order, so this has no effect on the
+    /// other diff functions.
+    pub fn compare_key_order(mut self, enabled: bool) -> Self {
+        self.options.key_order = enabled;
+        self
+    }
+
+    /// Treats the strings at paths matching `pattern` as base64-encoded
+    /// binary data, comparing the bytes they decode to.
+    ///
+    /// A change is reported as [`DiffKind::BinaryChanged`] with the sizes
+    /// and hashes of both payloads instead of the (possibly huge) strings.
+    /// Strings that are not valid base64 are compared as text.
+    pub fn binary_at(mut self, pattern: impl AsRef<str>) -> Self {
+        if let Some(pattern) = self.pattern(pattern.as_ref()) {
+            self.options.binary_paths.push(pattern);
+        }
+        self
+    }
+
+    /// Compares strings that are both RFC 3339 (ISO 8601) timestamps, such
+    /// as `"2024-01-01T01:00:00+01:00"`, as instants: they are equal if they
+    /// lie at most `tolerance` apart, whatever their time zone offsets.
+    ///
+    /// Timestamps without an offset, and other strings, are compared as
+    /// usual.
+    pub fn compare_datetimes(mut self, tolerance: Duration) -> Self {
+        self.options.datetime_tolerance = Some(tolerance);
+        self
+    }
+
+    /// Sets whether empty objects and arrays are treated as equal to missing
+    /// keys and to `null`, for serializers that omit empty collections
+    /// inconsistently.
+    pub fn empty_equals_missing(mut self, enabled: bool) -> Self {
+        self.options.empty_equals_missing = enabled;
+        self
+    }
+
+    /// Keeps comparing an array with an object instead of reporting one
+    /// type change, for sources that flip between the two representations.
+    ///
+    /// The array is read as an object: its pairs if every element is a
+    /// `[key, value]` pair, and otherwise its elements keyed by index, so
+    /// `["a", "b"]` matches `{"0": "a", "1": "b"}`. Differences inside are
+    /// reported at the paths of the object, which do not exist in the
+    /// array, so they cannot be applied as a patch.
+    pub fn coerce_containers(mut self, enabled: bool) -> Self {
+        self.options.coerce_containers = enabled;
+        self
+    }
+
+    /// Sets how the path of each difference is rendered.
+    pub fn path_format(mut self, format: PathFormat) -> Self {
+        self.options.path_format = format;
+        self
+    }
+
+    /// Finishes building the options.
+    pub fn build(self) -> DiffOptions {
+        self.options
+    }
+
+    /// Finishes building the options, failing if any of them cannot be
+    /// applied, such as a path pattern that does not parse.
+    ///
+    /// ```
+    /// use deep_diff::{DiffError, DiffOptions};
+    ///
+    /// let result = DiffOptions::builder().ignore("items[x").try_build();
+    /// assert_eq!(result.unwrap_err(), DiffError::InvalidPattern("items[x".to_string()));
+    /// ```
+    pub fn try_build(self) -> Result<DiffOptions, DiffError> {
+        self.options.check()?;
+        Ok(self.options)
+    }
+}
+
+// Compares two numbers by value, whether they are stored as integers or floats
+fn same_number(a: &Number, b: &Number) -> bool {
+    let as_integer = |n: &Number| n.as_i64().map(i128::from).or(n.as_u64().map(i128::from));
+    let float_equals = |float: &Number, int: i128| {
+        float
+            .as_f64()
+            .is_some_and(|f| f % 1.0 == 0.0 && f as i128 == int)
+    };
+    match (as_integer(a), as_integer(b)) {
+        (Some(x), Some(y)) => x == y,
+        (Some(int), None) => float_equals(b, int),
+        (None, Some(int)) => float_equals(a, int),
+        (None, None) => a.as_f64() == b.as_f64(),
+    }
+}
+
+pub(crate) fn is_empty_container(value: &Value) -> bool {
+    match value {
+        Value::Array(values) => values.is_empty(),
+        Value::Object(map) => map.is_empty(),
+        _ => false,
+    }
+}
+
+// Options used by the entry points that take none, equal to the default
+pub(crate) static DEFAULT_OPTIONS: DiffOptions = DiffOptions {
+    ignored_paths: Vec::new(),
+    ignored_keys: Vec::new(),
+    ignore_filters: Vec::new(),
+    included_paths: Vec::new(),
+    numeric_epsilon: None,
+    decimal_tolerance: None,
+    array_strategy: ArrayDiffStrategy::Index,
+    array_strategies: Vec::new(),
+    max_depth: None,
+    max_differences: None,
+    max_captured_bytes: None,
+    redactions: Vec::new(),
+    hash_pruning: false,
+    replaced_below: None,
+    renamed_above: None,
+    context: None,
+    null_semantics: NullSemantics::Distinct,
+    empty_equals_missing: false,
+    coerce_containers: false,
+    mode: DiffMode::Full,
+    path_format: PathFormat::Dotted,
+    array_keys: Vec::new(),
+    default_array_key: None,
+    detect_moves: false,
+    coerce_numbers: false,
+    string_modes: Vec::new(),
+    binary_paths: Vec::new(),
+    datetime_tolerance: None,
+    string_modes_at: Vec::new(),
+    comparators: Vec::new(),
+    severities: Vec::new(),
+    value_comparator: None,
+    duplicate_keys: DuplicateKeys::LastWins,
+    key_order: false,
+    invalid_patterns: Vec::new(),
+};
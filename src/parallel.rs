@@ -0,0 +1,167 @@
+//! Diffing the children of large documents, and batches of documents,
+//! across threads.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use serde_json::Value;
+
+use crate::batch::diff_pair;
+use crate::engine::{Task, Walker};
+use crate::{DiffOptions, Difference, DifferenceRef};
+
+/// Containers with fewer children than this are diffed on a single thread,
+/// since spawning threads would cost more than it saves.
+const MIN_PARALLEL_CHILDREN: usize = 256;
+
+/// Runs `walker` to completion, splitting the children of the first
+/// container with more than one child across threads.
+///
+/// The children are handed out as contiguous chunks and the results are
+/// concatenated in order, so the differences come out exactly as a
+/// single-threaded walk would report them.
+pub(crate) fn collect<'a>(mut walker: Walker<'a, '_>) -> Vec<DifferenceRef<'a>> {
+    // Step through containers with a single child, e.g. `{"items": [...]}`
+    let mut differences = Vec::new();
+    while walker.stack.len() == 1
+        && let Some(task) = walker.stack.pop()
+    {
+        differences.extend(walker.run(task));
+    }
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    if walker.stack.len() < MIN_PARALLEL_CHILDREN || threads < 2 {
+        differences.extend(walker);
+        return differences;
+    }
+
+    let options = walker.options;
+    let hashes = walker.hashes;
+    let mut tasks: Vec<Task<'a>> = walker.stack;
+    tasks.reverse();
+    let chunk_size = tasks.len().div_ceil(threads);
+    let mut chunks = Vec::new();
+    while !tasks.is_empty() {
+        let rest = tasks.split_off(chunk_size.min(tasks.len()));
+        chunks.push(std::mem::replace(&mut tasks, rest));
+    }
+    thread::scope(|scope| {
+        let handles: Vec<_> = chunks
+            .into_iter()
+            .map(|mut chunk| {
+                chunk.reverse();
+                let walker = Walker {
+                    options,
+                    stack: chunk,
+                    hashes: hashes.clone(),
+                    record_descents: false,
+                    descent: None,
+                    visited: 0,
+                    deepest: 0,
+                };
+                scope.spawn(move || walker.collect::<Vec<_>>())
+            })
+            .collect();
+        for handle in handles {
+            differences.extend(
+                handle
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic)),
+            );
+        }
+    });
+    differences
+}
+
+/// Diffs each pair on one of a pool of threads, returning the differences
+/// in the order of `pairs`.
+///
+/// Each thread takes the next pair left when it finishes one, so a few
+/// large pairs do not hold up the rest of the batch.
+pub(crate) fn batch<F>(
+    pairs: &[(Value, Value)],
+    options: &DiffOptions,
+    on_done: &F,
+) -> Vec<Vec<Difference>>
+where
+    F: Fn(usize, &[Difference]) + Sync,
+{
+    let threads = thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(pairs.len());
+    if threads < 2 {
+        return pairs
+            .iter()
+            .enumerate()
+            .map(|(index, pair)| diff_pair(index, pair, options, on_done))
+            .collect();
+    }
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<Vec<Difference>> = vec![Vec::new(); pairs.len()];
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(pair) = pairs.get(index) else {
+                            return done;
+                        };
+                        done.push((index, diff_pair(index, pair, options, on_done)));
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            let done = handle
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+            for (index, differences) in done {
+                results[index] = differences;
+            }
+        }
+    });
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use crate::{ArrayDiffStrategy, DiffOptions, deep_diff_with_options};
+
+    // Diffs on the calling thread only, for comparison
+    fn sequential(a: &Value, b: &Value, options: &DiffOptions) -> Vec<crate::Difference> {
+        crate::engine::Walker::new(a, b, options)
+            .map(crate::Difference::from)
+            .collect()
+    }
+
+    /// Test that parallel diffing reports the same differences in the same order.
+    #[test]
+    fn test_parallel_matches_sequential() {
+        let a: Value = (0..2000)
+            .map(|i| json!({"id": i, "value": i % 7, "tags": ["x", i % 3]}))
+            .collect();
+        let b: Value = (0..2100)
+            .map(|i| json!({"id": i, "value": i % 5, "tags": ["x", i % 4]}))
+            .collect();
+        let wrapped = (json!({"data": a}), json!({"data": b}));
+        for strategy in [ArrayDiffStrategy::Index, ArrayDiffStrategy::Lcs] {
+            let options = DiffOptions::builder().array_strategy(strategy).build();
+            let result = deep_diff_with_options(&wrapped.0, &wrapped.1, &options);
+            assert!(result.len() > 1000);
+            assert_eq!(result, sequential(&wrapped.0, &wrapped.1, &options));
+        }
+    }
+
+    /// Test that small documents and changed roots are handled.
+    #[test]
+    fn test_parallel_small_inputs() {
+        let options = DiffOptions::default();
+        let small = deep_diff_with_options(&json!([1, 2]), &json!([1, 3]), &options);
+        assert_eq!(small.len(), 1);
+        let root = deep_diff_with_options(&json!(1), &json!("1"), &options);
+        assert_eq!(root.len(), 1);
+    }
+}
@@ -0,0 +1,426 @@
+//! Application of differences to documents.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use serde_json::Value;
+
+use crate::{DiffKind, Difference, Path, PathSegment};
+
+/// Error returned when a [`Difference`] cannot be applied to a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApplyError {
+    /// The difference's path does not exist in the document.
+    PathNotFound(String),
+    /// The difference does not carry the value needed to apply it, as for
+    /// [`DiffKind::BinaryChanged`] or a value marked in
+    /// [`Difference::withheld`].
+    ValueNotCaptured(String),
+    /// The document no longer holds the value the difference was computed
+    /// against; only reported by [`apply_diff_checked`].
+    Drifted(ConflictReport),
+}
+
+impl core::fmt::Display for ApplyError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ApplyError::PathNotFound(path) => write!(f, "path '{}' does not exist", path),
+            ApplyError::ValueNotCaptured(path) => {
+                write!(f, "the new value at '{}' was not captured", path)
+            }
+            ApplyError::Drifted(conflict) => {
+                write!(f, "the value at '{}' has changed", conflict.path)
+            }
+        }
+    }
+}
+
+impl core::error::Error for ApplyError {}
+
+/// Error returned by [`apply_diff_checked`]: the difference that could not
+/// be applied, and why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApplyFailure {
+    /// The position of the failing difference in the applied slice.
+    pub index: usize,
+    /// Why it could not be applied.
+    pub error: ApplyError,
+}
+
+impl core::fmt::Display for ApplyFailure {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "difference {}: {}", self.index, self.error)
+    }
+}
+
+impl core::error::Error for ApplyFailure {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// A place where a document no longer holds what a difference expects,
+/// as found by [`check_applicability`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictReport {
+    /// The path of the difference, e.g. `"items[0].name"`.
+    pub path: String,
+    /// The same location as `path`, split into typed segments.
+    pub segments: Path,
+    /// The value the difference was computed against, or `None` if it
+    /// expects nothing there, as for an addition.
+    pub expected: Option<Value>,
+    /// The value found in the document, or `None` if the path does not exist.
+    pub actual: Option<Value>,
+}
+
+/// Checks that `doc` still holds the values `diffs` were computed against,
+/// before they are applied with [`apply_diff`].
+///
+/// Each difference is checked against the document as the differences
+/// before it would leave it: changed and removed values must equal their
+/// `before` value, added object keys must not exist yet and array
+/// insertions must be in range. Returns a report for every path that has
+/// drifted; an empty result means `diffs` apply cleanly.
+pub fn check_applicability(doc: &Value, diffs: &[Difference]) -> Vec<ConflictReport> {
+    let mut working = doc.clone();
+    let mut conflicts = Vec::new();
+    for diff in diffs {
+        conflicts.extend(drift(&working, diff));
+        // Later differences are checked against the document as far as it
+        // could be patched
+        let _ = apply_one(&mut working, diff);
+    }
+    conflicts
+}
+
+// Describes how `doc` differs from what `diff` expects to find, if it does
+fn drift(doc: &Value, diff: &Difference) -> Option<ConflictReport> {
+    let segments = diff.segments.segments();
+    let actual = get(doc, segments);
+    let drifted = match (&diff.kind, segments.split_last()) {
+        // Added keys must not exist yet, and inserted elements must be in range
+        (DiffKind::Added, Some((last, parents))) => match (get(doc, parents), last) {
+            (Some(Value::Array(values)), PathSegment::Index(index)) => *index > values.len(),
+            (Some(Value::Object(map)), PathSegment::Key(key)) => map.contains_key(key),
+            (Some(Value::Object(map)), PathSegment::Index(index)) => {
+                map.contains_key(&index.to_string())
+            }
+            _ => true,
+        },
+        (DiffKind::Moved { from, .. }, Some((_, parents))) => {
+            let mut source = parents.to_vec();
+            source.push(PathSegment::Index(*from));
+            get(doc, &source) != diff.before.as_ref()
+        }
+        // The new key must not exist yet, and the old one must hold the value
+        (DiffKind::Renamed { from_key, to_key }, Some((_, parents))) => {
+            let key = |key: &String| {
+                let mut segments = parents.to_vec();
+                segments.push(PathSegment::Key(key.clone()));
+                get(doc, &segments)
+            };
+            key(to_key).is_some() || key(from_key) != diff.before.as_ref()
+        }
+        // The old bytes are not captured, so only the path is checked
+        (DiffKind::BinaryChanged { .. }, _) => actual.is_none(),
+        (DiffKind::KeyOrderChanged, _) => !matches!(actual, Some(Value::Object(_))),
+        _ => actual != diff.before.as_ref(),
+    };
+    drifted.then(|| ConflictReport {
+        path: diff.path.clone(),
+        segments: diff.segments.clone(),
+        expected: diff.before.clone(),
+        actual: actual.cloned(),
+    })
+}
+
+// Walks down to the value at `segments`, if it exists. Indices into objects
+// are read as keys, as JSON Pointers do not tell them apart.
+pub(crate) fn get<'a>(mut value: &'a Value, segments: &[PathSegment]) -> Option<&'a Value> {
+    for segment in segments {
+        value = match (value, segment) {
+            (Value::Object(map), PathSegment::Key(key)) => map.get(key)?,
+            (Value::Object(map), PathSegment::Index(index)) => map.get(&index.to_string())?,
+            (Value::Array(values), PathSegment::Index(index)) => values.get(*index)?,
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+// Walks down to the value at `segments` for modification, if it exists,
+// reading indices into objects as keys
+fn get_mut<'a>(mut value: &'a mut Value, segments: &[PathSegment]) -> Option<&'a mut Value> {
+    for segment in segments {
+        value = match (value, segment) {
+            (Value::Object(map), PathSegment::Key(key)) => map.get_mut(key)?,
+            (Value::Object(map), PathSegment::Index(index)) => map.get_mut(&index.to_string())?,
+            (Value::Array(values), PathSegment::Index(index)) => values.get_mut(*index)?,
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+// Whether `diff` carries the value it writes: binary changes never do, and
+// withheld values were replaced in the report
+pub(crate) fn captures_value(diff: &Difference) -> bool {
+    match diff.kind {
+        DiffKind::BinaryChanged { .. } => false,
+        // Moves keep the element they move, whatever `after` holds
+        DiffKind::Moved { .. } => true,
+        _ => !diff.withheld.after,
+    }
+}
+
+// Applies a single difference in place
+pub(crate) fn apply_one(doc: &mut Value, diff: &Difference) -> Result<(), ApplyError> {
+    let not_found = || ApplyError::PathNotFound(diff.path.clone());
+    if !captures_value(diff) {
+        return Err(ApplyError::ValueNotCaptured(diff.path.clone()));
+    }
+    // Objects do not keep their key order, so there is nothing to reorder
+    if diff.kind == DiffKind::KeyOrderChanged {
+        return match get(doc, diff.segments.segments()) {
+            Some(Value::Object(_)) => Ok(()),
+            _ => Err(not_found()),
+        };
+    }
+    let Some((last, parents)) = diff.segments.segments().split_last() else {
+        // The root itself changed
+        *doc = diff.after.clone().unwrap_or(Value::Null);
+        return Ok(());
+    };
+    let parent = get_mut(doc, parents).ok_or_else(not_found)?;
+    let after = diff.after.clone().unwrap_or(Value::Null);
+    // An index into an object is read as its key
+    let key;
+    let last = match (&*parent, last) {
+        (Value::Object(_), PathSegment::Index(index)) => {
+            key = PathSegment::Key(index.to_string());
+            &key
+        }
+        _ => last,
+    };
+    match (parent, last) {
+        (Value::Object(map), PathSegment::Key(key)) => match &diff.kind {
+            DiffKind::Added => {
+                map.insert(key.clone(), after);
+            }
+            DiffKind::Removed => {
+                map.remove(key).ok_or_else(not_found)?;
+            }
+            DiffKind::Changed | DiffKind::TypeChanged { .. } => {
+                *map.get_mut(key).ok_or_else(not_found)? = after;
+            }
+            DiffKind::Renamed { from_key, to_key } => {
+                let value = map.remove(from_key).ok_or_else(not_found)?;
+                // Renames read from a JSON Patch carry no value of their own
+                map.insert(to_key.clone(), diff.after.clone().unwrap_or(value));
+            }
+            // A JSON Patch `move` between digit keys is read as a move
+            DiffKind::Moved { from, .. } => {
+                let value = map.remove(&from.to_string()).ok_or_else(not_found)?;
+                map.insert(key.clone(), value);
+            }
+            DiffKind::BinaryChanged { .. } | DiffKind::KeyOrderChanged => {
+                return Err(not_found());
+            }
+        },
+        (Value::Array(values), PathSegment::Index(index)) => {
+            let index = *index;
+            match diff.kind {
+                DiffKind::Added if index <= values.len() => values.insert(index, after),
+                DiffKind::Removed if index < values.len() => {
+                    values.remove(index);
+                }
+                DiffKind::Changed | DiffKind::TypeChanged { .. } => {
+                    *values.get_mut(index).ok_or_else(not_found)? = after;
+                }
+                DiffKind::Moved { from, to } if from < values.len() && to < values.len() => {
+                    let moved = values.remove(from);
+                    values.insert(to, moved);
+                }
+                _ => return Err(not_found()),
+            }
+        }
+        _ => return Err(not_found()),
+    }
+    Ok(())
+}
+
+/// Applies `diffs` to a copy of `base`, reconstructing the document they
+/// were computed against.
+///
+/// Fails if a difference refers to a path that no longer exists in the
+/// document being patched.
+pub fn apply_diff(base: &Value, diffs: &[Difference]) -> Result<Value, ApplyError> {
+    let mut doc = base.clone();
+    for diff in diffs {
+        apply_one(&mut doc, diff)?;
+    }
+    Ok(doc)
+}
+
+/// Applies `diffs` to `doc` as a single transaction: either every
+/// difference applies or `doc` is left untouched.
+///
+/// Each difference is first tested against a working copy, as a JSON Patch
+/// `test` operation would: values it changes or removes must still equal
+/// its `before` value, as in [`check_applicability`]. The working copy
+/// replaces `doc` only once all differences have applied.
+pub fn apply_diff_checked(doc: &mut Value, diffs: &[Difference]) -> Result<(), ApplyFailure> {
+    let mut working = doc.clone();
+    for (index, diff) in diffs.iter().enumerate() {
+        let result = match drift(&working, diff) {
+            Some(conflict) => Err(ApplyError::Drifted(conflict)),
+            None => apply_one(&mut working, diff),
+        };
+        result.map_err(|error| ApplyFailure { index, error })?;
+    }
+    *doc = working;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DiffOptions, PathFormat, Severity, Withheld, deep_diff, deep_diff_with_options};
+    use serde_json::json;
+
+    /// Test that applying a diff reconstructs the target document.
+    #[test]
+    fn test_apply_diff_round_trip() {
+        let a = json!({"name": "Alice", "age": 30, "tags": ["a", "b"], "meta": {"v": 1}});
+        let b = json!({"name": "Bob", "tags": ["a", "c"], "meta": {"v": 2}, "email": "x"});
+        assert_eq!(apply_diff(&a, &deep_diff(&a, &b)), Ok(b));
+    }
+
+    /// Test that nested removals are applied at their full path.
+    #[test]
+    fn test_apply_diff_nested_removal() {
+        let a = json!({"a": {"b": 1, "c": 2}});
+        let b = json!({"a": {"b": 1}});
+        assert_eq!(apply_diff(&a, &deep_diff(&a, &b)), Ok(b));
+    }
+
+    /// Test that a changed root value is replaced.
+    #[test]
+    fn test_apply_diff_root() {
+        let a = json!([1]);
+        let b = json!({"a": 1});
+        assert_eq!(apply_diff(&a, &deep_diff(&a, &b)), Ok(b));
+    }
+
+    /// Test that paths which no longer exist are reported.
+    #[test]
+    fn test_apply_diff_missing_path() {
+        let diffs = deep_diff(&json!({"a": {"b": 1}}), &json!({"a": {"b": 2}}));
+        assert_eq!(
+            apply_diff(&json!({"c": 1}), &diffs),
+            Err(ApplyError::PathNotFound("a.b".to_string()))
+        );
+        let removal = Difference {
+            path: "[3]".to_string(),
+            segments: Path::root().child_index(3),
+            kind: DiffKind::Removed,
+            severity: Severity::Info,
+            before: Some(json!(1)),
+            after: None,
+            context: None,
+            withheld: Withheld::default(),
+        };
+        assert_eq!(
+            apply_diff(&json!([1]), &[removal]),
+            Err(ApplyError::PathNotFound("[3]".to_string()))
+        );
+    }
+
+    /// Test that diffs rendered as JSON Pointers can be applied.
+    #[test]
+    fn test_apply_diff_pointer_paths() {
+        let a = json!({"a.b": {"items": [1, 2]}, "c": 1});
+        let b = json!({"a.b": {"items": [1, 3]}});
+        let options = DiffOptions::builder()
+            .path_format(PathFormat::JsonPointer)
+            .build();
+        let diffs = deep_diff_with_options(&a, &b, &options);
+        assert_eq!(apply_diff(&a, &diffs), Ok(b));
+    }
+
+    /// Test that a document the diff was computed against has no conflicts.
+    #[test]
+    fn test_check_applicability_clean() {
+        let a = json!({"name": "Alice", "tags": ["a", "b"], "old": 1});
+        let b = json!({"name": "Bob", "tags": ["b", "c", "a"], "new": 2});
+        let options = DiffOptions::builder()
+            .array_strategy(crate::ArrayDiffStrategy::Lcs)
+            .detect_moves(true)
+            .build();
+        assert!(check_applicability(&a, &deep_diff_with_options(&a, &b, &options)).is_empty());
+    }
+
+    /// Test that drifted values, existing keys and missing paths are reported.
+    #[test]
+    fn test_check_applicability_drift() {
+        let a = json!({"name": "Alice", "age": 30, "tags": ["a"]});
+        let b = json!({"name": "Bob", "email": "x", "tags": ["a", "b"]});
+        let diffs = deep_diff(&a, &b);
+        let live = json!({"name": "Carol", "age": 30, "email": "y"});
+        let conflicts = check_applicability(&live, &diffs);
+        assert_eq!(
+            conflicts,
+            vec![
+                ConflictReport {
+                    path: "name".to_string(),
+                    segments: "name".parse().unwrap(),
+                    expected: Some(json!("Alice")),
+                    actual: Some(json!("Carol")),
+                },
+                ConflictReport {
+                    path: "tags[1]".to_string(),
+                    segments: "tags[1]".parse().unwrap(),
+                    expected: None,
+                    actual: None,
+                },
+                ConflictReport {
+                    path: "email".to_string(),
+                    segments: "email".parse().unwrap(),
+                    expected: None,
+                    actual: Some(json!("y")),
+                },
+            ]
+        );
+    }
+
+    /// Test that a checked apply commits all differences or none.
+    #[test]
+    fn test_apply_diff_checked() {
+        let a = json!({"name": "Alice", "age": 30, "tags": ["a"]});
+        let b = json!({"name": "Bob", "tags": ["a", "b"]});
+        let diffs = deep_diff(&a, &b);
+        let mut doc = a.clone();
+        assert_eq!(apply_diff_checked(&mut doc, &diffs), Ok(()));
+        assert_eq!(doc, b);
+
+        let mut live = json!({"name": "Alice", "age": 31, "tags": ["a"]});
+        let original = live.clone();
+        let failure = apply_diff_checked(&mut live, &diffs).unwrap_err();
+        assert_eq!(live, original);
+        assert_eq!(failure.index, 0);
+        assert_eq!(
+            failure.error,
+            ApplyError::Drifted(ConflictReport {
+                path: "age".to_string(),
+                segments: "age".parse().unwrap(),
+                expected: Some(json!(30)),
+                actual: Some(json!(31)),
+            })
+        );
+        assert_eq!(
+            failure.to_string(),
+            "difference 0: the value at 'age' has changed"
+        );
+    }
+}
@@ -0,0 +1,356 @@
+//! Conversion to and from RFC 6902 JSON Patch documents.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use serde_json::{Value, json};
+
+use crate::patch::captures_value;
+use crate::{DiffKind, Difference, Path, PathSegment, Severity, Withheld};
+
+/// Converts differences into an RFC 6902 JSON Patch document.
+///
+/// Added values become `add` operations, removed values become `remove`
+/// operations, moved elements and renamed keys become `move` operations and
+/// everything else becomes a `replace`. A key renamed along with a change to
+/// its value is followed by a `replace`. Binary changes, like differences
+/// whose new value was [`withheld`](Difference::withheld), carry no values
+/// to write, and JSON Patch has no notion of key order, so all of them are
+/// left out.
+pub fn to_json_patch(differences: &[Difference]) -> Value {
+    let operations = differences
+        .iter()
+        .filter(|diff| captures_value(diff) && diff.kind != DiffKind::KeyOrderChanged)
+        .flat_map(|diff| {
+            let path = diff.segments.to_pointer();
+            let parent = || diff.segments.parent().unwrap_or_default();
+            match &diff.kind {
+                DiffKind::Added => vec![json!({"op": "add", "path": path, "value": diff.after})],
+                DiffKind::Removed => vec![json!({"op": "remove", "path": path})],
+                DiffKind::Changed | DiffKind::TypeChanged { .. } => {
+                    vec![json!({"op": "replace", "path": path, "value": diff.after})]
+                }
+                DiffKind::Moved { from, .. } => {
+                    let from = parent().child_index(*from);
+                    vec![json!({"op": "move", "from": from.to_pointer(), "path": path})]
+                }
+                DiffKind::Renamed { from_key, .. } => {
+                    let from = parent().child_key(from_key.as_str());
+                    let mut operations =
+                        vec![json!({"op": "move", "from": from.to_pointer(), "path": path})];
+                    if diff.before != diff.after {
+                        operations
+                            .push(json!({"op": "replace", "path": path, "value": diff.after}));
+                    }
+                    operations
+                }
+                DiffKind::BinaryChanged { .. } | DiffKind::KeyOrderChanged => {
+                    unreachable!("filtered out above")
+                }
+            }
+        })
+        .collect();
+    Value::Array(operations)
+}
+
+/// Error returned when a JSON Patch cannot be read by [`from_json_patch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsePatchError(String);
+
+impl core::fmt::Display for ParsePatchError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "invalid JSON Patch: {}", self.0)
+    }
+}
+
+impl core::error::Error for ParsePatchError {}
+
+/// Reads an RFC 6902 JSON Patch document into differences, so patches
+/// produced elsewhere can be rendered, inverted or applied.
+///
+/// `add` becomes [`DiffKind::Added`], `remove` [`DiffKind::Removed`],
+/// `replace` [`DiffKind::Changed`], a `move` within one array
+/// [`DiffKind::Moved`] and a `move` within one object
+/// [`DiffKind::Renamed`]. A patch does not hold the values it replaces, so
+/// `before` is `None` unless a `test` of the same path precedes the
+/// operation. Pointer tokens made of digits are read as array indices,
+/// which [`apply_diff`](crate::apply_diff) uses as keys where the document
+/// holds an object.
+///
+/// Fails on `copy`, on moves between different parents, and on the `-`
+/// token for appending, none of which can be described without the
+/// document the patch applies to.
+pub fn from_json_patch(patch: &Value) -> Result<Vec<Difference>, ParsePatchError> {
+    let operations = patch
+        .as_array()
+        .ok_or_else(|| ParsePatchError("expected an array of operations".to_string()))?;
+    let mut differences = Vec::new();
+    // The value the last `test` operation expects, to fill in `before`
+    let mut tested: Option<(Path, Value)> = None;
+    for (i, operation) in operations.iter().enumerate() {
+        let invalid = |message: &str| ParsePatchError(format!("operation {}: {}", i, message));
+        let pointer = |field: &str| -> Result<Path, ParsePatchError> {
+            let pointer = operation
+                .get(field)
+                .and_then(Value::as_str)
+                .ok_or_else(|| invalid(&format!("missing '{}'", field)))?;
+            parse_patch_pointer(pointer).ok_or_else(|| invalid("cannot append with '-'"))
+        };
+        let value = || {
+            operation
+                .get("value")
+                .cloned()
+                .ok_or_else(|| invalid("missing 'value'"))
+        };
+        let op = operation
+            .get("op")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let segments = pointer("path")?;
+        let before = match tested.take() {
+            Some((path, value)) if path == segments => Some(value),
+            _ => None,
+        };
+        let (kind, after) = match op {
+            "add" => (DiffKind::Added, Some(value()?)),
+            "remove" => (DiffKind::Removed, None),
+            "replace" => (DiffKind::Changed, Some(value()?)),
+            "move" => {
+                let source = pointer("from")?;
+                match (
+                    source.segments().split_last(),
+                    segments.segments().split_last(),
+                ) {
+                    (
+                        Some((PathSegment::Index(from), from_parent)),
+                        Some((PathSegment::Index(to), to_parent)),
+                    ) if from_parent == to_parent => (
+                        DiffKind::Moved {
+                            from: *from,
+                            to: *to,
+                        },
+                        None,
+                    ),
+                    (
+                        Some((PathSegment::Key(from_key), from_parent)),
+                        Some((PathSegment::Key(to_key), to_parent)),
+                    ) if from_parent == to_parent => (
+                        DiffKind::Renamed {
+                            from_key: from_key.clone(),
+                            to_key: to_key.clone(),
+                        },
+                        None,
+                    ),
+                    _ => {
+                        return Err(invalid(
+                            "only moves within one array or object are supported",
+                        ));
+                    }
+                }
+            }
+            "test" => {
+                tested = Some((segments, value()?));
+                continue;
+            }
+            "copy" => return Err(invalid("'copy' is not supported")),
+            _ => return Err(invalid(&format!("unknown op '{}'", op))),
+        };
+        differences.push(Difference {
+            path: segments.to_string(),
+            segments,
+            kind,
+            severity: Severity::Info,
+            before,
+            after,
+            context: None,
+            withheld: Withheld::default(),
+        });
+    }
+    Ok(differences)
+}
+
+// Parses a JSON Pointer, reading tokens made of digits as array indices.
+// Returns `None` for the `-` token, which needs the document to resolve.
+fn parse_patch_pointer(pointer: &str) -> Option<Path> {
+    let path: Path = pointer.parse().ok()?;
+    let segments = path.segments().iter().map(|segment| match segment {
+        PathSegment::Key(key) if key == "-" => None,
+        PathSegment::Key(key)
+            if key.bytes().all(|b| b.is_ascii_digit()) && (key == "0" || !key.starts_with('0')) =>
+        {
+            key.parse().ok().map(PathSegment::Index)
+        }
+        segment => Some(segment.clone()),
+    });
+    segments.collect::<Option<Vec<_>>>().map(Path::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ApplyError, DiffOptions, apply_diff, deep_diff, deep_diff_with_options};
+
+    /// Test that each kind of difference maps to the matching patch operation.
+    #[test]
+    fn test_to_json_patch_operations() {
+        let a = json!({"name": "Alice", "age": 30});
+        let b = json!({"name": "Bob", "email": "bob@example.com"});
+        let patch = to_json_patch(&deep_diff(&a, &b));
+        assert_eq!(
+            patch,
+            json!([
+                {"op": "remove", "path": "/age"},
+                {"op": "replace", "path": "/name", "value": "Bob"},
+                {"op": "add", "path": "/email", "value": "bob@example.com"},
+            ])
+        );
+    }
+
+    /// Test that a replaced root value uses the empty pointer.
+    #[test]
+    fn test_to_json_patch_root() {
+        let patch = to_json_patch(&deep_diff(&json!(1), &json!("one")));
+        assert_eq!(
+            patch,
+            json!([{"op": "replace", "path": "", "value": "one"}])
+        );
+    }
+
+    /// Test that moved array elements become `move` operations.
+    #[test]
+    fn test_to_json_patch_move() {
+        let a = json!({"list": ["a", "b", "c"]});
+        let b = json!({"list": ["c", "a", "b"]});
+        let options = DiffOptions::builder()
+            .array_strategy(crate::ArrayDiffStrategy::Lcs)
+            .detect_moves(true)
+            .build();
+        let patch = to_json_patch(&deep_diff_with_options(&a, &b, &options));
+        assert_eq!(
+            patch,
+            json!([{"op": "move", "from": "/list/2", "path": "/list/0"}])
+        );
+    }
+
+    /// Test that renamed keys become `move` operations and are read back.
+    #[test]
+    fn test_json_patch_rename() {
+        let a = json!({"old": {"x": 1, "y": 2, "z": 3}, "same": "a"});
+        let b = json!({"new": {"x": 1, "y": 2, "z": 4}, "same": "a"});
+        let options = DiffOptions::builder().rename_threshold(0.5).build();
+        let patch = to_json_patch(&deep_diff_with_options(&a, &b, &options));
+        assert_eq!(
+            patch,
+            json!([
+                {"op": "move", "from": "/old", "path": "/new"},
+                {"op": "replace", "path": "/new", "value": {"x": 1, "y": 2, "z": 4}}
+            ])
+        );
+        let differences = from_json_patch(&patch).unwrap();
+        assert_eq!(apply_diff(&a, &differences), Ok(b));
+    }
+
+    /// Test that patches round trip through objects with digit keys, which
+    /// pointers read as indices.
+    #[test]
+    fn test_json_patch_digit_keys() {
+        let a = json!({"responses": {"200": "old", "404": {"x": 1}, "500": 1}, "1": [0]});
+        let b = json!({"responses": {"200": "ok", "201": "new", "500": 1}, "1": [0, 1]});
+        let differences = from_json_patch(&to_json_patch(&deep_diff(&a, &b))).unwrap();
+        assert_eq!(apply_diff(&a, &differences), Ok(b.clone()));
+        let patch = json!([
+            {"op": "replace", "path": "/responses/200", "value": "ok"},
+            {"op": "move", "from": "/responses/404", "path": "/responses/201"}
+        ]);
+        let differences = from_json_patch(&patch).unwrap();
+        assert_eq!(
+            apply_diff(&a, &differences),
+            Ok(json!({"responses": {"200": "ok", "201": {"x": 1}, "500": 1}, "1": [0]}))
+        );
+    }
+
+    /// Test that binary changes are left out of patches and cannot be applied.
+    #[test]
+    fn test_binary_changes() {
+        let a = json!({"blob": "aGk=", "n": 1});
+        let b = json!({"blob": "aGVsbG8=", "n": 2});
+        let options = DiffOptions::builder().binary_at("blob").build();
+        let diffs = deep_diff_with_options(&a, &b, &options);
+        assert_eq!(
+            to_json_patch(&diffs),
+            json!([{"op": "replace", "path": "/n", "value": 2}])
+        );
+        assert_eq!(
+            apply_diff(&a, &diffs),
+            Err(ApplyError::ValueNotCaptured("blob".to_string()))
+        );
+    }
+
+    /// Test that JSON Patch operations are read back as differences.
+    #[test]
+    fn test_from_json_patch() {
+        let patch = json!([
+            {"op": "test", "path": "/name", "value": "Alice"},
+            {"op": "replace", "path": "/name", "value": "Bob"},
+            {"op": "remove", "path": "/tags/0"},
+            {"op": "add", "path": "/a~1b", "value": {"x": 1}},
+            {"op": "move", "from": "/list/2", "path": "/list/0"},
+        ]);
+        let diffs = from_json_patch(&patch).unwrap();
+        let summary: Vec<_> = diffs
+            .iter()
+            .map(|diff| (diff.path.as_str(), diff.kind.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("name", DiffKind::Changed),
+                ("tags[0]", DiffKind::Removed),
+                ("a/b", DiffKind::Added),
+                ("list[0]", DiffKind::Moved { from: 2, to: 0 }),
+            ]
+        );
+        assert_eq!(diffs[0].before, Some(json!("Alice")));
+        assert_eq!(diffs[1].before, None);
+        let doc = json!({"name": "Alice", "tags": ["t"], "list": ["a", "b", "c"]});
+        assert_eq!(
+            apply_diff(&doc, &diffs),
+            Ok(json!({"name": "Bob", "tags": [], "a/b": {"x": 1}, "list": ["c", "a", "b"]}))
+        );
+        let own = deep_diff(&json!({"a": [1, 2]}), &json!({"a": [1], "b": null}));
+        assert_eq!(
+            from_json_patch(&to_json_patch(&own)).map(|d| d.len()),
+            Ok(2)
+        );
+    }
+
+    /// Test that operations needing the document are rejected.
+    #[test]
+    fn test_from_json_patch_invalid() {
+        let error = |patch: Value| from_json_patch(&patch).unwrap_err().to_string();
+        assert_eq!(
+            error(json!([{"op": "add", "path": "/a/-", "value": 1}])),
+            "invalid JSON Patch: operation 0: cannot append with '-'"
+        );
+        assert_eq!(
+            error(
+                json!([{"op": "remove", "path": "/a"}, {"op": "copy", "from": "/a", "path": "/b"}])
+            ),
+            "invalid JSON Patch: operation 1: 'copy' is not supported"
+        );
+        assert_eq!(
+            error(json!([{"op": "move", "from": "/a/x", "path": "/b"}])),
+            "invalid JSON Patch: operation 0: only moves within one array or object are supported"
+        );
+        assert_eq!(
+            error(json!([{"op": "replace", "path": "/a"}])),
+            "invalid JSON Patch: operation 0: missing 'value'"
+        );
+        assert_eq!(
+            error(json!({"op": "add"})),
+            "invalid JSON Patch: expected an array of operations"
+        );
+    }
+}
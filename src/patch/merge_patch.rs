@@ -0,0 +1,116 @@
+//! Conversion to and from RFC 7396 JSON Merge Patch documents.
+
+use alloc::vec::Vec;
+
+use serde_json::{Map, Value};
+
+use crate::{Difference, deep_diff};
+
+/// Produces an RFC 7396 JSON Merge Patch that transforms `a` into `b`.
+///
+/// Merge patches cannot express setting a key to `null`, since `null`
+/// means "remove this key"; such changes are reported as removals.
+pub fn merge_patch(a: &Value, b: &Value) -> Value {
+    let (Value::Object(a_map), Value::Object(b_map)) = (a, b) else {
+        return b.clone();
+    };
+    let mut patch = Map::new();
+    for ak in a_map.keys() {
+        if !b_map.contains_key(ak) {
+            patch.insert(ak.clone(), Value::Null);
+        }
+    }
+    for (bk, bv) in b_map {
+        match a_map.get(bk) {
+            Some(av) if av == bv => {}
+            Some(av) => {
+                patch.insert(bk.clone(), merge_patch(av, bv));
+            }
+            None => {
+                patch.insert(bk.clone(), bv.clone());
+            }
+        }
+    }
+    Value::Object(patch)
+}
+
+/// Describes the RFC 7396 JSON Merge Patch `patch` as the differences it
+/// makes to `base`.
+pub fn from_merge_patch(base: &Value, patch: &Value) -> Vec<Difference> {
+    deep_diff(base, &apply_merge_patch(base, patch))
+}
+
+// Applies a merge patch: `null` removes a key, objects merge recursively and
+// any other value replaces the target
+fn apply_merge_patch(target: &Value, patch: &Value) -> Value {
+    let Value::Object(patch) = patch else {
+        return patch.clone();
+    };
+    let mut merged = match target {
+        Value::Object(map) => map.clone(),
+        _ => Map::new(),
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            merged.remove(key);
+        } else {
+            let current = merged.get(key).unwrap_or(&Value::Null);
+            let value = apply_merge_patch(current, value);
+            merged.insert(key.clone(), value);
+        }
+    }
+    Value::Object(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apply_diff;
+    use serde_json::json;
+
+    /// Test that a merge patch only contains changed, added and removed keys.
+    #[test]
+    fn test_merge_patch() {
+        let a = json!({"name": "Alice", "age": 30, "address": {"city": "Oslo", "zip": "0150"}});
+        let b =
+            json!({"name": "Alice", "address": {"city": "Bergen", "zip": "0150"}, "tags": ["x"]});
+        assert_eq!(
+            merge_patch(&a, &b),
+            json!({"age": null, "address": {"city": "Bergen"}, "tags": ["x"]})
+        );
+    }
+
+    /// Test that non-object documents are replaced wholesale.
+    #[test]
+    fn test_merge_patch_non_object() {
+        assert_eq!(merge_patch(&json!([1, 2]), &json!([1, 3])), json!([1, 3]));
+        assert_eq!(merge_patch(&json!({"a": 1}), &json!("a")), json!("a"));
+        assert_eq!(merge_patch(&json!("a"), &json!({"a": 1})), json!({"a": 1}));
+    }
+
+    /// Test that identical documents produce an empty merge patch.
+    #[test]
+    fn test_merge_patch_identical() {
+        let a = json!({"a": {"b": [1, 2]}});
+        assert_eq!(merge_patch(&a, &a), json!({}));
+    }
+
+    /// Test that a merge patch is described by the changes it makes.
+    #[test]
+    fn test_from_merge_patch() {
+        let base = json!({"name": "Alice", "age": 30, "address": {"city": "Oslo"}});
+        let patch = json!({"age": null, "address": {"city": "Bergen", "zip": "5003"}});
+        let diffs = from_merge_patch(&base, &patch);
+        let paths: Vec<_> = diffs.iter().map(|diff| diff.path.as_str()).collect();
+        assert_eq!(paths, ["address.city", "address.zip", "age"]);
+        assert_eq!(diffs[2].before, Some(json!(30)));
+        let target = json!({"name": "Bob", "tags": ["x"]});
+        assert_eq!(
+            apply_diff(
+                &base,
+                &from_merge_patch(&base, &merge_patch(&base, &target))
+            ),
+            Ok(target)
+        );
+    }
+}
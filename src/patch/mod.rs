@@ -0,0 +1,12 @@
+//! Patch generation and application.
+
+mod apply;
+mod json_patch;
+mod merge_patch;
+
+pub use apply::{
+    ApplyError, ApplyFailure, ConflictReport, apply_diff, apply_diff_checked, check_applicability,
+};
+pub(crate) use apply::{apply_one, captures_value, get};
+pub use json_patch::{ParsePatchError, from_json_patch, to_json_patch};
+pub use merge_patch::{from_merge_patch, merge_patch};
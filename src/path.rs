@@ -0,0 +1,649 @@
+//! Typed paths into a JSON document and their string renderings.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+/// A single step into a JSON document.
+///
+/// Keys sort before indices; keys compare as strings and indices as numbers.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Hash)]
+pub enum PathSegment {
+    /// A key of an object.
+    Key(String),
+    /// An index of an array.
+    Index(usize),
+}
+
+/// The location of a value inside a JSON document.
+///
+/// The empty path refers to the document root. Paths are ordered segment by
+/// segment, so a path sorts right before the paths inside it.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Default, Hash)]
+pub struct Path(pub Vec<PathSegment>);
+
+impl Path {
+    /// Returns the path of the document root.
+    pub fn root() -> Path {
+        Path(Vec::new())
+    }
+
+    /// Returns the segments of this path, outermost first.
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.0
+    }
+
+    /// Returns the number of segments in this path.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns whether this path has no segments, i.e. refers to the root.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the path one level up, or `None` for the root.
+    pub fn parent(&self) -> Option<Path> {
+        let (_, parent) = self.0.split_last()?;
+        Some(Path(parent.to_vec()))
+    }
+
+    /// Returns whether this path is `prefix` or lies inside it, comparing
+    /// whole segments, so `items2` does not start with `items`.
+    pub fn starts_with(&self, prefix: &Path) -> bool {
+        self.0.starts_with(&prefix.0)
+    }
+
+    /// Returns the path of the given key below this one.
+    pub fn child_key(&self, key: impl Into<String>) -> Path {
+        let mut segments = self.0.clone();
+        segments.push(PathSegment::Key(key.into()));
+        Path(segments)
+    }
+
+    /// Returns the path of the given index below this one.
+    pub fn child_index(&self, index: usize) -> Path {
+        let mut segments = self.0.clone();
+        segments.push(PathSegment::Index(index));
+        Path(segments)
+    }
+
+    /// Renders this path in the given format.
+    pub fn render(&self, format: PathFormat) -> String {
+        self.0
+            .iter()
+            .fold(String::new(), |parent, segment| match segment {
+                PathSegment::Key(key) => format.key_path(&parent, key),
+                PathSegment::Index(index) => format.index_path(&parent, *index),
+            })
+    }
+
+    /// Returns this path as an RFC 6901 JSON Pointer.
+    pub fn to_pointer(&self) -> String {
+        self.render(PathFormat::JsonPointer)
+    }
+}
+
+impl From<Vec<PathSegment>> for Path {
+    fn from(segments: Vec<PathSegment>) -> Path {
+        Path(segments)
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(PathFormat::Dotted))
+    }
+}
+
+/// A path built up while walking a document, in which each step links to its
+/// parent instead of copying it, so descending costs one allocation however
+/// deep the value is. The full [`Path`] is only assembled when needed.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PathTrail(Option<Arc<TrailStep>>);
+
+#[derive(Debug)]
+struct TrailStep {
+    parent: PathTrail,
+    segment: PathSegment,
+    len: usize,
+}
+
+impl PathTrail {
+    pub(crate) fn root() -> PathTrail {
+        PathTrail(None)
+    }
+
+    pub(crate) fn child_key(&self, key: impl Into<String>) -> PathTrail {
+        self.child(PathSegment::Key(key.into()))
+    }
+
+    pub(crate) fn child_index(&self, index: usize) -> PathTrail {
+        self.child(PathSegment::Index(index))
+    }
+
+    fn child(&self, segment: PathSegment) -> PathTrail {
+        let len = self.0.as_ref().map_or(0, |step| step.len) + 1;
+        PathTrail(Some(Arc::new(TrailStep {
+            parent: self.clone(),
+            segment,
+            len,
+        })))
+    }
+
+    // Assembles the full path by following the links back to the root
+    pub(crate) fn to_path(&self) -> Path {
+        let mut segments = Vec::with_capacity(self.0.as_ref().map_or(0, |step| step.len));
+        let mut trail = self;
+        while let Some(step) = &trail.0 {
+            segments.push(step.segment.clone());
+            trail = &step.parent;
+        }
+        segments.reverse();
+        Path(segments)
+    }
+}
+
+// Unlinks the steps one at a time, so dropping a deep trail cannot overflow
+// the stack
+impl Drop for PathTrail {
+    fn drop(&mut self) {
+        let mut next = self.0.take();
+        while let Some(step) = next {
+            next = Arc::try_unwrap(step)
+                .ok()
+                .and_then(|mut step| step.parent.0.take());
+        }
+    }
+}
+
+/// Error returned when a string cannot be parsed as a [`Path`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsePathError(String);
+
+impl fmt::Display for ParsePathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid path '{}'", self.0)
+    }
+}
+
+impl core::error::Error for ParsePathError {}
+
+impl FromStr for Path {
+    type Err = ParsePathError;
+
+    /// Parses a dotted path (`items[0].name`, optionally with quoted keys
+    /// such as `["a.b"]`) or, when the string starts with `/`, a JSON
+    /// Pointer (`/items/0/name`).
+    ///
+    /// Pointer tokens are always parsed as keys, since a pointer does not
+    /// say whether `0` names an object key or an array index.
+    fn from_str(path: &str) -> Result<Path, ParsePathError> {
+        if path.starts_with('/') {
+            Ok(parse_pointer(path))
+        } else {
+            parse_dotted(path).ok_or_else(|| ParsePathError(path.to_string()))
+        }
+    }
+}
+
+// Parses a JSON Pointer such as `/items/0/name` into its segments
+fn parse_pointer(pointer: &str) -> Path {
+    let segments = pointer
+        .split('/')
+        .skip(1)
+        .map(|token| PathSegment::Key(token.replace("~1", "/").replace("~0", "~")))
+        .collect();
+    Path(segments)
+}
+
+// A lexical piece of a dotted path, before its meaning is decided
+enum Token {
+    // A bare key, e.g. `name`
+    Key(String),
+    // A quoted key, e.g. `["a.b"]`
+    Quoted(String),
+    // The contents of an unquoted bracket, e.g. `0` in `[0]`
+    Bracket(String),
+}
+
+// Splits a dotted path such as `items[0].name` into tokens
+fn tokenize(path: &str) -> Option<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => tokens.push(Token::Key(core::mem::take(&mut token))),
+            '[' => {
+                if !token.is_empty() {
+                    tokens.push(Token::Key(core::mem::take(&mut token)));
+                }
+                if chars.as_str().starts_with('"') {
+                    chars.next();
+                    tokens.push(Token::Quoted(parse_quoted_key(&mut chars)?));
+                } else {
+                    let inner: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                    tokens.push(Token::Bracket(inner));
+                }
+                // A key may follow an index, e.g. `items[0].name`
+                if chars.as_str().starts_with('.') {
+                    chars.next();
+                }
+            }
+            _ => token.push(c),
+        }
+    }
+    if !token.is_empty() {
+        tokens.push(Token::Key(token));
+    }
+    Some(tokens)
+}
+
+// Parses a dotted path such as `items[0].name` into its segments
+fn parse_dotted(path: &str) -> Option<Path> {
+    let segments = tokenize(path)?
+        .into_iter()
+        .map(|token| match token {
+            Token::Key(key) | Token::Quoted(key) => Some(PathSegment::Key(key)),
+            Token::Bracket(index) => index.parse().ok().map(PathSegment::Index),
+        })
+        .collect::<Option<_>>()?;
+    Some(Path(segments))
+}
+
+// Parses the rest of a `["key"]` segment, undoing `\"` and `\\` escapes
+fn parse_quoted_key(chars: &mut core::str::Chars<'_>) -> Option<String> {
+    let mut key = String::new();
+    loop {
+        match chars.next()? {
+            '\\' => key.push(chars.next()?),
+            '"' => break,
+            c => key.push(c),
+        }
+    }
+    (chars.next()? == ']').then_some(key)
+}
+
+// Whether a key must be quoted to be unambiguous in an escaped dotted path
+fn needs_quoting(key: &str, at_root: bool) -> bool {
+    key.is_empty() || (at_root && key.starts_with('/')) || key.contains(['.', '[', ']', '"', '\\'])
+}
+
+// Escapes a single reference token as described in RFC 6901
+fn escape_pointer_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+/// How the `path` of each [`Difference`](crate::Difference) is rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathFormat {
+    /// Dotted keys with bracketed indices, e.g. `items[0].name`.
+    #[default]
+    Dotted,
+    /// RFC 6901 JSON Pointers, e.g. `/items/0/name`.
+    JsonPointer,
+    /// Like [`PathFormat::Dotted`], but keys that would be ambiguous are
+    /// quoted in brackets, e.g. `config["a.b"].items[0]`. Such paths parse
+    /// back into the exact same [`Path`].
+    Escaped,
+}
+
+impl PathFormat {
+    // Appends an object key to a parent path
+    pub(crate) fn key_path(self, parent: &str, key: &str) -> String {
+        match self {
+            PathFormat::Escaped if needs_quoting(key, parent.is_empty()) => {
+                let quoted = key.replace('\\', "\\\\").replace('"', "\\\"");
+                format!("{}[\"{}\"]", parent, quoted)
+            }
+            PathFormat::Dotted | PathFormat::Escaped if parent.is_empty() => key.to_string(),
+            PathFormat::Dotted | PathFormat::Escaped => format!("{}.{}", parent, key),
+            PathFormat::JsonPointer => format!("{}/{}", parent, escape_pointer_token(key)),
+        }
+    }
+
+    // Appends an array index to a parent path
+    pub(crate) fn index_path(self, parent: &str, index: usize) -> String {
+        match self {
+            PathFormat::Dotted | PathFormat::Escaped => format!("{}[{}]", parent, index),
+            PathFormat::JsonPointer => format!("{}/{}", parent, index),
+        }
+    }
+}
+
+// A single step of a path pattern
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum PatternSegment {
+    Key(String),
+    Index(usize),
+    // `*`: any one object key
+    AnyKey,
+    // `[*]`: any one array index
+    AnyIndex,
+    // `**`: any number of segments, including none
+    AnyDepth,
+}
+
+impl PatternSegment {
+    fn matches(&self, segment: &PathSegment) -> bool {
+        match (self, segment) {
+            (PatternSegment::Key(k), PathSegment::Key(key)) => k == key,
+            (PatternSegment::Index(i), PathSegment::Index(index)) => i == index,
+            (PatternSegment::AnyKey, PathSegment::Key(_)) => true,
+            (PatternSegment::AnyIndex, PathSegment::Index(_)) => true,
+            (PatternSegment::AnyDepth, _) => true,
+            _ => false,
+        }
+    }
+}
+
+/// A pattern matching a set of [`Path`]s, written like a dotted path with
+/// wildcards.
+///
+/// `*` matches any single key, `[*]` any single index and `**` any number
+/// of segments, including none; for example `metadata.timestamp`,
+/// `**.updated_at` or `items[*].etag`. A leading `$.` as in JSONPath is
+/// accepted and ignored. Use a quoted key such as `["*"]` to match a
+/// literal `*`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PathPattern(Vec<PatternSegment>);
+
+impl PathPattern {
+    /// Returns whether `path` matches this pattern exactly.
+    pub fn matches(&self, path: &Path) -> bool {
+        matches_from(&self.0, path.segments())
+    }
+
+    // Whether `path` or one of its ancestors matches this pattern
+    pub(crate) fn covers(&self, path: &Path) -> bool {
+        let segments = path.segments();
+        (0..=segments.len()).any(|len| matches_from(&self.0, &segments[..len]))
+    }
+
+    // A pattern matching exactly the root
+    pub(crate) fn root() -> PathPattern {
+        PathPattern(Vec::new())
+    }
+
+    // This pattern extended by the key `key`
+    pub(crate) fn child_key(&self, key: &str) -> PathPattern {
+        let mut segments = self.0.clone();
+        segments.push(PatternSegment::Key(key.to_string()));
+        PathPattern(segments)
+    }
+
+    // This pattern extended by any one index
+    pub(crate) fn child_any_index(&self) -> PathPattern {
+        let mut segments = self.0.clone();
+        segments.push(PatternSegment::AnyIndex);
+        PathPattern(segments)
+    }
+
+    // Whether some path below `path` could match this pattern
+    pub(crate) fn leads_through(&self, path: &Path) -> bool {
+        leads_from(&self.0, path.segments())
+    }
+}
+
+// Whether `path` can be extended into a path matching `pattern`
+fn leads_from(pattern: &[PatternSegment], path: &[PathSegment]) -> bool {
+    match (pattern.split_first(), path.split_first()) {
+        (_, None) => true,
+        (None, Some(_)) => false,
+        (Some((PatternSegment::AnyDepth, _)), Some(_)) => true,
+        (Some((segment, rest)), Some((first, tail))) => {
+            segment.matches(first) && leads_from(rest, tail)
+        }
+    }
+}
+
+// Matches pattern segments against path segments, backtracking over `**`
+fn matches_from(pattern: &[PatternSegment], path: &[PathSegment]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((PatternSegment::AnyDepth, rest)) => {
+            (0..=path.len()).any(|skip| matches_from(rest, &path[skip..]))
+        }
+        Some((segment, rest)) => path
+            .split_first()
+            .is_some_and(|(first, tail)| segment.matches(first) && matches_from(rest, tail)),
+    }
+}
+
+impl FromStr for PathPattern {
+    type Err = ParsePathError;
+
+    fn from_str(pattern: &str) -> Result<PathPattern, ParsePathError> {
+        let invalid = || ParsePathError(pattern.to_string());
+        let stripped = pattern
+            .strip_prefix('$')
+            .map_or(pattern, |rest| rest.strip_prefix('.').unwrap_or(rest));
+        let segments = tokenize(stripped)
+            .ok_or_else(invalid)?
+            .into_iter()
+            .map(|token| match token {
+                Token::Key(key) if key == "**" => Some(PatternSegment::AnyDepth),
+                Token::Key(key) if key == "*" => Some(PatternSegment::AnyKey),
+                Token::Key(key) | Token::Quoted(key) => Some(PatternSegment::Key(key)),
+                Token::Bracket(inner) if inner == "*" => Some(PatternSegment::AnyIndex),
+                Token::Bracket(inner) => inner.parse().ok().map(PatternSegment::Index),
+            })
+            .collect::<Option<_>>()
+            .ok_or_else(invalid)?;
+        Ok(PathPattern(segments))
+    }
+}
+
+impl fmt::Display for PathPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            let separator = if i > 0 { "." } else { "" };
+            match segment {
+                PatternSegment::Key(key) if key == "*" || key == "**" => {
+                    write!(f, "[\"{}\"]", key)?
+                }
+                PatternSegment::Key(key) => write!(f, "{}{}", separator, key)?,
+                PatternSegment::Index(index) => write!(f, "[{}]", index)?,
+                PatternSegment::AnyKey => write!(f, "{}*", separator)?,
+                PatternSegment::AnyIndex => f.write_str("[*]")?,
+                PatternSegment::AnyDepth => write!(f, "{}**", separator)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(key: &str) -> PathSegment {
+        PathSegment::Key(key.to_string())
+    }
+
+    /// Test parsing of dotted paths, including malformed indices.
+    #[test]
+    fn test_parse_dotted() {
+        assert_eq!("".parse(), Ok(Path::root()));
+        assert_eq!(
+            "items[0].id".parse(),
+            Ok(Path(vec![key("items"), PathSegment::Index(0), key("id")]))
+        );
+        assert_eq!(
+            "items[x]".parse::<Path>(),
+            Err(ParsePathError("items[x]".to_string()))
+        );
+    }
+
+    /// Test that JSON Pointers are parsed with their escapes undone.
+    #[test]
+    fn test_parse_pointer() {
+        assert_eq!(
+            "/a~1b/0/c~0d".parse(),
+            Ok(Path(vec![key("a/b"), key("0"), key("c~d")]))
+        );
+    }
+
+    /// Test that prefixes are compared segment by segment.
+    #[test]
+    fn test_starts_with() {
+        let path: Path = "items[0].name".parse().unwrap();
+        assert!(path.starts_with(&"items".parse().unwrap()));
+        assert!(path.starts_with(&"items[0].name".parse().unwrap()));
+        assert!(path.starts_with(&Path::root()));
+        assert!(!path.starts_with(&"item".parse().unwrap()));
+        assert!(!path.starts_with(&"items[1]".parse().unwrap()));
+    }
+
+    /// Test rendering typed paths in each format.
+    #[test]
+    fn test_render() {
+        let path = Path::root().child_key("a/b").child_index(2).child_key("c");
+        assert_eq!(path.to_string(), "a/b[2].c");
+        assert_eq!(path.to_pointer(), "/a~1b/2/c");
+        assert_eq!(Path::root().to_string(), "");
+        assert_eq!(Path::root().to_pointer(), "");
+    }
+
+    /// Test that ambiguous keys are quoted in the escaped format.
+    #[test]
+    fn test_render_escaped() {
+        let path = Path::root()
+            .child_key("a.b")
+            .child_key("items[0]")
+            .child_index(1)
+            .child_key("plain")
+            .child_key("say \"hi\"");
+        assert_eq!(
+            path.render(PathFormat::Escaped),
+            r#"["a.b"]["items[0]"][1].plain["say \"hi\""]"#
+        );
+        assert_eq!(path.to_string(), r#"a.b.items[0][1].plain.say "hi""#);
+    }
+
+    /// Test that escaped paths parse back into the same typed path.
+    #[test]
+    fn test_escaped_round_trip() {
+        let keys = [
+            "a.b",
+            "items[0]",
+            "",
+            "/root",
+            "back\\slash",
+            "q\"uote",
+            "]",
+            "x",
+        ];
+        for key in keys {
+            let path = Path::root().child_key(key).child_index(0).child_key(key);
+            let rendered = path.render(PathFormat::Escaped);
+            assert_eq!(rendered.parse(), Ok(path), "round trip of {}", rendered);
+        }
+    }
+
+    /// Test that unterminated quoted keys are rejected.
+    #[test]
+    fn test_parse_escaped_invalid() {
+        assert!(r#"["a.b"#.parse::<Path>().is_err());
+        assert!(r#"["a.b"x"#.parse::<Path>().is_err());
+    }
+
+    fn pattern(pattern: &str) -> PathPattern {
+        pattern.parse().unwrap()
+    }
+
+    fn path(path: &str) -> Path {
+        path.parse().unwrap()
+    }
+
+    /// Test that literal patterns match only the exact path.
+    #[test]
+    fn test_pattern_literal() {
+        assert!(pattern("metadata.timestamp").matches(&path("metadata.timestamp")));
+        assert!(!pattern("metadata.timestamp").matches(&path("metadata")));
+        assert!(!pattern("metadata").matches(&path("metadata.timestamp")));
+        assert!(pattern("items[2]").matches(&path("items[2]")));
+        assert!(pattern("").matches(&Path::root()));
+    }
+
+    /// Test single-segment wildcards.
+    #[test]
+    fn test_pattern_single_wildcards() {
+        let etag = pattern("items[*].etag");
+        assert!(etag.matches(&path("items[0].etag")));
+        assert!(etag.matches(&path("items[12].etag")));
+        assert!(!etag.matches(&path("items.x.etag")));
+        let any_key = pattern("*.id");
+        assert!(any_key.matches(&path("user.id")));
+        assert!(!any_key.matches(&path("[0].id")));
+        assert!(!any_key.matches(&path("a.b.id")));
+    }
+
+    /// Test that `**` matches any number of segments.
+    #[test]
+    fn test_pattern_any_depth() {
+        let updated = pattern("**.updated_at");
+        assert!(updated.matches(&path("updated_at")));
+        assert!(updated.matches(&path("a.updated_at")));
+        assert!(updated.matches(&path("a[3].b.updated_at")));
+        assert!(!updated.matches(&path("a.updated_at.x")));
+        let spec = pattern("spec.**");
+        assert!(spec.matches(&path("spec")));
+        assert!(spec.matches(&path("spec.a[0]")));
+        assert!(!spec.matches(&path("status")));
+    }
+
+    /// Test JSONPath prefixes, quoted literals and invalid patterns.
+    #[test]
+    fn test_pattern_parsing() {
+        assert_eq!(pattern("$.a.b"), pattern("a.b"));
+        assert!(pattern(r#"["*"]"#).matches(&path(r#"["*"]"#)));
+        assert!(!pattern(r#"["*"]"#).matches(&path("x")));
+        assert!("items[x]".parse::<PathPattern>().is_err());
+        for text in ["**.a[*].b", "a[0].*", r#"x["**"]"#] {
+            assert_eq!(pattern(text).to_string(), text);
+        }
+    }
+
+    /// Test the prefix checks used to restrict traversal.
+    #[test]
+    fn test_pattern_covers_and_leads_through() {
+        let replicas = pattern("status.replicas");
+        assert!(replicas.covers(&path("status.replicas")));
+        assert!(replicas.covers(&path("status.replicas.x[0]")));
+        assert!(!replicas.covers(&path("status")));
+        assert!(replicas.leads_through(&Path::root()));
+        assert!(replicas.leads_through(&path("status")));
+        assert!(!replicas.leads_through(&path("spec")));
+        let items = pattern("items[*].**.id");
+        assert!(items.leads_through(&path("items[3].a.b")));
+        assert!(!items.leads_through(&path("items.a")));
+    }
+
+    /// Test that trails assemble into the path they were built along.
+    #[test]
+    fn test_path_trail() {
+        let parent = PathTrail::root().child_key("items");
+        let first = parent.child_index(0).child_key("name");
+        let second = parent.child_index(1);
+        assert_eq!(first.to_path(), "items[0].name".parse().unwrap());
+        assert_eq!(second.to_path(), "items[1]".parse().unwrap());
+        assert_eq!(PathTrail::root().to_path(), Path::root());
+    }
+
+    /// Test that very long trails are dropped without overflowing the stack.
+    #[test]
+    fn test_path_trail_deep_drop() {
+        let mut trail = PathTrail::root();
+        for i in 0..200_000 {
+            trail = trail.child_index(i);
+        }
+        assert_eq!(trail.to_path().len(), 200_000);
+        drop(trail);
+    }
+}
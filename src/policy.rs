@@ -0,0 +1,162 @@
+//! Declarative allowlists of expected changes, for CI checks such as "only
+//! `version` and `build_time` may change between releases".
+
+use alloc::vec::Vec;
+
+use serde_json::Value;
+
+use crate::{DiffKind, DiffOptions, Difference, Path, PathPattern, deep_diff_with_options};
+
+/// The changes expected between two documents; anything else is
+/// unexpected.
+///
+/// ```
+/// use deep_diff::DiffPolicy;
+/// use serde_json::json;
+///
+/// let policy = DiffPolicy::new().allow("version").allow("build_time");
+/// let old = json!({"version": "1.0", "build_time": 1, "debug": false});
+/// let new = json!({"version": "1.1", "build_time": 2, "debug": true});
+/// let unexpected = policy.check(&old, &new);
+/// assert_eq!(unexpected.len(), 1);
+/// assert_eq!(unexpected[0].path, "debug");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DiffPolicy {
+    // Patterns whose values may change, optionally only in one way
+    rules: Vec<(PathPattern, Option<DiffKind>)>,
+    // Changes accepted from a baseline diff
+    baseline: Vec<(Path, DiffKind)>,
+}
+
+impl DiffPolicy {
+    /// Creates a policy that allows no changes.
+    pub fn new() -> Self {
+        DiffPolicy::default()
+    }
+
+    /// Creates a policy that allows the changes in `baseline`, such as the
+    /// diff between two earlier releases.
+    ///
+    /// A change is allowed if the baseline has a change of the same kind at
+    /// the same path, whatever the values involved.
+    pub fn from_baseline(baseline: &[Difference]) -> Self {
+        DiffPolicy {
+            rules: Vec::new(),
+            baseline: baseline
+                .iter()
+                .map(|diff| (diff.segments.clone(), diff.kind.clone()))
+                .collect(),
+        }
+    }
+
+    /// Allows any change to the values matching `pattern`, and to anything
+    /// inside them.
+    ///
+    /// See [`PathPattern`] for the syntax; patterns that fail to parse allow
+    /// nothing.
+    pub fn allow(mut self, pattern: impl AsRef<str>) -> Self {
+        if let Ok(pattern) = pattern.as_ref().parse() {
+            self.rules.push((pattern, None));
+        }
+        self
+    }
+
+    /// Allows only changes of `kind` to the values matching `pattern`, and
+    /// to anything inside them, e.g. new entries but no removals.
+    pub fn allow_kind(mut self, pattern: impl AsRef<str>, kind: DiffKind) -> Self {
+        if let Ok(pattern) = pattern.as_ref().parse() {
+            self.rules.push((pattern, Some(kind)));
+        }
+        self
+    }
+
+    /// Returns whether the policy allows `difference`.
+    pub fn is_allowed(&self, difference: &Difference) -> bool {
+        let kind_allowed =
+            |kind: &Option<DiffKind>| kind.as_ref().is_none_or(|k| *k == difference.kind);
+        self.rules
+            .iter()
+            .any(|(pattern, kind)| kind_allowed(kind) && pattern.covers(&difference.segments))
+            || self
+                .baseline
+                .iter()
+                .any(|(path, kind)| *kind == difference.kind && *path == difference.segments)
+    }
+
+    /// Returns the differences the policy does not allow, in their original
+    /// order.
+    pub fn unexpected(&self, differences: impl IntoIterator<Item = Difference>) -> Vec<Difference> {
+        differences
+            .into_iter()
+            .filter(|diff| !self.is_allowed(diff))
+            .collect()
+    }
+
+    /// Diffs two documents and returns the differences the policy does not
+    /// allow.
+    pub fn check(&self, a: &Value, b: &Value) -> Vec<Difference> {
+        self.check_with_options(a, b, &DiffOptions::default())
+    }
+
+    /// Like [`check`](DiffPolicy::check), diffing with the given options.
+    pub fn check_with_options(
+        &self,
+        a: &Value,
+        b: &Value,
+        options: &DiffOptions,
+    ) -> Vec<Difference> {
+        self.unexpected(deep_diff_with_options(a, b, options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deep_diff;
+    use serde_json::json;
+
+    fn paths(diffs: &[Difference]) -> Vec<&str> {
+        diffs.iter().map(|diff| diff.path.as_str()).collect()
+    }
+
+    /// Test that allowed patterns cover the values inside them.
+    #[test]
+    fn test_allow() {
+        let old = json!({"version": {"major": 1, "minor": 0}, "items": [{"etag": "a", "n": 1}]});
+        let new = json!({"version": {"major": 1, "minor": 1}, "items": [{"etag": "b", "n": 2}]});
+        let policy = DiffPolicy::new().allow("version").allow("items[*].etag");
+        assert_eq!(paths(&policy.check(&old, &new)), ["items[0].n"]);
+        assert_eq!(paths(&DiffPolicy::new().check(&old, &new)).len(), 3);
+        let invalid = DiffPolicy::new().allow("items[x]");
+        assert_eq!(invalid.check(&old, &new).len(), 3);
+    }
+
+    /// Test that kind-restricted rules only allow that kind of change.
+    #[test]
+    fn test_allow_kind() {
+        let policy = DiffPolicy::new().allow_kind("features.*", DiffKind::Added);
+        let old = json!({"features": {"a": true, "b": true}});
+        let new = json!({"features": {"a": false, "c": true}});
+        assert_eq!(
+            paths(&policy.check(&old, &new)),
+            ["features.a", "features.b"]
+        );
+    }
+
+    /// Test that a baseline allows the same kinds of change at the same paths.
+    #[test]
+    fn test_from_baseline() {
+        let baseline = deep_diff(
+            &json!({"version": "1.0", "notes": "x"}),
+            &json!({"version": "1.1"}),
+        );
+        let policy = DiffPolicy::from_baseline(&baseline);
+        let old = json!({"version": "1.1", "notes": "y", "debug": false});
+        let new = json!({"version": "1.2", "debug": true});
+        assert_eq!(paths(&policy.check(&old, &new)), ["debug"]);
+        let new = json!({"version": "1.2", "notes": "z", "debug": false});
+        assert_eq!(paths(&policy.check(&old, &new)), ["notes"]);
+        assert!(policy.is_allowed(&deep_diff(&json!({"version": 1}), &json!({"version": 2}))[0]));
+    }
+}
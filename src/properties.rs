@@ -0,0 +1,202 @@
+//! Property tests: invariants checked over many generated pairs of
+//! documents, under the options whose differences can be applied.
+//!
+//! Documents come from a seeded generator, so a failure names the case
+//! that reproduces it. `fuzz/` feeds the same invariants arbitrary input.
+
+use serde_json::{Map, Value, json};
+
+use crate::{
+    ArrayDiffStrategy, DiffOptions, apply_diff, deep_diff, deep_diff_with_options, from_json_patch,
+    invert_diff, to_json_patch,
+};
+
+// The number of generated pairs each property is checked against
+const CASES: u64 = 500;
+
+// A xorshift generator, enough to spread documents over their shapes
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn chance(&mut self, percent: u64) -> bool {
+        self.below(100) < percent
+    }
+}
+
+// Keys and strings come from small sets, so generated documents overlap
+const KEYS: [&str; 6] = ["a", "b", "id", "name", "x.y", "~/"];
+const STRINGS: [&str; 4] = ["", "alpha", "beta", "gamma delta"];
+
+// Generates a document nested at most `depth` levels deep
+fn value(rng: &mut Rng, depth: u32) -> Value {
+    let kinds = if depth == 0 { 4 } else { 6 };
+    match rng.below(kinds) {
+        0 => Value::Null,
+        1 => Value::Bool(rng.chance(50)),
+        2 => match rng.below(3) {
+            0 => json!(rng.below(5)),
+            1 => json!(-(rng.below(1000) as i64)),
+            _ => json!(rng.below(100) as f64 / 8.0),
+        },
+        3 => json!(STRINGS[rng.below(STRINGS.len() as u64) as usize]),
+        4 => (0..rng.below(5)).map(|_| value(rng, depth - 1)).collect(),
+        _ => (0..rng.below(5))
+            .map(|_| {
+                let key = KEYS[rng.below(KEYS.len() as u64) as usize];
+                (key.to_string(), value(rng, depth - 1))
+            })
+            .collect::<Map<String, Value>>()
+            .into(),
+    }
+}
+
+// Returns a copy of `value` with a few of its parts changed, so pairs are
+// usually similar rather than unrelated
+fn mutate(rng: &mut Rng, value: &Value, depth: u32) -> Value {
+    if rng.chance(15) {
+        return self::value(rng, depth);
+    }
+    match value {
+        Value::Array(values) => {
+            let mut values: Vec<Value> = values.iter().map(|v| mutate(rng, v, depth)).collect();
+            if rng.chance(30) {
+                let at = rng.below(values.len() as u64 + 1) as usize;
+                values.insert(at, self::value(rng, depth));
+            }
+            if !values.is_empty() && rng.chance(30) {
+                let at = rng.below(values.len() as u64) as usize;
+                values.remove(at);
+            }
+            if values.len() > 1 && rng.chance(20) {
+                values.rotate_left(1);
+            }
+            Value::Array(values)
+        }
+        Value::Object(map) => {
+            let mut map: Map<String, Value> = map
+                .iter()
+                .filter_map(|(key, v)| match rng.chance(15) {
+                    true => None,
+                    false => Some((key.clone(), mutate(rng, v, depth))),
+                })
+                .collect();
+            if rng.chance(30) {
+                let key = KEYS[rng.below(KEYS.len() as u64) as usize];
+                map.insert(key.to_string(), self::value(rng, depth));
+            }
+            Value::Object(map)
+        }
+        _ => value.clone(),
+    }
+}
+
+// Calls `check` with each generated pair and its case number
+fn for_pairs(mut check: impl FnMut(u64, &Value, &Value)) {
+    for case in 0..CASES {
+        let mut rng = Rng::new(case);
+        let a = value(&mut rng, 4);
+        let b = match rng.chance(20) {
+            true => value(&mut rng, 4),
+            false => mutate(&mut rng, &a, 3),
+        };
+        check(case, &a, &b);
+    }
+}
+
+// Options whose differences all carry what applying them needs
+fn applicable_options() -> Vec<DiffOptions> {
+    vec![
+        DiffOptions::default(),
+        DiffOptions::builder()
+            .array_strategy(ArrayDiffStrategy::Lcs)
+            .build(),
+        DiffOptions::builder().detect_moves(true).build(),
+        DiffOptions::builder().detect_renames(true).build(),
+        DiffOptions::builder()
+            .array_strategy(ArrayDiffStrategy::Lcs)
+            .detect_moves(true)
+            .rename_threshold(0.5)
+            .build(),
+    ]
+}
+
+/// Test that no document differs from itself.
+#[test]
+fn test_diff_with_self_is_empty() {
+    for_pairs(|case, a, _| {
+        assert!(deep_diff(a, a).is_empty(), "case {}: {}", case, a);
+    });
+}
+
+/// Test that applying the differences to the first document gives the
+/// second.
+#[test]
+fn test_apply_diff_round_trips() {
+    for options in applicable_options() {
+        for_pairs(|case, a, b| {
+            let diffs = deep_diff_with_options(a, b, &options);
+            assert_eq!(
+                apply_diff(a, &diffs).as_ref(),
+                Ok(b),
+                "case {}: {} -> {} with {:?}",
+                case,
+                a,
+                b,
+                options
+            );
+        });
+    }
+}
+
+/// Test that inverted differences take the second document back to the
+/// first, and invert back to the originals.
+#[test]
+fn test_invert_round_trips() {
+    for options in applicable_options() {
+        for_pairs(|case, a, b| {
+            let diffs = deep_diff_with_options(a, b, &options);
+            let inverted = invert_diff(&diffs);
+            assert_eq!(
+                apply_diff(b, &inverted).as_ref(),
+                Ok(a),
+                "case {}: {} -> {} with {:?}",
+                case,
+                a,
+                b,
+                options
+            );
+            assert_eq!(invert_diff(&inverted), diffs, "case {}", case);
+        });
+    }
+}
+
+/// Test that differences survive a round trip through a JSON Patch.
+#[test]
+fn test_json_patch_round_trips() {
+    for_pairs(|case, a, b| {
+        let patch = to_json_patch(&deep_diff(a, b));
+        let diffs = from_json_patch(&patch).unwrap();
+        assert_eq!(
+            apply_diff(a, &diffs).as_ref(),
+            Ok(b),
+            "case {}: {}",
+            case,
+            patch
+        );
+    });
+}
@@ -0,0 +1,119 @@
+//! Parsing of Kubernetes resource quantities such as `"500m"` or `"1Gi"`.
+
+use alloc::string::ToString;
+
+use serde_json::Value;
+
+// Binary suffixes and the power of two they stand for
+const BINARY: [(&str, u32); 6] = [
+    ("Ki", 10),
+    ("Mi", 20),
+    ("Gi", 30),
+    ("Ti", 40),
+    ("Pi", 50),
+    ("Ei", 60),
+];
+
+// Decimal suffixes and the power of ten they stand for
+const DECIMAL: [(&str, i32); 10] = [
+    ("n", -9),
+    ("u", -6),
+    ("m", -3),
+    ("k", 3),
+    ("M", 6),
+    ("G", 9),
+    ("T", 12),
+    ("P", 15),
+    ("E", 18),
+    ("", 0),
+];
+
+/// Parses a quantity, given as a string or a JSON number, into billionths
+/// of its unit, so `"1"`, `"1000m"` and `1` all parse to the same value.
+///
+/// Precision beyond a billionth is dropped, as Kubernetes does.
+pub(crate) fn parse_quantity(value: &Value) -> Option<i128> {
+    match value {
+        Value::String(text) => parse(text),
+        Value::Number(number) => parse(&number.to_string()),
+        _ => None,
+    }
+}
+
+fn parse(text: &str) -> Option<i128> {
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let end = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let (number, suffix) = text.split_at(end);
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if (whole.is_empty() && fraction.is_empty()) || fraction.contains('.') {
+        return None;
+    }
+    let mut mantissa: i128 = 0;
+    for digit in whole.bytes().chain(fraction.bytes()) {
+        mantissa = mantissa
+            .checked_mul(10)?
+            .checked_add(i128::from(digit - b'0'))?;
+    }
+    // The value is `mantissa * 10^exponent * 2^shift`, in billionths
+    let mut exponent = 9 - i32::try_from(fraction.len()).ok()?;
+    let mut shift = 0;
+    if let Some(&(_, power)) = BINARY.iter().find(|(name, _)| *name == suffix) {
+        shift = power;
+    } else if let Some(rest) = suffix.strip_prefix(['e', 'E'])
+        && !rest.is_empty()
+    {
+        exponent += rest.parse::<i32>().ok()?;
+    } else {
+        let &(_, power) = DECIMAL.iter().find(|(name, _)| *name == suffix)?;
+        exponent += power;
+    }
+    let mut value = mantissa.checked_mul(1i128.checked_shl(shift)?)?;
+    if exponent >= 0 {
+        value = value.checked_mul(10i128.checked_pow(exponent.try_into().ok()?)?)?;
+    } else {
+        value /= 10i128.checked_pow(exponent.unsigned_abs())?;
+    }
+    Some(if negative { -value } else { value })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Test that every suffix scales the number.
+    #[test]
+    fn test_parse_quantity() {
+        let billion = 1_000_000_000;
+        assert_eq!(parse_quantity(&json!("1")), Some(billion));
+        assert_eq!(parse_quantity(&json!("1000m")), Some(billion));
+        assert_eq!(parse_quantity(&json!(1)), Some(billion));
+        assert_eq!(parse_quantity(&json!(0.5)), parse_quantity(&json!("500m")));
+        assert_eq!(
+            parse_quantity(&json!("1Gi")),
+            parse_quantity(&json!("1024Mi"))
+        );
+        assert_eq!(parse_quantity(&json!("1k")), parse_quantity(&json!("1e3")));
+        assert_eq!(parse_quantity(&json!("1.5Ki")), Some(1536 * billion));
+        assert_eq!(parse_quantity(&json!("-2")), Some(-2 * billion));
+        assert_eq!(parse_quantity(&json!("1n")), Some(1));
+        assert_eq!(
+            parse_quantity(&json!("8Ei")),
+            Some(8 << 60).map(|v: i128| v * billion)
+        );
+    }
+
+    /// Test that malformed quantities are rejected.
+    #[test]
+    fn test_parse_quantity_invalid() {
+        for text in ["", "m", "1x", "1.2.3", "1e", "Gi", "1 Gi"] {
+            assert_eq!(parse_quantity(&json!(text)), None, "{}", text);
+        }
+        assert_eq!(parse_quantity(&json!(true)), None);
+    }
+}
@@ -0,0 +1,227 @@
+//! Parsing of raw JSON text with control over duplicate object keys.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt;
+
+use serde::de::{DeserializeSeed, Error, MapAccess, SeqAccess, Visitor};
+use serde_json::{Map, Number, Value};
+
+use crate::{DuplicateKeys, Path};
+
+// The key serde_json uses to pass numbers it keeps as text
+#[cfg(feature = "arbitrary-precision")]
+const NUMBER_TOKEN: &str = "$serde_json::private::Number";
+
+/// The keys of every object in a document in the order they were written,
+/// by the object's path.
+pub(crate) type KeyOrders = Vec<(Path, Vec<String>)>;
+
+/// Parses `text` as a single JSON document, handling repeated keys in an
+/// object according to `policy`.
+pub(crate) fn parse(text: &str, policy: DuplicateKeys) -> Result<Value, serde_json::Error> {
+    parse_with(text, policy, None)
+}
+
+/// Parses `text` like [`parse`], also returning the order of every
+/// object's keys, which `Value` does not keep.
+pub(crate) fn parse_ordered(
+    text: &str,
+    policy: DuplicateKeys,
+) -> Result<(Value, KeyOrders), serde_json::Error> {
+    let orders = RefCell::new(Vec::new());
+    let value = parse_with(text, policy, Some(&orders))?;
+    Ok((value, orders.into_inner()))
+}
+
+fn parse_with(
+    text: &str,
+    policy: DuplicateKeys,
+    orders: Option<&RefCell<KeyOrders>>,
+) -> Result<Value, serde_json::Error> {
+    let mut deserializer = serde_json::Deserializer::from_str(text);
+    let seed = ValueSeed {
+        policy,
+        orders,
+        path: Path::root(),
+    };
+    let value = seed.deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+// Builds a `Value`, applying the duplicate key policy to every object and
+// recording key orders if asked to
+struct ValueSeed<'a> {
+    policy: DuplicateKeys,
+    orders: Option<&'a RefCell<KeyOrders>>,
+    // The path of the value, only tracked when recording key orders
+    path: Path,
+}
+
+impl<'a> ValueSeed<'a> {
+    fn child(&self, path: impl FnOnce(&Path) -> Path) -> ValueSeed<'a> {
+        ValueSeed {
+            policy: self.policy,
+            orders: self.orders,
+            path: if self.orders.is_some() {
+                path(&self.path)
+            } else {
+                Path::root()
+            },
+        }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for ValueSeed<'_> {
+    type Value = Value;
+
+    fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ValueSeed<'_> {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("any JSON value")
+    }
+
+    fn visit_bool<E: Error>(self, value: bool) -> Result<Value, E> {
+        Ok(Value::Bool(value))
+    }
+
+    fn visit_i64<E: Error>(self, value: i64) -> Result<Value, E> {
+        Ok(Value::from(value))
+    }
+
+    fn visit_u64<E: Error>(self, value: u64) -> Result<Value, E> {
+        Ok(Value::from(value))
+    }
+
+    fn visit_f64<E: Error>(self, value: f64) -> Result<Value, E> {
+        Ok(Number::from_f64(value).map_or(Value::Null, Value::Number))
+    }
+
+    fn visit_str<E: Error>(self, value: &str) -> Result<Value, E> {
+        Ok(Value::from(value))
+    }
+
+    fn visit_string<E: Error>(self, value: String) -> Result<Value, E> {
+        Ok(Value::String(value))
+    }
+
+    fn visit_unit<E: Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut values = Vec::new();
+        while let Some(value) =
+            seq.next_element_seed(self.child(|path| path.child_index(values.len())))?
+        {
+            values.push(value);
+        }
+        Ok(Value::Array(values))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Value, A::Error> {
+        let mut map = Map::new();
+        let mut order = Vec::new();
+        while let Some(key) = access.next_key::<String>()? {
+            // serde_json hands over arbitrary precision numbers as a map
+            // with a single reserved key
+            #[cfg(feature = "arbitrary-precision")]
+            if map.is_empty() && key == NUMBER_TOKEN {
+                let number = access.next_value::<String>()?;
+                return number.parse().map(Value::Number).map_err(A::Error::custom);
+            }
+            let value = access.next_value_seed(self.child(|path| path.child_key(key.as_str())))?;
+            if !map.contains_key(&key) {
+                if self.orders.is_some() {
+                    order.push(key.clone());
+                }
+                map.insert(key, value);
+                continue;
+            }
+            match self.policy {
+                DuplicateKeys::LastWins => {
+                    map.insert(key, value);
+                }
+                DuplicateKeys::Error => {
+                    return Err(A::Error::custom(format!("duplicate key \"{}\"", key)));
+                }
+                // The nth occurrence is kept as `key#n`
+                DuplicateKeys::Preserve => {
+                    let renamed = (2..)
+                        .map(|n| format!("{}#{}", key, n))
+                        .find(|renamed| !map.contains_key(renamed))
+                        .expect("some suffix is unused");
+                    if self.orders.is_some() {
+                        order.push(renamed.clone());
+                    }
+                    map.insert(renamed, value);
+                }
+            }
+        }
+        if let Some(orders) = self.orders {
+            orders.borrow_mut().push((self.path, order));
+        }
+        Ok(Value::Object(map))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Test each policy on an object with a repeated key.
+    #[test]
+    fn test_parse_duplicate_keys() {
+        let text = r#"{"a": 1, "b": [{"c": 2, "c": 3}], "a": 4, "a": 5}"#;
+        assert_eq!(
+            parse(text, DuplicateKeys::LastWins).unwrap(),
+            json!({"a": 5, "b": [{"c": 3}]})
+        );
+        assert_eq!(
+            parse(text, DuplicateKeys::Preserve).unwrap(),
+            json!({"a": 1, "a#2": 4, "a#3": 5, "b": [{"c": 2, "c#2": 3}]})
+        );
+        let error = parse(text, DuplicateKeys::Error).unwrap_err();
+        assert!(error.to_string().starts_with("duplicate key \"c\""));
+    }
+
+    /// Test that key orders are recorded for every object, inner ones first.
+    #[test]
+    fn test_parse_ordered() {
+        let text = r#"{"z": [{"b": 1, "a": 2}], "y": {}, "z": 3}"#;
+        let (value, orders) = parse_ordered(text, DuplicateKeys::Preserve).unwrap();
+        assert_eq!(value, parse(text, DuplicateKeys::Preserve).unwrap());
+        let orders: Vec<_> = orders
+            .iter()
+            .map(|(path, keys)| (path.to_string(), keys.join(",")))
+            .collect();
+        assert_eq!(
+            orders,
+            [
+                ("z[0]".to_string(), "b,a".to_string()),
+                ("y".to_string(), String::new()),
+                (String::new(), "z,y,z#2".to_string())
+            ]
+        );
+    }
+
+    /// Test that values of every type parse as `serde_json` parses them.
+    #[test]
+    fn test_parse_values() {
+        let text = r#"[null, true, -1, 18446744073709551615, 1.5, "s\n", {}, []]"#;
+        let expected: Value = serde_json::from_str(text).unwrap();
+        assert_eq!(parse(text, DuplicateKeys::Error).unwrap(), expected);
+        assert!(parse("[1] 2", DuplicateKeys::LastWins).is_err());
+        assert!(parse("{\"a\": }", DuplicateKeys::LastWins).is_err());
+    }
+}
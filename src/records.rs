@@ -0,0 +1,471 @@
+//! Reconciliation of two datasets of records, paired by an identifier
+//! rather than by position.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use serde_json::Value;
+
+use crate::engine::deep_diff_with_options;
+use crate::options::{DEFAULT_OPTIONS, DiffOptions};
+use crate::render::csv_field;
+use crate::{DiffKind, Difference, PathSegment};
+
+// How the column of records replaced whole is displayed
+const WHOLE_RECORD: &str = "(record)";
+
+/// The records of two datasets reconciled by [`diff_record_sets`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RecordSetDiff {
+    /// The records only in the second dataset, in its order.
+    pub added: Vec<Value>,
+    /// The records only in the first dataset, in its order.
+    pub removed: Vec<Value>,
+    /// The records in both datasets that differ, in the order of the first.
+    pub changed: Vec<RecordDiff>,
+    /// The number of records in both datasets that are equal.
+    pub unchanged: usize,
+}
+
+impl RecordSetDiff {
+    /// Returns whether the datasets hold the same records.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Summarizes the changed records column by column, keeping up to
+    /// `examples` changed records as examples for each column.
+    ///
+    /// A column is a top-level field of the records; changes nested inside
+    /// a field count towards it.
+    ///
+    /// ```
+    /// use deep_diff::diff_record_sets;
+    /// use serde_json::json;
+    ///
+    /// let a = [json!({"id": 1, "price": 10}), json!({"id": 2, "price": 5})];
+    /// let b = [json!({"id": 1, "price": 12}), json!({"id": 2, "price": 6})];
+    /// let table = diff_record_sets(&a, &b, "id").table(1);
+    /// assert_eq!(table.columns[0].name, "price");
+    /// assert_eq!(table.columns[0].changed, 2);
+    /// assert!(table.render_text().contains("price: 2 changed\n  1: price: 10 -> 12\n"));
+    /// ```
+    pub fn table(&self, examples: usize) -> TableDiff {
+        let mut columns: BTreeMap<String, ColumnDiff> = BTreeMap::new();
+        for record in &self.changed {
+            // The record's differences in each column, in order
+            let mut by_column: BTreeMap<String, Vec<&Difference>> = BTreeMap::new();
+            for diff in &record.differences {
+                let name = match diff.segments.segments().first() {
+                    Some(PathSegment::Key(key)) => key.clone(),
+                    Some(PathSegment::Index(index)) => index.to_string(),
+                    None => String::new(),
+                };
+                by_column.entry(name).or_default().push(diff);
+            }
+            for (name, differences) in by_column {
+                let column = columns.entry(name.clone()).or_insert_with(|| ColumnDiff {
+                    name,
+                    ..ColumnDiff::default()
+                });
+                let whole = |kind: DiffKind| {
+                    differences
+                        .iter()
+                        .any(|diff| diff.segments.len() == 1 && diff.kind == kind)
+                };
+                if whole(DiffKind::Added) {
+                    column.added += 1;
+                } else if whole(DiffKind::Removed) {
+                    column.removed += 1;
+                } else {
+                    column.changed += 1;
+                }
+                if column.examples.len() < examples {
+                    column.examples.push(ColumnExample {
+                        key: record.key.clone(),
+                        differences: differences.into_iter().cloned().collect(),
+                    });
+                }
+            }
+        }
+        let mut columns: Vec<ColumnDiff> = columns.into_values().collect();
+        columns.sort_by_key(|column| core::cmp::Reverse(column.rows()));
+        TableDiff {
+            matched: self.changed.len() + self.unchanged,
+            changed: self.changed.len(),
+            added: self.added.len(),
+            removed: self.removed.len(),
+            columns,
+        }
+    }
+}
+
+/// A column-by-column summary of two tables of records, made by
+/// [`RecordSetDiff::table`], e.g. to validate a data migration.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TableDiff {
+    /// The number of records in both tables.
+    pub matched: usize,
+    /// The number of records in both tables that differ.
+    pub changed: usize,
+    /// The number of records only in the second table.
+    pub added: usize,
+    /// The number of records only in the first table.
+    pub removed: usize,
+    /// The columns that differ in any matched record, most often changed
+    /// first, then by name.
+    pub columns: Vec<ColumnDiff>,
+}
+
+/// How one column differs across the matched records of a [`TableDiff`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ColumnDiff {
+    /// The name of the column, or an empty string for records replaced
+    /// whole, e.g. by a value of another type.
+    pub name: String,
+    /// The number of records whose value in the column changed.
+    pub changed: usize,
+    /// The number of records that gained the column.
+    pub added: usize,
+    /// The number of records that lost the column.
+    pub removed: usize,
+    /// The first records that differ in the column, in the order of the
+    /// first table.
+    pub examples: Vec<ColumnExample>,
+}
+
+impl ColumnDiff {
+    /// Returns the number of records that differ in the column.
+    pub fn rows(&self) -> usize {
+        self.changed + self.added + self.removed
+    }
+
+    // The name as it is displayed
+    fn display_name(&self) -> &str {
+        if self.name.is_empty() {
+            WHOLE_RECORD
+        } else {
+            &self.name
+        }
+    }
+
+    // The non-zero counts, e.g. `2 changed, 1 added`
+    fn counts(&self) -> String {
+        let counts: Vec<String> = [
+            (self.changed, "changed"),
+            (self.added, "added"),
+            (self.removed, "removed"),
+        ]
+        .into_iter()
+        .filter(|&(count, _)| count > 0)
+        .map(|(count, label)| format!("{} {}", count, label))
+        .collect();
+        counts.join(", ")
+    }
+}
+
+/// A record that differs in a column, as an example for a [`ColumnDiff`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnExample {
+    /// The value of the record's key.
+    pub key: Value,
+    /// The record's differences in the column.
+    pub differences: Vec<Difference>,
+}
+
+impl ColumnExample {
+    // Each difference on one line as `key: path: before -> after`
+    fn lines(&self) -> impl Iterator<Item = String> + '_ {
+        let key = match &self.key {
+            Value::String(key) => key.clone(),
+            key => key.to_string(),
+        };
+        self.differences
+            .iter()
+            .map(move |diff| format!("{}: {}", key, diff))
+    }
+}
+
+impl TableDiff {
+    /// Renders the summary as plain text: a line of record counts, then
+    /// each column with its counts and examples.
+    ///
+    /// ```text
+    /// 4 records matched: 2 changed, 2 unchanged; 1 added, 0 removed
+    /// price: 2 changed
+    ///   1: price: 10 -> 12
+    ///   2: price: 5 -> 6
+    /// email: 1 added
+    ///   2: email: (none) -> "bo@example.com"
+    /// ```
+    pub fn render_text(&self) -> String {
+        let mut out = format!(
+            "{} records matched: {} changed, {} unchanged; {} added, {} removed\n",
+            self.matched,
+            self.changed,
+            self.matched - self.changed,
+            self.added,
+            self.removed
+        );
+        for column in &self.columns {
+            out.push_str(&format!("{}: {}\n", column.display_name(), column.counts()));
+            for line in column.examples.iter().flat_map(ColumnExample::lines) {
+                out.push_str(&format!("  {}\n", line));
+            }
+        }
+        out
+    }
+
+    /// Renders the columns as CSV with a header row, one row per column:
+    ///
+    /// ```text
+    /// column,changed,added,removed,examples
+    /// price,2,0,0,1: price: 10 -> 12; 2: price: 5 -> 6
+    /// email,0,1,0,"2: email: (none) -> ""bo@example.com"""
+    /// ```
+    ///
+    /// Fields are quoted as RFC 4180 asks, and lines end with CRLF.
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("column,changed,added,removed,examples\r\n");
+        for column in &self.columns {
+            let examples: Vec<String> = column
+                .examples
+                .iter()
+                .flat_map(ColumnExample::lines)
+                .collect();
+            out.push_str(&format!(
+                "{},{},{},{},{}\r\n",
+                csv_field(column.display_name()),
+                column.changed,
+                column.added,
+                column.removed,
+                csv_field(&examples.join("; "))
+            ));
+        }
+        out
+    }
+}
+
+/// A record present in both datasets whose fields differ.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordDiff {
+    /// The value of the record's key, e.g. `42` for `{"id": 42}`.
+    pub key: Value,
+    /// The differences between the two versions of the record, with paths
+    /// relative to the record, e.g. `"address.city"`.
+    pub differences: Vec<Difference>,
+}
+
+/// Pairs the records of two datasets by the value of their `key` field,
+/// e.g. `"id"`, and reports the records added, removed and changed.
+///
+/// Records are matched on the JSON text of their key, so `1` and `"1"` are
+/// different keys. Records sharing a key are paired in order, and records
+/// without the key cannot be paired, so they are reported as removed or
+/// added.
+///
+/// ```
+/// use deep_diff::diff_record_sets;
+/// use serde_json::json;
+///
+/// let a = [json!({"id": 1, "name": "Ann"}), json!({"id": 2, "name": "Bo"})];
+/// let b = [json!({"id": 2, "name": "Bob"}), json!({"id": 3, "name": "Cy"})];
+/// let diff = diff_record_sets(&a, &b, "id");
+/// assert_eq!(diff.removed, [json!({"id": 1, "name": "Ann"})]);
+/// assert_eq!(diff.added, [json!({"id": 3, "name": "Cy"})]);
+/// assert_eq!(diff.changed[0].key, json!(2));
+/// assert_eq!(diff.changed[0].differences[0].path, "name");
+/// ```
+pub fn diff_record_sets(a: &[Value], b: &[Value], key: &str) -> RecordSetDiff {
+    diff_record_sets_with_options(a, b, key, &DEFAULT_OPTIONS)
+}
+
+/// Like [`diff_record_sets`], diffing matched records with the given
+/// options.
+pub fn diff_record_sets_with_options(
+    a: &[Value],
+    b: &[Value],
+    key: &str,
+    options: &DiffOptions,
+) -> RecordSetDiff {
+    let id = |record: &Value| record.get(key).map(Value::to_string);
+    // The unmatched records of `b` by key, last first so they pop in order
+    let mut unmatched_b: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (j, record) in b.iter().enumerate().rev() {
+        if let Some(id) = id(record) {
+            unmatched_b.entry(id).or_default().push(j);
+        }
+    }
+    let mut matched_b = alloc::vec![false; b.len()];
+    let mut diff = RecordSetDiff::default();
+    for record in a {
+        let matched = id(record).and_then(|id| unmatched_b.get_mut(&id)?.pop());
+        let Some(j) = matched else {
+            diff.removed.push(record.clone());
+            continue;
+        };
+        matched_b[j] = true;
+        let differences = deep_diff_with_options(record, &b[j], options);
+        if differences.is_empty() {
+            diff.unchanged += 1;
+        } else {
+            diff.changed.push(RecordDiff {
+                key: record[key].clone(),
+                differences,
+            });
+        }
+    }
+    diff.added = b
+        .iter()
+        .zip(matched_b)
+        .filter(|(_, matched)| !matched)
+        .map(|(record, _)| record.clone())
+        .collect();
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use serde_json::json;
+
+    /// Test that records are paired by key whatever their position.
+    #[test]
+    fn test_diff_record_sets() {
+        let a = [
+            json!({"id": 1, "name": "Ann", "city": "Oslo"}),
+            json!({"id": 2, "name": "Bo"}),
+            json!({"id": 3, "name": "Cy"}),
+        ];
+        let b = [
+            json!({"id": 3, "name": "Cy"}),
+            json!({"id": 4, "name": "Di"}),
+            json!({"id": 1, "name": "Ann", "city": "Bergen"}),
+        ];
+        let diff = diff_record_sets(&a, &b, "id");
+        assert_eq!(diff.removed, [json!({"id": 2, "name": "Bo"})]);
+        assert_eq!(diff.added, [json!({"id": 4, "name": "Di"})]);
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].key, json!(1));
+        assert_eq!(diff.changed[0].differences[0].path, "city");
+        assert_eq!(diff.changed[0].differences[0].kind, DiffKind::Changed);
+        assert!(!diff.is_empty());
+        assert!(diff_record_sets(&a, &a, "id").is_empty());
+        assert!(diff_record_sets(&[], &[], "id").is_empty());
+    }
+
+    /// Test that duplicate and missing keys are handled predictably.
+    #[test]
+    fn test_diff_record_sets_unpaired() {
+        let a = [
+            json!({"sku": "x", "qty": 1}),
+            json!({"sku": "x", "qty": 2}),
+            json!({"qty": 3}),
+            json!({"sku": 1}),
+        ];
+        let b = [
+            json!({"sku": "x", "qty": 1}),
+            json!({"qty": 3}),
+            json!({"sku": "1"}),
+        ];
+        let diff = diff_record_sets(&a, &b, "sku");
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(
+            diff.removed,
+            [
+                json!({"sku": "x", "qty": 2}),
+                json!({"qty": 3}),
+                json!({"sku": 1})
+            ]
+        );
+        assert_eq!(diff.added, [json!({"qty": 3}), json!({"sku": "1"})]);
+    }
+
+    /// Test that matched records are diffed with the options.
+    #[test]
+    fn test_diff_record_sets_with_options() {
+        let options = DiffOptions::builder().ignore("updated").build();
+        let a = [json!({"id": 1, "updated": "mon", "n": 1})];
+        let b = [json!({"id": 1, "updated": "tue", "n": 1})];
+        let diff = diff_record_sets_with_options(&a, &b, "id", &options);
+        assert!(diff.is_empty());
+        assert_eq!(diff.unchanged, 1);
+    }
+
+    // Two tables of products where prices and stock changed
+    fn tables() -> (Vec<Value>, Vec<Value>) {
+        let a = vec![
+            json!({"id": 1, "price": 10, "stock": {"a": 1}}),
+            json!({"id": 2, "price": 5}),
+            json!({"id": "x", "price": 1}),
+            json!({"id": 4, "price": 7}),
+        ];
+        let b = vec![
+            json!({"id": 1, "price": 12, "stock": {"a": 2}}),
+            json!({"id": 2, "price": 6, "email": "bo@example.com"}),
+            json!({"id": "x", "price": 1}),
+            json!({"id": 5, "price": 9}),
+        ];
+        (a, b)
+    }
+
+    /// Test that the table counts changes per column.
+    #[test]
+    fn test_table() {
+        let (a, b) = tables();
+        let table = diff_record_sets(&a, &b, "id").table(1);
+        assert_eq!((table.matched, table.changed), (3, 2));
+        assert_eq!((table.added, table.removed), (1, 1));
+        let names: Vec<&str> = table.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["price", "email", "stock"]);
+        assert_eq!(table.columns[0].rows(), 2);
+        assert_eq!(table.columns[0].examples.len(), 1);
+        assert_eq!(table.columns[1].added, 1);
+        assert_eq!(table.columns[2].changed, 1);
+        assert_eq!(table.columns[2].examples[0].differences[0].path, "stock.a");
+        assert!(
+            diff_record_sets(&a, &b, "id").table(0).columns[0]
+                .examples
+                .is_empty()
+        );
+        let replaced = diff_record_sets(&[json!(1)], &[json!([1])], "id");
+        assert!(replaced.table(1).columns.is_empty());
+        let whole = RecordSetDiff {
+            changed: vec![RecordDiff {
+                key: json!(1),
+                differences: crate::deep_diff(&json!(1), &json!("1")),
+            }],
+            ..RecordSetDiff::default()
+        };
+        assert_eq!(whole.table(1).columns[0].display_name(), "(record)");
+    }
+
+    /// Test the text and CSV renderings of a table.
+    #[test]
+    fn test_table_render() {
+        let (a, b) = tables();
+        let table = diff_record_sets(&a, &b, "id").table(2);
+        assert_eq!(
+            table.render_text(),
+            "3 records matched: 2 changed, 1 unchanged; 1 added, 1 removed\n\
+             price: 2 changed\n\
+             \x20 1: price: 10 -> 12\n\
+             \x20 2: price: 5 -> 6\n\
+             email: 1 added\n\
+             \x20 2: email: (none) -> \"bo@example.com\"\n\
+             stock: 1 changed\n\
+             \x20 1: stock.a: 1 -> 2\n"
+        );
+        assert_eq!(
+            table.to_csv(),
+            "column,changed,added,removed,examples\r\n\
+             price,2,0,0,1: price: 10 -> 12; 2: price: 5 -> 6\r\n\
+             email,0,1,0,\"2: email: (none) -> \"\"bo@example.com\"\"\"\r\n\
+             stock,1,0,0,1: stock.a: 1 -> 2\r\n"
+        );
+    }
+}
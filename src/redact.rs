@@ -0,0 +1,184 @@
+//! Redaction of sensitive values from the differences reported.
+
+use alloc::borrow::Cow;
+
+use serde_json::Value;
+
+use crate::{Path, PathPattern};
+
+/// The placeholder standing in for values at paths given to
+/// [`redact`](crate::DiffOptionsBuilder::redact).
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Replaces `value`, found at `path`, with [`REDACTED`] if a pattern covers
+/// the path, or otherwise replaces the values inside it that a pattern
+/// matches. Returns whether anything was replaced.
+pub(crate) fn redact(value: &mut Cow<'_, Value>, path: &Path, patterns: &[PathPattern]) -> bool {
+    if patterns.iter().any(|pattern| pattern.covers(path)) {
+        *value = Cow::Owned(Value::String(REDACTED.into()));
+        return true;
+    }
+    if !patterns.iter().any(|pattern| pattern.leads_through(path)) {
+        return false;
+    }
+    redact_inside(value.to_mut(), path, patterns)
+}
+
+// Replaces the values below `path` that a pattern matches, descending only
+// where a pattern may still match, and returns whether any were replaced
+fn redact_inside(value: &mut Value, path: &Path, patterns: &[PathPattern]) -> bool {
+    let visit = |child: &mut Value, path: Path| {
+        if patterns.iter().any(|pattern| pattern.matches(&path)) {
+            *child = Value::String(REDACTED.into());
+            true
+        } else if patterns.iter().any(|pattern| pattern.leads_through(&path)) {
+            redact_inside(child, &path, patterns)
+        } else {
+            false
+        }
+    };
+    match value {
+        Value::Object(map) => map
+            .iter_mut()
+            .map(|(key, child)| visit(child, path.child_key(key.as_str())))
+            .fold(false, |redacted, replaced| redacted | replaced),
+        Value::Array(values) => values
+            .iter_mut()
+            .enumerate()
+            .map(|(index, child)| visit(child, path.child_index(index)))
+            .fold(false, |redacted, replaced| redacted | replaced),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ApplyError, DiffOptions, apply_diff, deep_diff_with_options, to_json_patch};
+    use alloc::vec::Vec;
+    use serde_json::json;
+
+    fn patterns(patterns: &[&str]) -> Vec<PathPattern> {
+        patterns.iter().map(|p| p.parse().unwrap()).collect()
+    }
+
+    /// Test that matching values are replaced, at the path or inside it.
+    #[test]
+    fn test_redact() {
+        let patterns = patterns(&["**.password", "keys[*].secret"]);
+        let redacted = |value: &Value, path: &str| {
+            let mut value = Cow::Borrowed(value);
+            let replaced = redact(&mut value, &path.parse().unwrap(), &patterns);
+            (value.into_owned(), replaced)
+        };
+        let user = json!({"name": "Al", "password": "hunter2", "auth": {"password": "x"}});
+        assert_eq!(
+            redacted(&user, "user"),
+            (
+                json!({"name": "Al", "password": REDACTED, "auth": {"password": REDACTED}}),
+                true
+            )
+        );
+        assert_eq!(
+            redacted(&json!("hunter2"), "user.password"),
+            (json!(REDACTED), true)
+        );
+        let keys = json!([{"id": 1, "secret": "s"}]);
+        assert_eq!(
+            redacted(&keys, "keys"),
+            (json!([{"id": 1, "secret": REDACTED}]), true)
+        );
+        // A path a pattern leads through, with nothing to replace below it
+        assert_eq!(
+            redacted(&json!([{"id": 1}]), "keys"),
+            (json!([{"id": 1}]), false)
+        );
+        let mut value = Cow::Borrowed(&keys);
+        let path: Path = "other".parse().unwrap();
+        assert!(!redact(&mut value, &path, &patterns[1..]));
+        assert!(matches!(value, Cow::Borrowed(_)));
+    }
+
+    /// Test that changes to redacted values are reported without them.
+    #[test]
+    fn test_options_redact() {
+        let options = DiffOptions::builder()
+            .redact(["**.password", "**.token"])
+            .context(1)
+            .build();
+        let a = json!({"user": {"name": "Al", "password": "hunter2", "token": "t"}});
+        let b = json!({"user": {"name": "Bo", "password": "hunter3", "token": "t"}});
+        let diffs = deep_diff_with_options(&a, &b, &options);
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[1].path, "user.password");
+        assert_eq!(diffs[1].before, Some(json!(REDACTED)));
+        assert_eq!(diffs[1].after, Some(json!(REDACTED)));
+        // The unchanged token is shown as context, redacted
+        assert_eq!(diffs[0].context, Some(json!({"token": REDACTED})));
+        let added = deep_diff_with_options(&json!({}), &b, &options);
+        assert_eq!(
+            added[0].after,
+            Some(json!({"name": "Bo", "password": REDACTED, "token": REDACTED}))
+        );
+        let same = json!({"password": "x"});
+        assert!(deep_diff_with_options(&same, &same, &options).is_empty());
+    }
+
+    /// Test that a key renamed away from a redacted path stays redacted.
+    #[test]
+    fn test_redact_renamed() {
+        let options = DiffOptions::builder()
+            .redact(["token"])
+            .detect_renames(true)
+            .build();
+        let diffs = deep_diff_with_options(
+            &json!({"token": "abcdef"}),
+            &json!({"tok": "abcdef"}),
+            &options,
+        );
+        assert_eq!(diffs.len(), 1);
+        assert!(matches!(diffs[0].kind, crate::DiffKind::Renamed { .. }));
+        assert_eq!(diffs[0].before, Some(json!(REDACTED)));
+        assert_eq!(diffs[0].after, Some(json!(REDACTED)));
+    }
+
+    /// Test that redacted values are neither applied nor written to patches.
+    #[test]
+    fn test_redact_apply() {
+        let options = DiffOptions::builder().redact(["**.password"]).build();
+        let a = json!({"user": {"name": "Al", "password": "a"}});
+        let b = json!({"user": {"name": "Al", "password": "b"}, "admin": {"password": "c"}});
+        let diffs = deep_diff_with_options(&a, &b, &options);
+        for diff in &diffs {
+            assert_eq!(
+                apply_diff(&a, core::slice::from_ref(diff)),
+                Err(ApplyError::ValueNotCaptured(diff.path.clone()))
+            );
+        }
+        assert_eq!(to_json_patch(&diffs), json!([]));
+        // Removals write nothing, so they still apply
+        let removed = deep_diff_with_options(&b, &a, &options);
+        let removal = removed.iter().find(|diff| diff.path == "admin").unwrap();
+        assert_eq!(
+            apply_diff(&b, core::slice::from_ref(removal)).unwrap()["admin"],
+            Value::Null
+        );
+    }
+
+    /// Test that real data equal to the placeholder is applied like any other.
+    #[test]
+    fn test_real_redacted_string() {
+        let a = json!({"note": "x"});
+        let b = json!({"note": REDACTED, "list": [{"x": REDACTED}]});
+        let diffs = crate::deep_diff(&a, &b);
+        assert!(diffs.iter().all(|diff| diff.withheld.is_none()));
+        assert_eq!(apply_diff(&a, &diffs), Ok(b));
+        assert_eq!(
+            to_json_patch(&diffs),
+            json!([
+                {"op": "replace", "path": "/note", "value": REDACTED},
+                {"op": "add", "path": "/list", "value": [{"x": REDACTED}]},
+            ])
+        );
+    }
+}
@@ -0,0 +1,162 @@
+//! Self-contained HTML reports.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use serde_json::Value;
+
+use super::kind_name;
+use crate::{Difference, PathSegment};
+
+// Stylesheet embedded in HTML reports
+const HTML_STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; }
+ul.tree { list-style: none; padding-left: 1.2em; }
+summary { cursor: pointer; font-family: monospace; }
+.diff { margin: 0.4em 0; border-left: 4px solid; padding-left: 0.6em; }
+.added { border-color: #2da44e; }
+.removed { border-color: #cf222e; }
+.changed, .type-changed, .moved { border-color: #bf8700; }
+.kind { font-weight: bold; font-size: 0.85em; text-transform: uppercase; }
+table.panes { border-collapse: collapse; width: 100%; table-layout: fixed; }
+table.panes th, table.panes td { border: 1px solid #d0d7de; padding: 0.3em; vertical-align: top; text-align: left; }
+td.before { background: #ffebe9; }
+td.after { background: #dafbe1; }
+pre { margin: 0; white-space: pre-wrap; word-break: break-all; }
+";
+
+// Escapes text for inclusion in HTML
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// A node of the tree of paths shown in an HTML report
+#[derive(Default)]
+struct TreeNode<'a> {
+    children: Vec<(String, TreeNode<'a>)>,
+    differences: Vec<&'a Difference>,
+}
+
+impl<'a> TreeNode<'a> {
+    fn insert(&mut self, diff: &'a Difference) {
+        let mut node = self;
+        for segment in diff.segments.segments() {
+            let label = match segment {
+                PathSegment::Key(key) => key.clone(),
+                PathSegment::Index(index) => format!("[{}]", index),
+            };
+            let position = match node.children.iter().position(|(l, _)| *l == label) {
+                Some(position) => position,
+                None => {
+                    node.children.push((label, TreeNode::default()));
+                    node.children.len() - 1
+                }
+            };
+            node = &mut node.children[position].1;
+        }
+        node.differences.push(diff);
+    }
+
+    fn count(&self) -> usize {
+        self.differences.len() + self.children.iter().map(|(_, c)| c.count()).sum::<usize>()
+    }
+
+    fn render(&self, out: &mut String) {
+        for diff in &self.differences {
+            let (class, label) = kind_name(&diff.kind);
+            let pane = |value: &Option<Value>| match value {
+                Some(value) => escape_html(&serde_json::to_string_pretty(value).unwrap()),
+                None => String::new(),
+            };
+            writeln!(
+                out,
+                "<div class=\"diff {}\"><span class=\"kind\">{}</span>\
+                 <table class=\"panes\"><tr><th>Before</th><th>After</th></tr>\
+                 <tr><td class=\"before\"><pre>{}</pre></td>\
+                 <td class=\"after\"><pre>{}</pre></td></tr></table></div>",
+                class,
+                escape_html(&label),
+                pane(&diff.before),
+                pane(&diff.after)
+            )
+            .unwrap();
+        }
+        if self.children.is_empty() {
+            return;
+        }
+        out.push_str("<ul class=\"tree\">\n");
+        for (label, child) in &self.children {
+            writeln!(
+                out,
+                "<li><details open><summary>{} ({})</summary>",
+                escape_html(label),
+                child.count()
+            )
+            .unwrap();
+            child.render(out);
+            out.push_str("</details></li>\n");
+        }
+        out.push_str("</ul>\n");
+    }
+}
+
+/// Renders differences as a self-contained HTML report, with a collapsible
+/// tree of the changed paths and the values before and after each change
+/// shown side by side.
+pub fn render_html(differences: &[Difference]) -> String {
+    let mut tree = TreeNode::default();
+    for diff in differences {
+        tree.insert(diff);
+    }
+    let mut out = String::new();
+    write!(
+        out,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Diff report</title>\n<style>\n{}</style>\n</head>\n<body>\n\
+         <h1>Diff report</h1>\n<p>{} difference{}</p>\n",
+        HTML_STYLE,
+        differences.len(),
+        if differences.len() == 1 { "" } else { "s" }
+    )
+    .unwrap();
+    tree.render(&mut out);
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deep_diff;
+    use serde_json::json;
+
+    /// Test that the HTML report nests paths and shows both values.
+    #[test]
+    fn test_render_html() {
+        let a = json!({"user": {"name": "<Al>", "tags": ["a"]}, "n": 1});
+        let b = json!({"user": {"name": "Bo", "tags": ["a", "b"]}, "n": 1});
+        let html = render_html(&deep_diff(&a, &b));
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<p>2 differences</p>"));
+        assert!(html.contains("<summary>user (2)</summary>"));
+        assert!(html.contains("<summary>name (1)</summary>"));
+        assert!(html.contains("<summary>[1] (1)</summary>"));
+        assert!(html.contains("<pre>&quot;&lt;Al&gt;&quot;</pre>"));
+        assert!(html.contains("<div class=\"diff added\">"));
+        assert!(!html.contains("<Al>"));
+        assert!(html.ends_with("</html>\n"));
+    }
+}
@@ -0,0 +1,79 @@
+//! Markdown tables, for pull request comments.
+
+use alloc::format;
+use alloc::string::String;
+use core::fmt::Write;
+
+use super::{display_path, display_value, kind_name};
+use crate::{DiffKind, Difference};
+
+// Formats text as a Markdown code span that is safe inside a table cell
+fn markdown_code(text: &str) -> String {
+    if text.is_empty() {
+        return String::new();
+    }
+    let text = text.replace('|', "\\|");
+    // A code span must be fenced by more backticks than it contains in a row
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest + 1);
+    let pad = if longest > 0 { " " } else { "" };
+    format!("{fence}{pad}{text}{pad}{fence}")
+}
+
+// The label of a kind of difference, with a renamed key as a code span so
+// it shows as written and cannot break the table
+fn kind_label(kind: &DiffKind) -> String {
+    match kind {
+        DiffKind::Renamed { from_key, .. } => {
+            format!("renamed from {}", markdown_code(from_key))
+        }
+        kind => kind_name(kind).1,
+    }
+}
+
+/// Renders differences as a Markdown table with one row per difference,
+/// suitable for posting in pull request comments:
+///
+/// ```text
+/// | Path | Before | After | Kind |
+/// | --- | --- | --- | --- |
+/// | `name` | `"Alice"` | `"Bob"` | changed |
+/// ```
+pub fn render_markdown(differences: &[Difference]) -> String {
+    let mut out = String::from("| Path | Before | After | Kind |\n| --- | --- | --- | --- |\n");
+    for diff in differences {
+        writeln!(
+            out,
+            "| {} | {} | {} | {} |",
+            markdown_code(display_path(diff)),
+            markdown_code(&display_value(&diff.before)),
+            markdown_code(&display_value(&diff.after)),
+            kind_label(&diff.kind)
+        )
+        .unwrap();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deep_diff;
+    use serde_json::json;
+
+    /// Test the Markdown table rendering.
+    #[test]
+    fn test_render_markdown() {
+        let a = json!({"name": "Alice", "cmd": "a|b", "tick": "`x`"});
+        let b = json!({"name": "Bob", "tick": "y", "list": [1]});
+        assert_eq!(
+            render_markdown(&deep_diff(&a, &b)),
+            "| Path | Before | After | Kind |\n\
+             | --- | --- | --- | --- |\n\
+             | `cmd` | `\"a\\|b\"` |  | removed |\n\
+             | `name` | `\"Alice\"` | `\"Bob\"` | changed |\n\
+             | `tick` | `` \"`x`\" `` | `\"y\"` | changed |\n\
+             | `list` |  | `[1]` | added |\n"
+        );
+    }
+}
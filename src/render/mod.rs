@@ -0,0 +1,180 @@
+//! Human-readable renderings of diffs.
+
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::{String, ToString};
+
+use serde_json::Value;
+
+use crate::{DiffKind, Difference};
+
+mod html;
+mod markdown;
+mod side_by_side;
+mod text;
+
+pub use html::render_html;
+pub use markdown::render_markdown;
+pub use side_by_side::render_side_by_side;
+#[cfg(feature = "std")]
+pub use text::render_colored;
+pub use text::{Color, ColorScheme, render_text};
+
+// How the root path is shown, since it renders as an empty string
+const ROOT: &str = "(root)";
+
+// How a missing value is shown by `Display`
+const NONE: &str = "(none)";
+
+// The longest value `Display` shows when no precision is given
+const DISPLAY_MAX_LEN: usize = 40;
+
+// The path of a difference as it is displayed
+fn display_path(diff: &Difference) -> &str {
+    if diff.path.is_empty() {
+        ROOT
+    } else {
+        &diff.path
+    }
+}
+
+// A value rendered as compact JSON
+fn display_value(value: &Option<Value>) -> String {
+    value.as_ref().map_or_else(String::new, Value::to_string)
+}
+
+// The CSS class and label of a kind of difference
+fn kind_name(kind: &DiffKind) -> (&'static str, String) {
+    match kind {
+        DiffKind::Added => ("added", "added".to_string()),
+        DiffKind::Removed => ("removed", "removed".to_string()),
+        DiffKind::Changed => ("changed", "changed".to_string()),
+        DiffKind::TypeChanged { from, to } => (
+            "type-changed",
+            format!("type changed from {} to {}", from, to),
+        ),
+        DiffKind::Moved { from, to } => ("moved", format!("moved from {} to {}", from, to)),
+        DiffKind::BinaryChanged {
+            old_len, new_len, ..
+        } => (
+            "changed",
+            format!("binary changed from {} to {} bytes", old_len, new_len),
+        ),
+        DiffKind::KeyOrderChanged => ("moved", "key order changed".to_string()),
+        DiffKind::Renamed { from_key, .. } => ("moved", format!("renamed from {}", from_key)),
+    }
+}
+
+/// Renders a difference on one line as `path: before -> after`, with each
+/// value as compact JSON, or `(none)` when missing:
+///
+/// ```text
+/// name: "Alice" -> "Bob"
+/// email: (none) -> "bob@example.com"
+/// items[2]: moved from index 0
+/// ```
+///
+/// Values longer than 40 characters are cut short with `…`; the precision
+/// sets another limit, so `format!("{diff:.10}")` keeps at most 10.
+impl core::fmt::Display for Difference {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let path = display_path(self);
+        match &self.kind {
+            DiffKind::Moved { from, .. } => write!(f, "{}: moved from index {}", path, from),
+            DiffKind::Renamed { from_key, .. } => write!(f, "{}: renamed from {}", path, from_key),
+            DiffKind::BinaryChanged {
+                old_len, new_len, ..
+            } => write!(
+                f,
+                "{}: binary changed from {} to {} bytes",
+                path, old_len, new_len
+            ),
+            _ => {
+                let max_len = f.precision().unwrap_or(DISPLAY_MAX_LEN);
+                let value = |value: &Option<Value>| match value {
+                    Some(value) => truncate(value.to_string(), max_len),
+                    None => NONE.to_string(),
+                };
+                write!(
+                    f,
+                    "{}: {} -> {}",
+                    path,
+                    value(&self.before),
+                    value(&self.after)
+                )
+            }
+        }
+    }
+}
+
+// Cuts `text` to at most `max_len` characters, ending with `…` if shortened
+pub(crate) fn truncate(mut text: String, max_len: usize) -> String {
+    if let Some((end, _)) = text.char_indices().nth(max_len) {
+        let kept = text[..end]
+            .char_indices()
+            .last()
+            .map_or(0, |(last, _)| last);
+        text.truncate(if max_len == 0 { 0 } else { kept });
+        text.push('…');
+    }
+    text
+}
+
+// A CSV field, quoted as RFC 4180 asks if it holds a separator, quote or
+// line break
+pub(crate) fn csv_field(text: &str) -> Cow<'_, str> {
+    if text.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", text.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ArrayDiffStrategy, DiffOptions, deep_diff, deep_diff_with_options};
+    use serde_json::json;
+
+    /// Test the one-line rendering of each kind of difference.
+    #[test]
+    fn test_display() {
+        let a = json!({"name": "Alice", "age": 30, "items": ["x", "y"]});
+        let b = json!({"name": "Bob", "email": "b@x.io", "items": ["x", "y", "z"]});
+        let lines: Vec<String> = deep_diff(&a, &b).iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            lines,
+            [
+                "age: 30 -> (none)",
+                "items[2]: (none) -> \"z\"",
+                "name: \"Alice\" -> \"Bob\"",
+                "email: (none) -> \"b@x.io\""
+            ]
+        );
+        let diffs = deep_diff(&json!(1), &json!({"a": "x".repeat(50)}));
+        assert_eq!(format!("{:.8}", diffs[0]), "(root): 1 -> {\"a\":\"x…");
+        assert_eq!(
+            diffs[0].to_string().chars().count(),
+            "(root): 1 -> ".len() + 40
+        );
+
+        let options = DiffOptions::builder()
+            .array_strategy(ArrayDiffStrategy::Lcs)
+            .detect_moves(true)
+            .build();
+        let diffs = deep_diff_with_options(&json!([1, 2, 3]), &json!([3, 1, 2]), &options);
+        assert_eq!(diffs[0].to_string(), "[0]: moved from index 2");
+    }
+
+    /// Test that values are cut at a character boundary.
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("héllo".to_string(), 5), "héllo");
+        assert_eq!(truncate("héllo".to_string(), 3), "hé…");
+        assert_eq!(truncate("héllo".to_string(), 1), "…");
+        assert_eq!(truncate("héllo".to_string(), 0), "…");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("plain"), "plain");
+    }
+}
@@ -0,0 +1,210 @@
+//! Two documents pretty-printed side by side.
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeSet as HashSet;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+#[cfg(feature = "std")]
+use std::collections::HashSet;
+
+use serde_json::{Map, Value};
+
+use crate::{Difference, Path, PathSegment};
+
+// One row of a side-by-side rendering
+struct Row {
+    left: Option<String>,
+    right: Option<String>,
+    changed: bool,
+}
+
+// Pretty-prints `value` as indented lines, the first one after `label`
+fn pretty_lines(value: &Value, label: &str, indent: usize, comma: bool) -> Vec<String> {
+    let pretty = serde_json::to_string_pretty(value).unwrap();
+    let pad = " ".repeat(indent);
+    let mut lines: Vec<String> = pretty
+        .lines()
+        .enumerate()
+        .map(|(i, line)| match i {
+            0 => format!("{pad}{label}{line}"),
+            _ => format!("{pad}{line}"),
+        })
+        .collect();
+    if comma && let Some(last) = lines.last_mut() {
+        last.push(',');
+    }
+    lines
+}
+
+// Lays out two documents side by side, aligning them by structure
+struct SideBySide<'a> {
+    changed: HashSet<&'a [PathSegment]>,
+    rows: Vec<Row>,
+}
+
+impl SideBySide<'_> {
+    // Whether a difference was reported at `path` or one of its parents
+    fn covered(&self, path: &Path) -> bool {
+        let segments = path.segments();
+        (0..=segments.len()).any(|len| self.changed.contains(&segments[..len]))
+    }
+
+    // Lays out the values at one path; `None` means the side has no value
+    fn emit(
+        &mut self,
+        (a, b): (Option<&Value>, Option<&Value>),
+        label: &str,
+        path: Path,
+        indent: usize,
+        (a_comma, b_comma): (bool, bool),
+    ) {
+        let changed = self.covered(&path);
+        let pad = " ".repeat(indent);
+        let line = |open: &str, comma: bool| {
+            let comma = if comma { "," } else { "" };
+            format!("{pad}{open}{comma}")
+        };
+        match (a, b) {
+            (Some(Value::Object(a_map)), Some(Value::Object(b_map))) if !changed && a != b => {
+                self.rows.push(Row {
+                    left: Some(format!("{pad}{label}{{")),
+                    right: Some(format!("{pad}{label}{{")),
+                    changed: false,
+                });
+                let keys = a_map
+                    .keys()
+                    .chain(b_map.keys().filter(|k| !a_map.contains_key(*k)));
+                let not_last = |map: &Map<String, Value>, key: &str| {
+                    map.keys()
+                        .position(|k| k == key)
+                        .is_some_and(|i| i + 1 < map.len())
+                };
+                for key in keys {
+                    let label = format!("{}: ", Value::from(key.as_str()));
+                    let commas = (not_last(a_map, key), not_last(b_map, key));
+                    let values = (a_map.get(key), b_map.get(key));
+                    self.emit(values, &label, path.child_key(key), indent + 2, commas);
+                }
+                self.rows.push(Row {
+                    left: Some(line("}", a_comma)),
+                    right: Some(line("}", b_comma)),
+                    changed: false,
+                });
+            }
+            (Some(Value::Array(a_values)), Some(Value::Array(b_values))) if !changed && a != b => {
+                self.rows.push(Row {
+                    left: Some(format!("{pad}{label}[")),
+                    right: Some(format!("{pad}{label}[")),
+                    changed: false,
+                });
+                for i in 0..a_values.len().max(b_values.len()) {
+                    let commas = (i + 1 < a_values.len(), i + 1 < b_values.len());
+                    let values = (a_values.get(i), b_values.get(i));
+                    self.emit(values, "", path.child_index(i), indent + 2, commas);
+                }
+                self.rows.push(Row {
+                    left: Some(line("]", a_comma)),
+                    right: Some(line("]", b_comma)),
+                    changed: false,
+                });
+            }
+            _ => {
+                let left = a.map_or_else(Vec::new, |a| pretty_lines(a, label, indent, a_comma));
+                let right = b.map_or_else(Vec::new, |b| pretty_lines(b, label, indent, b_comma));
+                for i in 0..left.len().max(right.len()) {
+                    self.rows.push(Row {
+                        left: left.get(i).cloned(),
+                        right: right.get(i).cloned(),
+                        changed,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Pretty-prints both documents in two columns, aligned by their structure,
+/// with the lines of each difference in `differences` marked between them:
+/// `~` for a change, `-` for a removal and `+` for an addition.
+///
+/// ```text
+/// {                     {
+///   "age": 30,       -
+///   "name": "Alice"  ~    "name": "Bob"
+/// }                     }
+/// ```
+pub fn render_side_by_side(a: &Value, b: &Value, differences: &[Difference]) -> String {
+    let mut layout = SideBySide {
+        changed: differences.iter().map(|d| d.segments.segments()).collect(),
+        rows: Vec::new(),
+    };
+    layout.emit((Some(a), Some(b)), "", Path::root(), 0, (false, false));
+    let width = layout
+        .rows
+        .iter()
+        .filter_map(|row| row.left.as_ref())
+        .map(|left| left.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut out = String::new();
+    for row in &layout.rows {
+        let marker = match (row.changed, &row.left, &row.right) {
+            (false, _, _) => ' ',
+            (true, Some(_), Some(_)) => '~',
+            (true, Some(_), None) => '-',
+            (true, None, _) => '+',
+        };
+        let left = row.left.as_deref().unwrap_or("");
+        let right = row.right.as_deref().unwrap_or("");
+        let line = format!("{left:width$}  {marker}  {right}");
+        writeln!(out, "{}", line.trim_end()).unwrap();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deep_diff;
+    use serde_json::json;
+
+    /// Test the side-by-side rendering of two documents.
+    #[test]
+    fn test_render_side_by_side() {
+        let a = json!({"age": 30, "name": "Alice", "tags": ["a"], "same": {"x": 1}});
+        let b = json!({"name": "Bob", "tags": ["a", "b"], "same": {"x": 1}});
+        let rendered = render_side_by_side(&a, &b, &deep_diff(&a, &b));
+        let expected = [
+            "{                      {",
+            "  \"age\": 30,        -",
+            "  \"name\": \"Alice\",  ~    \"name\": \"Bob\",",
+            "  \"same\": {              \"same\": {",
+            "    \"x\": 1                 \"x\": 1",
+            "  },                     },",
+            "  \"tags\": [              \"tags\": [",
+            "    \"a\"                    \"a\",",
+            "                    +      \"b\"",
+            "  ]                      ]",
+            "}                      }",
+        ];
+        assert_eq!(rendered, expected.join("\n") + "\n");
+    }
+
+    /// Test that a type change shows both whole values.
+    #[test]
+    fn test_render_side_by_side_type_change() {
+        let a = json!({"v": [1]});
+        let b = json!({"v": 1});
+        let rendered = render_side_by_side(&a, &b, &deep_diff(&a, &b));
+        let expected = [
+            "{            {",
+            "  \"v\": [  ~    \"v\": 1",
+            "    1     -",
+            "  ]       -",
+            "}            }",
+        ];
+        assert_eq!(rendered, expected.join("\n") + "\n");
+    }
+}
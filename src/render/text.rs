@@ -0,0 +1,276 @@
+//! Unified-diff style text, plain or colored for terminals.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+#[cfg(feature = "std")]
+use std::io::IsTerminal;
+
+use super::{display_path, display_value};
+use crate::{DiffKind, Difference};
+
+/// One rendered line: a marker (`-`, `+` or `~`), a path and the text after it.
+pub(crate) struct Line<'a> {
+    pub(crate) kind: &'a DiffKind,
+    pub(crate) marker: char,
+    pub(crate) path: &'a str,
+    pub(crate) text: String,
+}
+
+/// Breaks differences into lines: `-` for the value before, `+` for the
+/// value after, and `~` for a move or rename.
+pub(crate) fn lines(differences: &[Difference]) -> Vec<Line<'_>> {
+    let mut lines = Vec::new();
+    for diff in differences {
+        let path = display_path(diff);
+        let kind = &diff.kind;
+        match kind {
+            DiffKind::Moved { from, .. } => lines.push(Line {
+                kind,
+                marker: '~',
+                path,
+                text: format!("moved from index {}", from),
+            }),
+            DiffKind::Renamed { from_key, .. } => {
+                lines.push(Line {
+                    kind,
+                    marker: '~',
+                    path,
+                    text: format!("renamed from {}", from_key),
+                });
+                // The value may have changed along with its key
+                if diff.before != diff.after {
+                    for (marker, value) in [('-', &diff.before), ('+', &diff.after)] {
+                        let text = display_value(value);
+                        lines.push(Line {
+                            kind,
+                            marker,
+                            path,
+                            text,
+                        });
+                    }
+                }
+            }
+            DiffKind::BinaryChanged {
+                old_len, new_len, ..
+            } => lines.push(Line {
+                kind,
+                marker: '~',
+                path,
+                text: format!("binary changed from {} to {} bytes", old_len, new_len),
+            }),
+            _ => {
+                if *kind != DiffKind::Added {
+                    let text = display_value(&diff.before);
+                    lines.push(Line {
+                        kind,
+                        marker: '-',
+                        path,
+                        text,
+                    });
+                }
+                if *kind != DiffKind::Removed {
+                    let text = display_value(&diff.after);
+                    lines.push(Line {
+                        kind,
+                        marker: '+',
+                        path,
+                        text,
+                    });
+                }
+            }
+        }
+    }
+    lines
+}
+
+/// Renders differences in a unified-diff style, one line per value, with
+/// the values aligned after their paths:
+///
+/// ```text
+/// - age   30
+/// - name  "Alice"
+/// + name  "Bob"
+/// + email "bob@example.com"
+/// ```
+///
+/// Moved array elements are shown as `~ path moved from index n`, and
+/// renamed keys as `~ path renamed from key`.
+pub fn render_text(differences: &[Difference]) -> String {
+    render_lines(differences, None)
+}
+
+/// A terminal color, from the standard ANSI palette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+}
+
+impl Color {
+    // The SGR escape sequence selecting this color
+    fn escape(self) -> &'static str {
+        match self {
+            Color::Black => "\x1b[30m",
+            Color::Red => "\x1b[31m",
+            Color::Green => "\x1b[32m",
+            Color::Yellow => "\x1b[33m",
+            Color::Blue => "\x1b[34m",
+            Color::Magenta => "\x1b[35m",
+            Color::Cyan => "\x1b[36m",
+            Color::White => "\x1b[37m",
+        }
+    }
+}
+
+// Resets the terminal color
+const RESET: &str = "\x1b[0m";
+
+/// The colors [`render_colored`] uses for each kind of difference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColorScheme {
+    /// The color of removed values.
+    pub removed: Color,
+    /// The color of added values.
+    pub added: Color,
+    /// The color of changed and moved values.
+    pub changed: Color,
+}
+
+impl Default for ColorScheme {
+    /// Removals in red, additions in green and changes in yellow.
+    fn default() -> Self {
+        ColorScheme {
+            removed: Color::Red,
+            added: Color::Green,
+            changed: Color::Yellow,
+        }
+    }
+}
+
+impl ColorScheme {
+    fn color(&self, kind: &DiffKind) -> Color {
+        match kind {
+            DiffKind::Added => self.added,
+            DiffKind::Removed => self.removed,
+            DiffKind::Changed
+            | DiffKind::TypeChanged { .. }
+            | DiffKind::Moved { .. }
+            | DiffKind::BinaryChanged { .. }
+            | DiffKind::KeyOrderChanged
+            | DiffKind::Renamed { .. } => self.changed,
+        }
+    }
+}
+
+/// Renders differences like [`render_text`], coloring each line with ANSI
+/// escape codes according to `scheme`.
+///
+/// Falls back to plain text when standard output is not a terminal or the
+/// `NO_COLOR` environment variable is set.
+#[cfg(feature = "std")]
+pub fn render_colored(differences: &[Difference], scheme: ColorScheme) -> String {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    if no_color || !std::io::stdout().is_terminal() {
+        return render_text(differences);
+    }
+    render_lines(differences, Some(&scheme))
+}
+
+// Renders aligned lines, colored if a scheme is given
+fn render_lines(differences: &[Difference], scheme: Option<&ColorScheme>) -> String {
+    let lines = lines(differences);
+    let width = lines.iter().map(|line| line.path.chars().count()).max();
+    let mut out = String::new();
+    for line in &lines {
+        let width = width.unwrap_or(0);
+        let (start, end) = match scheme {
+            Some(scheme) => (scheme.color(line.kind).escape(), RESET),
+            None => ("", ""),
+        };
+        writeln!(
+            out,
+            "{}{} {:width$} {}{}",
+            start, line.marker, line.path, line.text, end
+        )
+        .unwrap();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ArrayDiffStrategy, DiffOptions, deep_diff, deep_diff_with_options};
+    use serde_json::json;
+
+    /// Test the aligned text rendering of each kind of difference.
+    #[test]
+    fn test_render_text() {
+        let a = json!({"name": "Alice", "age": 30, "id": 1});
+        let b = json!({"name": "Bob", "email": "bob@example.com", "id": "1"});
+        assert_eq!(
+            render_text(&deep_diff(&a, &b)),
+            "- age   30\n\
+             - id    1\n\
+             + id    \"1\"\n\
+             - name  \"Alice\"\n\
+             + name  \"Bob\"\n\
+             + email \"bob@example.com\"\n"
+        );
+    }
+
+    /// Test that the root and moved elements are rendered.
+    #[test]
+    fn test_render_text_root_and_moves() {
+        assert_eq!(
+            render_text(&deep_diff(&json!(1), &json!([1]))),
+            "- (root) 1\n+ (root) [1]\n"
+        );
+        let options = DiffOptions::builder()
+            .array_strategy(ArrayDiffStrategy::Lcs)
+            .detect_moves(true)
+            .build();
+        let diffs = deep_diff_with_options(&json!(["a", "b"]), &json!(["b", "a"]), &options);
+        assert_eq!(render_text(&diffs), "~ [1] moved from index 0\n");
+    }
+
+    /// Test that lines are colored by the kind of difference.
+    #[test]
+    fn test_render_colored_lines() {
+        let diffs = deep_diff(&json!({"a": 1, "b": 2}), &json!({"b": 3, "c": 4}));
+        assert_eq!(
+            render_lines(&diffs, Some(&ColorScheme::default())),
+            "\x1b[31m- a 1\x1b[0m\n\
+             \x1b[33m- b 2\x1b[0m\n\
+             \x1b[33m+ b 3\x1b[0m\n\
+             \x1b[32m+ c 4\x1b[0m\n"
+        );
+    }
+
+    /// Test that output is plain when not written to a terminal.
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_render_colored_fallback() {
+        let diffs = deep_diff(&json!([1]), &json!([2]));
+        if !std::io::stdout().is_terminal() {
+            assert_eq!(
+                render_colored(&diffs, ColorScheme::default()),
+                render_text(&diffs)
+            );
+        }
+    }
+
+    /// Test that an empty diff renders as nothing.
+    #[test]
+    fn test_render_text_empty() {
+        assert_eq!(render_text(&[]), "");
+    }
+}
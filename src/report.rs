@@ -0,0 +1,314 @@
+//! Differences in a guaranteed order, for output that must not change
+//! between runs.
+
+use alloc::vec::Vec;
+
+use serde_json::{Map, Value, json};
+
+use crate::{DiffKind, Difference, PathSegment, Severity};
+
+/// The order in which a [`DiffReport`] lists its differences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReportOrder {
+    /// The order in which the differences were found, which follows the
+    /// documents depth first. Applying them in this order reproduces the
+    /// second document.
+    #[default]
+    Document,
+    /// Sorted by path, segment by segment, with array indices compared as
+    /// numbers. Differences at the same path keep their document order.
+    Path,
+}
+
+/// A list of differences in a stable order, with helpers to group them by
+/// kind.
+///
+/// The same inputs always produce the same report, byte for byte.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DiffReport {
+    differences: Vec<Difference>,
+}
+
+/// The differences of a [`DiffReport`] grouped by kind, each group in the
+/// report's order.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct KindGroups<'a> {
+    /// Values that only exist in the second document.
+    pub added: Vec<&'a Difference>,
+    /// Values that only exist in the first document.
+    pub removed: Vec<&'a Difference>,
+    /// Values whose content changed, including binary fields.
+    pub changed: Vec<&'a Difference>,
+    /// Values whose JSON type changed.
+    pub type_changed: Vec<&'a Difference>,
+    /// Array elements that moved, renamed keys, and objects whose keys were
+    /// reordered.
+    pub moved: Vec<&'a Difference>,
+}
+
+impl DiffReport {
+    /// Builds a report listing `differences` in the given order.
+    pub fn new(mut differences: Vec<Difference>, order: ReportOrder) -> Self {
+        if order == ReportOrder::Path {
+            differences.sort_by(|x, y| x.segments.cmp(&y.segments));
+        }
+        DiffReport { differences }
+    }
+
+    /// Returns the differences in the report's order.
+    pub fn differences(&self) -> &[Difference] {
+        &self.differences
+    }
+
+    /// Returns the differences grouped by kind.
+    pub fn by_kind(&self) -> KindGroups<'_> {
+        let mut groups = KindGroups::default();
+        for diff in &self.differences {
+            let group = match diff.kind {
+                DiffKind::Added => &mut groups.added,
+                DiffKind::Removed => &mut groups.removed,
+                DiffKind::Changed | DiffKind::BinaryChanged { .. } => &mut groups.changed,
+                DiffKind::TypeChanged { .. } => &mut groups.type_changed,
+                DiffKind::Moved { .. } | DiffKind::KeyOrderChanged | DiffKind::Renamed { .. } => {
+                    &mut groups.moved
+                }
+            };
+            group.push(diff);
+        }
+        groups
+    }
+
+    /// Returns the differences, consuming the report.
+    pub fn into_differences(self) -> Vec<Difference> {
+        self.differences
+    }
+
+    /// The version of the format written by [`to_json`](DiffReport::to_json).
+    pub const JSON_VERSION: u64 = 1;
+
+    /// Returns the report in a versioned format for other tools to consume,
+    /// which stays the same as the crate's own types evolve:
+    ///
+    /// ```json
+    /// {
+    ///   "version": 1,
+    ///   "differences": [
+    ///     {
+    ///       "path": "items[0].name",
+    ///       "pointer": "/items/0/name",
+    ///       "segments": ["items", 0, "name"],
+    ///       "kind": "changed",
+    ///       "severity": "info",
+    ///       "before": "Alice",
+    ///       "after": "Bob"
+    ///     }
+    ///   ]
+    /// }
+    /// ```
+    ///
+    /// Each difference has:
+    ///
+    /// - `path`: the path as a string, in the format the diff was made with.
+    /// - `pointer`: the path as an RFC 6901 JSON Pointer.
+    /// - `segments`: the path as an array of object keys (strings) and array
+    ///   indices (numbers).
+    /// - `kind`: one of `"added"`, `"removed"`, `"changed"`,
+    ///   `"type_changed"`, `"moved"`, `"binary_changed"`,
+    ///   `"key_order_changed"` or `"renamed"`.
+    /// - `severity`: one of `"info"`, `"warning"` or `"critical"`.
+    /// - `before` and `after`: the values on each side, left out where
+    ///   there is none, so they stay distinct from `null`. For
+    ///   `"key_order_changed"`, the object's keys in order on each side.
+    /// - `from` and `to`: for `"moved"`, the element's old and new index,
+    ///   and for `"type_changed"`, the name of the old and new type, e.g.
+    ///   `"string"`.
+    /// - `from_key` and `to_key`: for `"renamed"`, the old and new key.
+    /// - `old_len`, `new_len`, `old_hash` and `new_hash`: for
+    ///   `"binary_changed"`, the decoded length and hash on each side.
+    ///
+    /// Later versions only add fields and kinds; anything that would change
+    /// the meaning of an existing field raises the version.
+    pub fn to_json(&self) -> Value {
+        let differences = self.differences.iter().map(difference_json).collect();
+        json!({
+            "version": DiffReport::JSON_VERSION,
+            "differences": Value::Array(differences),
+        })
+    }
+}
+
+// The name of a kind of difference in `DiffReport::to_json`
+pub(crate) fn kind_name(kind: &DiffKind) -> &'static str {
+    match kind {
+        DiffKind::Added => "added",
+        DiffKind::Removed => "removed",
+        DiffKind::Changed => "changed",
+        DiffKind::TypeChanged { .. } => "type_changed",
+        DiffKind::Moved { .. } => "moved",
+        DiffKind::BinaryChanged { .. } => "binary_changed",
+        DiffKind::KeyOrderChanged => "key_order_changed",
+        DiffKind::Renamed { .. } => "renamed",
+    }
+}
+
+// One difference in the format of `DiffReport::to_json`
+pub(crate) fn difference_json(diff: &Difference) -> Value {
+    let segments = diff
+        .segments
+        .segments()
+        .iter()
+        .map(|segment| match segment {
+            PathSegment::Key(key) => json!(key),
+            PathSegment::Index(index) => json!(index),
+        })
+        .collect();
+    let mut map = Map::new();
+    map.insert("path".into(), json!(diff.path));
+    map.insert("pointer".into(), json!(diff.pointer()));
+    map.insert("segments".into(), Value::Array(segments));
+    match &diff.kind {
+        DiffKind::TypeChanged { from, to } => {
+            map.insert("from".into(), json!(from.name()));
+            map.insert("to".into(), json!(to.name()));
+        }
+        DiffKind::Moved { from, to } => {
+            map.insert("from".into(), json!(from));
+            map.insert("to".into(), json!(to));
+        }
+        DiffKind::BinaryChanged {
+            old_len,
+            new_len,
+            old_hash,
+            new_hash,
+        } => {
+            map.insert("old_len".into(), json!(old_len));
+            map.insert("new_len".into(), json!(new_len));
+            map.insert("old_hash".into(), json!(old_hash));
+            map.insert("new_hash".into(), json!(new_hash));
+        }
+        DiffKind::Renamed { from_key, to_key } => {
+            map.insert("from_key".into(), json!(from_key));
+            map.insert("to_key".into(), json!(to_key));
+        }
+        DiffKind::Added | DiffKind::Removed | DiffKind::Changed | DiffKind::KeyOrderChanged => {}
+    }
+    map.insert("kind".into(), json!(kind_name(&diff.kind)));
+    let severity = match diff.severity {
+        Severity::Info => "info",
+        Severity::Warning => "warning",
+        Severity::Critical => "critical",
+    };
+    map.insert("severity".into(), json!(severity));
+    for (name, value) in [("before", &diff.before), ("after", &diff.after)] {
+        if let Some(value) = value {
+            map.insert(name.into(), value.clone());
+        }
+    }
+    Value::Object(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deep_diff;
+    use serde_json::json;
+
+    fn paths(report: &DiffReport) -> Vec<&str> {
+        report
+            .differences()
+            .iter()
+            .map(|d| d.path.as_str())
+            .collect()
+    }
+
+    /// Test that document order keeps the traversal order.
+    #[test]
+    fn test_report_document_order() {
+        let a = json!({"b": 1, "list": [1, 2, 3]});
+        let b = json!({"a": 1, "list": [1]});
+        let report = DiffReport::new(deep_diff(&a, &b), ReportOrder::Document);
+        assert_eq!(paths(&report), vec!["b", "list[2]", "list[1]", "a"]);
+    }
+
+    /// Test that path order sorts indices numerically and parents first.
+    #[test]
+    fn test_report_path_order() {
+        let a = json!({"b": 1, "list": (0..12).collect::<Vec<_>>(), "c": {"d": 1}});
+        let b = json!({"a": 1, "list": [], "c": 2});
+        let report = DiffReport::new(deep_diff(&a, &b), ReportOrder::Path);
+        let paths = paths(&report);
+        assert_eq!(&paths[..5], ["a", "b", "c", "list[0]", "list[1]"]);
+        assert_eq!(paths[5], "list[2]");
+        assert_eq!(paths.last(), Some(&"list[11]"));
+    }
+
+    /// Test that differences are grouped by kind in the report's order.
+    #[test]
+    fn test_report_by_kind() {
+        let a = json!({"x": 1, "y": 2, "z": "s"});
+        let b = json!({"w": 0, "x": 2, "z": 1});
+        let report = DiffReport::new(deep_diff(&a, &b), ReportOrder::Path);
+        let groups = report.by_kind();
+        assert_eq!(groups.added[0].path, "w");
+        assert_eq!(groups.removed[0].path, "y");
+        assert_eq!(groups.changed[0].path, "x");
+        assert_eq!(groups.type_changed[0].path, "z");
+        assert!(groups.moved.is_empty());
+    }
+
+    /// Test the versioned JSON format of each kind of difference.
+    #[test]
+    fn test_report_to_json() {
+        let a = json!({"items": [{"name": "Al"}], "n": null});
+        let b = json!({"items": [{"name": "Bo"}], "m": 1});
+        let options = crate::DiffOptions::builder()
+            .severity("m", Severity::Warning)
+            .build();
+        let diffs = crate::deep_diff_with_options(&a, &b, &options);
+        let report = DiffReport::new(diffs, ReportOrder::Path);
+        assert_eq!(
+            report.to_json(),
+            json!({
+                "version": 1,
+                "differences": [
+                    {
+                        "path": "items[0].name",
+                        "pointer": "/items/0/name",
+                        "segments": ["items", 0, "name"],
+                        "kind": "changed",
+                        "severity": "info",
+                        "before": "Al",
+                        "after": "Bo"
+                    },
+                    {
+                        "path": "m",
+                        "pointer": "/m",
+                        "segments": ["m"],
+                        "kind": "added",
+                        "severity": "warning",
+                        "after": 1
+                    },
+                    {
+                        "path": "n",
+                        "pointer": "/n",
+                        "segments": ["n"],
+                        "kind": "removed",
+                        "severity": "info",
+                        "before": null
+                    }
+                ]
+            })
+        );
+
+        let moved = Difference {
+            kind: DiffKind::Moved { from: 0, to: 2 },
+            ..crate::deep_diff(&json!([1]), &json!([2])).remove(0)
+        };
+        let json = DiffReport::new(vec![moved], ReportOrder::Document).to_json();
+        let moved = &json["differences"][0];
+        assert_eq!(
+            (&moved["kind"], &moved["from"], &moved["to"]),
+            (&json!("moved"), &json!(0), &json!(2))
+        );
+    }
+}
@@ -0,0 +1,623 @@
+//! Diffing guided by a JSON Schema, and validation of documents against it.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use serde_json::{Map, Value};
+
+use crate::{
+    DiffOptions, Difference, JsonType, Path, PathPattern, PathSegment, deep_diff_with_options,
+};
+
+/// A place where a document does not conform to a schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+    /// The path to the offending value, e.g. `"items[0].name"`.
+    pub path: String,
+    /// The same location as `path`, split into typed segments.
+    pub segments: Path,
+    /// What is wrong with the value, e.g. `"expected string, found number"`.
+    pub message: String,
+}
+
+/// The outcome of [`deep_diff_with_schema`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaDiff {
+    /// The differences between the documents, as seen through the schema.
+    pub differences: Vec<Difference>,
+    /// Where the first document does not conform to the schema.
+    pub old_violations: Vec<SchemaViolation>,
+    /// Where the second document does not conform to the schema.
+    pub new_violations: Vec<SchemaViolation>,
+}
+
+impl SchemaDiff {
+    /// Returns whether both documents conform to the schema.
+    pub fn is_valid(&self) -> bool {
+        self.old_violations.is_empty() && self.new_violations.is_empty()
+    }
+}
+
+/// Diffs two documents described by the JSON Schema `schema`, and validates
+/// both against it.
+///
+/// Keys only allowed by `additionalProperties` are ignored and arrays with
+/// `uniqueItems` are compared as sets, as with
+/// [`DiffOptionsBuilder::schema`](crate::DiffOptionsBuilder::schema). The
+/// documents are diffed even if they do not conform.
+pub fn deep_diff_with_schema(a: &Value, b: &Value, schema: &Value) -> SchemaDiff {
+    let options = DiffOptions::builder().schema(schema).build();
+    SchemaDiff {
+        differences: deep_diff_with_options(a, b, &options),
+        old_violations: validate_schema(schema, a),
+        new_violations: validate_schema(schema, b),
+    }
+}
+
+/// Checks `value` against the JSON Schema `schema`, returning every
+/// violation found.
+///
+/// Supports `type`, `enum`, `const`, `properties`, `required`,
+/// `additionalProperties`, `items`, `prefixItems`, `minItems`, `maxItems`,
+/// `uniqueItems`, `minimum`, `maximum`, `exclusiveMinimum`,
+/// `exclusiveMaximum`, `minLength`, `maxLength`, `allOf`, `anyOf`, `oneOf`,
+/// `not` and references within the schema (`$ref` starting with `#`).
+/// Other keywords, such as `pattern` and `format`, are not checked.
+///
+/// A schema applied again to the same value through its own `allOf`,
+/// `anyOf`, `oneOf` or `not` is taken as matching, so cyclic schemas end.
+/// These keywords are followed at most 128 levels deep; past that, a
+/// violation says the value was not fully checked.
+pub fn validate_schema(schema: &Value, value: &Value) -> Vec<SchemaViolation> {
+    let mut validator = Validator {
+        root: schema,
+        active: Vec::new(),
+        too_deep: false,
+    };
+    let mut violations = Vec::new();
+    validator.validate(schema, value, Path::root(), &mut violations);
+    if validator.too_deep {
+        violations.push(SchemaViolation {
+            path: String::new(),
+            segments: Path::root(),
+            message: format!(
+                "schemas nest more than {} levels deep through allOf, anyOf, oneOf or not; \
+                 the value was not fully checked",
+                MAX_NESTING
+            ),
+        });
+    }
+    violations
+}
+
+// How deep `allOf`, `anyOf`, `oneOf` and `not` are followed, each level
+// checking a value on the call stack
+const MAX_NESTING: usize = 128;
+
+// Checks values against the schema `root`
+struct Validator<'a> {
+    root: &'a Value,
+    // The schemas and values being checked for a combinator, outermost first
+    active: Vec<(*const Value, *const Value)>,
+    // Whether a combinator was left unchecked for nesting too deep
+    too_deep: bool,
+}
+
+impl<'a> Validator<'a> {
+    // Checks `value` at `path` against `node`, a part of the root schema,
+    // iteratively so deep documents are safe
+    fn validate(
+        &mut self,
+        node: &'a Value,
+        value: &'a Value,
+        path: Path,
+        violations: &mut Vec<SchemaViolation>,
+    ) {
+        let root = self.root;
+        let mut stack = vec![(node, value, path)];
+        while let Some((node, value, path)) = stack.pop() {
+            let mut report = |message: String| {
+                violations.push(SchemaViolation {
+                    path: path.to_string(),
+                    segments: path.clone(),
+                    message,
+                })
+            };
+            let schema = match resolve(root, node) {
+                Ok(Value::Object(schema)) => schema,
+                Ok(Value::Bool(false)) => {
+                    report("no value is allowed here".to_string());
+                    continue;
+                }
+                Ok(_) => continue,
+                Err(reference) => {
+                    report(format!("cannot resolve the reference '{}'", reference));
+                    continue;
+                }
+            };
+
+            for message in self.check_value(schema, value) {
+                report(message);
+            }
+            // Children are pushed in reverse so violations come out in document order
+            let mut children = Vec::new();
+            match value {
+                Value::Object(map) => {
+                    let properties = schema.get("properties").and_then(Value::as_object);
+                    for (key, child) in map {
+                        let declared = properties.and_then(|properties| properties.get(key));
+                        match (declared, schema.get("additionalProperties")) {
+                            (Some(sub), _) => children.push((sub, child, path.child_key(key))),
+                            (None, Some(Value::Bool(false)))
+                                if !schema.contains_key("patternProperties") =>
+                            {
+                                report(format!("property '{}' is not allowed", key))
+                            }
+                            (None, Some(sub)) if !schema.contains_key("patternProperties") => {
+                                children.push((sub, child, path.child_key(key)))
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                Value::Array(values) => {
+                    for (i, child) in values.iter().enumerate() {
+                        if let Some(sub) = item_schema(schema, i) {
+                            children.push((sub, child, path.child_index(i)));
+                        }
+                    }
+                }
+                _ => {}
+            }
+            stack.extend(children.into_iter().rev());
+        }
+    }
+
+    // Checks the keywords that apply to `value` itself rather than to its children
+    fn check_value(&mut self, schema: &'a Map<String, Value>, value: &'a Value) -> Vec<String> {
+        let mut messages = Vec::new();
+        if let Some(expected) = schema.get("type")
+            && !type_matches(expected, value)
+        {
+            let expected = match expected {
+                Value::Array(names) => names
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<_>>()
+                    .join(" or "),
+                other => other.as_str().unwrap_or_default().to_string(),
+            };
+            messages.push(format!("expected {}, found {}", expected, type_name(value)));
+        }
+        if let Some(Value::Array(allowed)) = schema.get("enum")
+            && !allowed.contains(value)
+        {
+            messages.push("value is not one of the allowed values".to_string());
+        }
+        if let Some(constant) = schema.get("const")
+            && constant != value
+        {
+            messages.push(format!("expected {}", constant));
+        }
+        match value {
+            Value::Object(map) => {
+                let required = schema.get("required").and_then(Value::as_array);
+                for key in required.into_iter().flatten().filter_map(Value::as_str) {
+                    if !map.contains_key(key) {
+                        messages.push(format!("missing required property '{}'", key));
+                    }
+                }
+            }
+            Value::Array(values) => {
+                check_bounds(schema, "Items", values.len(), "items", &mut messages);
+                if schema.get("uniqueItems") == Some(&Value::Bool(true))
+                    && values
+                        .iter()
+                        .enumerate()
+                        .any(|(i, x)| values[i + 1..].contains(x))
+                {
+                    messages.push("items are not unique".to_string());
+                }
+            }
+            Value::String(text) => {
+                let len = text.chars().count();
+                check_bounds(schema, "Length", len, "characters", &mut messages);
+            }
+            Value::Number(number) => {
+                let number = number.as_f64().unwrap_or_default();
+                let limit = |keyword: &str| schema.get(keyword).and_then(Value::as_f64);
+                if let Some(minimum) = limit("minimum")
+                    && number < minimum
+                {
+                    messages.push(format!("expected at least {}, found {}", minimum, number));
+                }
+                if let Some(maximum) = limit("maximum")
+                    && number > maximum
+                {
+                    messages.push(format!("expected at most {}, found {}", maximum, number));
+                }
+                if let Some(minimum) = limit("exclusiveMinimum")
+                    && number <= minimum
+                {
+                    messages.push(format!("expected more than {}, found {}", minimum, number));
+                }
+                if let Some(maximum) = limit("exclusiveMaximum")
+                    && number >= maximum
+                {
+                    messages.push(format!("expected less than {}, found {}", maximum, number));
+                }
+            }
+            _ => {}
+        }
+
+        let subschemas = |keyword: &str| schema.get(keyword).and_then(Value::as_array);
+        if let Some(schemas) = subschemas("allOf")
+            && !schemas.iter().all(|sub| self.conforms(sub, value))
+        {
+            messages.push("does not match all of the schemas in allOf".to_string());
+        }
+        if let Some(schemas) = subschemas("anyOf")
+            && !schemas.iter().any(|sub| self.conforms(sub, value))
+        {
+            messages.push("does not match any of the schemas in anyOf".to_string());
+        }
+        if let Some(schemas) = subschemas("oneOf") {
+            let matched = schemas
+                .iter()
+                .filter(|sub| self.conforms(sub, value))
+                .count();
+            if matched != 1 {
+                messages.push(format!(
+                    "matches {} of the schemas in oneOf instead of one",
+                    matched
+                ));
+            }
+        }
+        if let Some(sub) = schema.get("not")
+            && self.conforms(sub, value)
+        {
+            messages.push("matches the schema in not".to_string());
+        }
+        messages
+    }
+
+    // Whether `value` conforms to `node` entirely
+    fn conforms(&mut self, node: &'a Value, value: &'a Value) -> bool {
+        // The same schema checked again against the same value only repeats
+        // the check in progress, so it is taken as matching
+        let schema = resolve(self.root, node).unwrap_or(node);
+        let key = (schema as *const Value, value as *const Value);
+        if self.active.contains(&key) {
+            return true;
+        }
+        if self.active.len() >= MAX_NESTING {
+            self.too_deep = true;
+            return false;
+        }
+        self.active.push(key);
+        let mut violations = Vec::new();
+        self.validate(node, value, Path::root(), &mut violations);
+        self.active.pop();
+        violations.is_empty()
+    }
+}
+
+// Checks the `min{suffix}` and `max{suffix}` keywords against `len`
+fn check_bounds(
+    schema: &Map<String, Value>,
+    suffix: &str,
+    len: usize,
+    unit: &str,
+    messages: &mut Vec<String>,
+) {
+    let bound = |keyword: String| schema.get(&keyword).and_then(Value::as_u64);
+    if let Some(min) = bound(format!("min{}", suffix))
+        && (len as u64) < min
+    {
+        messages.push(format!("expected at least {} {}, found {}", min, unit, len));
+    }
+    if let Some(max) = bound(format!("max{}", suffix))
+        && len as u64 > max
+    {
+        messages.push(format!("expected at most {} {}, found {}", max, unit, len));
+    }
+}
+
+fn type_matches(expected: &Value, value: &Value) -> bool {
+    let matches = |name: &str| match name {
+        "integer" => value.as_f64().is_some_and(|number| number % 1.0 == 0.0),
+        "number" => value.is_number(),
+        name => name == type_name(value),
+    };
+    match expected {
+        Value::String(name) => matches(name),
+        Value::Array(names) => names.iter().filter_map(Value::as_str).any(matches),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    JsonType::of(value).name()
+}
+
+// Follows `$ref`s from `node` until reaching a schema without one. Fails
+// with the reference that cannot be resolved, including cyclic ones.
+fn resolve<'a>(root: &'a Value, mut node: &'a Value) -> Result<&'a Value, String> {
+    let mut seen = Vec::new();
+    while let Some(reference) = node.get("$ref").and_then(Value::as_str) {
+        let target = reference
+            .strip_prefix('#')
+            .filter(|_| !seen.contains(&reference))
+            .and_then(|pointer| root.pointer(pointer));
+        match target {
+            Some(target) => node = target,
+            None => return Err(reference.to_string()),
+        }
+        seen.push(reference);
+    }
+    Ok(node)
+}
+
+// The schema for the array element at `index`, from `prefixItems`, a tuple
+// form `items` or a single `items` schema
+fn item_schema(schema: &Map<String, Value>, index: usize) -> Option<&Value> {
+    let prefix = schema.get("prefixItems").and_then(Value::as_array);
+    match (prefix, schema.get("items")) {
+        (Some(prefix), _) if index < prefix.len() => Some(&prefix[index]),
+        (_, Some(Value::Array(tuple))) => {
+            tuple.get(index).or_else(|| schema.get("additionalItems"))
+        }
+        (_, items) => items,
+    }
+}
+
+// Whether `path` passes through an object key the schema only allows
+// through `additionalProperties`
+pub(crate) fn is_additional(root: &Value, path: &Path) -> bool {
+    let mut node = root;
+    for segment in path.segments() {
+        let Ok(Value::Object(schema)) = resolve(root, node) else {
+            return false;
+        };
+        let next = match segment {
+            PathSegment::Key(key) => {
+                let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+                    return false;
+                };
+                match properties.get(key) {
+                    Some(sub) => sub,
+                    None => {
+                        return !schema.contains_key("patternProperties")
+                            && schema.get("additionalProperties") != Some(&Value::Bool(false));
+                    }
+                }
+            }
+            PathSegment::Index(index) => match item_schema(schema, *index) {
+                Some(sub) => sub,
+                None => return false,
+            },
+        };
+        node = next;
+    }
+    false
+}
+
+// Patterns for every array the schema marks with `uniqueItems`, following
+// `properties` and single `items` schemas. References are followed once
+// along each branch, so recursive schemas terminate.
+pub(crate) fn unique_item_arrays(root: &Value) -> Vec<PathPattern> {
+    let mut patterns = Vec::new();
+    let mut stack = vec![(root, PathPattern::root(), Vec::new())];
+    while let Some((mut node, pattern, mut seen)) = stack.pop() {
+        while let Some(reference) = node.get("$ref").and_then(Value::as_str) {
+            let target = reference
+                .strip_prefix('#')
+                .filter(|_| !seen.contains(&reference))
+                .and_then(|pointer| root.pointer(pointer));
+            let Some(target) = target else { break };
+            seen.push(reference);
+            node = target;
+        }
+        let Value::Object(schema) = node else {
+            continue;
+        };
+        if schema.get("uniqueItems") == Some(&Value::Bool(true)) {
+            patterns.push(pattern.clone());
+        }
+        if let Some(items @ Value::Object(_)) = schema.get("items") {
+            stack.push((items, pattern.child_any_index(), seen.clone()));
+        }
+        for (key, sub) in schema
+            .get("properties")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+        {
+            stack.push((sub, pattern.child_key(key), seen.clone()));
+        }
+    }
+    patterns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DiffKind, deep_diff};
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["id", "tags"],
+            "properties": {
+                "id": {"type": "integer", "minimum": 1},
+                "name": {"type": "string", "maxLength": 5},
+                "tags": {"type": "array", "uniqueItems": true, "items": {"type": "string"}},
+                "owner": {"$ref": "#/$defs/owner"}
+            },
+            "$defs": {
+                "owner": {
+                    "type": "object",
+                    "properties": {"email": {"type": "string"}},
+                    "additionalProperties": false
+                }
+            }
+        })
+    }
+
+    /// Test that additional keys are ignored and unique arrays compared as sets.
+    #[test]
+    fn test_deep_diff_with_schema() {
+        let a = json!({"id": 1, "tags": ["a", "b"], "_etag": "x", "owner": {"email": "a@x"}});
+        let b = json!({"id": 2, "tags": ["b", "a"], "_etag": "y", "owner": {"email": "b@x"}});
+        let result = deep_diff_with_schema(&a, &b, &schema());
+        let paths: Vec<_> = result.differences.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(paths, ["id", "owner.email"]);
+        assert!(result.is_valid());
+        assert_eq!(deep_diff(&a, &b).len(), 5);
+    }
+
+    /// Test that keys disallowed or matched by patterns are still compared.
+    #[test]
+    fn test_schema_additional_keys() {
+        let a = json!({"owner": {"email": "a", "extra": 1}, "free": {"x": 1}});
+        let b = json!({"owner": {"email": "a", "extra": 2}, "free": {"x": 2}});
+        let result = deep_diff_with_schema(&a, &b, &schema());
+        assert_eq!(result.differences[0].path, "owner.extra");
+        assert_eq!(result.differences.len(), 1);
+        let patterned = json!({"properties": {}, "patternProperties": {"^x": {}}});
+        assert_eq!(
+            deep_diff_with_schema(&json!({"x": 1}), &json!({"x": 2}), &patterned)
+                .differences
+                .len(),
+            1
+        );
+    }
+
+    /// Test that violations are reported for both documents.
+    #[test]
+    fn test_schema_violations() {
+        let a = json!({"id": 0, "tags": ["a", "a"], "name": "too long"});
+        let b = json!({"id": 1.5, "tags": [1], "owner": {"email": "e", "extra": 1}});
+        let result = deep_diff_with_schema(&a, &b, &schema());
+        assert!(!result.is_valid());
+        let messages = |violations: &[SchemaViolation]| -> Vec<String> {
+            violations
+                .iter()
+                .map(|v| format!("{}: {}", v.path, v.message))
+                .collect()
+        };
+        assert_eq!(
+            messages(&result.old_violations),
+            [
+                "id: expected at least 1, found 0",
+                "name: expected at most 5 characters, found 8",
+                "tags: items are not unique",
+            ]
+        );
+        assert_eq!(
+            messages(&result.new_violations),
+            [
+                "id: expected integer, found number",
+                "owner: property 'extra' is not allowed",
+                "tags[0]: expected string, found number",
+            ]
+        );
+        assert!(
+            result
+                .differences
+                .iter()
+                .any(|d| d.kind == DiffKind::Removed)
+        );
+    }
+
+    /// Test the combinators, references and boolean schemas.
+    #[test]
+    fn test_validate_schema_keywords() {
+        let schema = json!({
+            "oneOf": [{"type": "string"}, {"type": "integer"}],
+            "not": {"const": 3}
+        });
+        assert!(validate_schema(&schema, &json!("x")).is_empty());
+        assert_eq!(
+            validate_schema(&schema, &json!(3))[0].message,
+            "matches the schema in not"
+        );
+        assert_eq!(
+            validate_schema(&schema, &json!(null))[0].message,
+            "matches 0 of the schemas in oneOf instead of one"
+        );
+        assert_eq!(
+            validate_schema(&json!({"$ref": "#/nowhere"}), &json!(1))[0].message,
+            "cannot resolve the reference '#/nowhere'"
+        );
+        assert_eq!(
+            validate_schema(&json!({"$ref": "#"}), &json!(1))[0].message,
+            "cannot resolve the reference '#'"
+        );
+        let tuple = json!({"prefixItems": [{"type": "string"}], "items": false});
+        assert!(validate_schema(&tuple, &json!(["a"])).is_empty());
+        assert_eq!(validate_schema(&tuple, &json!(["a", 1]))[0].path, "[1]");
+        assert!(validate_schema(&json!(true), &json!({"any": 1})).is_empty());
+    }
+
+    /// Test that schemas reaching themselves through combinators end, and
+    /// that deep nesting is reported rather than followed.
+    #[test]
+    fn test_validate_schema_recursive_combinators() {
+        let cyclic = json!({"anyOf": [{"$ref": "#"}]});
+        assert!(validate_schema(&cyclic, &json!(1)).is_empty());
+        let negated = json!({"not": {"$ref": "#"}, "type": "string"});
+        assert!(!validate_schema(&negated, &json!(1)).is_empty());
+
+        let tree = json!({"allOf": [{
+            "type": "object",
+            "properties": {"child": {"$ref": "#"}, "n": {"type": "integer"}}
+        }]});
+        let mut shallow = json!({"n": 1});
+        for _ in 0..10 {
+            shallow = json!({"child": shallow, "n": 2});
+        }
+        assert!(validate_schema(&tree, &shallow).is_empty());
+        shallow["child"]["child"]["n"] = json!("x");
+        assert_eq!(
+            validate_schema(&tree, &shallow)[0].message,
+            "does not match all of the schemas in allOf"
+        );
+        let deep = (0..20_000).fold(json!({"n": 1}), |child, _| {
+            Value::Object([("child".to_string(), child)].into_iter().collect())
+        });
+        let violations = validate_schema(&tree, &deep);
+        assert!(
+            violations
+                .last()
+                .is_some_and(|v| v.message.contains("not fully checked"))
+        );
+        // Dropped iteratively, so the test thread's stack is not exhausted
+        let mut stack = vec![deep];
+        while let Some(mut value) = stack.pop() {
+            if let Some(map) = value.as_object_mut() {
+                stack.extend(core::mem::take(map).into_iter().map(|(_, v)| v));
+            }
+        }
+    }
+
+    /// Test that recursive schemas terminate, following each reference once.
+    #[test]
+    fn test_unique_item_arrays() {
+        let schema = json!({
+            "$ref": "#/$defs/node",
+            "$defs": {"node": {"properties": {
+                "children": {"items": {"$ref": "#/$defs/node"}},
+                "labels": {"uniqueItems": true}
+            }}}
+        });
+        let patterns: Vec<_> = unique_item_arrays(&schema)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(patterns, ["labels"]);
+    }
+}
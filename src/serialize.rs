@@ -0,0 +1,442 @@
+//! `Serialize` and `Deserialize` implementations for diff results.
+//!
+//! A [`Difference`] is represented as
+//! `{"path": "a[0]", "segments": ["a", 0], "kind": "changed", "before": 1, "after": 2}`,
+//! where a type change's kind is `{"type_changed": {"from": "string", "to": "number"}}`,
+//! a move's is `{"moved": {"from": 0, "to": 2}}`, a rename's is
+//! `{"renamed": {"from_key": "a", "to_key": "b"}}` and a binary
+//! change's is `{"binary_changed": {"old_len": 2, "new_len": 5, "old_hash": .., "new_hash": ..}}`. A missing
+//! `before` or `after` is left out, unlike one that is `null`. The
+//! `"severity"` (`"info"`, `"warning"` or `"critical"`) is left out when it
+//! is `"info"`, and the `"context"` is left out when there is none. Values
+//! withheld from the report are listed by name, as in
+//! `"withheld": ["after"]`, and the list is left out when it is empty.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde::de::{Deserialize, Deserializer, Error};
+use serde::ser::{Serialize, SerializeStruct, SerializeStructVariant, Serializer};
+use serde_json::Value;
+
+use crate::{DiffKind, Difference, JsonType, Path, PathSegment, Severity, Withheld};
+
+impl Serialize for PathSegment {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            PathSegment::Key(key) => serializer.serialize_str(key),
+            PathSegment::Index(index) => serializer.serialize_u64(*index as u64),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for PathSegment {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Value::deserialize(deserializer)? {
+            Value::String(key) => Ok(PathSegment::Key(key)),
+            Value::Number(n) => n
+                .as_u64()
+                .map(|index| PathSegment::Index(index as usize))
+                .ok_or_else(|| D::Error::custom(format!("invalid array index {}", n))),
+            other => Err(D::Error::custom(format!("invalid path segment {}", other))),
+        }
+    }
+}
+
+impl Serialize for Path {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.segments())
+    }
+}
+
+impl<'de> Deserialize<'de> for Path {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<PathSegment>::deserialize(deserializer).map(Path::from)
+    }
+}
+
+impl Serialize for JsonType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for JsonType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        json_type(&name).ok_or_else(|| D::Error::custom(format!("invalid JSON type \"{}\"", name)))
+    }
+}
+
+// The JSON type with the given name, as returned by `JsonType::name`
+fn json_type(name: &str) -> Option<JsonType> {
+    [
+        JsonType::Null,
+        JsonType::Bool,
+        JsonType::Number,
+        JsonType::String,
+        JsonType::Array,
+        JsonType::Object,
+    ]
+    .into_iter()
+    .find(|json_type| json_type.name() == name)
+}
+
+impl Serialize for DiffKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            DiffKind::Added => serializer.serialize_unit_variant("DiffKind", 0, "added"),
+            DiffKind::Removed => serializer.serialize_unit_variant("DiffKind", 1, "removed"),
+            DiffKind::Changed => serializer.serialize_unit_variant("DiffKind", 2, "changed"),
+            DiffKind::TypeChanged { from, to } => {
+                let mut changed =
+                    serializer.serialize_struct_variant("DiffKind", 3, "type_changed", 2)?;
+                changed.serialize_field("from", &from)?;
+                changed.serialize_field("to", &to)?;
+                changed.end()
+            }
+            DiffKind::Moved { from, to } => {
+                let mut moved = serializer.serialize_struct_variant("DiffKind", 4, "moved", 2)?;
+                moved.serialize_field("from", &from)?;
+                moved.serialize_field("to", &to)?;
+                moved.end()
+            }
+            DiffKind::BinaryChanged {
+                old_len,
+                new_len,
+                old_hash,
+                new_hash,
+            } => {
+                let mut binary =
+                    serializer.serialize_struct_variant("DiffKind", 5, "binary_changed", 4)?;
+                binary.serialize_field("old_len", &old_len)?;
+                binary.serialize_field("new_len", &new_len)?;
+                binary.serialize_field("old_hash", &old_hash)?;
+                binary.serialize_field("new_hash", &new_hash)?;
+                binary.end()
+            }
+            DiffKind::KeyOrderChanged => {
+                serializer.serialize_unit_variant("DiffKind", 6, "key_order_changed")
+            }
+            DiffKind::Renamed {
+                ref from_key,
+                ref to_key,
+            } => {
+                let mut renamed =
+                    serializer.serialize_struct_variant("DiffKind", 7, "renamed", 2)?;
+                renamed.serialize_field("from_key", from_key)?;
+                renamed.serialize_field("to_key", to_key)?;
+                renamed.end()
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for DiffKind {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        match &value {
+            Value::String(kind) => match kind.as_str() {
+                "added" => return Ok(DiffKind::Added),
+                "removed" => return Ok(DiffKind::Removed),
+                "changed" => return Ok(DiffKind::Changed),
+                "key_order_changed" => return Ok(DiffKind::KeyOrderChanged),
+                _ => {}
+            },
+            Value::Object(map) if map.len() == 1 => {
+                let index = |field: &str| {
+                    let index = map.get("moved")?.get(field)?.as_u64()?;
+                    Some(index as usize)
+                };
+                if let (Some(from), Some(to)) = (index("from"), index("to")) {
+                    return Ok(DiffKind::Moved { from, to });
+                }
+                let field_type = |field: &str| {
+                    let name = map.get("type_changed")?.get(field)?.as_str()?;
+                    json_type(name)
+                };
+                if let (Some(from), Some(to)) = (field_type("from"), field_type("to")) {
+                    return Ok(DiffKind::TypeChanged { from, to });
+                }
+                let key = |field: &str| map.get("renamed")?.get(field)?.as_str();
+                if let (Some(from_key), Some(to_key)) = (key("from_key"), key("to_key")) {
+                    return Ok(DiffKind::Renamed {
+                        from_key: from_key.into(),
+                        to_key: to_key.into(),
+                    });
+                }
+                let field = |name: &str| map.get("binary_changed")?.get(name)?.as_u64();
+                if let (Some(old_len), Some(new_len), Some(old_hash), Some(new_hash)) = (
+                    field("old_len"),
+                    field("new_len"),
+                    field("old_hash"),
+                    field("new_hash"),
+                ) {
+                    return Ok(DiffKind::BinaryChanged {
+                        old_len: old_len as usize,
+                        new_len: new_len as usize,
+                        old_hash,
+                        new_hash,
+                    });
+                }
+            }
+            _ => {}
+        }
+        Err(D::Error::custom(format!(
+            "invalid difference kind {}",
+            value
+        )))
+    }
+}
+
+impl Serialize for Severity {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Severity::Info => serializer.serialize_unit_variant("Severity", 0, "info"),
+            Severity::Warning => serializer.serialize_unit_variant("Severity", 1, "warning"),
+            Severity::Critical => serializer.serialize_unit_variant("Severity", 2, "critical"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Severity {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let severity = String::deserialize(deserializer)?;
+        match severity.as_str() {
+            "info" => Ok(Severity::Info),
+            "warning" => Ok(Severity::Warning),
+            "critical" => Ok(Severity::Critical),
+            _ => Err(D::Error::custom(format!(
+                "invalid severity \"{}\"",
+                severity
+            ))),
+        }
+    }
+}
+
+impl Serialize for Withheld {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let names = [("before", self.before), ("after", self.after)];
+        serializer.collect_seq(names.iter().filter(|(_, set)| *set).map(|(name, _)| name))
+    }
+}
+
+impl<'de> Deserialize<'de> for Withheld {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut withheld = Withheld::default();
+        for name in Vec::<String>::deserialize(deserializer)? {
+            match name.as_str() {
+                "before" => withheld.before = true,
+                "after" => withheld.after = true,
+                _ => {
+                    return Err(D::Error::custom(format!(
+                        "invalid withheld value \"{}\"",
+                        name
+                    )));
+                }
+            }
+        }
+        Ok(withheld)
+    }
+}
+
+impl Serialize for Difference {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut difference = serializer.serialize_struct("Difference", 8)?;
+        difference.serialize_field("path", &self.path)?;
+        difference.serialize_field("segments", &self.segments)?;
+        difference.serialize_field("kind", &self.kind)?;
+        if self.severity == Severity::Info {
+            difference.skip_field("severity")?;
+        } else {
+            difference.serialize_field("severity", &self.severity)?;
+        }
+        // Missing values are left out, so they stay distinct from `null`
+        for (name, value) in [("before", &self.before), ("after", &self.after)] {
+            match value {
+                Some(value) => difference.serialize_field(name, value)?,
+                None => difference.skip_field(name)?,
+            }
+        }
+        match &self.context {
+            Some(context) => difference.serialize_field("context", context)?,
+            None => difference.skip_field("context")?,
+        }
+        if self.withheld.is_none() {
+            difference.skip_field("withheld")?;
+        } else {
+            difference.serialize_field("withheld", &self.withheld)?;
+        }
+        difference.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Difference {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let Value::Object(mut map) = Value::deserialize(deserializer)? else {
+            return Err(D::Error::custom("expected a difference object"));
+        };
+        let mut field = |name: &str| {
+            map.remove(name)
+                .ok_or_else(|| D::Error::custom(format!("missing field `{}`", name)))
+        };
+        let path = field("path")?;
+        let segments = field("segments")?;
+        let kind = field("kind")?;
+        let severity = match map.remove("severity") {
+            Some(severity) => Severity::deserialize(severity).map_err(D::Error::custom)?,
+            None => Severity::Info,
+        };
+        let (before, after) = (map.remove("before"), map.remove("after"));
+        Ok(Difference {
+            path: String::deserialize(path).map_err(D::Error::custom)?,
+            segments: Path::deserialize(segments).map_err(D::Error::custom)?,
+            kind: DiffKind::deserialize(kind).map_err(D::Error::custom)?,
+            severity,
+            before,
+            after,
+            context: map.remove("context"),
+            withheld: match map.remove("withheld") {
+                Some(withheld) => Withheld::deserialize(withheld).map_err(D::Error::custom)?,
+                None => Withheld::default(),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ArrayDiffStrategy, DiffOptions, apply_diff, deep_diff_with_options};
+    use serde_json::json;
+
+    /// Test the serialized form of a difference.
+    #[test]
+    fn test_serialize_difference() {
+        let diff = Difference {
+            path: "a[0]".to_string(),
+            segments: "a[0]".parse().unwrap(),
+            kind: DiffKind::Changed,
+            severity: Severity::Info,
+            before: Some(json!(1)),
+            after: Some(json!(2)),
+            context: None,
+            withheld: Withheld::default(),
+        };
+        assert_eq!(
+            serde_json::to_value(&diff).unwrap(),
+            json!({"path": "a[0]", "segments": ["a", 0], "kind": "changed", "before": 1, "after": 2})
+        );
+        let withheld = Difference {
+            withheld: Withheld {
+                before: false,
+                after: true,
+            },
+            ..diff
+        };
+        let value = serde_json::to_value(&withheld).unwrap();
+        assert_eq!(value["withheld"], json!(["after"]));
+        assert_eq!(
+            serde_json::from_value::<Difference>(value).unwrap(),
+            withheld
+        );
+    }
+
+    /// Test that each kind round-trips, including moves.
+    #[test]
+    fn test_diff_kind_round_trip() {
+        let kinds = [
+            DiffKind::Added,
+            DiffKind::Removed,
+            DiffKind::Changed,
+            DiffKind::TypeChanged {
+                from: JsonType::Bool,
+                to: JsonType::Array,
+            },
+            DiffKind::Moved { from: 3, to: 0 },
+            DiffKind::BinaryChanged {
+                old_len: 2,
+                new_len: 0,
+                old_hash: u64::MAX,
+                new_hash: 7,
+            },
+            DiffKind::Renamed {
+                from_key: "a".to_string(),
+                to_key: "b".to_string(),
+            },
+        ];
+        for kind in kinds {
+            let value = serde_json::to_value(&kind).unwrap();
+            assert_eq!(serde_json::from_value::<DiffKind>(value).unwrap(), kind);
+        }
+        assert_eq!(
+            serde_json::to_value(DiffKind::Moved { from: 3, to: 0 }).unwrap(),
+            json!({"moved": {"from": 3, "to": 0}})
+        );
+        assert!(serde_json::from_value::<DiffKind>(json!("renamed")).is_err());
+    }
+
+    /// Test that stored diffs can be reloaded and applied.
+    #[test]
+    fn test_difference_round_trip_apply() {
+        let a = json!({"a.b": [1, 2, 3], "c": {"d": null}});
+        let b = json!({"a.b": [3, 1], "c": {"d": "x"}, "e": true});
+        let options = DiffOptions::builder()
+            .array_strategy(ArrayDiffStrategy::Lcs)
+            .detect_moves(true)
+            .build();
+        let diffs = deep_diff_with_options(&a, &b, &options);
+        let stored = serde_json::to_string(&diffs).unwrap();
+        let loaded: Vec<Difference> = serde_json::from_str(&stored).unwrap();
+        assert_eq!(loaded, diffs);
+        assert_eq!(apply_diff(&a, &loaded), Ok(b));
+    }
+
+    /// Test that malformed input is rejected.
+    #[test]
+    fn test_deserialize_errors() {
+        let missing = json!({"path": "a", "kind": "added"});
+        assert!(serde_json::from_value::<Difference>(missing).is_err());
+        let bad_segment = json!({"path": "a", "segments": [true], "kind": "added"});
+        assert!(serde_json::from_value::<Difference>(bad_segment).is_err());
+        assert!(serde_json::from_value::<Path>(json!(["a", -1])).is_err());
+    }
+
+    /// Test that missing values stay distinct from `null` ones.
+    #[test]
+    fn test_missing_and_null_values() {
+        let added = Difference {
+            path: "a".to_string(),
+            segments: "a".parse().unwrap(),
+            kind: DiffKind::Added,
+            severity: Severity::Info,
+            before: None,
+            after: Some(Value::Null),
+            context: None,
+            withheld: Withheld::default(),
+        };
+        let value = serde_json::to_value(&added).unwrap();
+        assert_eq!(
+            value,
+            json!({"path": "a", "segments": ["a"], "kind": "added", "after": null})
+        );
+        assert_eq!(serde_json::from_value::<Difference>(value).unwrap(), added);
+    }
+
+    /// Test that severities round-trip and are left out when informational.
+    #[test]
+    fn test_severity_round_trip() {
+        let a = json!({"key": "x", "name": "a"});
+        let b = json!({"key": "y", "name": "b"});
+        let options = DiffOptions::builder()
+            .severity("key", Severity::Critical)
+            .build();
+        let diffs = deep_diff_with_options(&a, &b, &options);
+        let value = serde_json::to_value(&diffs).unwrap();
+        assert_eq!(value[0]["severity"], json!("critical"));
+        assert!(value[1].get("severity").is_none());
+        let restored: Vec<Difference> = serde_json::from_value(value).unwrap();
+        assert_eq!(restored, diffs);
+    }
+}
@@ -0,0 +1,212 @@
+//! Chainable filters over a list of differences.
+
+use alloc::vec::Vec;
+
+use crate::{DiffKind, Difference, Path, PathPattern};
+
+/// A list of differences with chainable filters, each returning a new set.
+///
+/// ```
+/// use deep_diff::{DiffSet, deep_diff};
+/// use serde_json::json;
+///
+/// let a = json!({"spec": {"replicas": 1, "image": "app:1"}, "status": {}});
+/// let b = json!({"spec": {"replicas": 2, "paused": true}, "status": {"ready": 2}});
+/// let set = DiffSet::from(deep_diff(&a, &b));
+/// assert_eq!(set.clone().under("spec").added().len(), 1);
+/// assert_eq!(set.clone().changed().matching("**.replicas").len(), 1);
+/// assert_eq!(set.removed().paths().next().unwrap().to_string(), "spec.image");
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DiffSet {
+    differences: Vec<Difference>,
+}
+
+impl DiffSet {
+    /// Creates a set holding `differences`, in the same order.
+    pub fn new(differences: Vec<Difference>) -> Self {
+        DiffSet { differences }
+    }
+
+    /// Keeps the differences satisfying `predicate`.
+    pub fn filter(mut self, mut predicate: impl FnMut(&Difference) -> bool) -> Self {
+        self.differences.retain(|diff| predicate(diff));
+        self
+    }
+
+    /// Keeps the values that only exist in the second document.
+    pub fn added(self) -> Self {
+        self.filter(|diff| diff.kind == DiffKind::Added)
+    }
+
+    /// Keeps the values that only exist in the first document.
+    pub fn removed(self) -> Self {
+        self.filter(|diff| diff.kind == DiffKind::Removed)
+    }
+
+    /// Keeps the values whose content changed, including binary fields.
+    pub fn changed(self) -> Self {
+        self.filter(|diff| {
+            matches!(
+                diff.kind,
+                DiffKind::Changed | DiffKind::BinaryChanged { .. }
+            )
+        })
+    }
+
+    /// Keeps the values whose JSON type changed.
+    pub fn type_changed(self) -> Self {
+        self.filter(|diff| matches!(diff.kind, DiffKind::TypeChanged { .. }))
+    }
+
+    /// Keeps the array elements that moved.
+    pub fn moved(self) -> Self {
+        self.filter(|diff| matches!(diff.kind, DiffKind::Moved { .. }))
+    }
+
+    /// Keeps the differences at `prefix`, e.g. `"spec"`, or inside it.
+    ///
+    /// Segments are compared whole, so `"spec"` does not match `specs`.
+    /// Prefixes that fail to parse match nothing.
+    pub fn under(self, prefix: &str) -> Self {
+        let prefix = prefix.parse::<Path>().ok();
+        self.filter(|diff| {
+            prefix
+                .as_ref()
+                .is_some_and(|prefix| diff.segments.starts_with(prefix))
+        })
+    }
+
+    /// Keeps the differences whose path matches `pattern`, e.g.
+    /// `"items[*].name"` or `"**.updated_at"`.
+    ///
+    /// See [`PathPattern`] for the syntax; patterns that fail to parse
+    /// match nothing.
+    pub fn matching(self, pattern: &str) -> Self {
+        let pattern = pattern.parse::<PathPattern>().ok();
+        self.filter(|diff| {
+            pattern
+                .as_ref()
+                .is_some_and(|pattern| pattern.matches(&diff.segments))
+        })
+    }
+
+    /// Returns the number of differences in the set.
+    pub fn len(&self) -> usize {
+        self.differences.len()
+    }
+
+    /// Returns whether the set holds no differences.
+    pub fn is_empty(&self) -> bool {
+        self.differences.is_empty()
+    }
+
+    /// Iterates over the differences in order.
+    pub fn iter(&self) -> core::slice::Iter<'_, Difference> {
+        self.differences.iter()
+    }
+
+    /// Returns the path of each difference, in order.
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.differences.iter().map(|diff| &diff.segments)
+    }
+
+    /// Returns the differences in order.
+    pub fn differences(&self) -> &[Difference] {
+        &self.differences
+    }
+
+    /// Returns the differences, consuming the set.
+    pub fn into_differences(self) -> Vec<Difference> {
+        self.differences
+    }
+}
+
+impl From<Vec<Difference>> for DiffSet {
+    fn from(differences: Vec<Difference>) -> Self {
+        DiffSet::new(differences)
+    }
+}
+
+impl FromIterator<Difference> for DiffSet {
+    fn from_iter<I: IntoIterator<Item = Difference>>(iter: I) -> Self {
+        DiffSet::new(iter.into_iter().collect())
+    }
+}
+
+impl IntoIterator for DiffSet {
+    type Item = Difference;
+    type IntoIter = alloc::vec::IntoIter<Difference>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.differences.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a DiffSet {
+    type Item = &'a Difference;
+    type IntoIter = core::slice::Iter<'a, Difference>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.differences.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ArrayDiffStrategy, DiffOptions, deep_diff, deep_diff_with_options};
+    use serde_json::json;
+
+    fn paths(set: &DiffSet) -> Vec<String> {
+        set.paths().map(Path::to_string).collect()
+    }
+
+    /// Test that kind filters keep only their kind, in order.
+    #[test]
+    fn test_kind_filters() {
+        let a = json!({"a": 1, "b": "x", "c": [1, 2], "d": true});
+        let b = json!({"a": 2, "b": 3, "c": [1], "e": null});
+        let set: DiffSet = deep_diff(&a, &b).into_iter().collect();
+        assert_eq!(paths(&set.clone().added()), ["e"]);
+        assert_eq!(paths(&set.clone().removed()), ["c[1]", "d"]);
+        assert_eq!(paths(&set.clone().changed()), ["a"]);
+        assert_eq!(paths(&set.clone().type_changed()), ["b"]);
+        assert!(set.clone().moved().is_empty());
+        assert_eq!(set.len(), 5);
+
+        let options = DiffOptions::builder()
+            .array_strategy(ArrayDiffStrategy::Lcs)
+            .detect_moves(true)
+            .build();
+        let moved = DiffSet::from(deep_diff_with_options(
+            &json!(["x", "y", "z"]),
+            &json!(["z", "x", "y"]),
+            &options,
+        ))
+        .moved();
+        assert_eq!(moved.len(), 1);
+    }
+
+    /// Test that path filters compare whole segments and chain.
+    #[test]
+    fn test_path_filters() {
+        let a = json!({"spec": {"a": 1, "b": [{"name": "x"}]}, "specs": 1});
+        let b = json!({"spec": {"a": 2, "b": [{"name": "y"}]}, "specs": 2});
+        let set = DiffSet::from(deep_diff(&a, &b));
+        assert_eq!(
+            paths(&set.clone().under("spec")),
+            ["spec.a", "spec.b[0].name"]
+        );
+        assert_eq!(paths(&set.clone().matching("**.name")), ["spec.b[0].name"]);
+        assert_eq!(
+            paths(&set.clone().under("spec").matching("spec.*")),
+            ["spec.a"]
+        );
+        assert!(set.clone().under("spec[").is_empty());
+        assert!(set.clone().matching("[x]").is_empty());
+        assert_eq!(set.iter().count(), 3);
+        assert_eq!((&set).into_iter().count(), 3);
+        assert_eq!(set.into_differences().len(), 3);
+    }
+}
@@ -0,0 +1,438 @@
+//! Compatibility checks between two versions of an OpenAPI (or Swagger)
+//! document.
+//!
+//! ```
+//! use deep_diff::specdiff::diff_specs;
+//! use serde_json::json;
+//!
+//! let old = json!({"paths": {"/users": {"get": {}, "delete": {}}}});
+//! let new = json!({"paths": {"/users": {"get": {}, "post": {}}}});
+//! let report = diff_specs(&old, &new);
+//! assert!(!report.is_compatible());
+//! assert_eq!(report.breaking().next().unwrap().message, "removed operation DELETE /users");
+//! ```
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde_json::Value;
+
+use crate::{
+    ArrayDiffStrategy, DiffKind, DiffOptions, Difference, PathSegment, deep_diff_with_options,
+};
+
+// The keys of a path item that hold operations
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+// Keys whose contents only document the API
+const DOCUMENTATION: [&str; 8] = [
+    "description",
+    "summary",
+    "title",
+    "example",
+    "examples",
+    "externalDocs",
+    "tags",
+    "deprecated",
+];
+
+// Keys holding named entries that clients only use if they know about them
+const ENTRIES: [&str; 9] = [
+    "paths",
+    "responses",
+    "properties",
+    "schemas",
+    "definitions",
+    "content",
+    "headers",
+    "components",
+    "securitySchemes",
+];
+
+/// Whether a change can break existing clients of an API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compatibility {
+    /// Existing clients may stop working.
+    Breaking,
+    /// Existing clients keep working.
+    NonBreaking,
+}
+
+/// One change between two API descriptions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpecChange {
+    /// Whether the change can break clients.
+    pub compatibility: Compatibility,
+    /// The operation the change belongs to, e.g. `"GET /users/{id}"`.
+    pub operation: Option<String>,
+    /// A description of the change, e.g. `"removed response 404"`.
+    pub message: String,
+    /// The underlying difference between the documents.
+    pub difference: Difference,
+}
+
+/// The outcome of [`diff_specs`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CompatibilityReport {
+    /// Every change, in the order the differences were found.
+    pub changes: Vec<SpecChange>,
+}
+
+impl CompatibilityReport {
+    /// Returns whether no change can break existing clients.
+    pub fn is_compatible(&self) -> bool {
+        self.breaking().next().is_none()
+    }
+
+    /// Returns the changes that can break existing clients.
+    pub fn breaking(&self) -> impl Iterator<Item = &SpecChange> {
+        self.with(Compatibility::Breaking)
+    }
+
+    /// Returns the changes existing clients are not affected by.
+    pub fn non_breaking(&self) -> impl Iterator<Item = &SpecChange> {
+        self.with(Compatibility::NonBreaking)
+    }
+
+    fn with(&self, compatibility: Compatibility) -> impl Iterator<Item = &SpecChange> {
+        self.changes
+            .iter()
+            .filter(move |change| change.compatibility == compatibility)
+    }
+}
+
+/// Compares two OpenAPI documents and classifies every change as breaking or
+/// not.
+///
+/// Paths, operations, responses and schemas are matched by name, and
+/// parameters by their `name`; `required` and `enum` lists are compared as
+/// sets. The classification is conservative, from the point of view of a
+/// client:
+///
+/// - documentation changes (descriptions, examples, tags, ...) and `x-`
+///   extensions never break;
+/// - added paths, operations, responses, schemas, properties and optional
+///   parameters do not break, while other additions, such as new
+///   constraints, do;
+/// - making a request property required, or a response property optional,
+///   breaks, as does accepting fewer or returning more `enum` values;
+///   outside requests and responses both directions break;
+/// - every other removal or change breaks.
+pub fn diff_specs(old: &Value, new: &Value) -> CompatibilityReport {
+    let options = DiffOptions::builder()
+        .array_key("paths.*.parameters", "name")
+        .array_key("paths.*.*.parameters", "name")
+        .array_strategy_at("**.required", ArrayDiffStrategy::UnorderedSet)
+        .array_strategy_at("**.enum", ArrayDiffStrategy::UnorderedSet)
+        .build();
+    let changes = deep_diff_with_options(old, new, &options)
+        .into_iter()
+        .map(|difference| SpecChange {
+            compatibility: classify(&difference),
+            operation: operation(&difference),
+            message: describe(&difference),
+            difference,
+        })
+        .collect();
+    CompatibilityReport { changes }
+}
+
+// Which side of an exchange a location describes
+#[derive(PartialEq)]
+enum Direction {
+    Request,
+    Response,
+    // Shared definitions, which may be used on either side
+    Unknown,
+}
+
+fn direction(segments: &[PathSegment]) -> Direction {
+    let has_key = |name: &str| {
+        segments
+            .iter()
+            .any(|segment| matches!(segment, PathSegment::Key(key) if key == name))
+    };
+    if has_key("responses") {
+        Direction::Response
+    } else if has_key("requestBody") || has_key("parameters") {
+        Direction::Request
+    } else {
+        Direction::Unknown
+    }
+}
+
+fn classify(diff: &Difference) -> Compatibility {
+    let segments = diff.segments.segments();
+    // Keys naming an entry, such as a property called `title`, are not keywords
+    let documentation = segments.iter().enumerate().any(|(i, segment)| {
+        let entry = matches!(
+            i.checked_sub(1).map(|parent| &segments[parent]),
+            Some(PathSegment::Key(parent)) if ENTRIES.contains(&parent.as_str())
+        );
+        match segment {
+            PathSegment::Key(key) if !entry => {
+                DOCUMENTATION.contains(&key.as_str()) || key.starts_with("x-")
+            }
+            _ => false,
+        }
+    });
+    if documentation || matches!(diff.kind, DiffKind::Moved { .. }) {
+        return Compatibility::NonBreaking;
+    }
+    let direction = direction(segments);
+    let breaking = match (segments, &diff.kind) {
+        // A property became required, or stopped being so
+        ([.., PathSegment::Key(list), PathSegment::Index(_)], DiffKind::Added)
+            if list == "required" =>
+        {
+            direction != Direction::Response
+        }
+        ([.., PathSegment::Key(list), PathSegment::Index(_)], DiffKind::Removed)
+            if list == "required" =>
+        {
+            direction != Direction::Request
+        }
+        // Clients may send values that are no longer accepted, or receive
+        // values they do not know
+        ([.., PathSegment::Key(list), PathSegment::Index(_)], DiffKind::Added)
+            if list == "enum" =>
+        {
+            direction != Direction::Request
+        }
+        ([.., PathSegment::Key(list), PathSegment::Index(_)], DiffKind::Removed)
+            if list == "enum" =>
+        {
+            direction != Direction::Response
+        }
+        ([.., PathSegment::Key(list), PathSegment::Index(_)], DiffKind::Added)
+            if list == "parameters" =>
+        {
+            is_required(diff.after.as_ref())
+        }
+        // A parameter's `required` flag
+        ([.., PathSegment::Key(list), _, PathSegment::Key(flag)], _)
+            if list == "parameters" && flag == "required" =>
+        {
+            is_required(diff.after.as_ref())
+        }
+        (_, DiffKind::Added) => !is_entry(segments),
+        _ => true,
+    };
+    if breaking {
+        Compatibility::Breaking
+    } else {
+        Compatibility::NonBreaking
+    }
+}
+
+fn is_required(value: Option<&Value>) -> bool {
+    value.is_some_and(|value| value == &Value::Bool(true) || value["required"] == true)
+}
+
+// Whether `segments` leads to a named entry such as a path or a property
+fn is_entry(segments: &[PathSegment]) -> bool {
+    match segments {
+        [
+            PathSegment::Key(paths),
+            PathSegment::Key(_),
+            PathSegment::Key(method),
+        ] => paths == "paths" && METHODS.contains(&method.as_str()),
+        [.., PathSegment::Key(parent), PathSegment::Key(_)] => ENTRIES.contains(&parent.as_str()),
+        _ => false,
+    }
+}
+
+// The operation `diff` belongs to, e.g. `GET /users`
+fn operation(diff: &Difference) -> Option<String> {
+    match diff.segments.segments() {
+        [
+            PathSegment::Key(paths),
+            PathSegment::Key(path),
+            PathSegment::Key(method),
+            ..,
+        ] if paths == "paths" && METHODS.contains(&method.as_str()) => {
+            Some(format!("{} {}", method.to_uppercase(), path))
+        }
+        _ => None,
+    }
+}
+
+fn describe(diff: &Difference) -> String {
+    let verb = match diff.kind {
+        DiffKind::Added => "added",
+        DiffKind::Removed => "removed",
+        DiffKind::Moved { .. } => "moved",
+        DiffKind::Renamed { .. } => "renamed",
+        DiffKind::KeyOrderChanged => "reordered",
+        DiffKind::Changed | DiffKind::TypeChanged { .. } | DiffKind::BinaryChanged { .. } => {
+            "changed"
+        }
+    };
+    let value = diff.after.as_ref().or(diff.before.as_ref());
+    let segments = diff.segments.segments();
+    let target = match segments {
+        [PathSegment::Key(paths), PathSegment::Key(path)] if paths == "paths" => {
+            format!("path {}", path)
+        }
+        [PathSegment::Key(paths), _, _] if paths == "paths" && operation(diff).is_some() => {
+            format!("operation {}", operation(diff).unwrap_or_default())
+        }
+        [.., PathSegment::Key(parent), PathSegment::Key(name)] => match parent.as_str() {
+            "responses" => format!("response {}", name),
+            "schemas" | "definitions" => format!("schema '{}'", name),
+            "properties" => format!("property '{}'", name),
+            "content" => format!("media type {}", name),
+            _ => format!("'{}'", diff.path),
+        },
+        [.., PathSegment::Key(list), PathSegment::Index(_)] => match (list.as_str(), value) {
+            ("parameters", Some(value)) => {
+                format!("parameter '{}'", value["name"].as_str().unwrap_or_default())
+            }
+            ("required", Some(Value::String(name))) => format!("required property '{}'", name),
+            ("enum", Some(value)) => format!("enum value {}", value),
+            _ => format!("'{}'", diff.path),
+        },
+        _ => format!("'{}'", diff.path),
+    };
+    format!("{} {}", verb, target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn spec(operation: Value, schema: Value) -> Value {
+        json!({
+            "openapi": "3.0.0",
+            "info": {"title": "Users", "version": "1"},
+            "paths": {"/users/{id}": {"get": operation}},
+            "components": {"schemas": {"User": schema}}
+        })
+    }
+
+    fn messages(
+        changes: impl Iterator<Item = impl std::ops::Deref<Target = SpecChange>>,
+    ) -> Vec<String> {
+        changes.map(|change| change.message.clone()).collect()
+    }
+
+    /// Test that added entries are compatible and removed ones are not.
+    #[test]
+    fn test_paths_and_operations() {
+        let old = json!({"paths": {"/a": {"get": {}}, "/b": {"get": {}}}});
+        let new = json!({"paths": {"/a": {"get": {}, "post": {}}, "/c": {"get": {}}}});
+        let report = diff_specs(&old, &new);
+        assert_eq!(messages(report.breaking()), ["removed path /b"]);
+        assert_eq!(
+            messages(report.non_breaking()),
+            ["added operation POST /a", "added path /c"]
+        );
+        let operations: Vec<_> = report
+            .changes
+            .iter()
+            .map(|c| c.operation.as_deref())
+            .collect();
+        assert_eq!(operations, [Some("POST /a"), None, None]);
+    }
+
+    /// Test that parameters are matched by name and classified by whether they are required.
+    #[test]
+    fn test_parameters() {
+        let param =
+            |name: &str, required: bool| json!({"name": name, "in": "query", "required": required});
+        let old = spec(
+            json!({"parameters": [param("a", false), param("b", false)]}),
+            json!({}),
+        );
+        let new = spec(
+            json!({"parameters": [param("b", true), param("c", false), param("d", true)]}),
+            json!({}),
+        );
+        let report = diff_specs(&old, &new);
+        assert_eq!(
+            messages(report.breaking()),
+            [
+                "changed 'paths./users/{id}.get.parameters[1].required'",
+                "removed parameter 'a'",
+                "added parameter 'd'"
+            ]
+        );
+        assert_eq!(messages(report.non_breaking()), ["added parameter 'c'"]);
+        assert!(
+            report
+                .changes
+                .iter()
+                .all(|change| change.operation.as_deref() == Some("GET /users/{id}"))
+        );
+    }
+
+    /// Test that required properties and enums are judged by direction.
+    #[test]
+    fn test_request_and_response_schemas() {
+        let operation = |required: Value, status: Value| {
+            json!({
+                "requestBody": {"content": {"application/json": {"schema": {"required": required}}}},
+                "responses": {"200": {"content": {"application/json": {"schema": {
+                    "properties": {"status": {"enum": status}}
+                }}}}}
+            })
+        };
+        let old = spec(operation(json!(["name"]), json!(["on", "off"])), json!({}));
+        let new = spec(operation(json!(["email"]), json!(["on"])), json!({}));
+        let report = diff_specs(&old, &new);
+        assert_eq!(
+            messages(report.breaking()),
+            ["added required property 'email'"]
+        );
+        assert_eq!(
+            messages(report.non_breaking()),
+            [
+                "removed required property 'name'",
+                "removed enum value \"off\""
+            ]
+        );
+    }
+
+    /// Test that documentation and extensions never break clients.
+    #[test]
+    fn test_documentation() {
+        let old = spec(
+            json!({"summary": "Get"}),
+            json!({"type": "object", "description": "A user", "properties": {}}),
+        );
+        let new = spec(
+            json!({"summary": "Fetch", "x-internal": true, "deprecated": true}),
+            json!({"type": "string", "properties": {"name": {}}}),
+        );
+        let report = diff_specs(&old, &new);
+        assert_eq!(
+            messages(report.breaking()),
+            ["changed 'components.schemas.User.type'"]
+        );
+        assert_eq!(report.non_breaking().count(), 5);
+        assert!(diff_specs(&old, &old).changes.is_empty());
+    }
+
+    /// Test that entries named like documentation keywords are judged as
+    /// entries.
+    #[test]
+    fn test_entries_named_like_documentation() {
+        let properties = |properties: Value| json!({"type": "object", "properties": properties});
+        let old = spec(
+            json!({}),
+            properties(json!({"title": {"type": "string", "description": "Shown"}})),
+        );
+        let new = spec(json!({}), properties(json!({})));
+        let report = diff_specs(&old, &new);
+        assert_eq!(messages(report.breaking()), ["removed property 'title'"]);
+        let described = spec(
+            json!({}),
+            properties(json!({"title": {"type": "string", "description": "Hidden"}})),
+        );
+        assert_eq!(diff_specs(&old, &described).breaking().count(), 0);
+    }
+}
@@ -0,0 +1,367 @@
+//! Diffing of large JSON streams one element at a time, without loading
+//! either document into memory.
+
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Read};
+
+use serde_json::Value;
+
+use crate::{
+    DiffKind, DiffOptions, Difference, Path, PathSegment, Severity, Withheld,
+    deep_diff_with_options,
+};
+
+/// Diffs two streams of JSON values element by element, such as two
+/// multi-gigabyte exports.
+///
+/// Each stream is either a single top-level array or a sequence of values
+/// separated by whitespace, as in JSON Lines. Element `i` of one stream is
+/// compared with element `i` of the other, and differences are reported
+/// below `[i]`; memory use is bounded by the largest element.
+///
+/// The options apply to each pair of elements as if they were whole
+/// documents, so patterns are written relative to an element. Elements
+/// beyond the end of the shorter stream are reported as removed or added
+/// in ascending order. The iterator ends after the first error.
+///
+/// ```
+/// use deep_diff::{DiffOptions, diff_streams};
+///
+/// let old = r#"{"id": 1, "n": 1}
+/// {"id": 2, "n": 2}"#;
+/// let new = r#"[{"id": 1, "n": 1}, {"id": 2, "n": 3}]"#;
+/// let options = DiffOptions::default();
+/// let diffs: Vec<_> = diff_streams(old.as_bytes(), new.as_bytes(), &options)
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(diffs[0].path, "[1].n");
+/// ```
+pub fn diff_streams<A: Read, B: Read>(a: A, b: B, options: &DiffOptions) -> StreamDiff<'_, A, B> {
+    StreamDiff {
+        a: Elements::new(a),
+        b: Elements::new(b),
+        options,
+        index: 0,
+        pending: VecDeque::new(),
+        done: false,
+    }
+}
+
+/// The differences between two streams, as returned by [`diff_streams`].
+pub struct StreamDiff<'o, A, B> {
+    a: Elements<A>,
+    b: Elements<B>,
+    options: &'o DiffOptions,
+    index: usize,
+    // Differences found in the current pair of elements, not yet returned
+    pending: VecDeque<Difference>,
+    done: bool,
+}
+
+impl<A: Read, B: Read> StreamDiff<'_, A, B> {
+    // A difference at `path` below the current element
+    fn difference(
+        &self,
+        path: &[PathSegment],
+        kind: DiffKind,
+        before: Option<Value>,
+        after: Option<Value>,
+    ) -> Difference {
+        let mut segments = vec![PathSegment::Index(self.index)];
+        segments.extend_from_slice(path);
+        let segments = Path::from(segments);
+        Difference {
+            path: segments.render(self.options.path_format),
+            segments,
+            kind,
+            severity: Severity::Info,
+            before,
+            after,
+            context: None,
+            withheld: Withheld::default(),
+        }
+    }
+
+    // A whole element reported as `kind`, redacted and captured as the
+    // options ask, with the element as the document
+    fn element(&self, kind: DiffKind, before: Option<Value>, after: Option<Value>) -> Difference {
+        let mut diff = self.difference(&[], kind, before, after);
+        self.options.finish_at(&mut diff, &Path::root());
+        diff
+    }
+}
+
+impl<A: Read, B: Read> Iterator for StreamDiff<'_, A, B> {
+    type Item = Result<Difference, serde_json::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(diff) = self.pending.pop_front() {
+                return Some(Ok(diff));
+            }
+            if self.done {
+                return None;
+            }
+            match (self.a.next(), self.b.next()) {
+                (None, None) => self.done = true,
+                (Some(Err(error)), _) | (_, Some(Err(error))) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+                (Some(Ok(a)), Some(Ok(b))) => {
+                    for diff in deep_diff_with_options(&a, &b, self.options) {
+                        let path = diff.segments.segments();
+                        let mut prefixed =
+                            self.difference(path, diff.kind, diff.before, diff.after);
+                        prefixed.severity = diff.severity;
+                        prefixed.withheld = diff.withheld;
+                        self.pending.push_back(prefixed);
+                    }
+                }
+                (Some(Ok(a)), None) => {
+                    let diff = self.element(DiffKind::Removed, Some(a), None);
+                    self.pending.push_back(diff);
+                }
+                (None, Some(Ok(b))) => {
+                    let diff = self.element(DiffKind::Added, None, Some(b));
+                    self.pending.push_back(diff);
+                }
+            }
+            self.index += 1;
+        }
+    }
+}
+
+// Where a stream is in its top-level structure
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Start,
+    // Inside a top-level array; `true` once an element has been read
+    Array(bool),
+    // A sequence of values separated by whitespace
+    Lines,
+    Done,
+}
+
+// Splits a stream into its top-level elements, holding one at a time
+struct Elements<R> {
+    reader: BufReader<R>,
+    state: State,
+    // The raw bytes of the element being read
+    buffer: Vec<u8>,
+}
+
+impl<R: Read> Elements<R> {
+    fn new(reader: R) -> Self {
+        Elements {
+            reader: BufReader::new(reader),
+            state: State::Start,
+            buffer: Vec::new(),
+        }
+    }
+
+    fn peek(&mut self) -> io::Result<Option<u8>> {
+        Ok(self.reader.fill_buf()?.first().copied())
+    }
+
+    // Skips whitespace and returns the next byte without consuming it
+    fn peek_token(&mut self) -> io::Result<Option<u8>> {
+        while let Some(byte) = self.peek()? {
+            if !byte.is_ascii_whitespace() {
+                return Ok(Some(byte));
+            }
+            self.reader.consume(1);
+        }
+        Ok(None)
+    }
+
+    // Reads the next element, or `None` at the end of the stream
+    fn read_next(&mut self) -> io::Result<Option<Value>> {
+        let token = self.peek_token()?;
+        match (self.state, token) {
+            (State::Start, Some(b'[')) => {
+                self.reader.consume(1);
+                self.state = State::Array(false);
+                return self.read_next();
+            }
+            (State::Start, Some(_)) => self.state = State::Lines,
+            (State::Start | State::Lines, None) | (State::Done, _) => {
+                self.state = State::Done;
+                return Ok(None);
+            }
+            (State::Array(_), Some(b']')) => {
+                self.reader.consume(1);
+                self.state = State::Done;
+                return Ok(None);
+            }
+            (State::Array(true), Some(b',')) => {
+                self.reader.consume(1);
+                if self.peek_token()? == Some(b']') {
+                    return Err(invalid("trailing comma in array"));
+                }
+            }
+            (State::Array(true), Some(_)) => return Err(invalid("expected ',' or ']'")),
+            (State::Array(_), None) => return Err(invalid("unterminated array")),
+            (State::Array(false) | State::Lines, Some(_)) => {}
+        }
+        if let State::Array(_) = self.state {
+            self.state = State::Array(true);
+        }
+        self.read_element()?;
+        let value = serde_json::from_slice(&self.buffer)?;
+        Ok(Some(value))
+    }
+
+    // Copies the bytes of one value into the buffer, tracking strings and
+    // nesting to find where it ends
+    fn read_element(&mut self) -> io::Result<()> {
+        self.buffer.clear();
+        let (mut depth, mut in_string, mut escaped) = (0usize, false, false);
+        while let Some(byte) = self.peek()? {
+            let scalar_end = depth == 0
+                && !in_string
+                && !self.buffer.is_empty()
+                && (byte.is_ascii_whitespace() || matches!(byte, b',' | b']' | b'}' | b'{' | b'['));
+            if scalar_end {
+                break;
+            }
+            self.reader.consume(1);
+            self.buffer.push(byte);
+            if in_string {
+                match byte {
+                    _ if escaped => escaped = false,
+                    b'\\' => escaped = true,
+                    b'"' => in_string = false,
+                    _ => {}
+                }
+            } else {
+                match byte {
+                    b'"' => in_string = true,
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+            }
+            // Containers and strings end with their closing byte
+            let closed = depth == 0 && !in_string && matches!(byte, b'}' | b']' | b'"');
+            if closed {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Iterator for Elements<R> {
+    type Item = Result<Value, serde_json::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.read_next() {
+            Ok(value) => value.map(Ok),
+            Err(error) => {
+                self.state = State::Done;
+                Some(Err(serde_json::Error::io(error)))
+            }
+        }
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn elements(text: &str) -> Result<Vec<Value>, String> {
+        Elements::new(text.as_bytes())
+            .collect::<Result<_, _>>()
+            .map_err(|error| error.to_string())
+    }
+
+    /// Test that arrays and whitespace-separated values are split into elements.
+    #[test]
+    fn test_elements() {
+        let expected = vec![
+            json!(1),
+            json!("a]\"b"),
+            json!({"x": [true, null]}),
+            json!(-2.5),
+        ];
+        assert_eq!(
+            elements(r#" [1, "a]\"b" ,{"x":[true,null]},-2.5] "#),
+            Ok(expected.clone())
+        );
+        assert_eq!(
+            elements("1\n\"a]\\\"b\"\n{\"x\":[true,null]}\n-2.5\n"),
+            Ok(expected)
+        );
+        assert_eq!(elements("[]"), Ok(vec![]));
+        assert_eq!(elements("  "), Ok(vec![]));
+        assert_eq!(elements("[[1],[2]]"), Ok(vec![json!([1]), json!([2])]));
+    }
+
+    /// Test that malformed streams end with an error.
+    #[test]
+    fn test_elements_invalid() {
+        assert_eq!(elements("[1, 2"), Err("unterminated array".to_string()));
+        assert_eq!(elements("[1 2]"), Err("expected ',' or ']'".to_string()));
+        assert_eq!(elements("[1,]"), Err("trailing comma in array".to_string()));
+        assert!(elements("[{\"a\": ]").is_err());
+        assert!(elements("{\"a\": 1").is_err());
+    }
+
+    /// Test that elements are compared pairwise and extra ones reported.
+    #[test]
+    fn test_diff_streams() {
+        let old = "[{\"id\": 1, \"tags\": [\"a\"]}, {\"id\": 2}, {\"id\": 3}]";
+        let new = "{\"id\": 1, \"tags\": [\"b\"]}\n{\"id\": 2}\n";
+        let options = DiffOptions::default();
+        let diffs: Vec<_> = diff_streams(old.as_bytes(), new.as_bytes(), &options)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let summary: Vec<_> = diffs
+            .iter()
+            .map(|d| (d.path.as_str(), d.kind.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("[0].tags[0]", DiffKind::Changed),
+                ("[2]", DiffKind::Removed)
+            ]
+        );
+        assert_eq!(diffs[1].before, Some(json!({"id": 3})));
+        assert_eq!(diffs[0].segments, "[0].tags[0]".parse().unwrap());
+
+        let options = DiffOptions::builder().ignore("tags").build();
+        let mut diffs = diff_streams(old.as_bytes(), "[{\"id\": 1}, 7".as_bytes(), &options);
+        assert_eq!(
+            diffs.next().map(|d| d.unwrap().path),
+            Some("[1]".to_string())
+        );
+        assert!(diffs.next().unwrap().is_err());
+        assert!(diffs.next().is_none());
+    }
+
+    /// Test that extra elements are redacted and captured like the rest.
+    #[test]
+    fn test_diff_streams_redact() {
+        let old = "{\"id\": 1}";
+        let new = "{\"id\": 1}\n{\"id\": 2, \"password\": \"hunter2-very-long-secret\"}";
+        let options = DiffOptions::builder().redact(["password"]).build();
+        let diffs: Vec<_> = diff_streams(old.as_bytes(), new.as_bytes(), &options)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            diffs[0].after,
+            Some(json!({"id": 2, "password": crate::REDACTED}))
+        );
+        let options = DiffOptions::builder().max_captured_value_bytes(8).build();
+        let mut diffs = diff_streams(new.as_bytes(), old.as_bytes(), &options);
+        let removed = diffs.next().unwrap().unwrap();
+        assert!(crate::Truncated::from_value(removed.before.as_ref().unwrap()).is_some());
+    }
+}
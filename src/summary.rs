@@ -0,0 +1,195 @@
+//! Aggregate statistics about a set of differences.
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use serde_json::Value;
+
+use crate::{DiffKind, DiffOptions, Difference, Path, deep_diff_with_options};
+
+/// Counts and totals describing a set of differences, e.g. for a
+/// "3 fields changed" label.
+///
+/// Built from the differences alone with [`From`], or with
+/// [`DiffSummary::between`] to also measure how similar the documents are.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DiffSummary {
+    /// The number of added values.
+    pub added: usize,
+    /// The number of removed values.
+    pub removed: usize,
+    /// The number of changed values, including ones whose type changed and
+    /// binary fields.
+    pub changed: usize,
+    /// The number of array elements that only moved, of renamed keys, and of
+    /// objects whose keys were reordered.
+    pub moved: usize,
+    /// The paths of all differences, grouped by their first segment. Changes
+    /// to the root itself are grouped under `""`.
+    pub by_top_level_key: BTreeMap<String, Vec<String>>,
+    /// The combined size of the JSON text of every `before` and `after`
+    /// value. Moved elements and renamed keys are not counted.
+    pub bytes_changed: usize,
+    /// The percentage of leaf values (scalars and empty containers) the two
+    /// documents share, from 0.0 to 100.0. Only known when built
+    /// with [`DiffSummary::between`].
+    pub similarity: Option<f64>,
+}
+
+impl DiffSummary {
+    /// Summarizes the differences `diffs` found between `a` and `b`,
+    /// including their [`similarity`](DiffSummary::similarity).
+    pub fn between(a: &Value, b: &Value, diffs: &[Difference]) -> Self {
+        DiffSummary {
+            similarity: Some(100.0 * shared_ratio(a, b, diffs)),
+            ..DiffSummary::from(diffs)
+        }
+    }
+}
+
+/// Returns how structurally similar two documents are, from 0.0 (nothing in
+/// common) to 1.0 (equal).
+///
+/// This is the share of leaf values (scalars and empty containers) across
+/// both documents that are left untouched by their differences, so
+/// it can be used to cluster documents or pick the closest match from a set.
+pub fn similarity(a: &Value, b: &Value) -> f64 {
+    similarity_with_options(a, b, &DiffOptions::default())
+}
+
+/// Returns how structurally similar two documents are under the given
+/// options, from 0.0 to 1.0. Ignored paths count as shared.
+pub fn similarity_with_options(a: &Value, b: &Value, options: &DiffOptions) -> f64 {
+    shared_ratio(a, b, &deep_diff_with_options(a, b, options))
+}
+
+// The share of leaves in both documents that `diffs` leave untouched
+fn shared_ratio(a: &Value, b: &Value, diffs: &[Difference]) -> f64 {
+    let total = leaf_count(a) + leaf_count(b);
+    let changed: usize = diffs
+        .iter()
+        .filter(|diff| !matches!(diff.kind, DiffKind::Moved { .. } | DiffKind::Renamed { .. }))
+        .flat_map(|diff| [&diff.before, &diff.after])
+        .flatten()
+        .map(leaf_count)
+        .sum();
+    match total {
+        0 => 1.0,
+        _ => 1.0 - changed.min(total) as f64 / total as f64,
+    }
+}
+
+impl From<&[Difference]> for DiffSummary {
+    fn from(diffs: &[Difference]) -> Self {
+        let mut summary = DiffSummary::default();
+        for diff in diffs {
+            match diff.kind {
+                DiffKind::Added => summary.added += 1,
+                DiffKind::Removed => summary.removed += 1,
+                DiffKind::Changed
+                | DiffKind::TypeChanged { .. }
+                | DiffKind::BinaryChanged { .. } => summary.changed += 1,
+                DiffKind::Moved { .. } | DiffKind::KeyOrderChanged | DiffKind::Renamed { .. } => {
+                    summary.moved += 1
+                }
+            }
+            let group = match diff.segments.segments().first() {
+                Some(first) => Path::from(vec![first.clone()]).to_string(),
+                None => String::new(),
+            };
+            summary
+                .by_top_level_key
+                .entry(group)
+                .or_default()
+                .push(diff.path.clone());
+            if !matches!(diff.kind, DiffKind::Moved { .. } | DiffKind::Renamed { .. }) {
+                summary.bytes_changed += [&diff.before, &diff.after]
+                    .into_iter()
+                    .flatten()
+                    .map(|value| value.to_string().len())
+                    .sum::<usize>();
+            }
+        }
+        summary
+    }
+}
+
+// Counts the scalars and empty containers in a document, iteratively so
+// deep input is safe
+fn leaf_count(value: &Value) -> usize {
+    let mut count = 0;
+    let mut stack = vec![value];
+    while let Some(value) = stack.pop() {
+        match value {
+            Value::Array(values) if !values.is_empty() => stack.extend(values),
+            Value::Object(map) if !map.is_empty() => stack.extend(map.values()),
+            _ => count += 1,
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deep_diff;
+    use serde_json::json;
+
+    /// Test that differences are counted by kind and grouped by top-level key.
+    #[test]
+    fn test_summary_counts() {
+        let a = json!({"user": {"name": "Al", "age": 3}, "tags": ["a"], "old": true});
+        let b = json!({"user": {"name": "Bo", "email": "x"}, "tags": ["a", "b"]});
+        let summary = DiffSummary::from(deep_diff(&a, &b).as_slice());
+        assert_eq!((summary.added, summary.removed, summary.changed), (2, 2, 1));
+        assert_eq!(
+            summary.by_top_level_key,
+            BTreeMap::from([
+                ("old".to_string(), vec!["old".to_string()]),
+                ("tags".to_string(), vec!["tags[1]".to_string()]),
+                (
+                    "user".to_string(),
+                    vec![
+                        "user.age".to_string(),
+                        "user.name".to_string(),
+                        "user.email".to_string()
+                    ]
+                ),
+            ])
+        );
+        // `true`, `3`, `"Al"`, `"Bo"`, `"x"` and `"b"`
+        assert_eq!(summary.bytes_changed, 4 + 1 + 4 + 4 + 3 + 3);
+        assert_eq!(summary.similarity, None);
+    }
+
+    /// Test that similarity is the share of leaves left unchanged.
+    #[test]
+    fn test_summary_similarity() {
+        let a = json!({"a": 1, "b": [1, 2, 3]});
+        let b = json!({"a": 2, "b": [1, 2, 3]});
+        let summary = DiffSummary::between(&a, &b, &deep_diff(&a, &b));
+        assert_eq!(summary.similarity, Some(75.0));
+        let same = DiffSummary::between(&a, &a, &[]);
+        assert_eq!(same.similarity, Some(100.0));
+        let root = DiffSummary::between(&json!(1), &json!("1"), &deep_diff(&json!(1), &json!("1")));
+        assert_eq!(root.similarity, Some(0.0));
+        assert_eq!(root.by_top_level_key[""], vec![String::new()]);
+    }
+
+    /// Test that similarity ranges from 0.0 for disjoint to 1.0 for equal documents.
+    #[test]
+    fn test_similarity() {
+        let a = json!({"name": "Al", "tags": ["x", "y"], "n": 1});
+        assert_eq!(similarity(&a, &a), 1.0);
+        assert_eq!(similarity(&json!({}), &json!({})), 1.0);
+        assert_eq!(similarity(&json!({}), &json!([])), 0.0);
+        assert_eq!(similarity(&json!({"a": 1}), &json!({"b": 2})), 0.0);
+        let close = json!({"name": "Al", "tags": ["x", "y"], "n": 2});
+        let far = json!({"name": "Bo", "tags": ["z"], "n": 2});
+        assert!(similarity(&a, &close) > similarity(&a, &far));
+        let options = DiffOptions::builder().ignore("n").build();
+        assert_eq!(similarity_with_options(&a, &close, &options), 1.0);
+    }
+}
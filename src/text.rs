@@ -0,0 +1,165 @@
+//! Differences inside changed strings, for pinpointing edits to long text.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::lcs::lcs;
+
+/// The unit in which a [`TextDelta`] compares two strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextGranularity {
+    /// Compare character by character.
+    Char,
+    /// Compare whole words, keeping runs of whitespace as separate units.
+    #[default]
+    Word,
+}
+
+/// One step of a [`TextDelta`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextOp {
+    /// Text present in both strings.
+    Equal(String),
+    /// Text only present in the old string.
+    Delete(String),
+    /// Text only present in the new string.
+    Insert(String),
+}
+
+/// The edits that turn one string into another, as computed by
+/// [`Difference::text_delta`](crate::Difference::text_delta).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TextDelta {
+    /// The steps in order; adjacent steps never have the same kind.
+    pub ops: Vec<TextOp>,
+}
+
+impl TextDelta {
+    /// Computes the edits that turn `old` into `new`.
+    pub fn new(old: &str, new: &str, granularity: TextGranularity) -> Self {
+        let (old_units, new_units) = (split(old, granularity), split(new, granularity));
+        let mut delta = TextDelta::default();
+        let (mut i, mut j) = (0, 0);
+        for (mi, mj) in lcs(&old_units, &new_units, |x, y| x == y) {
+            delta.extend(&old_units[i..mi], TextOp::Delete);
+            delta.extend(&new_units[j..mj], TextOp::Insert);
+            delta.extend(&old_units[mi..=mi], TextOp::Equal);
+            (i, j) = (mi + 1, mj + 1);
+        }
+        delta.extend(&old_units[i..], TextOp::Delete);
+        delta.extend(&new_units[j..], TextOp::Insert);
+        delta
+    }
+
+    /// Rebuilds the old string.
+    pub fn old_text(&self) -> String {
+        self.text(|op| !matches!(op, TextOp::Insert(_)))
+    }
+
+    /// Rebuilds the new string.
+    pub fn new_text(&self) -> String {
+        self.text(|op| !matches!(op, TextOp::Delete(_)))
+    }
+
+    // Concatenates the text of the steps `keep` selects
+    fn text(&self, keep: impl Fn(&TextOp) -> bool) -> String {
+        self.ops
+            .iter()
+            .filter(|op| keep(op))
+            .map(|op| match op {
+                TextOp::Equal(text) | TextOp::Delete(text) | TextOp::Insert(text) => text.as_str(),
+            })
+            .collect()
+    }
+
+    // Appends `units` as a step, merging it into the last one of the same kind
+    fn extend(&mut self, units: &[&str], op: fn(String) -> TextOp) {
+        if units.is_empty() {
+            return;
+        }
+        let text = units.concat();
+        match (self.ops.last_mut(), op(String::new())) {
+            (Some(TextOp::Equal(last)), TextOp::Equal(_))
+            | (Some(TextOp::Delete(last)), TextOp::Delete(_))
+            | (Some(TextOp::Insert(last)), TextOp::Insert(_)) => last.push_str(&text),
+            _ => self.ops.push(op(text)),
+        }
+    }
+}
+
+// Splits `text` into the units compared at `granularity`
+fn split(text: &str, granularity: TextGranularity) -> Vec<&str> {
+    let mut units = Vec::new();
+    let mut start = 0;
+    let mut previous: Option<bool> = None;
+    for (at, c) in text.char_indices() {
+        let space = c.is_whitespace();
+        let boundary = match granularity {
+            TextGranularity::Char => true,
+            TextGranularity::Word => previous.is_some_and(|was_space| was_space != space),
+        };
+        if boundary && at > start {
+            units.push(&text[start..at]);
+            start = at;
+        }
+        previous = Some(space);
+    }
+    if start < text.len() {
+        units.push(&text[start..]);
+    }
+    units
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that words are compared as units.
+    #[test]
+    fn test_text_delta_words() {
+        let delta = TextDelta::new(
+            "the quick brown fox",
+            "the slow brown  fox jumps",
+            TextGranularity::Word,
+        );
+        assert_eq!(
+            delta.ops,
+            vec![
+                TextOp::Equal("the ".to_string()),
+                TextOp::Delete("quick".to_string()),
+                TextOp::Insert("slow".to_string()),
+                TextOp::Equal(" brown".to_string()),
+                TextOp::Delete(" ".to_string()),
+                TextOp::Insert("  ".to_string()),
+                TextOp::Equal("fox".to_string()),
+                TextOp::Insert(" jumps".to_string()),
+            ]
+        );
+    }
+
+    /// Test that characters are compared one at a time, including multibyte ones.
+    #[test]
+    fn test_text_delta_chars() {
+        let delta = TextDelta::new("café", "cafe!", TextGranularity::Char);
+        assert_eq!(
+            delta.ops,
+            vec![
+                TextOp::Equal("caf".to_string()),
+                TextOp::Delete("é".to_string()),
+                TextOp::Insert("e!".to_string()),
+            ]
+        );
+    }
+
+    /// Test that both strings can be rebuilt from the delta.
+    #[test]
+    fn test_text_delta_round_trip() {
+        let (old, new) = ("a b\nc  d e", "a x\nc d e f");
+        for granularity in [TextGranularity::Char, TextGranularity::Word] {
+            let delta = TextDelta::new(old, new, granularity);
+            assert_eq!(delta.old_text(), old);
+            assert_eq!(delta.new_text(), new);
+        }
+        assert!(TextDelta::new("", "", TextGranularity::Word).ops.is_empty());
+    }
+}
@@ -0,0 +1,436 @@
+//! Diffing of Rust values directly, without serializing them to JSON first.
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::hash::BuildHasher;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+use crate::{DiffKind, Difference, Path, Severity, Withheld};
+
+/// Types that can be diffed field by field.
+///
+/// Differences are reported in the same shape as [`deep_diff`](crate::deep_diff)
+/// reports them for the value's JSON form: struct fields and map keys become
+/// key segments and sequence elements become index segments. Only the values
+/// that changed are converted to JSON, for the `before` and `after` of each
+/// difference.
+///
+/// With the `derive` feature, `#[derive(DeepDiff)]` implements this for
+/// structs and enums whose fields implement it.
+pub trait DeepDiff {
+    /// Converts the value to JSON, for reporting it in a difference.
+    fn to_json(&self) -> Value;
+
+    /// Appends the differences between `self` and `other`, located under
+    /// `path`, to `differences`.
+    fn diff_at(&self, other: &Self, path: &Path, differences: &mut Vec<Difference>);
+
+    /// Computes the differences between `self` and `other`.
+    fn diff(&self, other: &Self) -> Vec<Difference> {
+        let mut differences = Vec::new();
+        self.diff_at(other, &Path::root(), &mut differences);
+        differences
+    }
+}
+
+// Builds a difference located at `path`
+fn difference(
+    path: Path,
+    kind: DiffKind,
+    before: Option<Value>,
+    after: Option<Value>,
+) -> Difference {
+    Difference {
+        path: path.to_string(),
+        segments: path,
+        kind,
+        severity: Severity::Info,
+        before,
+        after,
+        context: None,
+        withheld: Withheld::default(),
+    }
+}
+
+// Reports a changed leaf value
+fn diff_leaf<T: DeepDiff + PartialEq + ?Sized>(
+    a: &T,
+    b: &T,
+    path: &Path,
+    differences: &mut Vec<Difference>,
+) {
+    if a != b {
+        let (before, after) = (Some(a.to_json()), Some(b.to_json()));
+        differences.push(difference(path.clone(), DiffKind::Changed, before, after));
+    }
+}
+
+macro_rules! impl_leaf {
+    ($($ty:ty),*) => {
+        $(
+            impl DeepDiff for $ty {
+                fn to_json(&self) -> Value {
+                    Value::from(*self)
+                }
+
+                fn diff_at(&self, other: &Self, path: &Path, differences: &mut Vec<Difference>) {
+                    diff_leaf(self, other, path, differences);
+                }
+            }
+        )*
+    };
+}
+
+impl_leaf!(
+    bool, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64
+);
+
+impl DeepDiff for char {
+    fn to_json(&self) -> Value {
+        Value::String(self.to_string())
+    }
+
+    fn diff_at(&self, other: &Self, path: &Path, differences: &mut Vec<Difference>) {
+        diff_leaf(self, other, path, differences);
+    }
+}
+
+impl DeepDiff for str {
+    fn to_json(&self) -> Value {
+        Value::from(self)
+    }
+
+    fn diff_at(&self, other: &Self, path: &Path, differences: &mut Vec<Difference>) {
+        diff_leaf(self, other, path, differences);
+    }
+}
+
+impl DeepDiff for String {
+    fn to_json(&self) -> Value {
+        self.as_str().to_json()
+    }
+
+    fn diff_at(&self, other: &Self, path: &Path, differences: &mut Vec<Difference>) {
+        diff_leaf(self.as_str(), other, path, differences);
+    }
+}
+
+impl DeepDiff for () {
+    fn to_json(&self) -> Value {
+        Value::Null
+    }
+
+    fn diff_at(&self, _: &Self, _: &Path, _: &mut Vec<Difference>) {}
+}
+
+impl<T: DeepDiff + ?Sized> DeepDiff for Box<T> {
+    fn to_json(&self) -> Value {
+        (**self).to_json()
+    }
+
+    fn diff_at(&self, other: &Self, path: &Path, differences: &mut Vec<Difference>) {
+        (**self).diff_at(other, path, differences);
+    }
+}
+
+/// `None` is reported as a missing value, so a field becoming `Some` is
+/// [`DiffKind::Added`] and one becoming `None` is [`DiffKind::Removed`].
+impl<T: DeepDiff> DeepDiff for Option<T> {
+    fn to_json(&self) -> Value {
+        self.as_ref().map_or(Value::Null, T::to_json)
+    }
+
+    fn diff_at(&self, other: &Self, path: &Path, differences: &mut Vec<Difference>) {
+        match (self, other) {
+            (Some(a), Some(b)) => a.diff_at(b, path, differences),
+            (Some(a), None) => {
+                let kind = DiffKind::Removed;
+                differences.push(difference(path.clone(), kind, Some(a.to_json()), None));
+            }
+            (None, Some(b)) => {
+                let kind = DiffKind::Added;
+                differences.push(difference(path.clone(), kind, None, Some(b.to_json())));
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+/// Elements are compared by index, like
+/// [`ArrayDiffStrategy::Index`](crate::ArrayDiffStrategy::Index).
+impl<T: DeepDiff> DeepDiff for [T] {
+    fn to_json(&self) -> Value {
+        Value::Array(self.iter().map(T::to_json).collect())
+    }
+
+    fn diff_at(&self, other: &Self, path: &Path, differences: &mut Vec<Difference>) {
+        for (i, (a, b)) in self.iter().zip(other).enumerate() {
+            a.diff_at(b, &path.child_index(i), differences);
+        }
+        // Removed from the back so the remaining indices stay valid
+        for (i, a) in self.iter().enumerate().skip(other.len()).rev() {
+            let (child, before) = (path.child_index(i), Some(a.to_json()));
+            differences.push(difference(child, DiffKind::Removed, before, None));
+        }
+        for (i, b) in other.iter().enumerate().skip(self.len()) {
+            let (child, after) = (path.child_index(i), Some(b.to_json()));
+            differences.push(difference(child, DiffKind::Added, None, after));
+        }
+    }
+}
+
+impl<T: DeepDiff> DeepDiff for Vec<T> {
+    fn to_json(&self) -> Value {
+        self.as_slice().to_json()
+    }
+
+    fn diff_at(&self, other: &Self, path: &Path, differences: &mut Vec<Difference>) {
+        self.as_slice().diff_at(other, path, differences);
+    }
+}
+
+// Diffs two maps whose entries are sorted by key
+fn diff_entries<V: DeepDiff>(
+    a: &BTreeMap<&String, &V>,
+    b: &BTreeMap<&String, &V>,
+    path: &Path,
+    differences: &mut Vec<Difference>,
+) {
+    for (key, av) in a {
+        let child = path.child_key(key.as_str());
+        match b.get(key) {
+            Some(bv) => av.diff_at(bv, &child, differences),
+            None => {
+                let before = Some(av.to_json());
+                differences.push(difference(child, DiffKind::Removed, before, None));
+            }
+        }
+    }
+    for (key, bv) in b {
+        if !a.contains_key(key) {
+            let (child, after) = (path.child_key(key.as_str()), Some(bv.to_json()));
+            differences.push(difference(child, DiffKind::Added, None, after));
+        }
+    }
+}
+
+impl<V: DeepDiff> DeepDiff for BTreeMap<String, V> {
+    fn to_json(&self) -> Value {
+        let map: Map<String, Value> = self.iter().map(|(k, v)| (k.clone(), v.to_json())).collect();
+        Value::Object(map)
+    }
+
+    fn diff_at(&self, other: &Self, path: &Path, differences: &mut Vec<Difference>) {
+        let (a, b) = (self.iter().collect(), other.iter().collect());
+        diff_entries(&a, &b, path, differences);
+    }
+}
+
+/// Keys are visited in sorted order, so the differences are reported in a
+/// stable order.
+#[cfg(feature = "std")]
+impl<V: DeepDiff, S: BuildHasher> DeepDiff for HashMap<String, V, S> {
+    fn to_json(&self) -> Value {
+        let map: Map<String, Value> = self.iter().map(|(k, v)| (k.clone(), v.to_json())).collect();
+        Value::Object(map)
+    }
+
+    fn diff_at(&self, other: &Self, path: &Path, differences: &mut Vec<Difference>) {
+        let (a, b) = (self.iter().collect(), other.iter().collect());
+        diff_entries(&a, &b, path, differences);
+    }
+}
+
+// Support for the code generated by `#[derive(DeepDiff)]`
+#[doc(hidden)]
+pub mod private {
+    pub use alloc::vec;
+    pub use alloc::vec::Vec;
+    pub use serde_json::{Map, Value};
+
+    use alloc::string::ToString;
+
+    use super::difference;
+    use crate::{DiffKind, Difference, Path};
+
+    // Wraps an enum variant's contents in an object keyed by its name
+    pub fn tagged(variant: &str, value: Value) -> Value {
+        let mut map = Map::new();
+        map.insert(variant.to_string(), value);
+        Value::Object(map)
+    }
+
+    // Reports a value that changed to a different enum variant
+    pub fn changed(path: &Path, before: Value, after: Value) -> Difference {
+        difference(path.clone(), DiffKind::Changed, Some(before), Some(after))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn paths(differences: &[Difference]) -> Vec<(&str, DiffKind)> {
+        differences
+            .iter()
+            .map(|d| (d.path.as_str(), d.kind.clone()))
+            .collect()
+    }
+
+    /// Test that leaf values report their JSON form when they change.
+    #[test]
+    fn test_diff_leaves() {
+        assert!(1u8.diff(&1).is_empty());
+        let result = u64::MAX.diff(&0);
+        assert_eq!(result[0].before, Some(json!(u64::MAX)));
+        assert_eq!(result[0].path, "");
+        assert_eq!(
+            "a".to_string().diff(&"b".to_string())[0].after,
+            Some(json!("b"))
+        );
+    }
+
+    /// Test that optional values are reported as added and removed.
+    #[test]
+    fn test_diff_option() {
+        assert_eq!(paths(&Some(1).diff(&None)), vec![("", DiffKind::Removed)]);
+        assert_eq!(paths(&None.diff(&Some(1))), vec![("", DiffKind::Added)]);
+        assert!(None::<i32>.diff(&None).is_empty());
+    }
+
+    /// Test that sequences are compared by index.
+    #[test]
+    fn test_diff_vec() {
+        let result = vec![1, 2, 3, 4].diff(&vec![1, 5]);
+        assert_eq!(
+            paths(&result),
+            vec![
+                ("[1]", DiffKind::Changed),
+                ("[3]", DiffKind::Removed),
+                ("[2]", DiffKind::Removed),
+            ]
+        );
+        assert_eq!(
+            paths(&vec![1].diff(&vec![1, 2])),
+            vec![("[1]", DiffKind::Added)]
+        );
+    }
+
+    /// Test that maps are compared by key, in sorted order.
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_diff_maps() {
+        let a = HashMap::from([
+            ("b".to_string(), 1),
+            ("a".to_string(), 1),
+            ("c".to_string(), 1),
+        ]);
+        let b = HashMap::from([
+            ("d".to_string(), 1),
+            ("a".to_string(), 2),
+            ("b".to_string(), 1),
+        ]);
+        assert_eq!(
+            paths(&a.diff(&b)),
+            vec![
+                ("a", DiffKind::Changed),
+                ("c", DiffKind::Removed),
+                ("d", DiffKind::Added),
+            ]
+        );
+        let a = BTreeMap::from([("x".to_string(), vec![1])]);
+        assert_eq!(a.to_json(), json!({"x": [1]}));
+    }
+
+    /// Test that typed diffs match diffing the values' JSON forms.
+    #[test]
+    fn test_diff_matches_json() {
+        let a = BTreeMap::from([("k".to_string(), vec![Some(1.5), None, Some(2.0)])]);
+        let b = BTreeMap::from([("k".to_string(), vec![Some(1.5), Some(3.0)])]);
+        let typed: Vec<_> = paths(&a.diff(&b))
+            .into_iter()
+            .map(|(path, _)| path.to_string())
+            .collect();
+        let json: Vec<_> = crate::deep_diff(&a.to_json(), &b.to_json())
+            .into_iter()
+            .map(|d| d.path)
+            .collect();
+        assert_eq!(typed, json);
+    }
+
+    /// Test that derived implementations diff structs and enums field by field.
+    #[cfg(feature = "derive")]
+    #[test]
+    fn test_derive() {
+        #[derive(crate::DeepDiff)]
+        struct Point(i32, i32);
+
+        #[derive(crate::DeepDiff)]
+        enum Shape {
+            Empty,
+            Circle(u32),
+            Polygon { points: Vec<Point>, closed: bool },
+        }
+
+        #[derive(crate::DeepDiff)]
+        struct Drawing {
+            pub name: String,
+            pub(crate) r#type: Option<String>,
+            shapes: BTreeMap<String, Shape>,
+        }
+
+        let a = Drawing {
+            name: "a".to_string(),
+            r#type: None,
+            shapes: BTreeMap::from([
+                ("x".to_string(), Shape::Circle(1)),
+                (
+                    "y".to_string(),
+                    Shape::Polygon {
+                        points: vec![Point(0, 0)],
+                        closed: true,
+                    },
+                ),
+                ("z".to_string(), Shape::Empty),
+            ]),
+        };
+        let b = Drawing {
+            name: "a".to_string(),
+            r#type: Some("svg".to_string()),
+            shapes: BTreeMap::from([
+                ("x".to_string(), Shape::Circle(2)),
+                (
+                    "y".to_string(),
+                    Shape::Polygon {
+                        points: vec![Point(0, 1)],
+                        closed: true,
+                    },
+                ),
+                ("z".to_string(), Shape::Circle(3)),
+            ]),
+        };
+        let result = a.diff(&b);
+        assert_eq!(
+            paths(&result),
+            vec![
+                ("type", DiffKind::Added),
+                ("shapes.x.Circle", DiffKind::Changed),
+                ("shapes.y.Polygon.points[0][1]", DiffKind::Changed),
+                ("shapes.z", DiffKind::Changed),
+            ]
+        );
+        assert_eq!(result[3].before, Some(json!("Empty")));
+        assert_eq!(result[3].after, Some(json!({"Circle": 3})));
+        assert_eq!(
+            a.to_json()["shapes"]["y"],
+            json!({"Polygon": {"points": [[0, 0]], "closed": true}})
+        );
+    }
+}
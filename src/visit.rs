@@ -0,0 +1,212 @@
+//! Callback-based traversal of the differences between two documents.
+
+use alloc::string::String;
+use core::ops::ControlFlow;
+
+use serde_json::{Map, Value};
+
+use crate::engine::Walker;
+use crate::{DiffKind, DiffOptions, DifferenceRef, Path};
+
+/// Receives the differences between two documents as they are found, along
+/// with the containers the traversal descends into.
+///
+/// Every hook does nothing by default. Returning [`ControlFlow::Break`] from
+/// any hook stops the traversal immediately, so a visitor can build its own
+/// output or stop early without collecting the differences into a `Vec`.
+pub trait DiffVisitor<'a> {
+    /// Called for a value that only exists in the second document.
+    fn on_added(&mut self, diff: &DifferenceRef<'a>) -> ControlFlow<()> {
+        let _ = diff;
+        ControlFlow::Continue(())
+    }
+
+    /// Called for a value that only exists in the first document.
+    fn on_removed(&mut self, diff: &DifferenceRef<'a>) -> ControlFlow<()> {
+        let _ = diff;
+        ControlFlow::Continue(())
+    }
+
+    /// Called for a value that differs between the documents, including
+    /// changes of type and of binary fields.
+    fn on_changed(&mut self, diff: &DifferenceRef<'a>) -> ControlFlow<()> {
+        let _ = diff;
+        ControlFlow::Continue(())
+    }
+
+    /// Called for an array element that moved, or a key that was renamed.
+    fn on_moved(&mut self, diff: &DifferenceRef<'a>) -> ControlFlow<()> {
+        let _ = diff;
+        ControlFlow::Continue(())
+    }
+
+    /// Called before the entries of two objects at `path` are compared.
+    fn enter_object(
+        &mut self,
+        path: &Path,
+        a: &'a Map<String, Value>,
+        b: &'a Map<String, Value>,
+    ) -> ControlFlow<()> {
+        let _ = (path, a, b);
+        ControlFlow::Continue(())
+    }
+
+    /// Called before the elements of two arrays at `path` are compared.
+    fn enter_array(&mut self, path: &Path, a: &'a [Value], b: &'a [Value]) -> ControlFlow<()> {
+        let _ = (path, a, b);
+        ControlFlow::Continue(())
+    }
+}
+
+/// Walks the differences between two JSON values, passing each one to
+/// `visitor` in traversal order.
+///
+/// Returns [`ControlFlow::Break`] if the visitor stopped the traversal.
+pub fn walk_diff<'a>(
+    a: &'a Value,
+    b: &'a Value,
+    visitor: &mut impl DiffVisitor<'a>,
+) -> ControlFlow<()> {
+    walk_diff_with_options(a, b, &DiffOptions::default(), visitor)
+}
+
+/// Walks the differences between two JSON values using the given options,
+/// passing each one to `visitor` in traversal order.
+///
+/// Stops after [`max_differences`](crate::DiffOptionsBuilder::max_differences)
+/// differences. Returns [`ControlFlow::Break`] if the visitor stopped the
+/// traversal.
+pub fn walk_diff_with_options<'a>(
+    a: &'a Value,
+    b: &'a Value,
+    options: &DiffOptions,
+    visitor: &mut impl DiffVisitor<'a>,
+) -> ControlFlow<()> {
+    let mut walker = Walker::new(a, b, options);
+    walker.record_descents = true;
+    let mut remaining = options.max_differences.unwrap_or(usize::MAX);
+    while remaining > 0
+        && let Some(task) = walker.stack.pop()
+    {
+        let found = walker.run(task);
+        match walker.descent.take() {
+            Some((path, Value::Object(a), Value::Object(b))) => {
+                visitor.enter_object(&path, a, b)?
+            }
+            Some((path, Value::Array(a), Value::Array(b))) => visitor.enter_array(&path, a, b)?,
+            _ => {}
+        }
+        if let Some(diff) = found {
+            remaining -= 1;
+            match diff.kind {
+                DiffKind::Added => visitor.on_added(&diff)?,
+                DiffKind::Removed => visitor.on_removed(&diff)?,
+                DiffKind::Changed
+                | DiffKind::TypeChanged { .. }
+                | DiffKind::BinaryChanged { .. } => visitor.on_changed(&diff)?,
+                DiffKind::Moved { .. } | DiffKind::KeyOrderChanged | DiffKind::Renamed { .. } => {
+                    visitor.on_moved(&diff)?
+                }
+            }
+        }
+    }
+    ControlFlow::Continue(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // Records every hook call as a line
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<String>,
+        stop_at: Option<usize>,
+    }
+
+    impl Recorder {
+        fn record(&mut self, event: String) -> ControlFlow<()> {
+            self.events.push(event);
+            match self.stop_at {
+                Some(stop) if self.events.len() >= stop => ControlFlow::Break(()),
+                _ => ControlFlow::Continue(()),
+            }
+        }
+    }
+
+    impl<'a> DiffVisitor<'a> for Recorder {
+        fn on_added(&mut self, diff: &DifferenceRef<'a>) -> ControlFlow<()> {
+            self.record(format!("added {}", diff.path))
+        }
+
+        fn on_removed(&mut self, diff: &DifferenceRef<'a>) -> ControlFlow<()> {
+            self.record(format!("removed {}", diff.path))
+        }
+
+        fn on_changed(&mut self, diff: &DifferenceRef<'a>) -> ControlFlow<()> {
+            self.record(format!("changed {}", diff.path))
+        }
+
+        fn enter_object(
+            &mut self,
+            path: &Path,
+            a: &'a Map<String, Value>,
+            _: &'a Map<String, Value>,
+        ) -> ControlFlow<()> {
+            self.record(format!("object {} ({} keys)", path, a.len()))
+        }
+
+        fn enter_array(&mut self, path: &Path, a: &'a [Value], b: &'a [Value]) -> ControlFlow<()> {
+            self.record(format!("array {} ({} -> {})", path, a.len(), b.len()))
+        }
+    }
+
+    /// Test that hooks are called in traversal order.
+    #[test]
+    fn test_walk_diff_events() {
+        let a = json!({"name": "Al", "tags": ["x"], "old": 1});
+        let b = json!({"name": "Bo", "tags": ["x", "y"], "new": 1});
+        let mut recorder = Recorder::default();
+        assert_eq!(walk_diff(&a, &b, &mut recorder), ControlFlow::Continue(()));
+        assert_eq!(
+            recorder.events,
+            vec![
+                "object  (3 keys)",
+                "changed name",
+                "removed old",
+                "array tags (1 -> 2)",
+                "added tags[1]",
+                "added new",
+            ]
+        );
+    }
+
+    /// Test that breaking from a hook stops the traversal.
+    #[test]
+    fn test_walk_diff_break() {
+        let a = json!({"a": 1, "b": 2, "c": 3});
+        let b = json!({"a": 2, "b": 3, "c": 4});
+        let mut recorder = Recorder {
+            stop_at: Some(2),
+            ..Recorder::default()
+        };
+        assert_eq!(walk_diff(&a, &b, &mut recorder), ControlFlow::Break(()));
+        assert_eq!(recorder.events, vec!["object  (3 keys)", "changed a"]);
+    }
+
+    /// Test that the walk honours the difference limit.
+    #[test]
+    fn test_walk_diff_limit() {
+        let a = json!([1, 2, 3]);
+        let b = json!([4, 5, 6]);
+        let options = DiffOptions::builder().max_differences(2).build();
+        let mut recorder = Recorder::default();
+        let flow = walk_diff_with_options(&a, &b, &options, &mut recorder);
+        assert_eq!(flow, ControlFlow::Continue(()));
+        assert_eq!(
+            recorder.events,
+            vec!["array  (3 -> 3)", "changed [0]", "changed [1]"]
+        );
+    }
+}
@@ -0,0 +1,144 @@
+//! Snapshot tests of every output format, rendering the diff of each pair of
+//! documents in `tests/fixtures/` and comparing it with the file saved for
+//! that format in `tests/snapshots/`.
+//!
+//! A fixture may also hold an `options.json`, e.g.
+//! `{"detect_renames": true}`, to diff its documents with those options.
+//!
+//! A mismatch fails the test and writes the new output next to the saved
+//! one, with a `.new` extension, for review. After an intended change to a
+//! renderer, run `UPDATE_SNAPSHOTS=1 cargo test --test snapshots` to save
+//! the new outputs.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use deep_diff::render::{render_html, render_markdown, render_side_by_side, render_text};
+use deep_diff::{DiffOptions, Difference, deep_diff_with_options, merge_patch, to_json_patch};
+use serde_json::Value;
+
+// A pair of documents from `tests/fixtures/<name>/{a,b}.json`
+struct Fixture {
+    name: String,
+    a: Value,
+    b: Value,
+    differences: Vec<Difference>,
+}
+
+fn root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests")
+}
+
+// Loads every fixture, in order of name
+fn fixtures() -> Vec<Fixture> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(root().join("fixtures"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    dirs.sort();
+    dirs.into_iter()
+        .map(|dir| {
+            let read = |file: &str| -> Value {
+                let text = fs::read_to_string(dir.join(file)).unwrap();
+                serde_json::from_str(&text).unwrap()
+            };
+            let (a, b) = (read("a.json"), read("b.json"));
+            let options = match dir.join("options.json").exists() {
+                true => options(&read("options.json")),
+                false => DiffOptions::default(),
+            };
+            Fixture {
+                name: dir.file_name().unwrap().to_string_lossy().into_owned(),
+                differences: deep_diff_with_options(&a, &b, &options),
+                a,
+                b,
+            }
+        })
+        .collect()
+}
+
+// The options a fixture's `options.json` asks for
+fn options(config: &Value) -> DiffOptions {
+    DiffOptions::builder()
+        .detect_renames(config["detect_renames"].as_bool().unwrap_or(false))
+        .build()
+}
+
+// Renders every fixture and compares it with `<fixture>/<file>` in the
+// snapshots, failing with the list of mismatched files
+fn check_snapshots(file: &str, render: impl Fn(&Fixture) -> String) {
+    let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some();
+    let mut mismatched = Vec::new();
+    for fixture in fixtures() {
+        let path = root().join("snapshots").join(&fixture.name).join(file);
+        let output = render(&fixture);
+        let pending = path.with_extension(format!(
+            "{}.new",
+            path.extension().unwrap().to_string_lossy()
+        ));
+        if fs::read_to_string(&path).is_ok_and(|saved| saved == output) {
+            let _ = fs::remove_file(&pending);
+            continue;
+        }
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        if update {
+            fs::write(&path, output).unwrap();
+            let _ = fs::remove_file(&pending);
+        } else {
+            fs::write(&pending, output).unwrap();
+            mismatched.push(path.display().to_string());
+        }
+    }
+    assert!(
+        mismatched.is_empty(),
+        "output differs from the snapshots, see the .new files: {:?}",
+        mismatched
+    );
+}
+
+// Pretty-prints a JSON output with a trailing newline
+fn pretty(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap() + "\n"
+}
+
+/// Test that plain text output matches the snapshots.
+#[test]
+fn test_text_snapshots() {
+    check_snapshots("text.txt", |fixture| render_text(&fixture.differences));
+}
+
+/// Test that side-by-side output matches the snapshots.
+#[test]
+fn test_side_by_side_snapshots() {
+    check_snapshots("side_by_side.txt", |fixture| {
+        render_side_by_side(&fixture.a, &fixture.b, &fixture.differences)
+    });
+}
+
+/// Test that HTML reports match the snapshots.
+#[test]
+fn test_html_snapshots() {
+    check_snapshots("report.html", |fixture| render_html(&fixture.differences));
+}
+
+/// Test that Markdown tables match the snapshots.
+#[test]
+fn test_markdown_snapshots() {
+    check_snapshots("table.md", |fixture| render_markdown(&fixture.differences));
+}
+
+/// Test that JSON Patches match the snapshots.
+#[test]
+fn test_json_patch_snapshots() {
+    check_snapshots("json_patch.json", |fixture| {
+        pretty(&to_json_patch(&fixture.differences))
+    });
+}
+
+/// Test that JSON Merge Patches match the snapshots.
+#[test]
+fn test_merge_patch_snapshots() {
+    check_snapshots("merge_patch.json", |fixture| {
+        pretty(&merge_patch(&fixture.a, &fixture.b))
+    });
+}